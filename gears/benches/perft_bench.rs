@@ -14,6 +14,7 @@ const ROOKS_FEN: &str = "k7/4R3/5R2/8/2R3R1/2R5/2RR3R/KRR5 w - - 0 1";
 const BISHOPS_FEN: &str = "k7/3B3B/8/8/2B3B1/2BB4/2BB3B/KB6 w - - 0 1";
 const KNIGHTS_FEN: &str = "k6N/3N4/8/2NN4/2N1N1N1/2N5/2NN4/K7 w - - 0 1";
 const PAWNS_FEN: &str = "k7/3P3P/7p/1p3pP1/2P5/3Pp3/2PP3P/K7 w - f6 0 2";
+const LEONIDS_FEN: &str = "q2k2q1/2nqn2b/1n1P1n1b/2rnr2Q/1NQ1QN1Q/3Q3B/2RQR2B/Q2K2Q1 w - - 0 1";
 
 pub fn perft_startpos_bench(c: &mut Criterion) {
     c.bench_function("perft 4 startpos", |b| {
@@ -29,6 +30,32 @@ pub fn perft_kiwipete_bench(c: &mut Criterion) {
     });
 }
 
+pub fn perft_leonids_bench(c: &mut Criterion) {
+    c.bench_function("perft 3 leonids", |b| {
+        let pos = Chessboard::from_fen(LEONIDS_FEN, Relaxed).unwrap();
+        b.iter(|| perft(Depth::new_unchecked(3), pos));
+    });
+}
+
+/// Compares collecting the legal moves into a list with visiting them, which doesn't need to build a move list.
+pub fn legal_moves_bench(c: &mut Criterion) {
+    let positions = Chessboard::bench_positions();
+    c.bench_function("legal moves list", |b| {
+        b.iter(|| {
+            for pos in &positions {
+                black_box(black_box(pos).legal_moves_slow());
+            }
+        });
+    });
+    c.bench_function("legal moves visitor", |b| {
+        b.iter(|| {
+            for pos in &positions {
+                black_box(pos).for_each_legal_move(|mov| _ = black_box(mov));
+            }
+        });
+    });
+}
+
 fn gen_moves(c: &mut Criterion, name: &str, fen: &str) {
     c.bench_function(name, |b| {
         let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
@@ -125,6 +152,8 @@ criterion_group! {
     targets =
     perft_startpos_bench,
     perft_kiwipete_bench,
+    perft_leonids_bench,
+    legal_moves_bench,
     gen_pawn_moves_bench,
     gen_knight_moves_bench,
     gen_bishop_moves_bench,
@@ -1,6 +1,7 @@
 use anyhow::bail;
 use arbitrary::Arbitrary;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
@@ -15,7 +16,6 @@ use strum::IntoEnumIterator;
 use crate::games::PlayerResult::Lose;
 use crate::general::board::Board;
 use crate::general::common::{parse_int, EntityList, Res, StaticallyNamedEntity};
-use crate::general::move_list::MoveList;
 use crate::general::squares::{RectangularCoordinates, RectangularSize, SquareColor};
 use crate::output::OutputBuilder;
 use crate::PlayerResult;
@@ -287,6 +287,7 @@ pub type OutputList<B> = EntityList<Box<dyn OutputBuilder<B>>>;
     PartialEq,
     Default,
     Debug,
+    Hash,
     derive_more::Display,
     BitXor,
     BitXorAssign,
@@ -359,6 +360,28 @@ impl<B: Board> BoardHistory<B> for ZobristHistory<B> {
     }
 }
 
+/// Counts how often each position has occurred, which allows querying the number of repetitions of a position
+/// without scanning the history. Unlike [`n_fold_repetition`], this doesn't stop at irreversible moves,
+/// but positions from before an irreversible move can't reoccur anyway (except for hash collisions).
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+#[must_use]
+pub struct RepetitionTable<B: Board>(HashMap<ZobristHash, usize>, PhantomData<B>);
+
+impl<B: Board> RepetitionTable<B> {
+    /// How often `pos` has been added to the table.
+    pub fn count(&self, pos: &B) -> usize {
+        self.0.get(&pos.zobrist_hash()).copied().unwrap_or_default()
+    }
+
+    pub fn add(&mut self, pos: &B) {
+        *self.0.entry(pos.zobrist_hash()).or_default() += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
 /// Compares the actual board states as opposed to only comparing the hashes. This still isn't always entirely correct --
 /// For example, the FIDE rule state that the set of legal moves must be identical, which is not the case
 /// if the ep square is set but the pawn is pinned and can't actually take.
@@ -2,6 +2,7 @@ mod board_impl;
 mod common;
 mod perft_test;
 
+use crate::games::ataxx::common::AtaxxMoveType::{Cloning, Leaping};
 use crate::games::ataxx::common::ColoredAtaxxPieceType::{Blocked, Empty, OPiece, XPiece};
 use crate::games::ataxx::common::{AtaxxMove, ColoredAtaxxPieceType, MAX_ATAXX_MOVES_IN_POS};
 use crate::games::ataxx::AtaxxColor::{O, X};
@@ -17,13 +18,14 @@ use crate::general::board::{
     board_from_name, common_fen_part, SelfChecks, Strictness, UnverifiedBoard,
 };
 use crate::general::common::{Res, StaticallyNamedEntity, Tokens};
-use crate::general::move_list::{EagerNonAllocMoveList, MoveList};
+use crate::general::move_list::{EagerNonAllocMoveList, MoveSink};
 use crate::general::squares::SquareColor::White;
 use crate::general::squares::{SmallGridSize, SmallGridSquare, SquareColor};
 use crate::output::text_output::{
     board_to_string, display_board_pretty, BoardFormatter, DefaultBoardFormatter, PieceToChar,
 };
 use crate::output::OutputOpts;
+use crate::NormalGameOverReason;
 use crate::PlayerResult;
 use crate::PlayerResult::{Draw, Lose, Win};
 use anyhow::bail;
@@ -264,11 +266,11 @@ impl Board for AtaxxBoard {
         Self::Piece::new(typ, coordinates)
     }
 
-    fn gen_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T) {
+    fn gen_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T) {
         self.gen_legal(moves)
     }
 
-    fn gen_tactical_pseudolegal<T: MoveList<Self>>(&self, _moves: &mut T) {
+    fn gen_tactical_pseudolegal<T: MoveSink<Self>>(&self, _moves: &mut T) {
         // currently, no moves are considered tactical
     }
 
@@ -300,6 +302,14 @@ impl Board for AtaxxBoard {
         self.is_move_legal_impl(mov)
     }
 
+    /// Cloning moves gain a piece, so they're usually better than leaping moves.
+    fn move_order_score(&self, mov: Self::Move) -> i32 {
+        match mov.typ() {
+            Cloning => 1,
+            Leaping => 0,
+        }
+    }
+
     fn player_result_no_movegen<H: BoardHistory<Self>>(
         &self,
         _history: &H,
@@ -327,6 +337,16 @@ impl Board for AtaxxBoard {
         self.player_result_no_movegen(history)
     }
 
+    fn game_over_reason_slow<H: BoardHistory<Self>>(&self, _history: &H) -> NormalGameOverReason {
+        if self.color_bb(self.active_player).is_zero() {
+            NormalGameOverReason::NoPiecesLeft
+        } else if self.empty.has_set_bit() {
+            NormalGameOverReason::FiftyMoveRule
+        } else {
+            NormalGameOverReason::BoardFull
+        }
+    }
+
     /// If a player has no legal moves, a null move is generated, so this doesn't require any special handling during search.
     /// But if there are no pieces left, the player loses the game.
     fn no_moves_result(&self) -> PlayerResult {
@@ -517,6 +537,26 @@ mod tests {
         assert_eq!(moves.len(), 16);
     }
 
+    #[test]
+    fn move_order_score_test() {
+        for pos in AtaxxBoard::bench_positions() {
+            for mov in pos.pseudolegal_moves() {
+                let expected = i32::from(mov.typ() == Cloning);
+                assert_eq!(pos.move_order_score(mov), expected, "{pos} {mov}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_check_test() {
+        for pos in AtaxxBoard::bench_positions() {
+            assert!(!pos.is_in_check());
+            for mov in pos.legal_moves_slow() {
+                assert!(!pos.make_move(mov).unwrap().is_in_check());
+            }
+        }
+    }
+
     #[test]
     fn empty_pos_test() {
         let pos = AtaxxBoard::empty();
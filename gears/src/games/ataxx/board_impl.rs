@@ -11,7 +11,7 @@ use crate::general::board::{
     ply_counter_from_fullmove_nr, read_common_fen_part, Strictness, UnverifiedBoard,
 };
 use crate::general::common::{Res, Tokens};
-use crate::general::move_list::MoveList;
+use crate::general::move_list::MoveSink;
 use crate::general::moves::Move;
 use crate::general::squares::sup_distance;
 use anyhow::{anyhow, bail};
@@ -67,7 +67,7 @@ impl AtaxxBoard {
         self.color_bb(!self.active_player)
     }
 
-    pub(super) fn gen_legal<T: MoveList<Self>>(&self, moves: &mut T) {
+    pub(super) fn gen_legal<T: MoveSink<Self>>(&self, moves: &mut T) {
         let pieces = self.active_bb();
         let empty = self.empty_bb();
         let neighbors = pieces.moore_neighbors() & empty;
@@ -80,7 +80,8 @@ impl AtaxxBoard {
                 moves.add_move(AtaxxMove::leaping(source, target));
             }
         }
-        if moves.num_moves() == 0 && pieces.has_set_bit() {
+        // no moves were added iff no empty square can be reached by a clone or leap
+        if (pieces.extended_moore_neighbors(2) & empty).is_zero() && pieces.has_set_bit() {
             let other_bb = self.color_bb(self.active_player.other());
             // if the other player doesn't have any legal moves, the game is over.
             // return an empty move list in that case so that the user can pick up on this
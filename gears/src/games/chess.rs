@@ -9,6 +9,8 @@ use std::fmt::{Display, Formatter};
 use std::num::NonZeroUsize;
 use std::ops::Not;
 use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
@@ -17,8 +19,10 @@ use crate::games::chess::castling::{CastleRight, CastlingFlags};
 use crate::games::chess::moves::ChessMove;
 use crate::games::chess::pieces::ChessPieceType::*;
 use crate::games::chess::pieces::{
-    ChessPiece, ChessPieceType, ColoredChessPieceType, NUM_CHESS_PIECES, NUM_COLORS,
+    ChessPiece, ChessPieceType, ColoredChessPieceType, CHESS_PIECE_PHASE, MAX_CHESS_PHASE,
+    NUM_CHESS_PIECES, NUM_COLORS,
 };
+use crate::games::chess::see::SeeScore;
 use crate::games::chess::squares::{ChessSquare, ChessboardSize};
 use crate::games::chess::zobrist::PRECOMPUTED_ZOBRIST_KEYS;
 use crate::games::chess::ChessColor::{Black, White};
@@ -39,17 +43,23 @@ use crate::general::board::{
 use crate::general::common::{
     parse_int_from_str, EntityList, GenericSelect, Res, StaticallyNamedEntity, Tokens,
 };
-use crate::general::move_list::{EagerNonAllocMoveList, MoveList};
+use crate::general::move_list::{
+    legal_move_counter, EagerNonAllocMoveList, LegalMoveVisitor, MoveSink,
+};
+use crate::general::moves::Move;
 use crate::general::squares::{RectangularCoordinates, SquareColor};
 use crate::output::text_output::{
     board_to_string, display_board_pretty, display_color, AdaptFormatter, BoardFormatter,
     DefaultBoardFormatter, PieceToChar,
 };
 use crate::output::OutputOpts;
+use crate::NormalGameOverReason;
 use crate::PlayerResult;
 use crate::PlayerResult::{Draw, Lose};
 
 pub mod castling;
+pub mod epd;
+pub mod kpk;
 mod movegen;
 pub mod moves;
 mod perft_tests;
@@ -63,6 +73,31 @@ const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0
 #[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
 pub struct ChessSettings {}
 
+/// Moves get printed without access to the position, so the castling notation can't be stored in a board.
+/// Use [`ChessSettings::chess960_notation`] to access it. Note that this is global to the process.
+static CHESS960_NOTATION: AtomicBool = AtomicBool::new(false);
+
+impl ChessSettings {
+    /// If set, castling moves are printed as king-takes-rook (e.g. `e1h1`) and castling rights use the files of the
+    /// rooks (e.g. `HAha`), which works for chess960. Otherwise (the default), castling moves are printed as the king
+    /// moving two squares (e.g. `e1g1`) and castling rights use `KQkq` where possible, as expected by most GUIs.
+    /// Parsing always accepts both notations. Engines set this through the `UCI_Chess960` option.
+    ///
+    /// This is a process-wide setting, so it changes how *every* chessboard and chess move in this process gets printed.
+    /// Tests that change it must therefore run in their own process, like `motors/tests/chess960_option.rs`,
+    /// or they would race with all tests that print castling moves or FENs.
+    pub fn set_chess960_notation(chess960: bool) {
+        CHESS960_NOTATION.store(chess960, Relaxed);
+    }
+
+    /// See [`Self::set_chess960_notation`].
+    pub fn chess960_notation() -> bool {
+        CHESS960_NOTATION.load(Relaxed)
+    }
+}
+
+/// No legal position has more than 218 legal moves, see the `R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1`
+/// perft test. Pseudolegal movegen can produce a few more (e.g. king moves into check), so there's some leeway.
 pub const MAX_CHESS_MOVES_IN_POS: usize = 256;
 
 // for some reason, Chessboard::MoveList can be ambiguous? This should fix that
@@ -89,7 +124,6 @@ impl Not for ChessColor {
 }
 
 impl Color for ChessColor {
-    #[must_use]
     fn other(self) -> Self {
         match self {
             White => Black,
@@ -123,6 +157,8 @@ pub struct Chessboard {
     castling: CastlingFlags,
     ep_square: Option<ChessSquare>, // eventually, see if using Optional and Noned instead of Option improves nps
     hash: ZobristHash,
+    /// Updated incrementally, can be larger than [`MAX_CHESS_PHASE`] because of promotions.
+    phase: u8,
 }
 
 impl Default for Chessboard {
@@ -174,6 +210,7 @@ impl Board for Chessboard {
             castling: CastlingFlags::default(),
             ep_square: None,
             hash: ZobristHash(0),
+            phase: 0,
         })
     }
 
@@ -247,6 +284,33 @@ impl Board for Chessboard {
                     Self::from_fen("rk6/p1r3p1/P3B1Kp/1p2B3/8/8/8/8 w - - 0 1", Strict).unwrap()
                 },
             },
+            GenericSelect {
+                name: "saavedra",
+                val: || Self::from_fen("8/8/1KP5/3r4/8/8/8/k7 w - - 0 1", Strict).unwrap(),
+            },
+            GenericSelect {
+                name: "reti",
+                val: || Self::from_fen("7K/8/k1P5/7p/8/8/8/8 w - - 0 1", Strict).unwrap(),
+            },
+            GenericSelect {
+                name: "lasker_reichhelm",
+                val: || Self::from_fen("8/k7/3p4/p2P1p2/P2P1P2/8/8/K7 w - - 0 1", Strict).unwrap(),
+            },
+            // a fortress: The bishop doesn't control the promotion square, so this is a draw
+            GenericSelect {
+                name: "wrong_bishop",
+                val: || Self::from_fen("7k/8/6KP/8/8/8/8/5B2 b - - 0 1", Strict).unwrap(),
+            },
+            GenericSelect {
+                name: "bratko_kopec_1",
+                val: || {
+                    Self::from_fen(
+                        "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1",
+                        Strict,
+                    )
+                    .unwrap()
+                },
+            },
             // still very difficult for caps-lite to solve
             GenericSelect {
                 name: "mate_in_16",
@@ -391,14 +455,27 @@ impl Board for Chessboard {
         )
     }
 
-    fn gen_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T) {
+    fn gen_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T) {
         self.gen_pseudolegal_moves(moves, !self.colored_bb(self.active_player), false)
     }
 
-    fn gen_tactical_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T) {
+    fn gen_tactical_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T) {
         self.gen_pseudolegal_moves(moves, self.colored_bb(self.active_player.other()), true)
     }
 
+    /// Counts the legal moves while generating them, without storing them in a move list.
+    fn num_legal_moves(&self) -> usize {
+        let mut counter = legal_move_counter(self);
+        self.gen_pseudolegal(&mut counter);
+        counter.num_legal_moves()
+    }
+
+    /// Checks legality while generating the moves, without storing them in a move list.
+    fn for_each_legal_move<F: FnMut(Self::Move)>(&self, f: F) {
+        let mut visitor = LegalMoveVisitor::new(self, f);
+        self.gen_pseudolegal(&mut visitor);
+    }
+
     fn random_legal_move<T: Rng>(&self, rng: &mut T) -> Option<Self::Move> {
         let moves = self.legal_moves_slow();
         moves.into_iter().choose(rng)
@@ -417,10 +494,19 @@ impl Board for Chessboard {
         self.ply += 1;
         // nullmoves count as noisy. This also prevents detecting repetition to before the nullmove
         self.ply_100_ctr = 0;
-        if let Some(sq) = self.ep_square {
-            self.hash ^= PRECOMPUTED_ZOBRIST_KEYS.ep_file_keys[sq.file() as usize];
-            self.ep_square = None;
+        self.clear_ep_square();
+        self.hash ^= PRECOMPUTED_ZOBRIST_KEYS.side_to_move_key;
+        self.flip_side_to_move()
+    }
+
+    /// Keeps the halfmove clock and the fullmove number, like changing the side to move of a FEN.
+    fn with_side_to_move_flipped(mut self) -> Option<Self> {
+        if self.active_player == White {
+            self.ply += 1;
+        } else {
+            self.ply = self.ply.saturating_sub(1);
         }
+        self.clear_ep_square();
         self.hash ^= PRECOMPUTED_ZOBRIST_KEYS.side_to_move_key;
         self.flip_side_to_move()
     }
@@ -429,6 +515,29 @@ impl Board for Chessboard {
         self.is_move_pseudolegal_impl(mov)
     }
 
+    /// Uses pin rays and the check mask instead of playing the move, see [`Self::is_pseudolegal_move_legal_impl`].
+    fn is_pseudolegal_move_legal(&self, mov: Self::Move) -> bool {
+        self.is_pseudolegal_move_legal_impl(mov)
+    }
+
+    /// Uses the same piece values as [`Self::see_at_least`].
+    fn see(&self, mov: Self::Move, threshold: i32) -> bool {
+        self.see_at_least(mov, SeeScore(threshold))
+    }
+
+    /// Promotions first (ordered by the promoted piece), then captures ordered by MVV-LVA, then quiet moves.
+    fn move_order_score(&self, mov: Self::Move) -> i32 {
+        let mut score = 0;
+        if mov.is_promotion() {
+            score += 1000 + 100 * mov.promo_piece() as i32;
+        }
+        let victim = mov.captured(self);
+        if victim != Empty {
+            score += 100 + 10 * victim as i32 - mov.piece_type() as i32;
+        }
+        score
+    }
+
     fn player_result_no_movegen<H: BoardHistory<Chessboard>>(
         &self,
         history: &H,
@@ -447,13 +556,38 @@ impl Board for Chessboard {
             return Some(res);
         }
         let no_moves = self.legal_moves_slow().is_empty();
-        if no_moves {
+        let res = if no_moves {
             Some(self.no_moves_result())
         } else {
             None
+        };
+        debug_assert_eq!(
+            res,
+            self.no_moves_result_direct(),
+            "Movegen and check detection disagree about the result of position '{self}'"
+        );
+        res
+    }
+
+    fn game_over_reason_slow<H: BoardHistory<Self>>(&self, history: &H) -> NormalGameOverReason {
+        // same order as in `player_result_no_movegen`
+        if self.is_50mr_draw() {
+            NormalGameOverReason::FiftyMoveRule
+        } else if self.has_insufficient_material() {
+            NormalGameOverReason::InsufficientMaterial
+        } else if self.is_3fold_repetition(history) {
+            NormalGameOverReason::ThreefoldRepetition
+        } else if self.is_in_check() {
+            NormalGameOverReason::Checkmate
+        } else {
+            NormalGameOverReason::Stalemate
         }
     }
 
+    fn is_in_check(&self) -> bool {
+        Chessboard::is_in_check(self)
+    }
+
     fn no_moves_result(&self) -> PlayerResult {
         self.no_moves_result_if(self.is_in_check())
     }
@@ -495,44 +629,16 @@ impl Board for Chessboard {
         self.hash
     }
 
-    fn as_fen(&self) -> String {
-        let res = position_fen_part(self);
-        let mut castle_rights = String::default();
-        // Always output chess960 castling rights. FEN output isn't necessary for UCI
-        // and almost all tools support chess960 FEN notation.
-        for color in ChessColor::iter() {
-            for side in CastleRight::iter().rev() {
-                if self.castling.can_castle(color, side) {
-                    let mut file = file_to_char(self.castling.rook_start_file(color, side));
-                    if color == White {
-                        file = file.to_ascii_uppercase();
-                    }
-                    castle_rights.push(file);
-                }
-            }
-        }
-        if castle_rights.is_empty() {
-            castle_rights += "-";
-        }
-        let mut ep_square = "-".to_string();
-        if let Some(square) = self.ep_square() {
-            // Internally, the ep square is set whenever a pseudolegal ep move is possible, but the FEN standard requires
-            // the ep square to be set only iff there is a legal ep move possible. So we check for that when outputting
-            // the FEN (printing the FEN should not be performance critical).
-            if self.legal_moves_slow().iter().any(|m| m.is_ep()) {
-                ep_square = square.to_string();
-            }
-        }
+    fn hash_after_move(&self, mov: Self::Move) -> ZobristHash {
+        let hash = self.zobrist_after_move(mov);
+        debug_assert!(self
+            .make_move(mov)
+            .is_none_or(|pos| pos.compute_zobrist() == hash));
+        hash
+    }
 
-        let stm = match self.active_player {
-            White => 'w',
-            Black => 'b',
-        };
-        res + &format!(
-            " {stm} {castle_rights} {ep_square} {halfmove_clock} {move_number}",
-            halfmove_clock = self.ply_100_ctr,
-            move_number = self.fullmove_ctr_1_based()
-        )
+    fn as_fen(&self) -> String {
+        self.as_fen_with_notation(ChessSettings::chess960_notation())
     }
 
     fn read_fen_and_advance_input(words: &mut Tokens, strictness: Strictness) -> Res<Self> {
@@ -543,7 +649,7 @@ impl Board for Chessboard {
             bail!("FEN ends after color to move, missing castling rights")
         };
         let castling_rights =
-            CastlingFlags::default().parse_castling_rights(castling_word, &board.0, strictness)?;
+            CastlingFlags::default().parse_castling_rights(castling_word, &board.0)?;
 
         let Some(ep_square) = words.next() else {
             bail!("FEN ends after castling rights, missing en passant square")
@@ -567,10 +673,12 @@ impl Board for Chessboard {
             }
         };
         board = board.set_ep(ep_square);
-        let halfmove_clock = words.next().unwrap_or("");
+        let halfmove_clock = words.peek().copied().unwrap_or("");
         // Some FENs don't contain the halfmove clock and fullmove number, so assume that's the case if parsing
         // the halfmove clock fails -- but don't do this for the fullmove number.
+        // Only consume the next word if it is a halfmove clock so that e.g. a following `moves` still gets parsed.
         if let Ok(halfmove_clock) = halfmove_clock.parse::<usize>() {
+            _ = words.next();
             board = board.set_halfmove_repetition_clock(halfmove_clock);
             let Some(fullmove_number) = words.next() else {
                 bail!(
@@ -593,7 +701,15 @@ impl Board for Chessboard {
         board.0.active_player = color;
         board.0.castling = castling_rights;
         // also sets the zobrist hash
-        board.verify_with_level(CheckFen, strictness)
+        let board = board.verify_with_level(CheckFen, strictness)?;
+        // `verify_with_level` only checks that there is a pseudolegal ep capture, but the FEN standard requires a legal one.
+        // `as_fen` only outputs the ep square if there is a legal ep capture, so this makes strict FEN round trips exact.
+        if let Some(ep_square) = board.ep_square {
+            if strictness == Strict && !board.legal_moves_slow().iter().any(|m| m.is_ep()) {
+                bail!("The ep square is set to {ep_square} even though there is no legal en passant capture. In strict mode, this is not allowed")
+            }
+        }
+        Ok(board)
     }
 
     fn should_flip_visually() -> bool {
@@ -620,8 +736,9 @@ impl Board for Chessboard {
     ) -> Box<dyn BoardFormatter<Self>> {
         let pos = *self;
         let king_square = self.king_square(self.active_player);
+        let in_check = self.is_in_check();
         let color_frame = Box::new(move |square, col| {
-            if pos.is_in_check() && square == king_square {
+            if in_check && square == king_square {
                 Some(Red)
             } else {
                 col
@@ -676,6 +793,13 @@ impl Chessboard {
         ChessBitboard::new(self.color_bbs[color as usize])
     }
 
+    fn clear_ep_square(&mut self) {
+        if let Some(sq) = self.ep_square {
+            self.hash ^= PRECOMPUTED_ZOBRIST_KEYS.ep_file_keys[sq.file() as usize];
+            self.ep_square = None;
+        }
+    }
+
     pub fn active_player_bb(&self) -> ChessBitboard {
         self.colored_bb(self.active_player)
     }
@@ -701,6 +825,64 @@ impl Chessboard {
         self.colored_bb(color) & self.piece_bb(piece)
     }
 
+    /// Like [`Board::as_fen`], but uses the given castling notation instead of the global setting,
+    /// see [`ChessSettings::set_chess960_notation`].
+    pub fn as_fen_with_notation(&self, chess960: bool) -> String {
+        let res = position_fen_part(self);
+        let mut castle_rights = String::default();
+        for color in ChessColor::iter() {
+            for side in CastleRight::iter().rev() {
+                if !self.castling.can_castle(color, side) {
+                    continue;
+                }
+                let rook_file = self.castling.rook_start_file(color, side);
+                // `K` and `Q` refer to the outermost rook (X-FEN), so use the file if there's another rook further out
+                let mut outer_files = match side {
+                    Queenside => 0..rook_file,
+                    Kingside => rook_file + 1..8,
+                };
+                let rank = if color == White { 0 } else { 7 };
+                let is_outermost = !outer_files.any(|file| {
+                    self.is_piece_on(
+                        ChessSquare::from_rank_file(rank, file),
+                        ColoredChessPieceType::new(color, Rook),
+                    )
+                });
+                let mut c = match side {
+                    Kingside if !chess960 && is_outermost => 'k',
+                    Queenside if !chess960 && is_outermost => 'q',
+                    _ => file_to_char(rook_file),
+                };
+                if color == White {
+                    c = c.to_ascii_uppercase();
+                }
+                castle_rights.push(c);
+            }
+        }
+        if castle_rights.is_empty() {
+            castle_rights += "-";
+        }
+        let mut ep_square = "-".to_string();
+        if let Some(square) = self.ep_square() {
+            // Internally, the ep square is set whenever a pseudolegal ep move is possible, but the FEN standard requires
+            // the ep square to be set only iff there is a legal ep move possible. So we check for that when outputting
+            // the FEN (printing the FEN should not be performance critical).
+            if self.legal_moves_slow().iter().any(|m| m.is_ep()) {
+                ep_square = square.to_string();
+            }
+        }
+
+        let stm = match self.active_player {
+            White => 'w',
+            Black => 'b',
+        };
+        res + &format!(
+            " {stm} {castle_rights} {ep_square} {halfmove_clock} {move_number}",
+            halfmove_clock = self.ply_100_ctr,
+            move_number = self.fullmove_ctr_1_based()
+        )
+    }
+
     fn remove_piece_unchecked(
         &mut self,
         square: ChessSquare,
@@ -714,6 +896,7 @@ impl Chessboard {
         let bb = square.bb().raw();
         self.piece_bbs[piece as usize] ^= bb;
         self.color_bbs[color as usize] ^= bb;
+        self.phase -= CHESS_PIECE_PHASE[piece as usize];
         // It's not really clear how to so handle these flags when removing pieces, so we just unset them on a best effort basis
         if piece == Rook {
             for side in CastleRight::iter() {
@@ -774,6 +957,8 @@ impl Chessboard {
     /// its repetition, where the en passant move wouldn't be possible
     /// TODO: Should there be a `ZobristRepetition3FoldPedanticChess` that actually does movegen?
     /// TODO: Only set the ep square if there are pseudolegal en passants possible
+    /// This is the rule used for game adjudication, like [`Board::is_draw_by_repetition`] with a `ply_from_root` of 0.
+    /// Searches should use [`Board::is_draw_by_repetition`] instead.
     pub fn is_3fold_repetition<H: BoardHistory<Self>>(&self, history: &H) -> bool {
         // There's no need to test if the repetition is a checkmate, because checkmate positions can't repeat
         n_fold_repetition(3, history, self, self.ply_100_ctr)
@@ -821,6 +1006,29 @@ impl Chessboard {
         }
     }
 
+    /// Computes the result of a position without legal moves like [`Self::no_moves_result`], but without using the
+    /// legality checks of the movegen or [`Self::is_in_check`]: There are no legal moves if every pseudolegal move
+    /// gets rejected by `make_move`, and the current player is in check if the opponent could capture the king if it
+    /// was their turn. Returns `None` if there is a legal move.
+    /// This is much slower and only meant to catch bugs, see [`Board::player_result_slow`].
+    fn no_moves_result_direct(&self) -> Option<PlayerResult> {
+        if self
+            .pseudolegal_moves()
+            .into_iter()
+            .any(|mov| self.make_move(mov).is_some())
+        {
+            return None;
+        }
+        let king = self.king_square(self.active_player);
+        let mut opponent = *self;
+        opponent.active_player = self.active_player.other();
+        let in_check = opponent
+            .pseudolegal_moves()
+            .into_iter()
+            .any(|mov| mov.dest_square() == king);
+        Some(self.no_moves_result_if(in_check))
+    }
+
     pub fn ep_square(&self) -> Option<ChessSquare> {
         self.ep_square
     }
@@ -829,8 +1037,28 @@ impl Chessboard {
         ChessSquare::from_bb_index(self.colored_piece_bb(color, King).trailing_zeros())
     }
 
+    /// The game phase for tapered evaluation, from [`MAX_CHESS_PHASE`] in the starting position to 0 in pawn endgames.
+    /// Each knight and bishop counts 1, each rook 2 and each queen 4. The value is updated incrementally
+    /// and capped at [`MAX_CHESS_PHASE`], which can be exceeded because of promotions.
+    pub fn game_phase(&self) -> u8 {
+        debug_assert_eq!(self.phase, self.compute_phase(), "{self}");
+        self.phase.min(MAX_CHESS_PHASE)
+    }
+
+    fn compute_phase(&self) -> u8 {
+        ChessPieceType::non_king_pieces()
+            .map(|piece| self.piece_bb(piece).num_ones() as u8 * CHESS_PIECE_PHASE[piece as usize])
+            .sum()
+    }
+
+    /// The pieces of the inactive player that attack the king of the active player.
+    /// Contains at most two pieces in positions that can be reached from the startpos.
+    pub fn checkers(&self) -> ChessBitboard {
+        self.all_attacking(self.king_square(self.active_player)) & self.inactive_player_bb()
+    }
+
     pub fn is_in_check(&self) -> bool {
-        self.is_in_check_on_square(self.active_player, self.king_square(self.active_player))
+        self.checkers().has_set_bit()
     }
 
     pub fn gives_check(&self, mov: ChessMove) -> bool {
@@ -1040,9 +1268,7 @@ impl UnverifiedBoard<Chessboard> for UnverifiedChessboard {
         if this.is_in_check_on_square(inactive_player, this.king_square(inactive_player)) {
             bail!("Player {inactive_player} is in check, but it's not their turn to move");
         } else if strictness == Strict {
-            let checkers = this.all_attacking(this.king_square(this.active_player))
-                & this.inactive_player_bb();
-            let num_attacking = checkers.num_ones();
+            let num_attacking = this.checkers().num_ones();
             if num_attacking > 2 {
                 bail!(
                     "{} is in check from {num_attacking} pieces, which is not allowed in strict mode",
@@ -1103,6 +1329,8 @@ impl UnverifiedBoard<Chessboard> for UnverifiedChessboard {
             }
         }
         this.hash = this.compute_zobrist();
+        // some ways of setting up a position modify the bitboards directly, so don't trust the incremental phase
+        this.phase = this.compute_phase();
         Ok(this)
     }
 
@@ -1116,6 +1344,7 @@ impl UnverifiedBoard<Chessboard> for UnverifiedChessboard {
         let bb = square.bb().raw();
         this.piece_bbs[piece.uncolor() as usize] ^= bb;
         this.color_bbs[piece.color().unwrap() as usize] ^= bb;
+        this.phase += CHESS_PIECE_PHASE[piece.uncolor() as usize];
         this.into()
     }
 
@@ -1165,16 +1394,20 @@ pub enum SliderMove {
 
 #[cfg(test)]
 mod tests {
-    use rand::rng;
+    use rand::rngs::StdRng;
+    use rand::{rng, SeedableRng};
     use std::collections::HashSet;
 
-    use crate::games::chess::squares::{E_FILE_NO, F_FILE_NO, G_FILE_NO};
+    use crate::games::chess::squares::{E_FILE_NO, F_FILE_NO, G_FILE_NO, H_FILE_NO};
     use crate::games::{Coordinates, NoHistory, RectangularCoordinates, ZobristHistory};
     use crate::general::board::RectangularBoard;
     use crate::general::board::Strictness::Relaxed;
+    use crate::general::common::{tokens, GearsError};
     use crate::general::moves::Move;
     use crate::general::perft::perft;
     use crate::search::Depth;
+    use crate::{GameOverReason, GameResult, MatchResult};
+    use std::cmp::Reverse;
 
     use super::*;
 
@@ -1232,7 +1465,11 @@ mod tests {
             board.colored_piece_on(square),
             ChessPiece::new(ColoredChessPieceType::Empty, square)
         );
-        assert_eq!(board.as_fen(), START_FEN);
+        assert_eq!(board.as_fen_with_notation(true), START_FEN);
+        assert_eq!(
+            board.as_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
         let moves = board.pseudolegal_moves();
         assert_eq!(moves.len(), 20);
         let legal_moves = board.legal_moves_slow();
@@ -1284,7 +1521,7 @@ mod tests {
                 Strict,
             )
             .unwrap()
-            .as_fen(),
+            .as_fen_with_notation(true),
             "rnbqkbnr/1ppppppp/p7/8/8/8/PPPPPPP1/RNBQKBN1 w Ah - 0 1",
             "rnbqkbnr/1ppppppp/p7/8/3pP3/8/PPPP1PP1/RNBQKBN1 b Ah e3 3 1",
             // chess960 fens (from webperft):
@@ -1294,7 +1531,7 @@ mod tests {
         ];
         for fen in fens {
             let board = Chessboard::from_fen(fen, Relaxed).unwrap();
-            assert_eq!(fen, board.as_fen());
+            assert_eq!(fen, board.as_fen_with_notation(true));
             assert_eq!(
                 board,
                 Chessboard::from_fen(&board.as_fen(), Relaxed).unwrap()
@@ -1302,6 +1539,217 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fen_without_counters_test() {
+        let fen = "8/8/8/3K4/8/8/5k2/8 b - -";
+        assert!(Chessboard::from_fen(fen, Strict).is_err());
+        let board = Chessboard::from_fen(fen, Relaxed).unwrap();
+        assert_eq!(board.halfmove_repetition_clock(), 0);
+        assert_eq!(board.fullmove_ctr_1_based(), 1);
+        assert_eq!(board.active_player(), Black);
+        assert_eq!(board.as_fen(), format!("{fen} 0 1"));
+        assert_eq!(
+            Chessboard::from_fen(&board.as_fen(), Strict).unwrap(),
+            board
+        );
+
+        // the word after a FEN without counters isn't swallowed
+        let mut words = tokens("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - moves e2e4");
+        let board = Chessboard::read_fen_and_advance_input(&mut words, Relaxed).unwrap();
+        assert_eq!(board, Chessboard::default());
+        assert_eq!(words.next(), Some("moves"));
+        let mut words = tokens("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - moves e2e4");
+        assert!(Chessboard::read_fen_and_advance_input(&mut words, Strict).is_err());
+    }
+
+    #[test]
+    fn make_move_str_test() {
+        let mut pos = Chessboard::default();
+        for mov in ["e4", "e7e5", "Nf3", "b8c6"] {
+            pos = pos.make_move_str(mov, Relaxed).unwrap();
+        }
+        assert_eq!(
+            pos.as_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+        );
+        assert!(pos.make_move_str("f1c4", Strict).is_ok());
+        // strict parsing only accepts the UGI notation
+        assert!(pos.make_move_str("Bc4", Strict).is_err());
+        let err = pos.make_move_str("e4e5", Relaxed).unwrap_err().to_string();
+        assert!(err.contains("e4e5"), "{err}");
+        let pos = Chessboard::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1", Strict).unwrap();
+        let err = pos.make_move_str("e1d2", Strict).unwrap_err().to_string();
+        assert!(err.contains("not legal"), "{err}");
+        assert!(pos.make_move_str("e1e2", Strict).is_ok());
+    }
+
+    #[test]
+    fn checkers_test() {
+        let pos = Chessboard::default();
+        assert!(pos.checkers().is_zero());
+        assert!(!pos.is_in_check());
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        assert!(pos.checkers().is_zero());
+        // double check by a rook and a bishop
+        let pos = Chessboard::from_fen("4k3/8/8/7B/8/8/4R3/4K3 b - - 0 1", Strict).unwrap();
+        assert_eq!(pos.checkers().num_ones(), 2);
+        assert!(pos.is_in_check());
+        assert_eq!(
+            pos.checkers(),
+            pos.colored_piece_bb(White, Rook) | pos.colored_piece_bb(White, Bishop)
+        );
+        assert!(pos
+            .legal_moves_slow()
+            .iter()
+            .all(|m| m.piece_type() == King));
+        let pos = pos
+            .make_move(ChessMove::from_text("e8d8", &pos).unwrap())
+            .unwrap();
+        let pos = pos
+            .make_move(ChessMove::from_text("e2d2", &pos).unwrap())
+            .unwrap();
+        assert_eq!(pos.checkers().num_ones(), 1);
+        assert!(pos
+            .checkers()
+            .is_bit_set_at(ChessSquare::from_str("d2").unwrap().bb_idx()));
+    }
+
+    #[test]
+    fn is_in_check_pin_test() {
+        // the knight is pinned, but the king isn't in check
+        let pos = Chessboard::from_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1", Strict).unwrap();
+        assert!(!Board::is_in_check(&pos));
+        assert!(pos
+            .legal_moves_slow()
+            .iter()
+            .all(|m| m.piece_type() == King));
+        // removing the pinned piece results in a check
+        let pos = Chessboard::from_fen("4k3/4r3/8/8/8/8/8/4K3 w - - 0 1", Strict).unwrap();
+        assert!(Board::is_in_check(&pos));
+        // a pinned piece still gives check
+        let pos = Chessboard::from_fen("k1b4R/8/8/8/8/7K/8/8 w - - 0 1", Strict).unwrap();
+        assert!(Board::is_in_check(&pos));
+        let pos = pos.make_move_str("h3h4", Strict).unwrap();
+        assert!(!Board::is_in_check(&pos));
+    }
+
+    #[test]
+    fn no_moves_classification_test() {
+        let tests = [
+            // fool's mate
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                Some(Lose),
+            ),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Some(Draw)),
+            // A stalemate where the only move that doesn't walk the king into check is an en passant capture,
+            // which is illegal because it would remove both pawns from the king's rank and expose it to the rook.
+            ("8/8/8/8/kpP4R/1N6/8/2BB2K1 b - c3 0 1", Some(Draw)),
+            // capturing en passant resolves the check
+            ("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", None),
+        ];
+        for (fen, expected) in tests {
+            // Relaxed because the ep square of the stalemate FEN doesn't allow a legal ep capture
+            let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+            assert_eq!(
+                pos.player_result_slow(&NoHistory::default()),
+                expected,
+                "{fen}"
+            );
+            assert_eq!(pos.no_moves_result_direct(), expected, "{fen}");
+        }
+        for pos in Chessboard::bench_positions() {
+            let res = pos.player_result_slow(&NoHistory::default());
+            if let Some(Draw) = pos.player_result_no_movegen(&NoHistory::default()) {
+                continue;
+            }
+            assert_eq!(res, pos.no_moves_result_direct(), "{pos}");
+        }
+    }
+
+    #[test]
+    fn flip_side_to_move_test() {
+        let ep_pos = Chessboard::from_fen(
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+            Strict,
+        )
+        .unwrap();
+        assert!(ep_pos.ep_square.is_some());
+        for pos in Chessboard::bench_positions().into_iter().chain([ep_pos]) {
+            let Some(flipped) = pos.with_side_to_move_flipped() else {
+                assert!(pos.is_in_check());
+                continue;
+            };
+            assert_eq!(flipped.active_player(), pos.active_player().other());
+            assert!(flipped.ep_square.is_none());
+            assert_eq!(flipped.fullmove_ctr_1_based(), pos.fullmove_ctr_1_based());
+            assert_eq!(
+                flipped.halfmove_repetition_clock(),
+                pos.halfmove_repetition_clock()
+            );
+            let _ = flipped.debug_verify_invariants(Strict).unwrap();
+            assert_eq!(
+                Chessboard::from_fen(&flipped.as_fen(), Strict).unwrap(),
+                flipped
+            );
+            let mut expected = pos;
+            expected.clear_ep_square();
+            if flipped.is_in_check() {
+                assert!(flipped.with_side_to_move_flipped().is_none());
+            } else {
+                assert_eq!(flipped.with_side_to_move_flipped().unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn gears_error_test() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1";
+        let err = Chessboard::from_fen(fen, Relaxed).unwrap_err();
+        let GearsError::Fen {
+            fen: ref err_fen, ..
+        } = err
+        else {
+            panic!("{err:?}")
+        };
+        assert_eq!(err_fen, fen);
+        assert!(err.to_string().contains(fen));
+        let fen = "8/8/8/3K4/8/8/5k2/8 b - - 0 1 moves";
+        assert!(matches!(
+            Chessboard::from_fen(fen, Relaxed),
+            Err(GearsError::Fen { .. })
+        ));
+        // still works with `?` in functions returning `Res`
+        let res: Res<Chessboard> = (|| Ok(Chessboard::from_fen(fen, Relaxed)?))();
+        assert!(res.unwrap_err().downcast_ref::<GearsError>().is_some());
+
+        let board = Chessboard::default();
+        let err = ChessMove::from_text("e2e5", &board).unwrap_err();
+        let GearsError::Move { mov, pos, .. } = err else {
+            panic!("{err:?}")
+        };
+        assert_eq!(mov, "e2e5");
+        assert_eq!(pos, board.as_fen());
+    }
+
+    #[test]
+    fn max_moves_test() {
+        let fen = "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1";
+        let pos = Chessboard::from_fen(fen, Strict).unwrap();
+        let pseudolegal = pos.pseudolegal_moves();
+        assert!(pseudolegal.len() <= MAX_CHESS_MOVES_IN_POS);
+        assert!(pseudolegal.len() >= 218);
+        assert_eq!(pos.legal_moves_slow().len(), 218);
+        assert_eq!(pos.num_legal_moves(), 218);
+    }
+
+    #[test]
+    #[should_panic]
+    fn move_list_overflow_test() {
+        let mut moves = EagerNonAllocMoveList::<Chessboard, 4>::new();
+        Chessboard::default().gen_pseudolegal(&mut moves);
+    }
+
     #[test]
     fn invalid_castle_right_test() {
         let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w AQk - 0 1";
@@ -1402,6 +1850,64 @@ mod tests {
         assert_eq!(board.tactical_pseudolegal().len(), 8);
     }
 
+    #[test]
+    fn nullmove_test() {
+        let pos = Chessboard::from_fen("4k3/8/8/2pP4/8/8/8/4K3 w - c6 0 2", Strict).unwrap();
+        assert!(pos.ep_square.is_some());
+        let new_pos = pos.make_nullmove().unwrap();
+        assert_eq!(new_pos.active_player, Black);
+        assert_eq!(new_pos.ep_square, None);
+        assert_eq!(new_pos.ply, pos.ply + 1);
+        assert_eq!(new_pos.zobrist_hash(), new_pos.compute_zobrist());
+        assert!(new_pos.debug_verify_invariants(Relaxed).is_ok());
+        let back = new_pos.make_nullmove().unwrap();
+        assert_eq!(back.active_player, White);
+        assert_ne!(back.zobrist_hash(), pos.zobrist_hash());
+        assert_eq!(back.zobrist_hash(), back.compute_zobrist());
+        let in_check = Chessboard::from_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1", Strict).unwrap();
+        assert!(in_check.make_nullmove().is_none());
+    }
+
+    #[test]
+    fn draw_by_repetition_test() {
+        let moves = [
+            "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8",
+        ];
+        let mut pos = Chessboard::default();
+        let mut hist = ZobristHistory::default();
+        for (i, mov) in moves.iter().enumerate() {
+            // the startpos has occurred twice after 4 plies and three times after 8 plies
+            assert!(!pos.is_draw_by_repetition(&hist, 0), "{i}");
+            assert!(pos.match_result_slow(&hist).is_none());
+            hist.push(&pos);
+            pos = pos.make_move_str(mov, Strict).unwrap();
+            // a search starting from the startpos sees a twofold repetition after 4 plies
+            assert_eq!(pos.is_draw_by_repetition(&hist, i + 1), i + 1 >= 4, "{i}");
+        }
+        assert!(pos.is_draw_by_repetition(&hist, 0));
+        let res = pos.match_result_slow(&hist).unwrap();
+        assert_eq!(res.result, GameResult::Draw);
+        assert_eq!(
+            res.reason,
+            GameOverReason::Normal(NormalGameOverReason::ThreefoldRepetition)
+        );
+        // a twofold repetition where the first occurrence is before the root doesn't count
+        let mut hist = ZobristHistory::default();
+        let mut pos = Chessboard::default();
+        for mov in &moves[..4] {
+            hist.push(&pos);
+            pos = pos.make_move_str(mov, Strict).unwrap();
+        }
+        assert!(pos.is_draw_by_repetition(&hist, 4));
+        assert!(!pos.is_draw_by_repetition(&hist, 3));
+        assert!(!pos.is_draw_by_repetition(&hist, 0));
+        // irreversible moves reset the repetition clock
+        hist.push(&pos);
+        let pos = pos.make_move_str("e2e4", Strict).unwrap();
+        assert_eq!(pos.halfmove_repetition_clock(), 0);
+        assert!(!pos.is_draw_by_repetition(&hist, 5));
+    }
+
     #[test]
     fn repetition_test() {
         let mut board = Chessboard::default();
@@ -1549,6 +2055,137 @@ mod tests {
         assert!(startpos_found);
     }
 
+    #[test]
+    fn ep_fen_test() {
+        // black can capture en passant
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+        let pos = Chessboard::from_fen(fen, Strict).unwrap();
+        assert_eq!(pos.ep_square, Some(ChessSquare::from_str("e3").unwrap()));
+        assert_eq!(pos.as_fen_with_notation(false), fen);
+        assert_eq!(Chessboard::from_fen(fen, Relaxed).unwrap(), pos);
+        // no black pawn next to the pawn that just moved
+        let fen = "rnbqkbnr/pp2pppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 2";
+        assert!(Chessboard::from_fen(fen, Strict).is_err());
+        let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+        assert!(pos.ep_square.is_none());
+        // the only pawn that could capture en passant is pinned
+        let fen = "8/8/8/8/kpP4R/1N6/8/2BB2K1 b - c3 0 1";
+        assert!(Chessboard::from_fen(fen, Strict).is_err());
+        let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+        assert!(pos.ep_square.is_some());
+        assert!(!pos.legal_moves_slow().iter().any(|m| m.is_ep()));
+        assert_eq!(
+            pos.as_fen_with_notation(false),
+            "8/8/8/8/kpP4R/1N6/8/2BB2K1 b - - 0 1"
+        );
+    }
+
+    #[test]
+    fn strict_fen_roundtrip_test() {
+        for pos in Chessboard::bench_positions() {
+            for mov in pos.legal_moves_slow() {
+                let new_pos = pos.make_move(mov).unwrap();
+                let fen = new_pos.as_fen_with_notation(true);
+                let Ok(parsed) = Chessboard::from_fen(&fen, Strict) else {
+                    // e.g. because a side is in check from too many pieces
+                    continue;
+                };
+                let fen = parsed.as_fen_with_notation(true);
+                let reparsed = Chessboard::from_fen(&fen, Strict).unwrap();
+                assert_eq!(parsed, reparsed, "{fen}");
+                assert_eq!(reparsed.as_fen_with_notation(true), fen);
+            }
+        }
+    }
+
+    #[test]
+    fn chess960_fen_roundtrip_test() {
+        for i in 0..960 {
+            let board = Chessboard::chess_960_startpos(i).unwrap();
+            let fen = board.as_fen();
+            assert_eq!(Chessboard::from_fen(&fen, Strict).unwrap(), board, "{fen}");
+            assert_eq!(Chessboard::from_fen(&fen, Relaxed).unwrap(), board, "{fen}");
+            // X-FEN castling rights refer to the outermost rook
+            let mut words = fen.split_whitespace().collect_vec();
+            words[2] = "KQkq";
+            let xfen = words.join(" ");
+            assert_eq!(
+                Chessboard::from_fen(&xfen, Strict).unwrap(),
+                board,
+                "{xfen}"
+            );
+            for mov in board.legal_moves_slow() {
+                let new_pos = board.make_move(mov).unwrap();
+                let fen = new_pos.as_fen();
+                assert_eq!(
+                    Chessboard::from_fen(&fen, Strict).unwrap(),
+                    new_pos,
+                    "{fen}"
+                );
+            }
+        }
+        // an inner rook needs Shredder-FEN notation
+        for (rights, file) in [("K", H_FILE_NO), ("H", H_FILE_NO), ("F", F_FILE_NO)] {
+            let fen = format!("4k3/8/8/8/8/8/8/4KR1R w {rights} - 0 1");
+            let pos = Chessboard::from_fen(&fen, Strict).unwrap();
+            assert_eq!(pos.castling.rook_start_file(White, Kingside), file);
+            assert_eq!(Chessboard::from_fen(&pos.as_fen(), Strict).unwrap(), pos);
+        }
+    }
+
+    #[test]
+    fn move_order_score_test() {
+        for pos in Chessboard::bench_positions() {
+            let moves = pos
+                .pseudolegal_moves()
+                .into_iter()
+                .sorted_by_key(|&mov| Reverse(pos.move_order_score(mov)))
+                .collect_vec();
+            let is_noisy = |mov: &ChessMove| mov.is_capture(&pos) || mov.is_promotion();
+            let first_quiet = moves
+                .iter()
+                .position(|mov| !is_noisy(mov))
+                .unwrap_or(moves.len());
+            assert!(moves[..first_quiet].iter().all(is_noisy));
+            assert!(!moves[first_quiet..].iter().any(is_noisy));
+            assert!(moves[first_quiet..]
+                .iter()
+                .all(|&mov| pos.move_order_score(mov) == 0));
+        }
+        let pos = Chessboard::from_fen("4k3/1P6/8/3q1r2/4P3/2N5/8/4K3 w - - 0 1", Strict).unwrap();
+        let scores = ["b7b8q", "b7b8n", "e4d5", "c3d5", "e4f5", "e1e2"]
+            .map(|mov| pos.move_order_score(ChessMove::from_compact_text(mov, &pos).unwrap()));
+        assert!(scores.is_sorted_by(|a, b| a > b), "{scores:?}");
+    }
+
+    #[test]
+    fn fen_castling_notation_test() {
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        assert!(pos.as_fen_with_notation(false).contains(" w KQkq "));
+        assert!(pos.as_fen_with_notation(true).contains(" w HAha "));
+        // (fen, normal castling rights, chess960 castling rights)
+        let tests = [
+            ("4k3/8/8/8/8/8/8/4KR1R w F - 0 1", "F", "F"),
+            ("4k3/8/8/8/8/8/8/4KR1R w H - 0 1", "K", "H"),
+            ("rk4rr/8/8/8/8/8/8/RK4RR w GAga - 0 1", "GQgq", "GAga"),
+            ("1r2k1r1/8/8/8/8/8/8/1R2K1R1 b GBgb - 0 1", "KQkq", "GBgb"),
+        ];
+        for (fen, normal, chess960) in tests {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            for (chess960, rights) in [(false, normal), (true, chess960)] {
+                let fen = pos.as_fen_with_notation(chess960);
+                assert_eq!(fen.split_whitespace().nth(2), Some(rights), "{fen}");
+                assert_eq!(Chessboard::from_fen(&fen, Strict).unwrap(), pos, "{fen}");
+            }
+        }
+        for i in (0..960).step_by(7) {
+            let pos = Chessboard::chess_960_startpos(i).unwrap();
+            let fen = pos.as_fen_with_notation(false);
+            assert!(fen.contains(" w KQkq "), "{fen}");
+            assert_eq!(Chessboard::from_fen(&fen, Strict).unwrap(), pos, "{fen}");
+        }
+    }
+
     #[test]
     fn castling_attack_test() {
         let fen = "8/8/8/8/8/8/3k4/RK6 b A - 0 1";
@@ -1612,4 +2249,180 @@ mod tests {
             assert!(!board.can_reasonably_win(board.active_player), "{fen}");
         }
     }
+
+    #[test]
+    fn insufficient_material_result_test() {
+        let draw = Some(MatchResult {
+            result: GameResult::Draw,
+            reason: GameOverReason::Normal(NormalGameOverReason::InsufficientMaterial),
+        });
+        for fen in [
+            "8/4k3/8/8/8/8/8/2K5 w - - 0 1",
+            "8/4k3/8/8/8/8/5N2/2K5 b - - 0 1",
+            "8/4k3/8/8/8/8/5b2/2K5 w - - 0 1",
+            "8/8/3B4/7k/8/8/1K6/6b1 w - - 0 1",
+        ] {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            assert_eq!(pos.match_result_slow(&NoHistory::default()), draw, "{fen}");
+        }
+        for fen in [
+            // KNN vs K isn't a forced win, but it's not a draw by the rules either
+            "8/3k4/8/8/8/8/1NN5/1K6 w - - 0 1",
+            // opposite colored bishops
+            "8/8/4B3/7k/8/8/1K6/6b1 w - - 0 1",
+            // a pawn is always enough
+            "8/4k3/8/8/8/8/4P3/2K5 w - - 0 1",
+            "8/4k3/8/8/8/8/4p3/1BK5 w - - 0 1",
+        ] {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            assert_eq!(pos.match_result_slow(&NoHistory::default()), None, "{fen}");
+        }
+        // capturing the last rook leaves only a bishop
+        let pos = Chessboard::from_fen("8/4k3/8/8/8/8/5r2/2K1B3 w - - 0 1", Strict).unwrap();
+        assert!(pos.match_result_slow(&NoHistory::default()).is_none());
+        let pos = pos.make_move_str("e1f2", Strict).unwrap();
+        assert_eq!(pos.match_result_slow(&NoHistory::default()), draw);
+    }
+
+    #[test]
+    fn pseudolegal_move_legal_test() {
+        let mut positions = Chessboard::bench_positions();
+        // the ep capture is pseudolegal but not legal, which strict mode doesn't allow
+        positions.push(Chessboard::from_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 2", Relaxed).unwrap());
+        for pos in positions {
+            let children = pos
+                .legal_moves_slow()
+                .into_iter()
+                .map(|mov| pos.make_move(mov).unwrap());
+            for board in children.chain(std::iter::once(pos)) {
+                for mov in board.pseudolegal_moves() {
+                    assert_eq!(
+                        board.is_pseudolegal_move_legal(mov),
+                        board.make_move(mov).is_some(),
+                        "{board} {mov}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn named_positions_test() {
+        let expected = [
+            (
+                "kiwipete",
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            ),
+            ("lucena", "1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1"),
+            ("philidor", "3k4/R7/7r/2KP4/8/8/8/8 w - - 0 1"),
+            ("mate_in_1", "8/7r/8/K1k5/8/8/4p3/8 b - - 10 11"),
+            ("draw_in_1", "2B2k2/8/8/5B2/8/8/8/KR6 w - - 99 123"),
+            (
+                "unusual",
+                "2kb1b2/pR2P1P1/P1N1P3/1p2Pp2/P5P1/1N6/4P2B/2qR2K1 w - f6 99 123",
+            ),
+            (
+                "see_win_pawn",
+                "k6q/3n1n2/3b4/2P1p3/3P1P2/3N1NP1/8/1K6 w - - 0 1",
+            ),
+            ("see_xray", "5q1k/8/8/8/RRQ2nrr/8/8/K7 w - - 0 1"),
+            ("zugzwang", "6Q1/8/8/7k/8/8/3p1pp1/3Kbrrb w - - 26 14"),
+            ("puzzle", "rk6/p1r3p1/P3B1Kp/1p2B3/8/8/8/8 w - - 0 1"),
+            ("saavedra", "8/8/1KP5/3r4/8/8/8/k7 w - - 0 1"),
+            ("reti", "7K/8/k1P5/7p/8/8/8/8 w - - 0 1"),
+            (
+                "lasker_reichhelm",
+                "8/k7/3p4/p2P1p2/P2P1P2/8/8/K7 w - - 0 1",
+            ),
+            ("wrong_bishop", "7k/8/6KP/8/8/8/8/5B2 b - - 0 1"),
+            (
+                "bratko_kopec_1",
+                "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1",
+            ),
+            (
+                "mate_in_16",
+                "1r1q1r2/5pk1/p2p1Np1/2pBp2p/1p2P2P/2PP2P1/1P1Q4/2K2R1b w - - 0 29",
+            ),
+        ];
+        let positions = Chessboard::named_positions();
+        assert_eq!(positions.len(), expected.len());
+        for ((name, fen), (expected_name, expected_fen)) in positions.into_iter().zip(expected) {
+            assert_eq!(name, expected_name);
+            assert_eq!(fen, expected_fen, "{name}");
+            let pos = Chessboard::from_name(name).unwrap();
+            assert!(pos.debug_verify_invariants(Strict).is_ok(), "{name}");
+            assert_eq!(
+                pos,
+                Chessboard::from_fen(expected_fen, Strict).unwrap(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn attacks_by_test() {
+        let squares = |names: &str| {
+            names
+                .split_whitespace()
+                .map(|name| ChessSquare::from_str(name).unwrap().bb())
+                .fold(ChessBitboard::default(), |a, b| a | b)
+        };
+        let pos = Chessboard::default();
+        assert_eq!(pos.attacks_by(White), ChessBitboard::from_u64(0x00ff_ff7e));
+        assert_eq!(
+            pos.attacks_by(Black),
+            ChessBitboard::from_u64(0x7eff_ff00_0000_0000)
+        );
+        let pos = Chessboard::from_fen("4k3/8/8/8/3N4/8/p7/R3K3 w - - 0 1", Strict).unwrap();
+        assert_eq!(
+            pos.attacks_by(White),
+            squares("b3 b5 c2 c6 e2 e6 f3 f5 a2 b1 c1 d1 e1 f1 d2 f2")
+        );
+        assert_eq!(pos.attacks_by(Black), squares("b1 d8 f8 d7 e7 f7"));
+        // sliders stop at the first blocker of either color, which counts as attacked
+        let pos = Chessboard::from_fen("3k4/8/8/1b6/8/3B4/8/1K1Q4 w - - 0 1", Strict).unwrap();
+        assert_eq!(
+            pos.attacks_by(White),
+            squares("a1 a2 b2 c2 c1 b1 d2 d3 b3 a4 e2 f3 g4 h5 e1 f1 g1 h1 c4 b5 e4 f5 g6 h7")
+        );
+        assert_eq!(
+            pos.attacks_by(Black),
+            squares("c8 e8 c7 d7 e7 a6 a4 c6 d7 e8 c4 d3")
+        );
+    }
+
+    #[test]
+    fn game_phase_test() {
+        assert_eq!(Chessboard::default().game_phase(), MAX_CHESS_PHASE);
+        let pos = Chessboard::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", Strict).unwrap();
+        assert_eq!(pos.game_phase(), 0);
+        let pos = Chessboard::from_fen("4kn2/1P6/8/8/8/8/8/4KR2 w - - 0 1", Strict).unwrap();
+        assert_eq!(pos.game_phase(), 3);
+        let promo = ChessMove::from_compact_text("b7b8q", &pos).unwrap();
+        assert_eq!(pos.make_move(promo).unwrap().game_phase(), 7);
+        let capture = ChessMove::from_compact_text("f1f8", &pos).unwrap();
+        assert_eq!(pos.make_move(capture).unwrap().game_phase(), 2);
+        // the phase is capped after promotions
+        let pos = Chessboard::from_fen(
+            "q2k2q1/2nqn2b/1n1P1n1b/2rnr2Q/1NQ1QN1Q/3Q3B/2RQR2B/Q2K2Q1 w - - 0 1",
+            Strict,
+        )
+        .unwrap();
+        assert!(pos.phase > MAX_CHESS_PHASE);
+        assert_eq!(pos.game_phase(), MAX_CHESS_PHASE);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for pos in Chessboard::bench_positions() {
+            let mut pos = pos;
+            for _ in 0..40 {
+                let Some(mov) = pos.random_legal_move(&mut rng) else {
+                    break;
+                };
+                pos = pos.make_move(mov).unwrap();
+                assert_eq!(pos.phase, pos.compute_phase(), "{pos}");
+                let recomputed = Chessboard::from_fen(&pos.as_fen(), Relaxed).unwrap();
+                assert_eq!(pos.game_phase(), recomputed.game_phase(), "{pos}");
+            }
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use anyhow::{anyhow, bail};
+use anyhow::bail;
 use arbitrary::Arbitrary;
 use itertools::Itertools;
 use strum_macros::EnumIter;
@@ -13,8 +13,6 @@ use crate::games::chess::ChessColor::*;
 use crate::games::chess::{ChessColor, Chessboard};
 use crate::games::{char_to_file, Board, ColoredPieceType, DimT};
 use crate::general::bitboards::RawBitboard;
-use crate::general::board::Strictness;
-use crate::general::board::Strictness::Strict;
 use crate::general::common::Res;
 use crate::general::squares::RectangularCoordinates;
 
@@ -99,12 +97,7 @@ impl CastlingFlags {
         self.0 &= !(0x3f << (color as usize * 6));
     }
 
-    pub fn parse_castling_rights(
-        mut self,
-        rights: &str,
-        board: &Chessboard,
-        strictness: Strictness,
-    ) -> Res<Self> {
+    pub fn parse_castling_rights(mut self, rights: &str, board: &Chessboard) -> Res<Self> {
         self.0 = 0;
         if rights == "-" {
             return Ok(self);
@@ -144,48 +137,27 @@ impl CastlingFlags {
                     Kingside
                 }
             };
-            // Unless in strict mode, support normal chess style castling fens for chess960 and hope it's unambiguous
+            // Normal chess style castling rights use X-FEN semantics for chess960, i.e. they refer to the outermost rook
             // (`verify_position_legal` will return an error if there is no such rook).
             let mut find_rook = |side: CastleRight| {
-                let (start, end, strict_file) = match side {
-                    Queenside => (A_FILE_NO, king_file, A_FILE_NO),
-                    Kingside => (king_file, H_FILE_NO, H_FILE_NO),
-                };
-                if strictness == Strict
-                    && !board.is_piece_on(
-                        ChessSquare::from_rank_file(rank, strict_file),
-                        ColoredChessPieceType::new(color, Rook),
-                    )
-                {
-                    bail!("In strict mode, normal chess ('q' and 'k') castle rights can only be used for rooks on the a and h file")
+                let mut files = match side {
+                    Queenside => (A_FILE_NO..king_file).collect_vec(),
+                    Kingside => (king_file + 1..=H_FILE_NO).rev().collect_vec(),
                 }
-                for file in start..end {
-                    if board.is_piece_on(
+                .into_iter();
+                let Some(file) = files.find(|&file| {
+                    board.is_piece_on(
                         ChessSquare::from_rank_file(rank, file),
                         ColoredChessPieceType::new(color, Rook),
-                    ) {
-                        self.set_castle_right(color, side, file)?;
-                        return Ok(());
-                    }
-                }
-                Err(anyhow!(
-                    "There is no {side} rook to castle with for the {color} player"
-                ))
+                    )
+                }) else {
+                    bail!("There is no {side} rook to castle with for the {color} player")
+                };
+                self.set_castle_right(color, side, file)
             };
             match c.to_ascii_lowercase() {
                 'q' => find_rook(Queenside)?,
-                'k' => {
-                    // the `if` isn't just a small performance improvement, it's also necessary if there is a rook on the
-                    // g file, for example (for chess960, this is ambiguous, but just picking one is fine).
-                    if board.is_piece_on(
-                        ChessSquare::from_rank_file(rank, H_FILE_NO),
-                        ColoredChessPieceType::new(color, Rook),
-                    ) {
-                        self.set_castle_right(color, Kingside, H_FILE_NO)?;
-                    } else {
-                        find_rook(Kingside)?;
-                    }
-                }
+                'k' => find_rook(Kingside)?,
                 x @ 'a'..='h' => {
                     let file = char_to_file(x);
                     self.set_castle_right(color, side(file), file)?;
@@ -0,0 +1,201 @@
+//! Parsing of [EPD](https://www.chessprogramming.org/Extended_Position_Description) strings,
+//! as used by test suites like WAC or ECM.
+//! An EPD consists of a (possibly abbreviated) FEN followed by operations of the form `<opcode> <operands>;`.
+//! The perft-style `;D<n> <nodes>` strings used by many perft suites are also supported.
+
+use crate::games::chess::moves::ChessMove;
+use crate::games::chess::Chessboard;
+use crate::general::board::Board;
+use crate::general::board::Strictness::Relaxed;
+use crate::general::common::{tokens, Res};
+use crate::general::moves::Move;
+use anyhow::bail;
+use colored::Colorize;
+use std::collections::HashMap;
+
+/// The opcodes defined by the EPD standard. `c0` to `c9`, `v0` to `v9` and `D<n>` are handled separately.
+const EPD_OPCODES: [&str; 25] = [
+    "acd",
+    "acn",
+    "acs",
+    "am",
+    "bm",
+    "ce",
+    "dm",
+    "draw_accept",
+    "draw_claim",
+    "draw_offer",
+    "draw_reject",
+    "eco",
+    "fmvn",
+    "hmvc",
+    "id",
+    "nic",
+    "noop",
+    "pm",
+    "pv",
+    "rc",
+    "resign",
+    "sm",
+    "tcgs",
+    "tcri",
+    "tcsi",
+];
+
+fn is_known_opcode(opcode: &str) -> bool {
+    let numbered = |prefix: char| {
+        opcode.len() == 2
+            && opcode.starts_with(prefix)
+            && opcode.chars().nth(1).is_some_and(|c| c.is_ascii_digit())
+    };
+    let perft_depth = opcode
+        .strip_prefix('D')
+        .is_some_and(|depth| depth.parse::<usize>().is_ok());
+    EPD_OPCODES.contains(&opcode) || numbered('c') || numbered('v') || perft_depth
+}
+
+/// Splits the operations into `(opcode, operands)` pairs. Semicolons inside quoted strings don't end an operation,
+/// and a single quoted string operand is returned without the quotes.
+fn split_operations(ops: &str) -> Res<Vec<(String, String)>> {
+    let mut res = vec![];
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut finish = |op: &str| -> Res<()> {
+        let op = op.trim();
+        if op.is_empty() {
+            return Ok(());
+        }
+        let (opcode, operands) = op.split_once(char::is_whitespace).unwrap_or((op, ""));
+        if !is_known_opcode(opcode) {
+            bail!("Unknown EPD opcode '{}'", opcode.red());
+        }
+        let operands = operands.trim();
+        let operands = operands
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .filter(|s| !s.contains('"'))
+            .unwrap_or(operands);
+        res.push((opcode.to_string(), operands.to_string()));
+        Ok(())
+    };
+    for c in ops.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            ';' if !in_string => finish(&std::mem::take(&mut current))?,
+            _ => current.push(c),
+        }
+    }
+    if in_string {
+        bail!("Unterminated string in EPD operations '{ops}'");
+    }
+    finish(&current)?;
+    Ok(res)
+}
+
+impl Chessboard {
+    /// Parses an EPD string into the position and a map from opcodes to operands.
+    /// The FEN part may omit the halfmove clock and fullmove counter.
+    /// The moves of `bm` and `am` are checked to be legal SAN moves in the position; use [`Self::epd_moves`]
+    /// to convert them to [`ChessMove`]s.
+    pub fn from_epd(s: &str) -> Res<(Self, HashMap<String, String>)> {
+        let mut words = tokens(s);
+        let pos = Self::read_fen_and_advance_input(&mut words, Relaxed)?;
+        // Use the raw input instead of the tokens so that whitespace inside quoted operands is preserved.
+        let ops_str = words
+            .peek()
+            .map_or("", |op| &s[op.as_ptr() as usize - s.as_ptr() as usize..]);
+        let mut ops = HashMap::new();
+        for (opcode, operands) in split_operations(ops_str)? {
+            if matches!(opcode.as_str(), "bm" | "am") {
+                _ = pos.epd_moves(&operands)?;
+            }
+            if ops.insert(opcode.clone(), operands).is_some() {
+                bail!("EPD opcode '{}' appears more than once", opcode.red());
+            }
+        }
+        Ok((pos, ops))
+    }
+
+    /// Parses a whitespace-separated list of SAN moves, such as the operands of the `bm` and `am` opcodes.
+    /// Other notations, like the compact notation used by UCI, are rejected because EPD requires SAN.
+    /// Check and mate indicators are optional.
+    pub fn epd_moves(&self, moves: &str) -> Res<Vec<ChessMove>> {
+        moves
+            .split_whitespace()
+            .map(|mov| {
+                let parsed = ChessMove::from_extended_text(mov, self)?;
+                if !self.is_move_legal(parsed) {
+                    bail!("Illegal move '{}' in EPD", mov.red());
+                }
+                let san = parsed.san_formatter(self).to_string();
+                let san = san.trim_end_matches(['+', '#']);
+                if mov.trim_end_matches(['+', '#']) != san {
+                    bail!(
+                        "The EPD move '{0}' isn't in SAN, it should be '{1}'",
+                        mov.red(),
+                        san.bold()
+                    );
+                }
+                Ok(parsed)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_test() {
+        let epd =
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#;
+        let (pos, ops) = Chessboard::from_epd(epd).unwrap();
+        assert_eq!(
+            pos.as_fen(),
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"
+        );
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops["id"], "WAC.001");
+        let bm = pos.epd_moves(&ops["bm"]).unwrap();
+        assert_eq!(bm, vec![ChessMove::from_text("g3g6", &pos).unwrap()]);
+
+        let epd = r#"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3 7 am f3 g4; bm e4 d4; c0 "a; b"; acd 12;"#;
+        let (pos, ops) = Chessboard::from_epd(epd).unwrap();
+        assert_eq!(pos.halfmove_repetition_clock(), 3);
+        assert_eq!(pos.fullmove_ctr_1_based(), 7);
+        assert_eq!(ops["c0"], "a; b");
+        assert_eq!(ops["acd"], "12");
+        assert_eq!(pos.epd_moves(&ops["am"]).unwrap().len(), 2);
+        assert_eq!(pos.epd_moves(&ops["bm"]).unwrap().len(), 2);
+        // whitespace inside strings is preserved
+        let (_, ops) = Chessboard::from_epd(r#"8/8/8/8/8/8/8/K6k w - - c0 "a  b	c";"#).unwrap();
+        assert_eq!(ops["c0"], "a  b\tc");
+
+        let (_, ops) = Chessboard::from_epd(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400",
+        )
+        .unwrap();
+        assert_eq!(ops["D1"], "20");
+        assert_eq!(ops["D2"], "400");
+
+        let (_, ops) = Chessboard::from_epd("8/8/8/8/8/8/8/K6k w - -").unwrap();
+        assert!(ops.is_empty());
+
+        let err = Chessboard::from_epd("8/8/8/8/8/8/8/K6k w - - xyz 1;").unwrap_err();
+        assert!(err.to_string().contains("xyz"));
+        assert!(Chessboard::from_epd("8/8/8/8/8/8/8/K6k w - - bm Kb3;").is_err());
+        assert!(Chessboard::from_epd("8/8/8/8/8/8/8/K6k w - - bm e4;").is_err());
+        // EPD requires SAN, so the UCI notation and long algebraic notation are rejected
+        let fen = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -";
+        assert!(Chessboard::from_epd(&format!("{fen} bm g3g6;")).is_err());
+        assert!(Chessboard::from_epd(&format!("{fen} bm Qg3g6;")).is_err());
+        let (pos, ops) = Chessboard::from_epd(&format!("{fen} bm Qg6 Rf4;")).unwrap();
+        assert_eq!(pos.epd_moves(&ops["bm"]).unwrap().len(), 2);
+        assert!(Chessboard::from_epd(r#"8/8/8/8/8/8/8/K6k w - - id "abc;"#).is_err());
+        assert!(Chessboard::from_epd("8/8/8/8/8/8/8/K6k w - - id a; id b;").is_err());
+    }
+}
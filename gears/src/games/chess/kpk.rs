@@ -0,0 +1,289 @@
+//! A bitbase for the king and pawn versus king endgame.
+//!
+//! The bitbase is generated by retrograde analysis the first time it's needed and only stores whether the side with the
+//! pawn wins, so it doesn't know the distance to mate. Positions are always seen from the perspective of white having
+//! the pawn, and the pawn is mirrored onto files A to D, so there are `2 * 64 * 64 * 24` entries.
+
+use crate::games::chess::pieces::ChessPieceType::Pawn;
+use crate::games::chess::squares::ChessSquare;
+use crate::games::chess::ChessColor::{Black, White};
+use crate::games::chess::{ChessColor, Chessboard};
+use crate::general::bitboards::chess::{ChessBitboard, KINGS};
+use crate::general::bitboards::{Bitboard, RawBitboard};
+use crate::general::board::Board;
+use crate::PlayerResult;
+use crate::PlayerResult::{Draw, Lose, Win};
+use std::sync::OnceLock;
+
+const NUM_PAWN_SQUARES: usize = 4 * 6;
+
+const NUM_KPK_ENTRIES: usize = 2 * 64 * 64 * NUM_PAWN_SQUARES;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum KpkState {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+/// One bit per position, set if white wins
+struct KpkBitbase(Vec<u64>);
+
+static KPK_BITBASE: OnceLock<KpkBitbase> = OnceLock::new();
+
+/// `pawn` must be on files A to D
+fn index(stm: ChessColor, white_king: usize, black_king: usize, pawn: usize) -> usize {
+    let pawn_idx = (pawn / 8 - 1) * 4 + pawn % 8;
+    ((stm as usize * 64 + black_king) * 64 + white_king) * NUM_PAWN_SQUARES + pawn_idx
+}
+
+fn decode(idx: usize) -> (ChessColor, usize, usize, usize) {
+    let pawn_idx = idx % NUM_PAWN_SQUARES;
+    let pawn = (pawn_idx / 4 + 1) * 8 + pawn_idx % 4;
+    let idx = idx / NUM_PAWN_SQUARES;
+    let stm = if idx / (64 * 64) == 0 { White } else { Black };
+    (stm, idx % 64, idx / 64 % 64, pawn)
+}
+
+fn king_attacks(square: usize) -> u64 {
+    KINGS[square].to_u64()
+}
+
+fn pawn_attacks(pawn: usize) -> u64 {
+    ChessBitboard::single_piece(pawn)
+        .pawn_attacks(White)
+        .to_u64()
+}
+
+fn initial_state(stm: ChessColor, wk: usize, bk: usize, pawn: usize) -> KpkState {
+    let bit = |square: usize| 1_u64 << square;
+    if wk == bk || wk == pawn || bk == pawn || king_attacks(wk) & bit(bk) != 0 {
+        return KpkState::Invalid;
+    }
+    if stm == White {
+        if pawn_attacks(pawn) & bit(bk) != 0 {
+            return KpkState::Invalid;
+        }
+        // The pawn can promote without getting captured
+        let promo = pawn + 8;
+        if pawn / 8 == 6
+            && wk != promo
+            && bk != promo
+            && (king_attacks(bk) & bit(promo) == 0 || king_attacks(wk) & bit(promo) != 0)
+        {
+            return KpkState::Win;
+        }
+    } else {
+        let black_moves = king_attacks(bk) & !(king_attacks(wk) | pawn_attacks(pawn));
+        // Stalemate, or the pawn can be captured. Checkmate isn't possible with just a king and a pawn.
+        if black_moves == 0 || black_moves & !king_attacks(wk) & bit(pawn) != 0 {
+            return KpkState::Draw;
+        }
+    }
+    KpkState::Unknown
+}
+
+/// Classifies an `Unknown` entry based on the states of all positions that can be reached with one move.
+fn classify(states: &[KpkState], idx: usize) -> KpkState {
+    let (stm, wk, bk, pawn) = decode(idx);
+    // at most 8 king moves and 2 pawn moves
+    let mut successors = [0; 10];
+    let mut num_successors = 0;
+    let mut add = |successor| {
+        successors[num_successors] = successor;
+        num_successors += 1;
+    };
+    if stm == White {
+        for square in ChessBitboard::from_u64(king_attacks(wk)).ones() {
+            add(index(Black, square.bb_idx(), bk, pawn));
+        }
+        // Positions where a king blocks the pawn are invalid, so they will be ignored.
+        // Promotions are only considered in the initial classification.
+        if pawn / 8 < 6 {
+            add(index(Black, wk, bk, pawn + 8));
+        }
+        if pawn / 8 == 1 && pawn + 8 != wk && pawn + 8 != bk {
+            add(index(Black, wk, bk, pawn + 16));
+        }
+    } else {
+        for square in ChessBitboard::from_u64(king_attacks(bk)).ones() {
+            add(index(White, wk, square.bb_idx(), pawn));
+        }
+    }
+    let (good, bad) = if stm == White {
+        (KpkState::Win, KpkState::Draw)
+    } else {
+        (KpkState::Draw, KpkState::Win)
+    };
+    let mut all_bad = true;
+    for &successor in &successors[..num_successors] {
+        match states[successor] {
+            KpkState::Invalid => {}
+            state if state == good => return good,
+            state => all_bad &= state == bad,
+        }
+    }
+    if all_bad {
+        bad
+    } else {
+        KpkState::Unknown
+    }
+}
+
+impl KpkBitbase {
+    fn generate() -> Self {
+        let mut states = (0..NUM_KPK_ENTRIES)
+            .map(|idx| {
+                let (stm, wk, bk, pawn) = decode(idx);
+                initial_state(stm, wk, bk, pawn)
+            })
+            .collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in 0..NUM_KPK_ENTRIES {
+                if states[idx] == KpkState::Unknown {
+                    let state = classify(&states, idx);
+                    if state != KpkState::Unknown {
+                        states[idx] = state;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        let mut bits = vec![0; NUM_KPK_ENTRIES.div_ceil(64)];
+        for (idx, state) in states.into_iter().enumerate() {
+            if state == KpkState::Win {
+                bits[idx / 64] |= 1 << (idx % 64);
+            }
+        }
+        Self(bits)
+    }
+
+    fn white_wins(&self, idx: usize) -> bool {
+        self.0[idx / 64] & (1 << (idx % 64)) != 0
+    }
+}
+
+/// Generates the bitbase if that hasn't happened yet. Calling this isn't necessary, but it can be used to avoid
+/// spending time on the generation once it's actually needed.
+pub fn init_kpk_bitbase() {
+    _ = KPK_BITBASE.get_or_init(KpkBitbase::generate);
+}
+
+/// The result of the KPK position where white has a king and a pawn and black only has a king, from the perspective
+/// of the side to move `stm`. The position must be legal.
+pub fn kpk_probe(
+    white_king: ChessSquare,
+    pawn: ChessSquare,
+    black_king: ChessSquare,
+    stm: ChessColor,
+) -> PlayerResult {
+    let (mut wk, mut bk, mut pawn) = (white_king.bb_idx(), black_king.bb_idx(), pawn.bb_idx());
+    debug_assert!((8..56).contains(&pawn));
+    if pawn % 8 >= 4 {
+        wk ^= 7;
+        bk ^= 7;
+        pawn ^= 7;
+    }
+    let bitbase = KPK_BITBASE.get_or_init(KpkBitbase::generate);
+    let idx = index(stm, wk, bk, pawn);
+    if !bitbase.white_wins(idx) {
+        Draw
+    } else if stm == White {
+        Win
+    } else {
+        Lose
+    }
+}
+
+/// Looks up positions that contain nothing but the two kings and a single pawn of either color in the KPK bitbase,
+/// see [`kpk_probe`]. Returns `None` for all other positions.
+pub fn kpk_result(pos: &Chessboard) -> Option<PlayerResult> {
+    if pos.occupied_bb().num_ones() != 3 || pos.piece_bb(Pawn).num_ones() != 1 {
+        return None;
+    }
+    let pawn = pos.piece_bb(Pawn).to_square()?;
+    let strong = if pos.colored_bb(White).is_bit_set_at(pawn.bb_idx()) {
+        White
+    } else {
+        Black
+    };
+    let (mut wk, mut pawn, mut bk) = (pos.king_square(strong), pawn, pos.king_square(!strong));
+    let mut stm = pos.active_player();
+    if strong == Black {
+        wk = wk.flip();
+        bk = bk.flip();
+        pawn = pawn.flip();
+        stm = !stm;
+    }
+    Some(kpk_probe(wk, pawn, bk, stm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::general::board::Strictness::Strict;
+    use std::time::Instant;
+
+    fn result(fen: &str) -> PlayerResult {
+        kpk_result(&Chessboard::from_fen(fen, Strict).unwrap()).unwrap()
+    }
+
+    /// Timing depends on the load of the machine, so this is only meant to be run on its own, e.g. with
+    /// `cargo test --release kpk_generation_time_test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn kpk_generation_time_test() {
+        let start = Instant::now();
+        let bitbase = KpkBitbase::generate();
+        let elapsed = start.elapsed();
+        assert!(!bitbase.0.is_empty());
+        assert!(elapsed.as_millis() < 50, "{elapsed:?}");
+    }
+
+    #[test]
+    fn kpk_test() {
+        init_kpk_bitbase();
+
+        // a king on the sixth rank in front of its pawn always wins
+        assert_eq!(result("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Win);
+        assert_eq!(result("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Lose);
+        // otherwise, it can depend on the opposition
+        assert_eq!(result("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1"), Draw);
+        assert_eq!(result("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1"), Lose);
+        // a defending king in front of a pawn on the sixth rank holds the draw
+        assert_eq!(result("4k3/8/4P3/4K3/8/8/8/8 w - - 0 1"), Draw);
+        assert_eq!(result("4k3/8/4P3/4K3/8/8/8/8 b - - 0 1"), Draw);
+        // the rook pawn can't be won if the defending king reaches the corner
+        assert_eq!(result("k7/8/1K6/P7/8/8/8/8 w - - 0 1"), Draw);
+        assert_eq!(result("7k/8/8/6KP/8/8/8/8 w - - 0 1"), Draw);
+        // the pawn runs away, unless the king can enter its square
+        assert_eq!(result("k7/8/8/8/8/8/6P1/K7 w - - 0 1"), Win);
+        assert_eq!(result("k7/8/8/8/8/8/6P1/K7 b - - 0 1"), Draw);
+        assert_eq!(result("8/8/8/8/8/k7/6P1/1K6 w - - 0 1"), Win);
+        assert_eq!(result("8/8/8/8/8/k7/6P1/1K6 b - - 0 1"), Draw);
+        // but a king that escorts its pawn wins even then
+        assert_eq!(result("k7/8/8/8/8/8/6P1/6K1 b - - 0 1"), Lose);
+        // an undefended pawn gets captured
+        assert_eq!(result("8/8/8/8/8/8/5kP1/1K6 b - - 0 1"), Draw);
+        // the rule of the square
+        assert_eq!(result("7K/8/k7/2P5/8/8/8/8 w - - 0 1"), Draw);
+        assert_eq!(result("8/8/8/2P4k/8/8/8/K7 w - - 0 1"), Win);
+        assert_eq!(result("8/8/8/2P4k/8/8/8/K7 b - - 0 1"), Lose);
+        // a Réti-style draw: the king catches the pawn by walking along the diagonal
+        assert_eq!(result("8/8/8/2P2k2/8/8/8/K7 w - - 0 1"), Draw);
+        // stalemate
+        assert_eq!(result("k7/P7/1K6/8/8/8/8/8 b - - 0 1"), Draw);
+        // the same positions with colors swapped
+        assert_eq!(result("8/8/8/4p3/4k3/8/4K3/8 b - - 0 1"), Draw);
+        assert_eq!(result("8/8/8/4p3/4k3/8/4K3/8 w - - 0 1"), Lose);
+        assert_eq!(result("1k6/6p1/8/8/8/8/8/K7 b - - 0 1"), Win);
+        assert!(kpk_result(&Chessboard::default()).is_none());
+        assert!(
+            kpk_result(&Chessboard::from_fen("8/8/8/8/8/k7/8/K6R w - - 0 1", Strict).unwrap())
+                .is_none()
+        );
+    }
+}
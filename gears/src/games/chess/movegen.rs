@@ -9,9 +9,10 @@ use crate::games::chess::ChessColor::*;
 use crate::games::chess::{ChessColor, Chessboard, SliderMove};
 use crate::games::{Board, Color, ColoredPieceType};
 use crate::general::bitboards::chess::{ChessBitboard, KINGS, KNIGHTS, PAWN_CAPTURES};
+use crate::general::bitboards::RayDirections;
 use crate::general::bitboards::RayDirections::{AntiDiagonal, Diagonal, Horizontal, Vertical};
 use crate::general::bitboards::{Bitboard, RawBitboard, RawStandardBitboard};
-use crate::general::move_list::MoveList;
+use crate::general::move_list::MoveSink;
 use crate::general::moves::Move;
 use crate::general::squares::RectangularCoordinates;
 
@@ -91,13 +92,91 @@ impl Chessboard {
         }
     }
 
+    /// Checks if a pseudolegal move is legal without playing it, using pin rays and the check mask.
+    /// This is faster than `make_move(mov).is_some()`, which is only used for the tricky cases of castling
+    /// (the king can't move through check) and en passant (removing two pawns from a rank can give a discovered check).
+    pub fn is_pseudolegal_move_legal_impl(&self, mov: ChessMove) -> bool {
+        debug_assert!(self.is_move_pseudolegal(mov));
+        if mov.is_castle() || mov.is_ep() {
+            return self.make_move(mov).is_some();
+        }
+        let us = self.active_player;
+        let king_square = self.king_square(us);
+        let from = mov.src_square();
+        let to = mov.dest_square();
+        if mov.piece_type() == King {
+            // the king must not be able to block a slider attack on its target square
+            let blockers = (self.occupied_bb() ^ from.bb()) & !to.bb();
+            return !self.is_attacked_with_blockers(to, us.other(), blockers);
+        }
+        let checkers = self.checkers();
+        if checkers.more_than_one_bit_set() {
+            // in a double check, only king moves are legal
+            return false;
+        } else if checkers.has_set_bit() {
+            let checker = ChessSquare::from_bb_index(checkers.trailing_zeros());
+            let check_mask = Self::ray_between(king_square, checker) | checkers;
+            if !check_mask.is_bit_set_at(to.bb_idx()) {
+                return false;
+            }
+        }
+        if self.pinned(us).is_bit_set_at(from.bb_idx()) {
+            // a pinned piece can only move along the ray through the king, including capturing the pinner
+            let Some(dir) = Self::ray_direction(king_square, from) else {
+                return false;
+            };
+            return ChessBitboard::slider_attacks(king_square, ChessBitboard::default(), dir)
+                .is_bit_set_at(to.bb_idx());
+        }
+        true
+    }
+
+    /// Returns the pieces of color `us` that can't leave the ray from their king to an enemy slider without
+    /// exposing the king to a check.
+    pub fn pinned(&self, us: ChessColor) -> ChessBitboard {
+        let king_square = self.king_square(us);
+        let them = self.colored_bb(us.other());
+        let rook_sliders = (self.piece_bb(Rook) | self.piece_bb(Queen)) & them;
+        let bishop_sliders = (self.piece_bb(Bishop) | self.piece_bb(Queen)) & them;
+        // the sliders that would attack the king if there were none of our pieces on the board
+        let snipers = (ChessBitboard::rook_attacks(king_square, them) & rook_sliders)
+            | (ChessBitboard::bishop_attacks(king_square, them) & bishop_sliders);
+        let mut pinned = ChessBitboard::default();
+        for sniper in snipers.ones() {
+            let blockers = Self::ray_between(king_square, sniper) & self.occupied_bb();
+            if blockers.is_single_piece() {
+                pinned |= blockers & self.colored_bb(us);
+            }
+        }
+        pinned
+    }
+
+    /// Like [`Self::is_in_check_on_square`], but uses the given blockers instead of the occupied squares.
+    /// Pieces on squares that aren't blockers don't attack.
+    fn is_attacked_with_blockers(
+        &self,
+        square: ChessSquare,
+        attacker: ChessColor,
+        blockers: ChessBitboard,
+    ) -> bool {
+        let them = self.colored_bb(attacker) & blockers;
+        let rook_sliders = self.piece_bb(Rook) | self.piece_bb(Queen);
+        let bishop_sliders = self.piece_bb(Bishop) | self.piece_bb(Queen);
+        let attackers = (ChessBitboard::rook_attacks(square, blockers) & rook_sliders)
+            | (ChessBitboard::bishop_attacks(square, blockers) & bishop_sliders)
+            | (Self::knight_attacks_from(square) & self.piece_bb(Knight))
+            | (Self::normal_king_attacks_from(square) & self.piece_bb(King))
+            | (Self::single_pawn_captures(attacker.other(), square) & self.piece_bb(Pawn));
+        (attackers & them).has_set_bit()
+    }
+
     /// Used for castling and to implement `is_in_check`:
     /// Pretend there is a king of color `us` at `square` and test if it is in check.
     pub fn is_in_check_on_square(&self, us: ChessColor, square: ChessSquare) -> bool {
         (self.all_attacking(square) & self.colored_bb(us.other())).has_set_bit()
     }
 
-    pub(super) fn gen_pseudolegal_moves<T: MoveList<Self>>(
+    pub(super) fn gen_pseudolegal_moves<T: MoveSink<Self>>(
         &self,
         moves: &mut T,
         filter: ChessBitboard,
@@ -110,7 +189,7 @@ impl Chessboard {
         self.gen_pawn_moves(moves, only_tactical);
     }
 
-    fn gen_pawn_moves<T: MoveList<Self>>(&self, moves: &mut T, only_tactical: bool) {
+    fn gen_pawn_moves<T: MoveSink<Self>>(&self, moves: &mut T, only_tactical: bool) {
         let color = self.active_player;
         let pawns = self.colored_piece_bb(color, Pawn);
         let occupied = self.occupied_bb();
@@ -242,7 +321,7 @@ impl Chessboard {
         false
     }
 
-    fn gen_king_moves<T: MoveList<Self>>(
+    fn gen_king_moves<T: MoveSink<Self>>(
         &self,
         moves: &mut T,
         filter: ChessBitboard,
@@ -274,7 +353,7 @@ impl Chessboard {
         }
     }
 
-    fn gen_knight_moves<T: MoveList<Self>>(&self, moves: &mut T, filter: ChessBitboard) {
+    fn gen_knight_moves<T: MoveSink<Self>>(&self, moves: &mut T, filter: ChessBitboard) {
         let knights = self.colored_piece_bb(self.active_player, Knight);
         for from in knights.ones() {
             let attacks = Self::knight_attacks_from(from) & filter;
@@ -284,7 +363,7 @@ impl Chessboard {
         }
     }
 
-    fn gen_slider_moves<T: MoveList<Self>>(
+    fn gen_slider_moves<T: MoveSink<Self>>(
         &self,
         slider_move: SliderMove,
         moves: &mut T,
@@ -361,28 +440,57 @@ impl Chessboard {
             | Self::single_pawn_captures(White, square) & self.colored_piece_bb(Black, Pawn)
     }
 
+    /// The threat map of `color`: All squares attacked by at least one piece of that color, including squares
+    /// occupied by their own pieces (i.e., defended pieces). Pawn pushes and castling don't count as attacks.
+    pub fn attacks_by(&self, color: ChessColor) -> ChessBitboard {
+        let mut attacks = self.colored_piece_bb(color, Pawn).pawn_attacks(color);
+        for piece in ChessPieceType::non_pawn_pieces() {
+            for square in self.colored_piece_bb(color, piece).ones() {
+                attacks |= self.attacks_no_castle_or_pawn_push(square, piece, color);
+            }
+        }
+        attacks
+    }
+
     pub fn ray_attacks(
         &self,
         target: ChessSquare,
         ray_square: ChessSquare,
         blockers: ChessBitboard,
     ) -> ChessBitboard {
-        let file_diff = target.file().wrapping_sub(ray_square.file());
-        let rank_diff = target.rank().wrapping_sub(ray_square.rank());
+        let Some(dir) = Self::ray_direction(target, ray_square) else {
+            return ChessBitboard::default();
+        };
+        let sliders = match dir {
+            Vertical | Horizontal => self.piece_bb(Rook) | self.piece_bb(Queen),
+            Diagonal | AntiDiagonal => self.piece_bb(Bishop) | self.piece_bb(Queen),
+        };
+        ChessBitboard::slider_attacks(target, blockers, dir) & sliders
+    }
+
+    /// The direction of the ray going through both squares, or `None` if they don't share a rank, file or (anti)diagonal.
+    pub fn ray_direction(a: ChessSquare, b: ChessSquare) -> Option<RayDirections> {
+        let file_diff = a.file().wrapping_sub(b.file());
+        let rank_diff = a.rank().wrapping_sub(b.rank());
         if file_diff == 0 {
-            ChessBitboard::slider_attacks(target, blockers, Vertical)
-                & (self.piece_bb(Rook) | self.piece_bb(Queen))
+            Some(Vertical)
         } else if rank_diff == 0 {
-            ChessBitboard::slider_attacks(target, blockers, Horizontal)
-                & (self.piece_bb(Rook) | self.piece_bb(Queen))
+            Some(Horizontal)
         } else if file_diff == rank_diff {
-            ChessBitboard::slider_attacks(target, blockers, Diagonal)
-                & (self.piece_bb(Bishop) | self.piece_bb(Queen))
+            Some(Diagonal)
         } else if file_diff == 0_u8.wrapping_sub(rank_diff) {
-            ChessBitboard::slider_attacks(target, blockers, AntiDiagonal)
-                & (self.piece_bb(Bishop) | self.piece_bb(Queen))
+            Some(AntiDiagonal)
         } else {
-            ChessBitboard::default()
+            None
         }
     }
+
+    /// The squares strictly between `a` and `b`, or an empty bitboard if they aren't on a common ray.
+    pub fn ray_between(a: ChessSquare, b: ChessSquare) -> ChessBitboard {
+        let Some(dir) = Self::ray_direction(a, b) else {
+            return ChessBitboard::default();
+        };
+        ChessBitboard::slider_attacks(a, b.bb(), dir)
+            & ChessBitboard::slider_attacks(b, a.bb(), dir)
+    }
 }
@@ -10,15 +10,17 @@ use num::iter;
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, FromRepr};
 
-use crate::games::chess::castling::CastleRight;
 use crate::games::chess::castling::CastleRight::*;
+use crate::games::chess::castling::{CastleRight, CastlingFlags};
 use crate::games::chess::moves::ChessMoveFlags::*;
 use crate::games::chess::pieces::ChessPieceType::*;
-use crate::games::chess::pieces::{ChessPiece, ChessPieceType, ColoredChessPieceType};
+use crate::games::chess::pieces::{
+    ChessPiece, ChessPieceType, ColoredChessPieceType, CHESS_PIECE_PHASE,
+};
 use crate::games::chess::squares::{ChessSquare, C_FILE_NO, D_FILE_NO, F_FILE_NO, G_FILE_NO};
 use crate::games::chess::zobrist::PRECOMPUTED_ZOBRIST_KEYS;
 use crate::games::chess::ChessColor::*;
-use crate::games::chess::{ChessColor, Chessboard};
+use crate::games::chess::{ChessColor, ChessSettings, Chessboard};
 use crate::games::{
     char_to_file, file_to_char, AbstractPieceType, Board, Color, ColoredPiece, ColoredPieceType,
     DimT, ZobristHash,
@@ -28,7 +30,9 @@ use crate::general::bitboards::{Bitboard, RawBitboard};
 use crate::general::common::Res;
 use crate::general::moves::ExtendedFormat::Standard;
 use crate::general::moves::Legality::PseudoLegal;
-use crate::general::moves::{ExtendedFormat, Legality, Move, MoveFlags, UntrustedMove};
+use crate::general::moves::{
+    ExtendedFormat, ExtendedFormatter, Legality, Move, MoveFlags, UntrustedMove,
+};
 use crate::general::squares::RectangularCoordinates;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default, Debug, EnumIter, FromRepr)]
@@ -193,9 +197,186 @@ impl ChessMove {
         }
     }
 
+    /// Formats the move in Standard Algebraic Notation (SAN), e.g. `Nbd7`, `exd8=Q#` or `O-O+`.
+    /// The move must be pseudolegal in the given position.
+    pub fn san_formatter(self, board: &Chessboard) -> ExtendedFormatter<Chessboard> {
+        self.extended_formatter(*board, Standard)
+    }
+
+    /// The SAN of a non-castling move without the check or checkmate suffix, or `None` if the move isn't legal.
+    fn san_without_suffix(self, board: &Chessboard, format: ExtendedFormat) -> Option<String> {
+        let piece = self.piece(board);
+        let mut res = match piece.uncolored() {
+            Pawn => String::default(),
+            uncolored => {
+                if format == Standard {
+                    uncolored.to_ascii_char().to_string()
+                } else {
+                    piece.to_utf8_char().to_string()
+                }
+            }
+        };
+        let mut from_str = if piece.uncolored() == Pawn && self.is_capture(board) {
+            self.src_square()
+                .to_string()
+                .chars()
+                .nth(0)
+                .unwrap()
+                .to_string()
+        } else {
+            String::default()
+        };
+        let moves = board
+            // only legal moves need to be disambiguated, e.g. a pinned piece doesn't make a move ambiguous
+            .pseudolegal_moves()
+            .into_iter()
+            .filter(|mov| {
+                mov.piece(board).symbol == piece.symbol
+                    && mov.dest_square() == self.dest_square()
+                    && mov.promo_piece() == self.promo_piece()
+                    && board.is_pseudolegal_move_legal(*mov)
+            })
+            .collect_vec();
+        if moves.is_empty() {
+            return None;
+        }
+
+        if moves.len() > 1 {
+            from_str = if moves
+                .iter()
+                .filter(|mov| mov.src_square().file() == self.src_square().file())
+                .count()
+                <= 1
+            {
+                self.src_square()
+                    .to_string()
+                    .chars()
+                    .nth(0)
+                    .unwrap()
+                    .to_string()
+            } else if moves
+                .iter()
+                .filter(|mov| mov.src_square().rank() == self.src_square().rank())
+                .count()
+                <= 1
+            {
+                self.src_square()
+                    .to_string()
+                    .chars()
+                    .nth(1)
+                    .unwrap()
+                    .to_string()
+            } else {
+                self.src_square().to_string()
+            }
+        };
+        res += &from_str;
+        if self.is_capture(board) {
+            res.push('x');
+        }
+        res += &self.dest_square().to_string();
+        if self.is_promotion() {
+            res.push('=');
+            if format == Standard {
+                res.push(self.flags().promo_piece().to_ascii_char());
+            } else {
+                res.push(self.flags().promo_piece().to_utf8_char());
+            }
+        }
+        Some(res)
+    }
+
+    /// The compact (UCI) text of this move with the given castling notation instead of the global setting,
+    /// see [`ChessSettings::set_chess960_notation`].
+    /// Castling moves are printed as king-takes-rook (`e1h1`) if `chess960` is set, and as the king moving two squares
+    /// (`e1g1`) otherwise.
+    pub fn compact_text(self, chess960: bool) -> String {
+        if self == Self::NULL {
+            return "0000".to_string();
+        }
+        let flag = match self.flags() {
+            PromoKnight => "n",
+            PromoBishop => "b",
+            PromoRook => "r",
+            PromoQueen => "q",
+            _ => "",
+        };
+        let from = self.src_square();
+        let to = if self.is_castle() && !chess960 {
+            let file = match self.castle_side() {
+                Queenside => C_FILE_NO,
+                Kingside => G_FILE_NO,
+            };
+            ChessSquare::from_rank_file(from.rank(), file)
+        } else {
+            self.dest_square()
+        };
+        format!("{from}{to}{flag}")
+    }
+
     pub fn from_to_square(self) -> usize {
         (self.0 & 0xfff) as usize
     }
+
+    /// Converts this move to the packed 16 bit format used by many opening books and training data formats,
+    /// which doesn't depend on the internal representation:
+    /// Bits 0-5: from square, bits 6-11: to square, bits 12-13: promotion piece (knight, bishop, rook, queen),
+    /// bits 14-15: move type (0: normal, 1: promotion, 2: en passant, 3: castling).
+    /// Castling moves are encoded as king-to-rook, which also works for chess960. The null move is encoded as 0.
+    pub fn to_u16(self) -> u16 {
+        let (promo, kind) = match self.flags() {
+            PromoKnight | PromoBishop | PromoRook | PromoQueen => {
+                (self.promo_piece() as u16 - Knight as u16, 1)
+            }
+            EnPassant => (0, 2),
+            CastleKingside | CastleQueenside => (0, 3),
+            _ => (0, 0),
+        };
+        self.from_to_square() as u16 | (promo << 12) | (kind << 14)
+    }
+
+    /// The inverse of [`Self::to_u16`]. Castling moves may also be encoded as king-to-square, like `e1g1`.
+    /// Fails if the resulting move isn't pseudolegal in `board`.
+    pub fn from_u16(val: u16, board: &Chessboard) -> Res<Self> {
+        if val == 0 {
+            return Ok(Self::NULL);
+        }
+        let from = ChessSquare::from_bb_index((val & 0x3f) as usize);
+        let mut to = ChessSquare::from_bb_index(((val >> 6) & 0x3f) as usize);
+        let color = board.active_player;
+        if !board.colored_bb(color).is_bit_set_at(from.bb_idx()) {
+            bail!("Invalid packed move {val:#06x}: There is no {color} piece on {from}")
+        }
+        let flags = match val >> 14 {
+            0 => ChessMoveFlags::normal_move(board.piece_type_on(from)),
+            1 => ChessMoveFlags::from_repr(PromoKnight as usize + ((val >> 12) & 0x3) as usize)
+                .unwrap(),
+            2 => EnPassant,
+            _ => {
+                if !board
+                    .colored_piece_bb(color, Rook)
+                    .is_bit_set_at(to.bb_idx())
+                {
+                    let to_file = match to.file() {
+                        C_FILE_NO => board.castling.rook_start_file(color, Queenside),
+                        G_FILE_NO => board.castling.rook_start_file(color, Kingside),
+                        _ => bail!("Invalid packed castling move {val:#06x} to {to}"),
+                    };
+                    to = ChessSquare::from_rank_file(to.rank(), to_file);
+                }
+                if to.file() == board.castling.rook_start_file(color, Queenside) {
+                    CastleQueenside
+                } else {
+                    CastleKingside
+                }
+            }
+        };
+        let res = Self::new(from, to, flags);
+        if !board.is_move_pseudolegal(res) {
+            bail!("The packed move {val:#06x} ({res}) is not pseudolegal in position '{board}'")
+        }
+        Ok(res)
+    }
 }
 
 impl Display for ChessMove {
@@ -231,21 +412,10 @@ impl Move<Chessboard> for ChessMove {
     }
 
     fn format_compact(self, f: &mut Formatter<'_>) -> fmt::Result {
-        if self == Self::NULL {
-            return write!(f, "0000");
-        }
-        let flag = match self.flags() {
-            PromoKnight => "n",
-            PromoBishop => "b",
-            PromoRook => "r",
-            PromoQueen => "q",
-            _ => "",
-        };
         write!(
             f,
-            "{from}{to}{flag}",
-            from = self.src_square(),
-            to = self.dest_square()
+            "{}",
+            self.compact_text(ChessSettings::chess960_notation())
         )
     }
 
@@ -313,90 +483,20 @@ impl Move<Chessboard> for ChessMove {
         board: &Chessboard,
         format: ExtendedFormat,
     ) -> fmt::Result {
-        if self.is_castle() {
-            return match self.castle_side() {
-                Queenside => write!(f, "O-O-O"),
-                Kingside => write!(f, "O-O"),
-            };
-        }
-        let piece = self.piece(board);
-        let mut res = match piece.uncolored() {
-            Pawn => String::default(),
-            uncolored => {
-                if format == Standard {
-                    uncolored.to_ascii_char().to_string()
-                } else {
-                    piece.to_utf8_char().to_string()
-                }
+        let mut res = if self.is_castle() {
+            match self.castle_side() {
+                Queenside => "O-O-O".to_string(),
+                Kingside => "O-O".to_string(),
             }
-        };
-        let mut from_str = if piece.uncolored() == Pawn && self.is_capture(board) {
-            self.src_square()
-                .to_string()
-                .chars()
-                .nth(0)
-                .unwrap()
-                .to_string()
         } else {
-            String::default()
+            let Some(res) = self.san_without_suffix(board, format) else {
+                return write!(f, "<Illegal move {}>", self);
+            };
+            res
         };
-        let moves = board
-            // we have to use .pseudolegal instead of legal moves here because that's what the rules demand.
-            .pseudolegal_moves()
-            .into_iter()
-            .filter(|mov| {
-                mov.piece(board).symbol == piece.symbol
-                    && mov.dest_square() == self.dest_square()
-                    && mov.promo_piece() == self.promo_piece()
-            })
-            .collect_vec();
-        if moves.is_empty() {
+        let Some(board) = board.make_move(self) else {
             return write!(f, "<Illegal move {}>", self);
-        }
-
-        if moves.len() > 1 {
-            from_str = if moves
-                .iter()
-                .filter(|mov| mov.src_square().file() == self.src_square().file())
-                .count()
-                <= 1
-            {
-                self.src_square()
-                    .to_string()
-                    .chars()
-                    .nth(0)
-                    .unwrap()
-                    .to_string()
-            } else if moves
-                .iter()
-                .filter(|mov| mov.src_square().rank() == self.src_square().rank())
-                .count()
-                <= 1
-            {
-                self.src_square()
-                    .to_string()
-                    .chars()
-                    .nth(1)
-                    .unwrap()
-                    .to_string()
-            } else {
-                self.src_square().to_string()
-            }
         };
-        res += &from_str;
-        if self.is_capture(board) {
-            res.push('x');
-        }
-        res += &self.dest_square().to_string();
-        if self.is_promotion() {
-            res.push('=');
-            if format == Standard {
-                res.push(self.flags().promo_piece().to_ascii_char());
-            } else {
-                res.push(self.flags().promo_piece().to_utf8_char());
-            }
-        }
-        let board = board.make_move(self).unwrap();
         if board.is_game_lost_slow() {
             res.push('#');
         } else if board.is_in_check() {
@@ -438,6 +538,19 @@ fn parse_short_promo_piece(s: &str) -> Option<(ChessMoveFlags, usize)> {
     None
 }
 
+/// True if `s` starts with `num_os` `O`s separated by `-`, where each `O` can also be written as `o` or `0`.
+fn starts_with_castling(s: &str, num_os: usize) -> bool {
+    s.get(..2 * num_os - 1).is_some_and(|s| {
+        s.chars().enumerate().all(|(i, c)| {
+            if i % 2 == 1 {
+                c == '-'
+            } else {
+                matches!(c, 'O' | 'o' | '0')
+            }
+        })
+    })
+}
+
 impl Chessboard {
     pub fn backrank(color: ChessColor) -> DimT {
         7 * color as DimT
@@ -461,6 +574,32 @@ impl Chessboard {
         self.make_move_impl(mov, prefetch)
     }
 
+    /// The castling rights after the active player has moved `piece` from `from` to `to`, where `to` is the
+    /// destination of the king when castling.
+    pub(super) fn castling_after_move(
+        &self,
+        piece: ChessPieceType,
+        from: ChessSquare,
+        to: ChessSquare,
+    ) -> CastlingFlags {
+        let color = self.active_player;
+        let other = color.other();
+        let mut castling = self.castling;
+        if piece == King {
+            castling.clear_castle_rights(color);
+        } else if from == self.rook_start_square(color, Queenside) {
+            castling.unset_castle_right(color, Queenside);
+        } else if from == self.rook_start_square(color, Kingside) {
+            castling.unset_castle_right(color, Kingside);
+        }
+        if to == self.rook_start_square(other, Queenside) {
+            castling.unset_castle_right(other, Queenside);
+        } else if to == self.rook_start_square(other, Kingside) {
+            castling.unset_castle_right(other, Kingside);
+        }
+        castling
+    }
+
     /// Is only ever called on a copy of the board, so no need to undo the changes when a move gets aborted due to pseudo-legality.
     #[allow(clippy::too_many_lines)]
     pub(super) fn make_move_impl<F: Fn(ZobristHash)>(
@@ -569,18 +708,7 @@ impl Chessboard {
                 new_hash ^= PRECOMPUTED_ZOBRIST_KEYS.ep_file_keys[to.file() as usize];
             }
         }
-        if piece == King {
-            self.castling.clear_castle_rights(color);
-        } else if from == self.rook_start_square(color, Queenside) {
-            self.castling.unset_castle_right(color, Queenside);
-        } else if from == self.rook_start_square(color, Kingside) {
-            self.castling.unset_castle_right(color, Kingside);
-        }
-        if to == self.rook_start_square(other, Queenside) {
-            self.castling.unset_castle_right(other, Queenside);
-        } else if to == self.rook_start_square(other, Kingside) {
-            self.castling.unset_castle_right(other, Kingside);
-        }
+        self.castling = self.castling_after_move(piece, from, to);
         new_hash ^=
             PRECOMPUTED_ZOBRIST_KEYS.castle_keys[self.castling.allowed_castling_directions()];
         self.move_piece(from, to, piece);
@@ -588,11 +716,13 @@ impl Chessboard {
             let bb = to.bb().raw();
             self.piece_bbs[Pawn as usize] ^= bb;
             self.piece_bbs[mov.flags().promo_piece() as usize] ^= bb;
+            self.phase += CHESS_PIECE_PHASE[mov.flags().promo_piece() as usize];
             new_hash ^= PRECOMPUTED_ZOBRIST_KEYS.piece_key(Pawn, color, to);
             new_hash ^= PRECOMPUTED_ZOBRIST_KEYS.piece_key(mov.flags().promo_piece(), color, to);
         }
         self.ply += 1;
         self.hash = new_hash;
+        debug_assert_eq!(self.phase, self.compute_phase());
         self.flip_side_to_move()
     }
 
@@ -714,10 +844,12 @@ impl<'a> MoveParser<'a> {
         false
     }
 
+    /// Accepts `O-O`, `o-o` and `0-0` (and the same spellings with three `O`s for queenside castling).
+    /// The king-to-square and king-to-rook notations like `e1g1` and `e1h1` are handled by [`ChessMove::parse_compact_text`].
     fn parse_castling(&mut self, board: &Chessboard) -> Option<ChessMove> {
         let color = board.active_player;
         let king_square = board.king_square(color);
-        if self.original_input.starts_with("0-0-0") || self.original_input.starts_with("O-O-O") {
+        if starts_with_castling(self.original_input, 3) {
             for _ in 0..5 {
                 self.advance_char();
             }
@@ -730,7 +862,7 @@ impl<'a> MoveParser<'a> {
                 CastleQueenside,
             ));
         }
-        if self.original_input.starts_with("0-0") || self.original_input.starts_with("O-O") {
+        if starts_with_castling(self.original_input, 2) {
             for _ in 0..3 {
                 self.advance_char();
             }
@@ -830,8 +962,8 @@ impl<'a> MoveParser<'a> {
         let file = self.current_char();
         self.advance_char();
         let rank = self.current_char();
-        if file.is_some() && rank.is_some() {
-            if let Ok(square) = ChessSquare::from_chars(file.unwrap(), rank.unwrap()) {
+        if let (Some(file), Some(rank)) = (file, rank) {
+            if let Ok(square) = ChessSquare::from_chars(file, rank) {
                 self.advance_char();
                 self.target_file = Some(square.file());
                 self.target_rank = Some(square.rank());
@@ -877,8 +1009,8 @@ impl<'a> MoveParser<'a> {
                 .map(ColoredChessPieceType::uncolor)
                 .or_else(|| ChessPieceType::from_utf8_char(c))
         });
-        if piece.is_some() {
-            self.promotion = piece.unwrap();
+        if let Some(piece) = piece {
+            self.promotion = piece;
             self.advance_char();
         } else if !allow_fail {
             bail!("Missing promotion piece after '='");
@@ -1093,19 +1225,64 @@ impl<'a> MoveParser<'a> {
 
 #[cfg(test)]
 mod tests {
+    use crate::games::chess::castling::CastleRight::{Kingside, Queenside};
     use crate::games::chess::moves::ChessMove;
     use crate::games::chess::pieces::ChessPieceType::Bishop;
     use crate::games::chess::squares::ChessSquare;
     use crate::games::chess::ChessColor::White;
     use crate::games::chess::Chessboard;
     use crate::games::generic_tests;
-    use crate::games::Board;
-    use crate::general::board::Strictness::Strict;
+    use crate::games::{AbstractPieceType, Board};
+    use crate::general::board::Strictness::{Relaxed, Strict};
     use crate::general::moves::ExtendedFormat::{Alternative, Standard};
     use crate::general::moves::Move;
+    use itertools::Itertools;
 
     type GenericTests = generic_tests::GenericTests<Chessboard>;
 
+    #[test]
+    fn packed_u16_test() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R2Pp1k/8/6P1/8 b - e3 0 1",
+            "r1bqkbnr/pP1ppppp/8/8/8/8/P1PPPPPP/RNBQKBNR w KQkq - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ];
+        for fen in fens {
+            let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+            for mov in pos.legal_moves_slow() {
+                let packed = mov.to_u16();
+                assert_eq!(
+                    ChessMove::from_u16(packed, &pos).unwrap(),
+                    mov,
+                    "{mov} in {fen}"
+                );
+            }
+        }
+        assert_eq!(
+            ChessMove::from_u16(0, &Chessboard::default()).unwrap(),
+            ChessMove::NULL
+        );
+        let pos = Chessboard::from_fen(fens[0], Relaxed).unwrap();
+        // castling as king-to-square
+        let king_to_square = 4 | (6 << 6) | (3 << 14);
+        let mov = ChessMove::from_u16(king_to_square, &pos).unwrap();
+        assert!(mov.is_castle());
+        assert_eq!(mov.castle_side(), Kingside);
+        assert_eq!(mov.to_u16(), 4 | (7 << 6) | (3 << 14));
+        let mov = ChessMove::from_u16(4 | (2 << 6) | (3 << 14), &pos).unwrap();
+        assert_eq!(mov.castle_side(), Queenside);
+        // promotion to a queen
+        let pos = Chessboard::from_fen(fens[3], Relaxed).unwrap();
+        let mov = ChessMove::from_text("bxa8=Q", &pos).unwrap();
+        assert_eq!(mov.to_u16(), 49 | (56 << 6) | (3 << 12) | (1 << 14));
+        // no piece on the start square
+        assert!(ChessMove::from_u16(20 | (28 << 6), &Chessboard::default()).is_err());
+        // not pseudolegal
+        assert!(ChessMove::from_u16(12 | (36 << 6), &Chessboard::default()).is_err());
+    }
+
     #[test]
     fn valid_algebraic_notation_test() {
         let transformations = [
@@ -1122,7 +1299,7 @@ mod tests {
             ("🨅e4", "e4"),
             ("♚f2", "Kf2"),
             ("♖b8+", "Rb8+"),
-            ("Rb7d7", "Rbd7"), // even though the move Rd1d7 isn't legal, it's still necessary to disambiguate with Rbd7
+            ("Rb7d7", "Rd7"), // the move Rd1d7 isn't legal, so there's no need to disambiguate
             ("gf8:🨂", "gxf8=R"),
             (":d8🨂 checkmate", "exd8=R#"),
             ("exf♘", "exf8=N"),
@@ -1145,6 +1322,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn san_test() {
+        let san = |fen: &str, mov: &str| {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            let mov = ChessMove::from_compact_text(mov, &pos).unwrap();
+            mov.san_formatter(&pos).to_string()
+        };
+        // two knights can reach the same square
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(san(fen, "b1d2"), "Nbd2");
+        assert_eq!(san(fen, "f1d2"), "Nfd2");
+        assert_eq!(san(fen, "b1c3"), "Nc3");
+        let fen = "4k3/8/8/8/8/1N6/8/1N2K3 w - - 0 1";
+        assert_eq!(san(fen, "b1d2"), "N1d2");
+        assert_eq!(san(fen, "b3d2"), "N3d2");
+        let fen = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+        assert_eq!(san(fen, "a1b2"), "Qa1b2");
+        // the knight on c3 is pinned, so only one knight can move to e2
+        let fen = "4k3/8/8/8/1b6/2N5/8/4K1N1 w - - 0 1";
+        assert_eq!(san(fen, "g1e2"), "Ne2");
+        assert_eq!(san(fen, "e1f2"), "Kf2");
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(san(fen, "a1a8"), "Ra8#");
+        assert_eq!(san(fen, "a1a7"), "Ra7");
+        let fen = "5k2/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(san(fen, "e1h1"), "O-O+");
+        assert_eq!(san(fen, "e1d2"), "Kd2");
+        assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+        assert_eq!(san("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"), "bxa8=N");
+    }
+
     #[test]
     fn failed_test() {
         let pos = Chessboard::from_fen("8/7r/8/K1k5/8/8/4p3/8 b - - 10 11", Strict).unwrap();
@@ -1201,6 +1409,59 @@ mod tests {
         GenericTests::long_notation_roundtrip_test();
     }
 
+    #[test]
+    fn promotion_roundtrip_test() {
+        let tests = [
+            (
+                "3r1n2/4P3/8/k7/8/8/8/K7 w - - 0 1",
+                [
+                    "e8=N", "e8=B", "e8=R", "e8=Q", "exd8=N", "exd8=B+", "exd8=R", "exd8=Q+",
+                    "exf8=N", "exf8=B", "exf8=R", "exf8=Q",
+                ],
+            ),
+            (
+                "k7/8/8/8/K7/8/1p6/R1N5 b - - 0 1",
+                [
+                    "b1=N", "b1=B", "b1=R", "b1=Q", "bxa1=N", "bxa1=B", "bxa1=R+", "bxa1=Q+",
+                    "bxc1=N", "bxc1=B", "bxc1=R", "bxc1=Q",
+                ],
+            ),
+        ];
+        for (fen, expected) in tests {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            let promos = pos
+                .legal_moves_slow()
+                .into_iter()
+                .filter(|mov| mov.is_promotion())
+                .collect_vec();
+            assert_eq!(promos.len(), expected.len());
+            for mov in promos {
+                let compact = mov.to_string();
+                let piece = mov
+                    .flags()
+                    .promo_piece()
+                    .to_ascii_char()
+                    .to_ascii_lowercase();
+                assert!(compact.ends_with(piece), "{compact}");
+                assert_eq!(ChessMove::from_compact_text(&compact, &pos).unwrap(), mov);
+                assert_eq!(ChessMove::from_text(&compact, &pos).unwrap(), mov);
+                assert_eq!(
+                    ChessMove::from_text(&compact.to_ascii_uppercase(), &pos).unwrap(),
+                    mov
+                );
+                let san = mov.to_extended_text(&pos, Standard);
+                assert!(expected.contains(&san.as_str()), "{san}");
+                assert_eq!(ChessMove::from_extended_text(&san, &pos).unwrap(), mov);
+                assert_eq!(ChessMove::from_text(&san, &pos).unwrap(), mov);
+                let alternative = mov.to_extended_text(&pos, Alternative);
+                assert_eq!(
+                    ChessMove::from_extended_text(&alternative, &pos).unwrap(),
+                    mov
+                );
+            }
+        }
+    }
+
     #[test]
     fn castle_test() {
         let mut p = Chessboard::chess_960_startpos(42).unwrap();
@@ -1220,6 +1481,98 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn castle_spellings_test() {
+        let tests: &[(&str, &[&str], &[&str])] = &[
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                &["O-O", "0-0", "o-o", "e1g1", "e1h1"],
+                &["O-O-O", "0-0-0", "o-o-o", "e1c1", "e1a1"],
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+                &["O-O", "0-0", "e8g8", "e8h8"],
+                &["O-O-O", "0-0-0", "e8c8", "e8a8"],
+            ),
+            // chess960, king between the rooks on b1 and g1
+            (
+                "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1",
+                &["O-O", "0-0", "e1g1"],
+                &["O-O-O", "0-0-0", "e1c1", "e1b1"],
+            ),
+            // chess960, the king is next to the queenside rook and moves towards the kingside when castling queenside
+            (
+                "rk5r/pppppppp/8/8/8/8/PPPPPPPP/RK5R w HAha - 0 1",
+                &["O-O", "0-0", "b1g1", "b1h1"],
+                &["O-O-O", "0-0-0", "b1a1"],
+            ),
+        ];
+        for (fen, kingside, queenside) in tests {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            for (side, spellings) in [(Kingside, kingside), (Queenside, queenside)] {
+                let moves = spellings
+                    .iter()
+                    .map(|s| ChessMove::from_text(s, &pos).unwrap())
+                    .collect_vec();
+                for (mov, spelling) in moves.iter().zip(spellings.iter()) {
+                    assert!(mov.is_castle(), "{spelling} {fen}");
+                    assert_eq!(mov.castle_side(), side, "{spelling} {fen}");
+                    assert_eq!(*mov, moves[0], "{spelling} {fen}");
+                    assert!(pos.is_move_legal(*mov));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chess960_notation_test() {
+        // (fen, castling moves in normal notation, castling moves in chess960 notation)
+        let tests: &[(&str, &[&str], &[&str])] = &[
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                &["e1g1", "e1c1"],
+                &["e1h1", "e1a1"],
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+                &["e8g8", "e8c8"],
+                &["e8h8", "e8a8"],
+            ),
+            (
+                "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1",
+                &["e1g1", "e1c1"],
+                &["e1g1", "e1b1"],
+            ),
+        ];
+        for (fen, normal, chess960) in tests {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            let mut castles = pos
+                .legal_moves_slow()
+                .into_iter()
+                .filter(|m| m.is_castle())
+                .collect_vec();
+            castles.sort_by_key(|m| m.castle_side() == Queenside);
+            assert_eq!(castles.len(), 2, "{fen}");
+            for ((mov, normal), chess960) in castles.iter().zip(*normal).zip(*chess960) {
+                assert_eq!(mov.compact_text(false), *normal);
+                assert_eq!(mov.compact_text(true), *chess960);
+                // both notations are accepted independently of the setting
+                assert_eq!(ChessMove::from_compact_text(normal, &pos).unwrap(), *mov);
+                assert_eq!(ChessMove::from_compact_text(chess960, &pos).unwrap(), *mov);
+            }
+        }
+        // non-castling moves don't depend on the notation
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        for mov in pos
+            .legal_moves_slow()
+            .into_iter()
+            .filter(|m| !m.is_castle())
+        {
+            assert_eq!(mov.compact_text(false), mov.compact_text(true));
+            assert_eq!(mov.to_string(), mov.compact_text(true));
+        }
+    }
 }
 
 // TODO: PGN import test (not here though)
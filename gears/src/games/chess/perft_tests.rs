@@ -5,14 +5,14 @@ mod tests {
     use crate::games::chess::Chessboard;
     use crate::games::Board;
     use crate::general::board::Strictness::{Relaxed, Strict};
-    use crate::general::perft::perft;
+    use crate::general::moves::Move;
+    use crate::general::perft::{perft, perft_divide, perft_parallel, split_perft};
     use crate::search::Depth;
     use itertools::Itertools;
     use rand::prelude::SliceRandom;
     use rand::rng;
     use std::num::NonZeroUsize;
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::thread::{available_parallelism, current, scope};
+    use std::thread::available_parallelism;
     use std::time::Instant;
 
     #[test]
@@ -41,6 +41,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn kiwipete_divide_test() {
+        let board = Chessboard::from_name("kiwipete").unwrap();
+        // the same numbers as Stockfish's `go perft 2`, except that castling uses king-takes-rook notation
+        let expected = [
+            ("a1b1", 43),
+            ("a1c1", 43),
+            ("a1d1", 43),
+            ("a2a3", 44),
+            ("a2a4", 44),
+            ("b2b3", 42),
+            ("c3a4", 42),
+            ("c3b1", 42),
+            ("c3b5", 39),
+            ("c3d1", 42),
+            ("d2c1", 43),
+            ("d2e3", 43),
+            ("d2f4", 43),
+            ("d2g5", 42),
+            ("d2h6", 41),
+            ("d5d6", 41),
+            ("d5e6", 46),
+            ("e1c1", 43),
+            ("e1d1", 43),
+            ("e1f1", 43),
+            ("e1g1", 43),
+            ("e2a6", 36),
+            ("e2b5", 39),
+            ("e2c4", 41),
+            ("e2d1", 44),
+            ("e2d3", 42),
+            ("e2f1", 44),
+            ("e5c4", 42),
+            ("e5c6", 41),
+            ("e5d3", 43),
+            ("e5d7", 45),
+            ("e5f7", 44),
+            ("e5g4", 44),
+            ("e5g6", 42),
+            ("f3d3", 42),
+            ("f3e3", 43),
+            ("f3f4", 43),
+            ("f3f5", 45),
+            ("f3f6", 39),
+            ("f3g3", 43),
+            ("f3g4", 43),
+            ("f3h3", 43),
+            ("f3h5", 43),
+            ("g2g3", 42),
+            ("g2g4", 42),
+            ("g2h3", 43),
+            ("h1f1", 43),
+            ("h1g1", 43),
+        ];
+        let res = perft_divide(Depth::new_unchecked(2), board);
+        let res = res
+            .iter()
+            .map(|(mov, nodes)| (mov.compact_formatter().to_string(), *nodes))
+            .collect_vec();
+        let expected = expected
+            .iter()
+            .map(|(mov, nodes)| (mov.to_string(), *nodes))
+            .collect_vec();
+        assert_eq!(res, expected);
+        let split = split_perft(Depth::new_unchecked(2), board);
+        assert_eq!(split.perft_res.nodes, 2039);
+        assert!(split.to_string().contains("\nd5e6\t46\n"), "{split}");
+        assert_eq!(
+            split.perft_res.nodes,
+            perft(Depth::new_unchecked(2), board).nodes
+        );
+    }
+
+    #[test]
+    fn parallel_perft_test() {
+        for pos in Chessboard::bench_positions() {
+            for depth in 0..=3 {
+                let depth = Depth::new_unchecked(depth);
+                let expected = perft(depth, pos);
+                for threads in [None, Some(0), Some(1), Some(3), Some(1000)] {
+                    let res = perft_parallel(depth, pos, threads);
+                    assert_eq!(res.nodes, expected.nodes, "{pos} {depth} {threads:?}");
+                    assert_eq!(res.depth, expected.depth);
+                }
+            }
+        }
+    }
+
     #[test]
     fn leonids_position_test() {
         let board = Chessboard::from_fen(
@@ -112,7 +200,7 @@ mod tests {
     #[test]
     #[ignore]
     fn standard_perft_test() {
-        perft_test(&STANDARD_FENS);
+        perft_test(STANDARD_FENS);
     }
 
     #[test]
@@ -138,58 +226,39 @@ mod tests {
         perft_test(&FENS);
     }
 
-    /// Parallelizes the perft testcases so that this takes less time, but the chess960 suite still takes a very long time.
+    /// Uses [`perft_parallel`] so that this takes less time, but the chess960 suite still takes a very long time.
     fn perft_test(fens: &'static [&'static str]) {
         let start_time = Instant::now();
         let num_threads = available_parallelism().unwrap_or(NonZeroUsize::new(1).unwrap());
         println!("Running perft test with {num_threads} threads in parallel");
-        let solved_tests = AtomicU64::new(0);
-        let solved_tests = &solved_tests; // lol (necessary to not move the atomic itself, which wouldn't compile)
         let num_fens = fens.len();
         let mut fens = fens.iter().collect_vec();
         // the chess960 perft suite takes so long that it makes sense to just stop the test suite at some point when doing
         // routine testing. Shuffle to ensure that all positions have a chance of being tested.
         fens.shuffle(&mut rng());
-        let testcases_per_thread = (num_fens + num_threads.get() - 1) / num_threads;
-        let thread_data = fens.iter().chunks(testcases_per_thread);
-        let mut thread_fens = vec![];
-        for chunk in &thread_data {
-            thread_fens.push(chunk.collect_vec());
-        }
-        scope(|s| {
-            for chunk in thread_fens {
-                s.spawn(move || {
-                    for testcase in chunk {
-                        let expected = ExpectedPerftRes::new(testcase);
-                        let board = Chessboard::from_fen(expected.fen, Strict).unwrap();
-                        println!("Thread {1:?}: Running test on fen {0}, board\n{board}", expected.fen, current().id());
-                        for (depth, expected_count) in expected
-                            .res
-                            .iter()
-                            .enumerate()
-                            .filter(|(_depth, x)| **x != INVALID)
-                        {
-                            let res = perft(Depth::new_unchecked(depth), board);
-                            assert_eq!(res.depth.get(), depth);
-                            assert_eq!(res.nodes, *expected_count);
-                            println!(
-                                "Thread {3:?}: Perft depth {0} took {1} ms, total time so far: {2}ms",
-                                res.depth.get(),
-                                res.time.as_millis(),
-                                start_time.elapsed().as_millis(),
-                                current().id()
-                            );
-                        }
-                        solved_tests.fetch_add(1, Ordering::Relaxed);
-                        println!(
-                            "Finished {0} / {1} positions",
-                            solved_tests.load(Ordering::Relaxed),
-                            num_fens
-                        );
-                    }
-                });
+        for (i, testcase) in fens.into_iter().enumerate() {
+            let expected = ExpectedPerftRes::new(testcase);
+            let board = Chessboard::from_fen(expected.fen, Strict).unwrap();
+            println!("Running test on fen {0}, board\n{board}", expected.fen);
+            for (depth, expected_count) in expected
+                .res
+                .iter()
+                .enumerate()
+                .filter(|(_depth, x)| **x != INVALID)
+            {
+                let res =
+                    perft_parallel(Depth::new_unchecked(depth), board, Some(num_threads.get()));
+                assert_eq!(res.depth.get(), depth);
+                assert_eq!(res.nodes, *expected_count);
+                println!(
+                    "Perft depth {0} took {1} ms, total time so far: {2}ms",
+                    res.depth.get(),
+                    res.time.as_millis(),
+                    start_time.elapsed().as_millis(),
+                );
             }
-        });
+            println!("Finished {0} / {1} positions", i + 1, num_fens);
+        }
     }
 
     const STANDARD_FENS: &[&str] = &[
@@ -15,6 +15,11 @@ pub const NUM_CHESS_PIECES: usize = 6;
 pub const NUM_COLORS: usize = 2;
 pub const BLACK_OFFSET: usize = 8;
 
+/// How much each piece type contributes to the game phase, see [`Chessboard::game_phase`].
+pub const CHESS_PIECE_PHASE: [u8; NUM_CHESS_PIECES] = [0, 1, 1, 2, 4, 0];
+/// The game phase of the starting position, which is also the maximum value of [`Chessboard::game_phase`].
+pub const MAX_CHESS_PHASE: u8 = 24;
+
 // These symbols were introduced in Unicode 12 and aren't widely supported yet
 // They also don't look that great, so while we accept them, we don't emit them
 pub const UNICODE_NEUTRAL_PAWN: char = '🨅';
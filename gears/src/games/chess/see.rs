@@ -216,6 +216,32 @@ mod tests {
         assert_eq!(see_score_good_pawn_capture, SeeScore(100));
     }
 
+    #[test]
+    fn board_see_test() {
+        // the queen takes a pawn that's defended by another pawn
+        let pos = Chessboard::from_fen("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", Relaxed).unwrap();
+        let mov = ChessMove::from_compact_text("d2d5", &pos).unwrap();
+        assert!(!Board::see(&pos, mov, 0));
+        assert!(Board::see(&pos, mov, -800));
+        assert_eq!(Board::see(&pos, mov, 0), pos.see_at_least(mov, SeeScore(0)));
+        // the same pawn is free if it's not defended
+        let pos = Chessboard::from_fen("4k3/8/8/3p4/8/8/3Q4/4K3 w - - 0 1", Relaxed).unwrap();
+        let mov = ChessMove::from_compact_text("d2d5", &pos).unwrap();
+        assert!(Board::see(&pos, mov, 0));
+        assert!(Board::see(&pos, mov, 100));
+        assert!(!Board::see(&pos, mov, 101));
+        for pos in Chessboard::bench_positions() {
+            for mov in pos.pseudolegal_moves() {
+                for threshold in [-500, -1, 0, 1, 100, 300] {
+                    assert_eq!(
+                        Board::see(&pos, mov, threshold),
+                        pos.see_at_least(mov, SeeScore(threshold))
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn see_test() {
         let board = Chessboard::from_name("see_win_pawn").unwrap();
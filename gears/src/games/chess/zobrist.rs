@@ -1,10 +1,19 @@
 use strum::IntoEnumIterator;
 
+use crate::games::chess::castling::CastleRight::*;
+use crate::games::chess::moves::ChessMove;
+use crate::games::chess::moves::ChessMoveFlags::CastleKingside;
 use crate::games::chess::pieces::ChessPieceType;
-use crate::games::chess::squares::{ChessSquare, NUM_COLUMNS};
+use crate::games::chess::pieces::ChessPieceType::{Pawn, Rook};
+use crate::games::chess::squares::{
+    ChessSquare, C_FILE_NO, D_FILE_NO, F_FILE_NO, G_FILE_NO, NUM_COLUMNS,
+};
 use crate::games::chess::ChessColor::*;
 use crate::games::chess::{ChessColor, Chessboard};
-use crate::games::ZobristHash;
+use crate::games::{Color, ZobristHash};
+use crate::general::bitboards::{Bitboard, RawBitboard};
+use crate::general::board::Board;
+use crate::general::moves::Move;
 use crate::general::squares::RectangularCoordinates;
 
 pub const NUM_PIECE_SQUARE_ENTRIES: usize = 64 * 6;
@@ -110,6 +119,58 @@ impl Chessboard {
         res
     }
 
+    /// The hash after playing the pseudolegal move `mov`, computed from the zobrist keys without playing the move.
+    /// Unlike [`Self::approximate_zobrist_after_move`], this handles captures, castling, en passant and promotions
+    /// and updates the castling and en passant keys.
+    pub fn zobrist_after_move(&self, mov: ChessMove) -> ZobristHash {
+        let keys = &PRECOMPUTED_ZOBRIST_KEYS;
+        let color = self.active_player;
+        let other = color.other();
+        let piece = mov.piece_type();
+        let from = mov.src_square();
+        let mut to = mov.dest_square();
+        let mut hash = self.hash ^ keys.side_to_move_key;
+        if let Some(square) = self.ep_square {
+            hash ^= keys.ep_file_keys[square.file() as usize];
+        }
+        if mov.is_castle() {
+            // the destination square of a castling move is the rook's square
+            let (side, to_file, rook_to_file) = if mov.flags() == CastleKingside {
+                (Kingside, G_FILE_NO, F_FILE_NO)
+            } else {
+                (Queenside, C_FILE_NO, D_FILE_NO)
+            };
+            let rook_from = self.rook_start_square(color, side);
+            let rook_to = ChessSquare::from_rank_file(from.rank(), rook_to_file);
+            hash ^= keys.piece_key(Rook, color, rook_from);
+            hash ^= keys.piece_key(Rook, color, rook_to);
+            to = ChessSquare::from_rank_file(from.rank(), to_file);
+        } else if mov.is_ep() {
+            let taken_pawn = mov.square_of_pawn_taken_by_ep().unwrap();
+            hash ^= keys.piece_key(Pawn, other, taken_pawn);
+        } else if mov.is_non_ep_capture(self) {
+            hash ^= keys.piece_key(self.piece_type_on(to), other, to);
+        } else if piece == Pawn && from.rank().abs_diff(to.rank()) == 2 {
+            // the ep square is only set if there is a pawn that could capture en passant
+            let possible_ep_pawns =
+                (to.bb().west() | to.bb().east()) & self.colored_piece_bb(other, Pawn);
+            if possible_ep_pawns.has_set_bit() {
+                hash ^= keys.ep_file_keys[to.file() as usize];
+            }
+        }
+        let castling = self.castling_after_move(piece, from, to);
+        hash ^= keys.castle_keys[self.castling.allowed_castling_directions()];
+        hash ^= keys.castle_keys[castling.allowed_castling_directions()];
+        let new_piece = if mov.is_promotion() {
+            mov.promo_piece()
+        } else {
+            piece
+        };
+        hash ^= keys.piece_key(piece, color, from);
+        hash ^= keys.piece_key(new_piece, color, to);
+        hash
+    }
+
     pub fn approximate_zobrist_after_move(
         mut old_hash: ZobristHash,
         color: ChessColor,
@@ -177,8 +238,7 @@ mod tests {
             for mov in new_board.legal_moves_slow() {
                 let new_board = new_board.make_move(mov).unwrap();
                 let previous = hashes.insert(new_board.hash.0, new_board);
-                if previous.is_some() {
-                    let old_board = previous.unwrap();
+                if let Some(old_board) = previous {
                     println!(
                         "Collision at hash {hash}, boards {0} and {1} (diagrams: \n{old_board} and \n{new_board}",
                         old_board.as_fen(),
@@ -254,4 +314,26 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn hash_after_move_test() {
+        let mut positions = Chessboard::bench_positions();
+        // castling in chess960, and en passant
+        for fen in [
+            "rbbqQ1kr/1p2p1pp/p5n1/2pp1p2/2P4P/P7/BP1PPPP1/R1B1NNKR b HAha - 0 10",
+            "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1",
+            "4k3/8/8/2pP4/8/8/8/4K3 w - c6 0 2",
+        ] {
+            positions.push(Chessboard::from_fen(fen, Strict).unwrap());
+        }
+        for pos in positions {
+            for m in pos.pseudolegal_moves() {
+                let hash = pos.hash_after_move(m);
+                if let Some(new_pos) = pos.make_move(m) {
+                    assert_eq!(hash, new_pos.zobrist_hash(), "{pos} {m}");
+                    assert_eq!(hash, new_pos.compute_zobrist(), "{pos} {m}");
+                }
+            }
+        }
+    }
 }
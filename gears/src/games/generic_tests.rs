@@ -2,7 +2,8 @@
 //! Since those generics aren't instantiated here, there are no actual tests here.
 use crate::games::{Color, ColoredPiece, Coordinates, Size, ZobristHash};
 use crate::general::board::Strictness::Strict;
-use crate::general::board::{Board, UnverifiedBoard};
+use crate::general::board::{random_opening, Board, UnverifiedBoard};
+use crate::general::move_list::MoveList;
 use crate::general::moves::ExtendedFormat::{Alternative, Standard};
 use crate::general::moves::Legality::Legal;
 use crate::general::moves::Move;
@@ -177,11 +178,63 @@ impl<B: Board> GenericTests<B> {
         }
     }
 
+    pub fn random_opening_test() {
+        let pos = B::default();
+        let opening = random_opening(pos, 6, 42);
+        assert_eq!(opening, random_opening(pos, 6, 42));
+        assert!(opening.len() <= 6);
+        let mut new_pos = pos;
+        for mov in &opening {
+            assert!(new_pos.is_move_legal(*mov));
+            new_pos = new_pos.make_move(*mov).unwrap();
+        }
+
+        let openings = (0..10)
+            .map(|seed| random_opening(pos, 6, seed))
+            .collect_vec();
+        assert!(openings.iter().any(|o| *o != openings[0]));
+        assert!(random_opening(pos, 0, 42).is_empty());
+    }
+
+    pub fn num_legal_moves_test() {
+        for pos in B::bench_positions() {
+            assert_eq!(
+                pos.num_legal_moves(),
+                pos.legal_moves_slow().num_moves(),
+                "{}",
+                pos.as_fen()
+            );
+            for mov in pos.legal_moves_slow() {
+                let new_pos = pos.make_move(mov).unwrap();
+                assert_eq!(
+                    new_pos.num_legal_moves(),
+                    new_pos.legal_moves_slow().num_moves()
+                );
+            }
+        }
+    }
+
+    pub fn for_each_legal_move_test() {
+        for pos in B::bench_positions() {
+            let mut moves = vec![];
+            pos.for_each_legal_move(|mov| moves.push(mov));
+            let expected = pos
+                .pseudolegal_moves()
+                .into_iter()
+                .filter(|mov| pos.make_move(*mov).is_some())
+                .collect_vec();
+            assert_eq!(moves, expected, "{}", pos.as_fen());
+        }
+    }
+
     pub fn all_tests() {
         Self::basic_test();
         Self::coordinates_test();
         Self::long_notation_roundtrip_test();
         Self::fen_roundtrip_test();
         Self::statistical_hash_test(B::default());
+        Self::random_opening_test();
+        Self::num_legal_moves_test();
+        Self::for_each_legal_move_test();
     }
 }
@@ -23,7 +23,7 @@ use crate::general::board::{
     Strictness, UnverifiedBoard,
 };
 use crate::general::common::*;
-use crate::general::move_list::EagerNonAllocMoveList;
+use crate::general::move_list::{EagerNonAllocMoveList, MoveSink};
 use crate::general::moves::Legality::Legal;
 use crate::general::moves::{Legality, Move, NoMoveFlags, UntrustedMove};
 use crate::general::squares::{GridCoordinates, GridSize};
@@ -31,6 +31,8 @@ use crate::output::text_output::{
     board_to_string, display_board_pretty, BoardFormatter, DefaultBoardFormatter, PieceToChar,
 };
 use crate::output::OutputOpts;
+use crate::search::Depth;
+use crate::NormalGameOverReason;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum Symbol {
@@ -548,6 +550,19 @@ impl Board for MNKBoard {
             .collect()
     }
 
+    /// Since the board size is determined at runtime, a fixed default depth would be way too large for large boards.
+    /// Instead, choose the largest depth where the number of leaves is guaranteed to stay below a million.
+    fn default_perft_depth(&self) -> Depth {
+        let empty = self.empty_bb().num_ones();
+        let mut nodes = 1;
+        let mut depth = 0;
+        while depth < empty && nodes * (empty - depth) <= 1_000_000 {
+            nodes *= empty - depth;
+            depth += 1;
+        }
+        Depth::new_unchecked(depth.max(1))
+    }
+
     fn settings(&self) -> Self::Settings {
         self.settings
     }
@@ -590,7 +605,7 @@ impl Board for MNKBoard {
         Square::new(symbol, coordinates)
     }
 
-    fn gen_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T) {
+    fn gen_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T) {
         let mut empty = self.empty_bb();
         while empty.has_set_bit() {
             let idx = empty.pop_lsb();
@@ -604,7 +619,7 @@ impl Board for MNKBoard {
         }
     }
 
-    fn gen_tactical_pseudolegal<T: MoveList<Self>>(&self, _moves: &mut T) {
+    fn gen_tactical_pseudolegal<T: MoveSink<Self>>(&self, _moves: &mut T) {
         // currently, no moves are considered tactical
     }
 
@@ -654,7 +669,7 @@ impl Board for MNKBoard {
         if self.is_game_lost() {
             Some(Lose)
         } else if self.empty_bb().is_zero() {
-            return Some(Draw);
+            Some(Draw)
         } else {
             None
         }
@@ -664,6 +679,14 @@ impl Board for MNKBoard {
         self.player_result_no_movegen(history)
     }
 
+    fn game_over_reason_slow<H: BoardHistory<Self>>(&self, _history: &H) -> NormalGameOverReason {
+        if self.is_game_lost() {
+            NormalGameOverReason::InARow(self.k() as usize)
+        } else {
+            NormalGameOverReason::BoardFull
+        }
+    }
+
     fn no_moves_result(&self) -> PlayerResult {
         Draw
     }
@@ -929,10 +952,20 @@ impl UnverifiedBoard<MNKBoard> for UnverifiedMnkBoard {
 mod test {
     use crate::general::board::Strictness::Relaxed;
     use crate::general::perft::{perft, split_perft};
-    use crate::search::Depth;
 
     use super::*;
 
+    #[test]
+    fn default_perft_depth_test() {
+        let tictactoe = MNKBoard::default();
+        assert_eq!(tictactoe.default_perft_depth(), Depth::new_unchecked(9));
+        for pos in MNKBoard::bench_positions() {
+            let depth = pos.default_perft_depth();
+            assert!(depth.get() >= 1);
+            assert!(perft(depth, pos).nodes <= 1_000_000, "{pos}");
+        }
+    }
+
     #[test]
     fn dimension_test() {
         let board = MNKBoard::default();
@@ -951,6 +984,21 @@ mod test {
         assert_eq!(board.settings, settings);
     }
 
+    #[test]
+    fn move_order_score_test() {
+        for pos in MNKBoard::bench_positions() {
+            let moves = pos.pseudolegal_moves().into_iter().collect_vec();
+            assert!(moves.iter().all(|&mov| pos.move_order_score(mov) == 0));
+            // the default score doesn't change the order of a stable sort
+            let sorted = moves
+                .iter()
+                .copied()
+                .sorted_by_key(|&mov| pos.move_order_score(mov))
+                .collect_vec();
+            assert_eq!(moves, sorted);
+        }
+    }
+
     #[test]
     #[should_panic]
     fn dimension_test_invalid_k_0() {
@@ -38,7 +38,7 @@ use crate::general::board::{
     SelfChecks, Strictness, UnverifiedBoard,
 };
 use crate::general::common::{ith_one_u128, parse_int, Res, StaticallyNamedEntity, Tokens};
-use crate::general::move_list::{EagerNonAllocMoveList, MoveList};
+use crate::general::move_list::{EagerNonAllocMoveList, MoveSink};
 use crate::general::moves::Legality::Legal;
 use crate::general::moves::{Legality, Move, NoMoveFlags, UntrustedMove};
 use crate::general::squares::{
@@ -49,6 +49,7 @@ use crate::output::text_output::{
     DefaultBoardFormatter, PieceToChar,
 };
 use crate::output::OutputOpts;
+use crate::NormalGameOverReason;
 use crate::PlayerResult;
 use crate::PlayerResult::{Draw, Lose};
 use anyhow::bail;
@@ -716,7 +717,7 @@ impl Board for UtttBoard {
         }
     }
 
-    fn gen_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T) {
+    fn gen_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T) {
         // don't assume that the board is empty in startpos to support different starting positions
         if self.player_result_no_movegen(&NoHistory::default()) == Some(Lose) {
             return;
@@ -741,7 +742,7 @@ impl Board for UtttBoard {
         }
     }
 
-    fn gen_tactical_pseudolegal<T: MoveList<Self>>(&self, _moves: &mut T) {
+    fn gen_tactical_pseudolegal<T: MoveSink<Self>>(&self, _moves: &mut T) {
         // TODO: Test considering moves that win a sub-board as tactical
         // currently, no moves are considered tactical
     }
@@ -812,6 +813,15 @@ impl Board for UtttBoard {
         self.player_result_no_movegen(history)
     }
 
+    fn game_over_reason_slow<H: BoardHistory<Self>>(&self, history: &H) -> NormalGameOverReason {
+        if self.player_result_no_movegen(history) == Some(Lose) {
+            // three won sub boards in a row
+            NormalGameOverReason::InARow(3)
+        } else {
+            NormalGameOverReason::BoardFull
+        }
+    }
+
     fn no_moves_result(&self) -> PlayerResult {
         debug_assert!(self.open_bb().is_zero());
         Draw
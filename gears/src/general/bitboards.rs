@@ -102,9 +102,9 @@ pub const MAX_WIDTH: usize = 27;
 
 const STEPS: [u128; 128] = compute_step_bbs();
 
-const DIAGONALS: [[u128; 128]; MAX_WIDTH] = compute_diagonal_bbs();
+static DIAGONALS: [[u128; 128]; MAX_WIDTH] = compute_diagonal_bbs();
 
-const ANTI_DIAGONALS: [[u128; 128]; MAX_WIDTH] = compute_anti_diagonal_bbs();
+static ANTI_DIAGONALS: [[u128; 128]; MAX_WIDTH] = compute_anti_diagonal_bbs();
 
 // This seems like a lot of boilerplate code.
 // Maybe there's a better way?
@@ -359,7 +359,7 @@ impl RawBitboard for ExtendedRawBitboard {
     }
 }
 
-#[derive(Debug, EnumIter)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, EnumIter)]
 pub enum RayDirections {
     Horizontal,
     Vertical,
@@ -17,28 +17,33 @@
  */
 use crate::games::{
     AbstractPieceType, BoardHistory, Color, ColoredPiece, ColoredPieceType, Coordinates, DimT,
-    NoHistory, Settings, Size, ZobristHash,
+    NoHistory, Settings, Size, ZobristHash, ZobristHistory,
 };
 use crate::general::board::SelfChecks::{Assertion, Verify};
 use crate::general::board::Strictness::Relaxed;
 use crate::general::common::Description::NoDescription;
 use crate::general::common::{
-    select_name_static, tokens, EntityList, GenericSelect, Res, StaticallyNamedEntity, Tokens,
+    select_name_static, tokens, EntityList, GearsError, GenericSelect, Res, StaticallyNamedEntity,
+    Tokens,
 };
-use crate::general::move_list::MoveList;
-use crate::general::moves::Legality::{Legal, PseudoLegal};
+use crate::general::move_list::{MoveList, MoveSink};
+use crate::general::moves::Legality::Legal;
 use crate::general::moves::Move;
 use crate::general::squares::{RectangularCoordinates, RectangularSize, SquareColor};
 use crate::output::text_output::{BoardFormatter, PieceToChar};
 use crate::output::OutputOpts;
 use crate::search::Depth;
 use crate::PlayerResult::Lose;
-use crate::{player_res_to_match_res, GameOver, GameOverReason, MatchResult, PlayerResult};
-use anyhow::{anyhow, bail};
+use crate::{
+    player_res_to_match_res, GameOver, GameOverReason, MatchResult, NormalGameOverReason,
+    PlayerResult,
+};
+use anyhow::bail;
 use arbitrary::Arbitrary;
 use colored::Colorize;
 use itertools::Itertools;
-use rand::Rng;
+use rand::prelude::{IndexedRandom, StdRng};
+use rand::{Rng, SeedableRng};
 use std::fmt;
 use std::fmt::{Debug, Display};
 use std::num::NonZeroUsize;
@@ -242,6 +247,15 @@ pub trait Board:
         vec![]
     }
 
+    /// The names and FENs of all positions in [`Self::name_to_pos_map`], so that tests and demos can refer to them.
+    #[must_use]
+    fn named_positions() -> Vec<(&'static str, String)> {
+        Self::name_to_pos_map()
+            .iter()
+            .map(|p| (p.name, (p.val)().as_fen()))
+            .collect()
+    }
+
     #[must_use]
     fn bench_positions() -> Vec<Self>;
 
@@ -329,12 +343,12 @@ pub trait Board:
     /// Generate pseudolegal moves into the supplied move list. Can be more efficient than `pseudolegal_moves`
     /// because it avoids moving around large move lists, and is generic over the move list to allow arbitrary code
     /// upon adding moves, such as scoring or filtering the new move.
-    fn gen_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T);
+    fn gen_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T);
 
     /// Generate moves that are considered "tactical" into the supplied move list.
     /// Can be more efficient than `tactical_pseudolegal` and is generic over the move list.
     /// Note that some games don't consider any moves tactical, so this function may have no effect.
-    fn gen_tactical_pseudolegal<T: MoveList<Self>>(&self, moves: &mut T);
+    fn gen_tactical_pseudolegal<T: MoveSink<Self>>(&self, moves: &mut T);
 
     /// Returns a list of pseudo legal moves that are considered "tactical", such as captures and promotions in chess.
     fn tactical_pseudolegal(&self) -> Self::MoveList {
@@ -346,11 +360,26 @@ pub trait Board:
     /// Returns a list of legal moves, that is, moves that can be played using `make_move`
     /// and will not return `None`.
     fn legal_moves_slow(&self) -> Self::MoveList {
-        let mut pseudo_legal = self.pseudolegal_moves();
-        if Self::Move::legality() == PseudoLegal {
-            pseudo_legal.filter_moves(|m| self.is_pseudolegal_move_legal(*m));
+        let mut moves = Self::MoveList::default();
+        self.for_each_legal_move(|mov| moves.add_move(mov));
+        moves
+    }
+
+    /// Calls `f` on each legal move without building a list of legal moves.
+    /// Games can override this to filter moves while generating them, e.g. chess does this with
+    /// [`LegalMoveVisitor`](crate::general::move_list::LegalMoveVisitor).
+    fn for_each_legal_move<F: FnMut(Self::Move)>(&self, mut f: F) {
+        for mov in self.pseudolegal_moves() {
+            if self.is_pseudolegal_move_legal(mov) {
+                f(mov);
+            }
         }
-        pseudo_legal
+    }
+
+    /// Returns the number of legal moves. Games can override this to avoid building the list of legal moves,
+    /// which is useful for mobility eval terms.
+    fn num_legal_moves(&self) -> usize {
+        self.legal_moves_slow().num_moves()
     }
 
     /// Returns a random legal move, that is, chooses a pseudorandom move from the set of legal moves.
@@ -367,11 +396,40 @@ pub trait Board:
     /// In other words, this function only gracefully checks legality assuming that the move is pseudolegal.
     fn make_move(self, mov: Self::Move) -> Option<Self>;
 
+    /// Parses a move and plays it. [`Strictness::Strict`] only accepts the compact notation used by UGI, such as
+    /// `e2e4` in chess, while [`Relaxed`] accepts everything [`Move::from_text`] understands, such as SAN.
+    /// Returns an error if the move can't be parsed or if it isn't legal.
+    fn make_move_str(&self, s: &str, strictness: Strictness) -> Res<Self> {
+        let mov = match strictness {
+            Relaxed => Self::Move::from_text(s, self)?,
+            Strictness::Strict => Self::Move::from_compact_text(s, self)?,
+        };
+        match self.make_move(mov) {
+            Some(pos) => Ok(pos),
+            None => bail!(
+                "The move '{0}' is pseudolegal but not legal in position '{1}'",
+                s.red(),
+                self.as_fen()
+            ),
+        }
+    }
+
     /// Makes a nullmove, i.e. flips the active player. While this action isn't strictly legal in most games,
     /// it's still very useful and necessary for null move pruning.
-    /// `make_move`
+    /// Like `make_move`, this returns `None` if the resulting position would be illegal, such as when the current
+    /// player is in check in chess. Game-specific state that only applies to the current player, like the en passant
+    /// square, gets cleared, and the zobrist hash is updated accordingly.
     fn make_nullmove(self) -> Option<Self>;
 
+    /// Returns the same position with the other player to move, e.g. to see what the opponent would do if it was
+    /// their turn. Unlike [`Self::make_nullmove`], this is meant as a query for analysis, not as something that
+    /// gets played, so games can override it to leave the move counters alone. Information that only makes sense
+    /// for the current player, like an en passant square in chess, gets cleared.
+    /// Returns `None` if the resulting position would be illegal, like when the current player is in check.
+    fn with_side_to_move_flipped(self) -> Option<Self> {
+        self.make_nullmove()
+    }
+
     /// Returns true iff the move is pseudolegal, that is, it can be played with `make_move` without
     /// causing a panic. When it is not certain that a move is definitely (pseudo)legal, `Untrusted<Move>`
     /// should be used.
@@ -391,6 +449,20 @@ pub trait Board:
         Self::Move::legality() == Legal || self.make_move(mov).is_some()
     }
 
+    /// Static exchange evaluation: Returns `true` if the material balance after `mov` and all following captures
+    /// on the same square is at least `threshold`. Games without an implementation always return `true`,
+    /// so that searchers can use this to prune losing captures without checking for the game.
+    fn see(&self, _mov: Self::Move, _threshold: i32) -> bool {
+        true
+    }
+
+    /// A cheap static guess of how promising `mov` is, where larger values should be tried first.
+    /// This is meant for searchers without a game-specific move picker, such as the generic `gaps` searcher.
+    /// The default returns 0 for every move, so a stable sort keeps the movegen order.
+    fn move_order_score(&self, _mov: Self::Move) -> i32 {
+        0
+    }
+
     /// Returns the result (win/draw/loss), if any, but doesn't necessarily catch all game-ending conditions.
     /// That is, this function might return `None` if the game has actually ended,
     fn player_result_no_movegen<H: BoardHistory<Self>>(&self, history: &H) -> Option<PlayerResult>;
@@ -405,11 +477,41 @@ pub trait Board:
     /// For example, being checkmated in chess is a loss for the current player.
     fn player_result_slow<H: BoardHistory<Self>>(&self, history: &H) -> Option<PlayerResult>;
 
+    /// Explains why the game is over, such as checkmate in chess. Only meaningful if [`Self::player_result_slow`]
+    /// returns `Some`, which means that it can assume that the game is over.
+    fn game_over_reason_slow<H: BoardHistory<Self>>(&self, history: &H) -> NormalGameOverReason;
+
+    /// Returns true iff the current position should be considered a draw because of repetitions.
+    /// `hist` contains the hashes of all previous positions, i.e. it doesn't include the current position,
+    /// and the last `ply_from_root` entries are the positions of the current search tree (including the root).
+    /// If an earlier occurrence of the current position is inside the search tree, the first repetition already counts
+    /// as a draw, because the opponent could simply repeat the position again (this is what a search should use).
+    /// Otherwise, the position must have occurred three times, including the current occurrence.
+    /// So `ply_from_root == 0` checks for a true threefold repetition, which is what game adjudication should use.
+    /// Only positions since the last irreversible move, see [`Self::halfmove_repetition_clock`], are considered.
+    fn is_draw_by_repetition(&self, hist: &ZobristHistory<Self>, ply_from_root: usize) -> bool {
+        let max_lookback = self.halfmove_repetition_clock().min(hist.len());
+        // the current position counts as one occurrence
+        let mut count = 1;
+        for plies_ago in (2..=max_lookback).step_by(2) {
+            if hist.is_repetition(self, plies_ago) {
+                if plies_ago <= ply_from_root {
+                    return true;
+                }
+                count += 1;
+                if count >= 3 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     fn match_result_slow<H: BoardHistory<Self>>(&self, history: &H) -> Option<MatchResult> {
         let player_res = self.player_result_slow(history)?;
         let game_over = GameOver {
             result: player_res,
-            reason: GameOverReason::Normal,
+            reason: GameOverReason::Normal(self.game_over_reason_slow(history)),
         };
         Some(player_res_to_match_res(game_over, self.active_player()))
     }
@@ -433,7 +535,12 @@ pub trait Board:
     /// to play it and use `game_result()` or `game_result_no_movegen()` and `no_moves_result` instead.
     fn is_game_won_after_slow(&self, mov: Self::Move) -> bool {
         self.make_move(mov)
-            .map_or(false, |new_pos| new_pos.is_game_lost_slow())
+            .is_some_and(|new_pos| new_pos.is_game_lost_slow())
+    }
+
+    /// Returns true iff the current player is in check. Games without a concept of check always return `false`.
+    fn is_in_check(&self) -> bool {
+        false
     }
 
     /// Returns `false` if it detects that `player` can not win the game except if the opponent runs out of time
@@ -451,22 +558,36 @@ pub trait Board:
 
     fn zobrist_hash(&self) -> ZobristHash;
 
+    /// The zobrist hash of the position after playing the pseudolegal move `mov`, without playing it.
+    /// Games can override this to update the hash incrementally, which is cheaper than [`Self::make_move`].
+    /// The result is unspecified if `mov` isn't legal.
+    fn hash_after_move(&self, mov: Self::Move) -> ZobristHash {
+        self.make_move(mov)
+            .map_or(self.zobrist_hash(), |pos| pos.zobrist_hash())
+    }
+
     /// Returns a compact textual description of the board that can be read in again with `from_fen`.
     fn as_fen(&self) -> String;
 
     /// Reads in a compact textual description of the board, such that `B::from_fen(board.as_fen()) == b` holds.
     /// Assumes that the entire string represents the FEN, without any trailing tokens.
     /// Use the lower-level `read_fen_and_advance_input` if this assumption doesn't have to hold.
-    fn from_fen(string: &str, strictness: Strictness) -> Res<Self> {
+    fn from_fen(string: &str, strictness: Strictness) -> Result<Self, GearsError> {
         let mut words = tokens(string);
-        let res = Self::read_fen_and_advance_input(&mut words, strictness)
-            .map_err(|err| anyhow!("Failed to parse FEN '{}': {err}", string.bold()))?;
+        let res = Self::read_fen_and_advance_input(&mut words, strictness).map_err(|err| {
+            GearsError::Fen {
+                fen: string.to_string(),
+                msg: err.to_string(),
+            }
+        })?;
         if let Some(next) = words.next() {
-            return Err(anyhow!(
-                "Input `{0}' contained additional characters after FEN, starting with '{1}'",
-                string.bold(),
-                next.red()
-            ));
+            return Err(GearsError::Fen {
+                fen: string.to_string(),
+                msg: format!(
+                    "Contained additional characters after FEN, starting with '{}'",
+                    next.red()
+                ),
+            });
         }
         Ok(res)
     }
@@ -580,6 +701,27 @@ pub fn ply_counter_from_fullmove_nr<B: Board>(
     (fullmove_nr.get() - 1) * 2 + usize::from(!active.is_first())
 }
 
+/// Plays up to `num_plies` uniformly random legal moves from `pos` and returns them.
+/// The RNG is seeded with `seed`, so the same seed always results in the same opening.
+/// Stops early if the game is over. Useful to diversify self-play games for games without an opening book.
+pub fn random_opening<B: Board>(pos: B, num_plies: usize, seed: u64) -> Vec<B::Move> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pos = pos;
+    let mut res = vec![];
+    for _ in 0..num_plies {
+        if pos.player_result_slow(&NoHistory::default()).is_some() {
+            break;
+        }
+        let moves = pos.legal_moves_slow().into_iter().collect_vec();
+        let Some(&mov) = moves.choose(&mut rng) else {
+            break;
+        };
+        pos = pos.make_move(mov).unwrap();
+        res.push(mov);
+    }
+    res
+}
+
 /// Constructs a specific, well-known position from its name, such as 'kiwipete' in chess.
 /// Not to be confused with `from_fen`, which can load arbitrary positions.
 /// However, `"fen <x>"` forwards to [`B::from_fen`]
@@ -590,7 +732,7 @@ pub fn board_from_name<B: Board>(name: &str) -> Res<B> {
         .unwrap_or_default()
         .eq_ignore_ascii_case("fen")
     {
-        return B::from_fen(&tokens.join(" "), Relaxed);
+        return Ok(B::from_fen(&tokens.join(" "), Relaxed)?);
     }
     select_name_static(
         name,
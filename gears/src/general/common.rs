@@ -1,16 +1,18 @@
 use crate::general::common::Description::WithDescription;
-use crate::score::Score;
+use crate::score::{cp_to_winrate, Score};
 pub use anyhow;
 use colored::Colorize;
 use edit_distance::edit_distance;
 use itertools::Itertools;
 use num::{Float, PrimInt};
+use rand::{Rng, RngCore, SeedableRng};
 #[cfg(all(target_arch = "x86_64", target_feature = "bmi2", feature = "unsafe"))]
 use std::arch::x86_64::{_pdep_u64, _pext_u64};
 use std::fmt::{Debug, Display};
 use std::io::stdin;
 use std::iter::Peekable;
 use std::num::{NonZeroU64, NonZeroUsize};
+use std::ops::RangeInclusive;
 use std::str::{FromStr, SplitWhitespace};
 use std::time::Duration;
 
@@ -108,31 +110,93 @@ pub fn ith_one_u128(idx: usize, val: u128) -> usize {
 
 pub type Tokens<'a> = Peekable<SplitWhitespace<'a>>;
 
-pub fn tokens(input: &str) -> Tokens {
+pub fn tokens(input: &str) -> Tokens<'_> {
     input.split_whitespace().peekable()
 }
 
 pub type Res<T> = anyhow::Result<T>;
 
+/// A concrete error type for some public functions, so that library consumers can match on the kind of error.
+/// Internally, errors are still mostly [`anyhow::Error`]s, and a `GearsError` converts into one with `?`.
+/// The message of the original error is kept in the `msg` field.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum GearsError {
+    /// A FEN couldn't be parsed, or doesn't describe a valid position.
+    #[error("Failed to parse FEN '{}': {msg}", .fen.bold())]
+    Fen { fen: String, msg: String },
+    /// A move couldn't be parsed, or isn't pseudolegal in the given position.
+    #[error("{msg}")]
+    Move {
+        mov: String,
+        pos: String,
+        msg: String,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// See [`cp_to_winrate`].
 pub fn sigmoid(score: Score, scale: f64) -> f64 {
-    let x = f64::from(score.0);
-    1.0 / (1.0 + (-x / scale).exp())
+    cp_to_winrate(f64::from(score.0), scale)
 }
 
+/// Parses a floating point number, where `name` describes the value for the error message.
 pub fn parse_fp_from_str<T: Float + FromStr>(as_str: &str, name: &str) -> Res<T> {
     as_str
         .parse::<T>()
         .map_err(|_err| anyhow::anyhow!("Couldn't parse {name} ('{as_str}')"))
 }
 
+fn is_int_literal(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+}
+
+fn int_to_string<T: PrimInt>(val: T) -> String {
+    val.to_i128()
+        .map(|v| v.to_string())
+        .or_else(|| val.to_u128().map(|v| v.to_string()))
+        .unwrap_or_default()
+}
+
+fn out_of_range_err<T: PrimInt>(as_str: &str, name: &str, min: T, max: T) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{name} ('{as_str}') is out of range: must be between {0} and {1}",
+        int_to_string(min),
+        int_to_string(max)
+    )
+}
+
+/// Parses an integer of type `T`, where `name` describes the value for the error message.
+/// Input that isn't an integer at all results in a different error message than an integer that doesn't fit into `T`.
 pub fn parse_int_from_str<T: PrimInt + FromStr>(as_str: &str, name: &str) -> Res<T> {
     // for some weird Rust reason, parse::<T>() returns a completely unbounded Err on failure,
     // so we just write the error message ourselves
-    as_str
-        .parse::<T>()
-        .map_err(|_err| anyhow::anyhow!("Couldn't parse {name} ('{as_str}')"))
+    as_str.parse::<T>().map_err(|_err| {
+        if is_int_literal(as_str) {
+            out_of_range_err(as_str, name, T::min_value(), T::max_value())
+        } else {
+            anyhow::anyhow!("Couldn't parse {name} ('{as_str}'): not an integer")
+        }
+    })
 }
 
+/// Like [`parse_int_from_str`], but also fails if the value isn't inside `range`.
+pub fn parse_int_in_range<T: PrimInt + FromStr>(
+    as_str: &str,
+    name: &str,
+    range: RangeInclusive<T>,
+) -> Res<T> {
+    let val = parse_int_from_str(as_str, name)?;
+    if range.contains(&val) {
+        Ok(val)
+    } else {
+        Err(out_of_range_err(as_str, name, *range.start(), *range.end()))
+    }
+}
+
+/// Consumes the next token and parses it with [`parse_int_from_str`]. Fails if there is no next token.
 pub fn parse_int<T: PrimInt + FromStr + Display>(words: &mut Tokens, name: &str) -> Res<T> {
     parse_int_from_str(
         words
@@ -142,12 +206,14 @@ pub fn parse_int<T: PrimInt + FromStr + Display>(words: &mut Tokens, name: &str)
     )
 }
 
+/// Reads a line from stdin and parses it with [`parse_int_from_str`].
 pub fn parse_int_from_stdin<T: PrimInt + FromStr>() -> Res<T> {
     let mut s = String::default();
     stdin().read_line(&mut s)?;
     parse_int_from_str(s.trim(), "integer")
 }
 
+/// Accepts `true` and `false`, ignoring case.
 pub fn parse_bool_from_str(input: &str, name: &str) -> Res<bool> {
     if input.eq_ignore_ascii_case("true") {
         Ok(true)
@@ -164,6 +230,7 @@ pub fn parse_bool_from_str(input: &str, name: &str) -> Res<bool> {
     }
 }
 
+/// Consumes the next token as a number of milliseconds. Negative values are treated as zero.
 pub fn parse_duration_ms(words: &mut Tokens, name: &str) -> Res<Duration> {
     let num_ms: i64 = parse_int(words, name)?;
     // The UGI client can send negative remaining time.
@@ -408,11 +475,59 @@ pub fn nonzero_u64(val: u64, name: &str) -> Res<NonZeroU64> {
     NonZeroU64::new(val).ok_or_else(|| anyhow::anyhow!("{name} can't be zero"))
 }
 
+/// An RNG that can be seeded, such as the one used by random engines and evals. Any seedable RNG works, but the output
+/// of RNGs like `StdRng` can change between versions of the `rand` crate, so [`XorShiftRng`] should be used when
+/// results need to be reproducible.
+pub trait SeedRng: Rng + SeedableRng {}
+
+impl<T> SeedRng for T where T: Rng + SeedableRng {}
+
+/// A small and fast xorshift64* RNG whose output for a given seed is fixed, e.g. for tests and datagen.
+/// Not suitable for anything where the quality of the random numbers is important.
+#[derive(Debug, Clone)]
+pub struct XorShiftRng(u64);
+
+impl RngCore for XorShiftRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+impl SeedableRng for XorShiftRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        // the state must never be zero
+        Self(u64::from_le_bytes(seed).max(1))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self::from_seed(state.to_le_bytes())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use rand::{rng, Rng};
+    use rand::{rng, Rng, RngCore, SeedableRng};
 
-    use crate::general::common::{ith_one_u128, ith_one_u64, pop_lsb128, pop_lsb64};
+    use crate::general::common::{
+        ith_one_u128, ith_one_u64, parse_int_from_str, parse_int_in_range, pop_lsb128, pop_lsb64,
+        XorShiftRng,
+    };
 
     #[test]
     fn pop_lsb64_test() {
@@ -484,4 +599,62 @@ mod tests {
         assert_eq!(ith_one_u128(3, val), 4);
         assert_eq!(ith_one_u128(4, val), 81);
     }
+
+    #[test]
+    fn parse_int_test() {
+        assert_eq!(parse_int_from_str::<u8>("255", "x").unwrap(), 255);
+        assert_eq!(parse_int_from_str::<i32>("-42", "x").unwrap(), -42);
+        assert_eq!(
+            parse_int_from_str::<u128>(&u128::MAX.to_string(), "x").unwrap(),
+            u128::MAX
+        );
+        let not_a_number = parse_int_from_str::<u8>("abc", "depth")
+            .unwrap_err()
+            .to_string();
+        assert!(not_a_number.contains("not an integer"), "{not_a_number}");
+        assert!(not_a_number.contains("depth"));
+        for input in ["", "-", "1.5", "1e3", "0x10", " 1"] {
+            let err = parse_int_from_str::<i64>(input, "x")
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("not an integer"), "{input}: {err}");
+        }
+        let too_large = parse_int_from_str::<u8>("256", "depth")
+            .unwrap_err()
+            .to_string();
+        assert!(too_large.contains("out of range"), "{too_large}");
+        assert!(too_large.contains("between 0 and 255"), "{too_large}");
+        let negative = parse_int_from_str::<usize>("-1", "depth")
+            .unwrap_err()
+            .to_string();
+        assert!(negative.contains("out of range"), "{negative}");
+        let huge = "1".repeat(50);
+        let err = parse_int_from_str::<u128>(&huge, "x")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("out of range"), "{err}");
+
+        assert_eq!(parse_int_in_range("7", "threads", 1..=8).unwrap(), 7);
+        let err = parse_int_in_range("9", "threads", 1..=8)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("between 1 and 8"), "{err}");
+        let err = parse_int_in_range::<i32>("nine", "threads", 1..=8)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("not an integer"), "{err}");
+    }
+
+    #[test]
+    fn xorshift_test() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        assert_eq!(rng.next_u64(), 0x56ce_4ab7_719b_a3a0);
+        assert_eq!(rng.next_u64(), 0xc841_eb53_ebbb_2dda);
+        assert_eq!(rng.next_u64(), 0xca46_6be0_c998_0276);
+        let mut bytes = [0; 11];
+        XorShiftRng::seed_from_u64(42).fill_bytes(&mut bytes);
+        assert_eq!(bytes[..8], 0x56ce_4ab7_719b_a3a0_u64.to_le_bytes());
+        // a zero seed doesn't result in a stuck rng
+        assert_ne!(XorShiftRng::seed_from_u64(0).next_u64(), 0);
+    }
 }
@@ -1,12 +1,17 @@
 use crate::general::board::Board;
 use arrayvec::ArrayVec;
-use std::fmt::Debug;
+use std::fmt::{Debug, Formatter};
 
-/// A list of moves as returned by the board's `pseudolegal_moves`.
-/// Moves may or may not be ordered and may or may not be computed lazily.
-pub trait MoveList<B: Board>: IntoIterator<Item = B::Move> + Debug {
+/// Something that the movegen can add moves to, see [`Board::gen_pseudolegal`].
+/// This is all that's needed to generate moves, so it's also implemented by types that don't store the moves,
+/// like [`LegalMoveVisitor`].
+pub trait MoveSink<B: Board> {
     fn add_move(&mut self, mov: B::Move);
+}
 
+/// A list of moves as returned by the board's `pseudolegal_moves`.
+/// Moves may or may not be ordered and may or may not be computed lazily.
+pub trait MoveList<B: Board>: MoveSink<B> + IntoIterator<Item = B::Move> + Debug {
     fn num_moves(&self) -> usize;
 
     /// Moves the last currently considered move to the `idx`th element and returns that.
@@ -21,14 +26,22 @@ pub trait MoveList<B: Board>: IntoIterator<Item = B::Move> + Debug {
 }
 
 /// A list of moves that is computed all at once and stored in-place.
+/// `N` must be at least the maximum number of pseudolegal moves in any position, which each game has to ensure
+/// when choosing its capacity. Adding more than `N` moves panics instead of silently dropping moves.
 #[allow(type_alias_bounds)]
 pub type EagerNonAllocMoveList<B: Board, const N: usize> = ArrayVec<B::Move, N>;
 
-impl<B: Board, const N: usize> MoveList<B> for EagerNonAllocMoveList<B, N> {
+impl<B: Board, const N: usize> MoveSink<B> for EagerNonAllocMoveList<B, N> {
     fn add_move(&mut self, mov: B::Move) {
+        debug_assert!(
+            !self.is_full(),
+            "Move list overflow: capacity of {N} moves exceeded while adding {mov:?}"
+        );
         self.push(mov);
     }
+}
 
+impl<B: Board, const N: usize> MoveList<B> for EagerNonAllocMoveList<B, N> {
     fn num_moves(&self) -> usize {
         self.len()
     }
@@ -51,3 +64,47 @@ impl<B: Board, const N: usize> MoveList<B> for EagerNonAllocMoveList<B, N> {
         self.retain(predicate)
     }
 }
+
+/// A [`MoveSink`] that doesn't store any moves and instead calls a function on each legal move among the added
+/// pseudolegal moves. Used to implement [`Board::for_each_legal_move`] on top of a game's movegen.
+pub struct LegalMoveVisitor<'a, B: Board, F: FnMut(B::Move)> {
+    pos: &'a B,
+    func: F,
+    count: usize,
+}
+
+impl<'a, B: Board, F: FnMut(B::Move)> LegalMoveVisitor<'a, B, F> {
+    pub fn new(pos: &'a B, func: F) -> Self {
+        Self {
+            pos,
+            func,
+            count: 0,
+        }
+    }
+
+    /// The number of legal moves visited so far.
+    pub fn num_legal_moves(&self) -> usize {
+        self.count
+    }
+}
+
+/// A [`LegalMoveVisitor`] that doesn't do anything with the moves and only counts them.
+/// Used to implement [`Board::num_legal_moves`] without building the full list of moves.
+pub fn legal_move_counter<B: Board>(pos: &B) -> LegalMoveVisitor<'_, B, impl FnMut(B::Move)> {
+    LegalMoveVisitor::new(pos, |_| ())
+}
+
+impl<B: Board, F: FnMut(B::Move)> Debug for LegalMoveVisitor<'_, B, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LegalMoveVisitor for '{}'", self.pos)
+    }
+}
+
+impl<B: Board, F: FnMut(B::Move)> MoveSink<B> for LegalMoveVisitor<'_, B, F> {
+    fn add_move(&mut self, mov: B::Move) {
+        if self.pos.is_pseudolegal_move_legal(mov) {
+            (self.func)(mov);
+            self.count += 1;
+        }
+    }
+}
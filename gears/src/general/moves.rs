@@ -17,7 +17,7 @@
  */
 
 use crate::general::board::Board;
-use crate::general::common::Res;
+use crate::general::common::{GearsError, Res};
 use anyhow::bail;
 use arbitrary::Arbitrary;
 use num::PrimInt;
@@ -94,6 +94,11 @@ where
     /// without using a `Board`.
     fn format_compact(self, f: &mut Formatter<'_>) -> fmt::Result;
 
+    /// Returns a formatter object that implements `Display` such that it prints the result of [`Self::format_compact`].
+    fn compact_formatter(self) -> CompactFormatter<B> {
+        CompactFormatter(self)
+    }
+
     /// Returns a longer representation of the move that may require the board, such as long algebraic notation
     /// Implementations of this trait *may* choose to ignore the board and to not require pseudolegality.
     fn format_extended(
@@ -158,7 +163,7 @@ where
     /// This is supposed to be used whenever the move format is unknown, such as when the user enters a move, and therefore
     /// should handle as many different cases as possible, but always needs to handle the compact text representation.
     /// Like all move parsing functions, this function needs to ensure that the move is pseudolegal in the current position.
-    fn from_text(s: &str, board: &B) -> Res<B::Move> {
+    fn from_text(s: &str, board: &B) -> Result<B::Move, GearsError> {
         match B::Move::from_extended_text(s, board) {
             Ok(m) => Ok(m),
             Err(e) => {
@@ -167,7 +172,11 @@ where
                         return Ok(m);
                     }
                 }
-                Err(e)
+                Err(GearsError::Move {
+                    mov: s.to_string(),
+                    pos: board.as_fen(),
+                    msg: e.to_string(),
+                })
             }
         }
     }
@@ -183,6 +192,15 @@ where
     fn to_underlying(self) -> Self::Underlying;
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct CompactFormatter<B: Board>(B::Move);
+
+impl<B: Board> Display for CompactFormatter<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.format_compact(f)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct ExtendedFormatter<B: Board> {
     pos: B,
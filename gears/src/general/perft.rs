@@ -1,6 +1,12 @@
 use crate::general::board::Board;
+use crate::general::moves::Move;
+use itertools::Itertools;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::thread::{available_parallelism, scope};
 use std::time::{Duration, Instant};
 
 use crate::search::Depth;
@@ -20,7 +26,7 @@ impl Display for PerftRes {
             depth = self.depth.get(),
             nodes = self.nodes,
             time = self.time.as_millis(),
-            nps = self.nodes * 1_000_000 / self.time.as_micros() as u64
+            nps = self.nodes * 1_000_000 / self.time.as_micros().max(1) as u64
         )
     }
 }
@@ -39,10 +45,10 @@ impl<B: Board> Display for SplitPerftRes<B> {
             depth = self.perft_res.depth.get(),
             nodes = self.perft_res.nodes,
             time = self.perft_res.time.as_millis(),
-            nps = self.perft_res.nodes * 1_000_000 / self.perft_res.time.as_micros() as u64
+            nps = self.perft_res.nodes * 1_000_000 / self.perft_res.time.as_micros().max(1) as u64
         )?;
         for child in &self.children {
-            write!(f, "\n{0}\t{1}", child.0, child.1)?;
+            write!(f, "\n{0}\t{1}", child.0.compact_formatter(), child.1)?;
         }
         Ok(())
     }
@@ -51,7 +57,8 @@ impl<B: Board> Display for SplitPerftRes<B> {
 fn do_perft<B: Board>(depth: usize, pos: B) -> u64 {
     let mut nodes = 0;
     if depth == 1 {
-        return pos.legal_moves_slow().into_iter().count() as u64;
+        // bulk counting: the number of leaf nodes is the number of legal moves, so there's no need to play them
+        return pos.num_legal_moves() as u64;
     }
     // if pos.game_result_no_movegen().is_some() {
     //     return 0; // the game is over (e.g. 50mr)
@@ -78,11 +85,52 @@ pub fn perft<B: Board>(depth: Depth, pos: B) -> PerftRes {
     PerftRes { time, nodes, depth }
 }
 
-pub fn split_perft<B: Board>(depth: Depth, pos: B) -> SplitPerftRes<B> {
-    assert!(depth.get() > 0);
+/// Like [`perft`], but distributes the legal root moves over `threads` threads, which defaults to
+/// [`available_parallelism`]. Each thread takes the next unclaimed root move until there are none left,
+/// so the result is always the same as for [`perft`]. Depths 0 and 1 don't spawn any threads.
+pub fn perft_parallel<B: Board>(depth: Depth, pos: B, threads: Option<usize>) -> PerftRes {
     let depth = depth.min(B::max_perft_depth());
-    let mut nodes = 0;
+    if depth.get() <= 1 {
+        return perft(depth, pos);
+    }
     let start = Instant::now();
+    let children = pos
+        .pseudolegal_moves()
+        .into_iter()
+        .filter_map(|mov| pos.make_move(mov))
+        .collect_vec();
+    let threads = threads
+        .unwrap_or_else(|| available_parallelism().map_or(1, NonZeroUsize::get))
+        .clamp(1, children.len().max(1));
+    let next_child = AtomicUsize::new(0);
+    let nodes = scope(|s| {
+        let handles = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut nodes = 0;
+                    while let Some(child) = children.get(next_child.fetch_add(1, Relaxed)) {
+                        nodes += do_perft(depth.get() - 1, *child);
+                    }
+                    nodes
+                })
+            })
+            .collect_vec();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum()
+    });
+    let time = start.elapsed();
+
+    PerftRes { time, nodes, depth }
+}
+
+/// Returns the number of leaf nodes below each legal root move, i.e. the output of "perft divide".
+/// The result is sorted by the moves' compact text, such as `e2e4` for chess, which makes it easy to compare against the
+/// output of other engines, like Stockfish's `go perft`, when debugging movegen.
+pub fn perft_divide<B: Board>(depth: Depth, pos: B) -> Vec<(B::Move, u64)> {
+    assert!(depth.get() > 0);
+    let depth = depth.min(B::max_perft_depth());
     let mut children = vec![];
     for mov in pos.pseudolegal_moves() {
         if let Some(new_pos) = pos.make_move(mov) {
@@ -92,11 +140,18 @@ pub fn split_perft<B: Board>(depth: Depth, pos: B) -> SplitPerftRes<B> {
                 do_perft(depth.get() - 1, new_pos)
             };
             children.push((mov, child_nodes));
-            nodes += child_nodes;
         }
     }
+    children.sort_by_key(|a| a.0.compact_formatter().to_string());
+    children
+}
+
+pub fn split_perft<B: Board>(depth: Depth, pos: B) -> SplitPerftRes<B> {
+    let start = Instant::now();
+    let children = perft_divide(depth, pos);
     let time = start.elapsed();
-    children.sort_by(|a, b| a.0.to_string().cmp(&b.0.to_string()));
+    let nodes = children.iter().map(|(_, nodes)| nodes).sum();
+    let depth = depth.min(B::max_perft_depth());
     let perft_res = PerftRes { time, nodes, depth };
     SplitPerftRes {
         perft_res,
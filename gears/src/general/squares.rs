@@ -24,7 +24,7 @@ pub trait RectangularCoordinates: Coordinates<Size: RectangularSize<Self>> {
         self.column()
     }
     fn square_color(self) -> SquareColor {
-        if (self.row() as usize + self.column() as usize) % 2 == 0 {
+        if (self.row() as usize + self.column() as usize).is_multiple_of(2) {
             Black
         } else {
             White
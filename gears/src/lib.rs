@@ -2,16 +2,17 @@
 //! It is designed to be easily extensible to new games. [`gears`](crate) forms the foundation of the `motors`, `monitors`
 //! and `pliers` crates, which deal with engines, UI, and tuning, respectively.
 
-use crate::games::{BoardHistory, Color, ZobristHistory};
+use crate::games::{BoardHistory, Color, RepetitionTable, ZobristHistory};
 use crate::general::board::{Board, Strictness};
 use crate::general::common::Description::WithDescription;
 use crate::general::common::{select_name_dyn, Res, Tokens};
+use crate::general::moves::Move;
 use crate::output::OutputBuilder;
 use crate::search::TimeControl;
 use crate::ugi::parse_ugi_position_and_moves;
 use crate::AdjudicationReason::*;
 use crate::GameResult::Aborted;
-use crate::MatchStatus::{NotStarted, Over};
+use crate::MatchStatus::{NotStarted, Ongoing, Over};
 use crate::PlayerResult::{Draw, Lose, Win};
 use crate::ProgramStatus::Run;
 use anyhow::{anyhow, bail};
@@ -96,6 +97,51 @@ impl MatchStatus {
             reason: GameOverReason::Adjudication(AbortedByUser),
         })
     }
+
+    /// Returns an error containing the result if the match is over, because then no more moves can be played.
+    pub fn check_can_move(&self) -> Res<()> {
+        if let Over(result) = self {
+            bail!(
+                "the game is already over: {0} ({1})",
+                result.result,
+                result.reason
+            )
+        }
+        Ok(())
+    }
+
+    /// Changes the status from `NotStarted` to `Ongoing`.
+    /// Starting a match that has already been started or that is already over is an error.
+    pub fn start(&mut self) -> Res<()> {
+        match self {
+            NotStarted => {
+                *self = Ongoing;
+                Ok(())
+            }
+            Ongoing => bail!("The match has already been started"),
+            Over(result) => bail!(
+                "Can't start the match because it is already over: {0} ({1})",
+                result.result,
+                result.reason
+            ),
+        }
+    }
+
+    /// Changes the status to `Over(result)`, which is possible from both `NotStarted` and `Ongoing`.
+    /// A match that is already over can't end again, so this returns an error and keeps the original result.
+    pub fn end(&mut self, result: MatchResult) -> Res<()> {
+        if let Over(old) = self {
+            bail!(
+                "Can't end the match with result {0} ({1}) because it is already over: {2} ({3})",
+                result.result,
+                result.reason,
+                old.result,
+                old.reason
+            )
+        }
+        *self = Over(result);
+        Ok(())
+    }
 }
 
 /// Low-level result of a match from a `MatchManager`'s perspective
@@ -193,9 +239,11 @@ impl GameResult {
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum AdjudicationReason {
     TimeUp,
-    InvalidMove,
+    /// The offending move text and the position (as FEN) in which it was played.
+    InvalidMove(String),
     AbortedByUser,
-    EngineError,
+    /// What went wrong, e.g. a malformed message sent by an engine.
+    EngineError(String),
     Adjudicator(String), // e.g. both engines displayed a winning score for one player for many consecutive moves
 }
 
@@ -203,25 +251,62 @@ impl Display for AdjudicationReason {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             TimeUp => write!(f, "Time up"),
-            InvalidMove => write!(f, "Invalid move"),
+            InvalidMove(details) => write!(f, "Invalid move {details}"),
             AbortedByUser => write!(f, "Aborted by user"),
-            EngineError => write!(f, "Engine error"),
+            EngineError(details) => write!(f, "Engine error: {details}"),
             Adjudicator(reason) => write!(f, "Matchmaker adjudication: {reason}"),
         }
     }
 }
 
+/// Reason for why a game ended according to its rules, as computed by [`Board::game_over_reason_slow`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum NormalGameOverReason {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    /// The player to move doesn't have any pieces left, like in ataxx.
+    NoPiecesLeft,
+    /// There are no empty squares left, like in m,n,k games.
+    BoardFull,
+    /// A player has occupied the given number of squares (or sub boards) in a row.
+    InARow(usize),
+    /// The game is over, but it's unknown why, e.g. because it's been read from a PGN.
+    Unspecified,
+}
+
+impl Display for NormalGameOverReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NormalGameOverReason::Checkmate => write!(f, "checkmate"),
+            NormalGameOverReason::Stalemate => write!(f, "stalemate"),
+            NormalGameOverReason::InsufficientMaterial => write!(f, "insufficient material"),
+            NormalGameOverReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            NormalGameOverReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            NormalGameOverReason::NoPiecesLeft => write!(f, "no pieces left"),
+            NormalGameOverReason::BoardFull => write!(f, "the board is full"),
+            NormalGameOverReason::InARow(k) => write!(f, "{k} in a row"),
+            NormalGameOverReason::Unspecified => write!(f, "normal"),
+        }
+    }
+}
+
 /// Reason for why a match ended.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum GameOverReason {
-    Normal,
+    Normal(NormalGameOverReason),
     Adjudication(AdjudicationReason),
 }
 
 impl Display for GameOverReason {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            GameOverReason::Normal => write!(f, "The game ended normally"),
+            GameOverReason::Normal(NormalGameOverReason::Unspecified) => {
+                write!(f, "The game ended normally")
+            }
+            GameOverReason::Normal(reason) => write!(f, "The game ended normally: {reason}"),
             GameOverReason::Adjudication(a) => write!(f, "{a}"),
         }
     }
@@ -252,6 +337,76 @@ pub fn player_res_to_match_res<C: Color>(game_over: GameOver, color: C) -> Match
     }
 }
 
+/// Parses a move sent by a player, usually an engine, and checks that it is legal in `pos`.
+pub fn parse_player_move<B: Board>(move_text: &str, pos: &B) -> Res<B::Move> {
+    let mov = B::Move::from_text(move_text, pos)?;
+    if !pos.is_move_legal(mov) {
+        bail!("The move '{move_text}' is not legal in the current position")
+    }
+    Ok(mov)
+}
+
+/// The result of a match where the active player of `pos` has sent the invalid move `move_text`:
+/// The offender loses, and the reason includes the move text and the position.
+pub fn invalid_move_result<B: Board>(move_text: &str, pos: &B) -> MatchResult {
+    let game_over = GameOver {
+        result: Lose,
+        reason: GameOverReason::Adjudication(InvalidMove(format!(
+            "'{move_text}' in position '{}'",
+            pos.as_fen()
+        ))),
+    };
+    player_res_to_match_res(game_over, pos.active_player())
+}
+
+/// A description of why `move_text` got rejected in `pos`, meant for debugging protocol or UI bugs.
+/// Lists the position and all legal moves.
+pub fn rejected_move_message<B: Board>(move_text: &str, pos: &B) -> String {
+    let legal_moves = pos
+        .legal_moves_slow()
+        .into_iter()
+        .map(|mov| mov.to_string())
+        .collect::<Vec<_>>();
+    let legal_moves = if legal_moves.is_empty() {
+        "none".to_string()
+    } else {
+        legal_moves.join(" ")
+    };
+    format!(
+        "Rejected move '{move_text}' in position '{0}'. Legal moves: {legal_moves}",
+        pos.as_fen()
+    )
+}
+
+/// A draw adjudication if neither player can reasonably win with the remaining material, see
+/// [`Board::can_reasonably_win`]. This catches more positions than the rules of the game, like two knights against a
+/// lone king in chess, so it's only meant to shorten games between engines.
+pub fn material_draw_result<B: Board>(pos: &B) -> Option<MatchResult> {
+    if pos.can_reasonably_win(B::Color::first()) || pos.can_reasonably_win(B::Color::second()) {
+        return None;
+    }
+    Some(MatchResult {
+        result: GameResult::Draw,
+        reason: GameOverReason::Adjudication(Adjudicator(
+            "Neither player can win with the remaining material".to_string(),
+        )),
+    })
+}
+
+/// A draw adjudication if the current position has occurred `times_occurred >= 3` times. This is meant for games where
+/// repetitions don't end the game by the rules, see [`MatchState::threefold_draw_claim`].
+pub fn repetition_draw_result(times_occurred: usize) -> Option<MatchResult> {
+    if times_occurred < 3 {
+        return None;
+    }
+    Some(MatchResult {
+        result: GameResult::Draw,
+        reason: GameOverReason::Adjudication(Adjudicator(
+            "The position has occurred three times".to_string(),
+        )),
+    })
+}
+
 #[derive(Debug, Clone)]
 #[must_use]
 pub struct OutputArgs {
@@ -362,6 +517,8 @@ pub struct MatchState<B: Board> {
     pub status: ProgramStatus,
     pub mov_hist: Vec<B::Move>,
     pub board_hist: ZobristHistory<B>,
+    /// Contains the same positions as `board_hist`, but allows looking up how often a position has occurred.
+    pub repetitions: RepetitionTable<B>,
     pub pos_before_moves: B,
     pub last_played_color: B::Color,
 }
@@ -373,13 +530,16 @@ impl<B: Board> MatchState<B> {
 
     pub fn make_move(&mut self, mov: B::Move) -> Res<B> {
         debug_assert!(self.board.is_move_pseudolegal(mov));
-        if let Run(Over(result)) = &self.status {
-            bail!(
-                "Cannot play move '{mov}' because the game is already over: {0} ({1}). The position is '{2}'",
-                result.result, result.reason, self.board
-            )
+        if let Run(status) = &self.status {
+            status.check_can_move().map_err(|err| {
+                anyhow!(
+                    "Cannot play move '{mov}' because {err}. The position is '{}'",
+                    self.board
+                )
+            })?;
         }
         self.board_hist.push(&self.board);
+        self.repetitions.add(&self.board);
         self.mov_hist.push(mov);
         self.board = self.board.make_move(mov).ok_or_else(|| {
             anyhow!(
@@ -390,10 +550,39 @@ impl<B: Board> MatchState<B> {
         Ok(self.board)
     }
 
+    /// How often `pos` has occurred in this match, including the current position.
+    pub fn times_occurred(&self, pos: &B) -> usize {
+        self.repetitions.count(pos) + usize::from(pos.zobrist_hash() == self.board.zobrist_hash())
+    }
+
+    /// A draw if the current position has occurred at least three times. This only looks at the match history,
+    /// so it doesn't depend on the engine claiming the draw, and it also works for games where repetitions
+    /// aren't a draw by the rules of the game.
+    pub fn threefold_draw_claim(&self) -> Option<MatchResult> {
+        repetition_draw_result(self.times_occurred(&self.board))
+    }
+
+    /// Iterates over the `(position, move)` pairs of the game so far, where `move` has been played in `position`.
+    /// This replays the moves starting at `pos_before_moves` on the fly, so unlike collecting the positions
+    /// into a `Vec`, it doesn't allocate. Useful for analysis tools that want to look at each move in context,
+    /// like annotating a game or finding repetitions.
+    pub fn positions_and_moves(&self) -> impl Iterator<Item = (B, B::Move)> + '_ {
+        self.mov_hist
+            .iter()
+            .scan(self.pos_before_moves, |pos, &mov| {
+                let before = *pos;
+                *pos = before.make_move(mov)?;
+                Some((before, mov))
+            })
+    }
+
+    /// Keeps the allocated memory of the history, so that repeatedly sending `position ... moves` commands for long
+    /// games doesn't need to reallocate.
     pub fn clear_state(&mut self) {
         self.board = self.pos_before_moves;
         self.mov_hist.clear();
         self.board_hist.clear();
+        self.repetitions.clear();
         self.status = Run(NotStarted);
     }
 
@@ -425,3 +614,366 @@ impl<B: Board> MatchState<B> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::NoHistory;
+    use crate::general::board::Strictness::Relaxed;
+
+    fn result_of<B: Board>(fen: &str) -> (GameResult, String) {
+        let pos = B::from_fen(fen, Relaxed).unwrap();
+        let res = pos.match_result_slow(&NoHistory::default()).unwrap();
+        let GameOverReason::Normal(reason) = res.reason else {
+            panic!("{:?}", res.reason)
+        };
+        (res.result, reason.to_string())
+    }
+
+    fn check_player_results<C: Color>() {
+        for (result, color, expected) in [
+            (Win, C::first(), GameResult::P1Win),
+            (Lose, C::first(), GameResult::P2Win),
+            (Draw, C::first(), GameResult::Draw),
+            (Win, C::second(), GameResult::P2Win),
+            (Lose, C::second(), GameResult::P1Win),
+            (Draw, C::second(), GameResult::Draw),
+        ] {
+            let game_over = GameOver {
+                result,
+                reason: GameOverReason::Adjudication(TimeUp),
+            };
+            let res = player_res_to_match_res(game_over, color);
+            assert_eq!(res.result, expected, "{result:?} {color}");
+            assert_eq!(res.reason, GameOverReason::Adjudication(TimeUp));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn chess_match_result_test() {
+        use crate::games::chess::moves::ChessMove;
+        use crate::games::chess::{ChessColor, Chessboard};
+        check_player_results::<ChessColor>();
+        // white is checkmated
+        let fen = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+        let checkmate = "checkmate".to_string();
+        assert_eq!(
+            result_of::<Chessboard>(fen),
+            (GameResult::P2Win, checkmate.clone())
+        );
+        // black is checkmated
+        let fen = "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4";
+        assert_eq!(result_of::<Chessboard>(fen), (GameResult::P1Win, checkmate));
+        let draws = [
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", "stalemate"),
+            ("8/8/3kb3/8/8/2K5/8/8 w - - 0 1", "insufficient material"),
+        ];
+        for (fen, reason) in draws {
+            assert_eq!(
+                result_of::<Chessboard>(fen),
+                (GameResult::Draw, reason.to_string())
+            );
+        }
+        // FENs where the 50 move rule has already been exceeded are rejected, so play the 100th ply instead
+        let after_100th_ply = |fen: &str, mov: &str| {
+            let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+            let pos = pos
+                .make_move(ChessMove::from_text(mov, &pos).unwrap())
+                .unwrap();
+            pos.match_result_slow(&NoHistory::default()).unwrap()
+        };
+        let res = after_100th_ply("8/8/3k4/8/8/2K5/8/7R w - - 99 80", "h1h2");
+        assert_eq!(res.result, GameResult::Draw);
+        assert_eq!(
+            res.reason.to_string(),
+            "The game ended normally: fifty-move rule"
+        );
+        // a mate on the 100th ply is still a mate
+        let res = after_100th_ply("7k/8/6K1/8/8/8/8/5Q2 w - - 99 80", "f1f8");
+        assert_eq!(res.result, GameResult::P1Win);
+        assert_eq!(
+            res.reason,
+            GameOverReason::Normal(NormalGameOverReason::Checkmate)
+        );
+        let mut hist = ZobristHistory::default();
+        let mut pos = Chessboard::default();
+        for mov in ["g1f3", "g8f6", "f3g1", "f6g8"].repeat(2) {
+            assert!(pos.match_result_slow(&hist).is_none());
+            hist.push(&pos);
+            pos = pos
+                .make_move(ChessMove::from_text(mov, &pos).unwrap())
+                .unwrap();
+        }
+        let res = pos.match_result_slow(&hist).unwrap();
+        assert_eq!(res.result, GameResult::Draw);
+        assert_eq!(
+            res.reason,
+            GameOverReason::Normal(NormalGameOverReason::ThreefoldRepetition)
+        );
+        assert_eq!(
+            res.reason.to_string(),
+            "The game ended normally: threefold repetition"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn invalid_move_result_test() {
+        use crate::games::chess::Chessboard;
+        // the moves sent by an engine as black after 1. e4
+        let pos = Chessboard::from_fen(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            Relaxed,
+        )
+        .unwrap();
+        assert!(parse_player_move("e7e5", &pos).is_ok());
+        for illegal in ["e2e4", "e7e4", "(none)", "0000", "e8e7x", ""] {
+            assert!(parse_player_move(illegal, &pos).is_err(), "{illegal}");
+            let res = invalid_move_result(illegal, &pos);
+            assert_eq!(res.result, GameResult::P1Win);
+            let GameOverReason::Adjudication(InvalidMove(details)) = res.reason else {
+                panic!("{:?}", res.reason)
+            };
+            assert!(details.contains(&format!("'{illegal}'")), "{details}");
+            assert!(details.contains(&pos.as_fen()), "{details}");
+        }
+        let pos = Chessboard::default();
+        let res = invalid_move_result("e2e5", &pos);
+        assert_eq!(res.result, GameResult::P2Win);
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn rejected_move_message_test() {
+        use crate::games::chess::Chessboard;
+        let pos = Chessboard::default();
+        let msg = rejected_move_message("e2e5", &pos);
+        assert!(msg.contains("'e2e5'"), "{msg}");
+        assert!(msg.contains(&pos.as_fen()), "{msg}");
+        let legal = msg.split_once("Legal moves: ").unwrap().1;
+        assert_eq!(legal.split_whitespace().count(), 20, "{msg}");
+        assert!(legal.split_whitespace().any(|m| m == "e2e4"), "{msg}");
+        let mated = Chessboard::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", Relaxed).unwrap();
+        let msg = rejected_move_message("h8g8", &mated);
+        assert!(msg.ends_with("Legal moves: none"), "{msg}");
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn material_draw_test() {
+        use crate::games::chess::Chessboard;
+        let drawn = |fen: &str| {
+            let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+            material_draw_result(&pos).map(|res| {
+                assert!(matches!(
+                    res.reason,
+                    GameOverReason::Adjudication(Adjudicator(_))
+                ));
+                res.result
+            })
+        };
+        // same-colored bishops
+        assert_eq!(
+            drawn("8/8/3kb3/8/8/2KB4/8/8 w - - 0 1"),
+            Some(GameResult::Draw)
+        );
+        // two knights can't force mate, even though this isn't a draw by the rules
+        assert_eq!(
+            drawn("8/8/3k4/8/8/2KNN3/8/8 b - - 0 1"),
+            Some(GameResult::Draw)
+        );
+        assert_eq!(drawn("8/8/3k4/8/8/2K5/8/7R w - - 0 1"), None);
+        assert_eq!(drawn("8/8/3k4/8/4P3/2K5/8/8 w - - 0 1"), None);
+        assert_eq!(drawn("8/8/3k4/8/8/2KBN3/8/8 w - - 0 1"), None);
+        assert_eq!(
+            drawn("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mnk")]
+    fn mnk_match_result_test() {
+        use crate::games::mnk::{MNKBoard, MnkColor};
+        check_player_results::<MnkColor>();
+        // the first player (X) has won
+        assert_eq!(
+            result_of::<MNKBoard>("3 3 3 o XXX/OO1/3"),
+            (GameResult::P1Win, "3 in a row".to_string())
+        );
+        // the second player (O) has won
+        assert_eq!(
+            result_of::<MNKBoard>("3 3 3 x OOO/XX1/X2"),
+            (GameResult::P2Win, "3 in a row".to_string())
+        );
+        assert_eq!(
+            result_of::<MNKBoard>("4 4 4 x OOOO/XX2/X3/XX2"),
+            (GameResult::P2Win, "4 in a row".to_string())
+        );
+        assert_eq!(
+            result_of::<MNKBoard>("3 3 3 o XOX/XOO/OXX"),
+            (GameResult::Draw, "the board is full".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ataxx")]
+    fn ataxx_match_result_test() {
+        use crate::games::ataxx::{AtaxxBoard, AtaxxColor};
+        check_player_results::<AtaxxColor>();
+        assert_eq!(
+            result_of::<AtaxxBoard>("7/7/7/7/7/7/o5o x 0 1"),
+            (GameResult::P2Win, "no pieces left".to_string())
+        );
+        assert_eq!(
+            result_of::<AtaxxBoard>(
+                "xxxxxxx/xxxxxxx/xxxxxxx/xxxxxxx/ooooooo/ooooooo/ooooooo o 0 1"
+            ),
+            (GameResult::P1Win, "the board is full".to_string())
+        );
+        assert_eq!(
+            result_of::<AtaxxBoard>("x5o/7/7/7/7/7/o5x x 100 60"),
+            (GameResult::Draw, "fifty-move rule".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn positions_and_moves_test() {
+        use crate::games::chess::Chessboard;
+        use crate::general::common::tokens;
+        let mut state = MatchState::<Chessboard>::default();
+        assert_eq!(state.positions_and_moves().count(), 0);
+        let input =
+            "fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves c7c5 g1f3 d7d6";
+        state
+            .handle_position(&mut tokens(input), false, Relaxed)
+            .unwrap();
+        let pairs = state.positions_and_moves().collect::<Vec<_>>();
+        assert_eq!(pairs.len(), state.mov_hist.len());
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].0, state.pos_before_moves);
+        let mut pos = state.pos_before_moves;
+        for ((p, mov), expected) in pairs.iter().zip(state.mov_hist.iter()) {
+            assert_eq!(*p, pos);
+            assert_eq!(mov, expected);
+            pos = pos.make_move(*mov).unwrap();
+        }
+        assert_eq!(pos, state.board);
+    }
+
+    #[test]
+    fn match_status_transition_test() {
+        let mut status = MatchStatus::default();
+        assert!(status.check_can_move().is_ok());
+        status.start().unwrap();
+        assert_eq!(status, Ongoing);
+        assert!(status.check_can_move().is_ok());
+        assert!(status.start().is_err());
+        let result = MatchResult {
+            result: GameResult::P1Win,
+            reason: GameOverReason::Adjudication(TimeUp),
+        };
+        status.end(result.clone()).unwrap();
+        assert!(status.start().is_err());
+        let draw = MatchResult {
+            result: GameResult::Draw,
+            reason: GameOverReason::Normal(NormalGameOverReason::Unspecified),
+        };
+        assert!(status.end(draw).is_err());
+        assert_eq!(status, Over(result));
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn move_after_game_over_test() {
+        use crate::games::chess::Chessboard;
+        let mut state = MatchState::<Chessboard>::default();
+        let mov = Move::from_text("e2e4", &state.board).unwrap();
+        state.make_move(mov).unwrap();
+        state.status = Run(Over(MatchResult {
+            result: GameResult::P2Win,
+            reason: GameOverReason::Adjudication(TimeUp),
+        }));
+        let mov = Move::from_text("e7e5", &state.board).unwrap();
+        let err = state.make_move(mov).unwrap_err().to_string();
+        assert!(err.contains(&GameResult::P2Win.to_string()), "{err}");
+        assert!(err.contains(&TimeUp.to_string()), "{err}");
+        assert_eq!(state.mov_hist.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn repetition_table_test() {
+        use crate::games::chess::Chessboard;
+        use crate::general::common::tokens;
+        let mut state = MatchState::<Chessboard>::default();
+        let input = "startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1";
+        state
+            .handle_position(&mut tokens(input), false, Relaxed)
+            .unwrap();
+        let startpos = Chessboard::default();
+        assert_eq!(state.times_occurred(&startpos), 2);
+        assert_eq!(state.times_occurred(&state.board), 2);
+        assert!(state.threefold_draw_claim().is_none());
+        let mov = Move::from_text("f6g8", &state.board).unwrap();
+        state.make_move(mov).unwrap();
+        assert_eq!(state.board.zobrist_hash(), startpos.zobrist_hash());
+        assert_eq!(state.times_occurred(&startpos), 3);
+        let res = state.threefold_draw_claim().unwrap();
+        assert_eq!(res.result, GameResult::Draw);
+        assert!(matches!(
+            res.reason,
+            GameOverReason::Adjudication(Adjudicator(_))
+        ));
+        state.clear_state();
+        assert_eq!(state.times_occurred(&startpos), 1);
+        assert!(state.threefold_draw_claim().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn long_position_command_test() {
+        use crate::games::chess::Chessboard;
+        use crate::general::board::random_opening;
+        use crate::general::common::tokens;
+        use itertools::Itertools;
+
+        let moves = (0..100)
+            .map(|seed| random_opening(Chessboard::default(), 300, seed))
+            .find(|moves| moves.len() == 300)
+            .unwrap();
+        let mut expected = Chessboard::default();
+        for &mov in &moves {
+            expected = expected.make_move(mov).unwrap();
+        }
+        let input = format!("startpos moves {}", moves.iter().join(" "));
+        let mut state = MatchState::<Chessboard>::default();
+        let mut capacities = None;
+        for _ in 0..10 {
+            state
+                .handle_position(&mut tokens(&input), false, Relaxed)
+                .unwrap();
+            assert_eq!(state.board, expected);
+            // one history entry per move
+            assert_eq!(state.mov_hist.len(), 300);
+            assert_eq!(state.board_hist.len(), 300);
+            // sending the same command again reuses the allocations of the previous command
+            let new_capacities = (state.mov_hist.capacity(), state.board_hist.0.capacity());
+            assert_eq!(*capacities.get_or_insert(new_capacities), new_capacities);
+        }
+    }
+
+    #[test]
+    fn aborted_test() {
+        let MatchStatus::Over(res) = MatchStatus::aborted() else {
+            panic!()
+        };
+        assert_eq!(res.result, Aborted);
+        assert_eq!(res.result.check_finished(), None);
+        assert!(f64::from(res.result).is_nan());
+        assert_eq!(GameResult::from_str("*").unwrap(), Aborted);
+        assert_eq!(GameResult::Draw.check_finished(), Some(GameResult::Draw));
+    }
+}
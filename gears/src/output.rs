@@ -10,14 +10,16 @@ use crate::general::board::{Board, RectangularBoard};
 use crate::general::common::{NamedEntity, Res, Tokens};
 use crate::output::chess::ChessOutputBuilder;
 use crate::output::engine_state::EngineStateOutputBuilder;
+use crate::output::fen_trace::FenTraceOutputBuilder;
 use crate::output::logger::LoggerBuilder;
 use crate::output::text_output::{DisplayType, TextOutputBuilder};
 use crate::output::Message::*;
 use crate::search::SearchInfo;
-use crate::{GameOverReason, GameState, MatchResult, MatchStatus};
+use crate::{GameOverReason, GameState, MatchResult, MatchStatus, NormalGameOverReason};
 
 pub mod chess;
 pub mod engine_state;
+pub mod fen_trace;
 pub mod logger;
 pub mod pgn;
 pub mod text_output;
@@ -48,7 +50,11 @@ pub fn game_over_message(result: MatchResult) -> String {
     let mut msg = String::new();
     writeln!(msg, "!!! {} !!!", result.result).unwrap();
     match result.reason {
-        GameOverReason::Normal => msg,
+        GameOverReason::Normal(NormalGameOverReason::Unspecified) => msg,
+        GameOverReason::Normal(reason) => {
+            writeln!(msg, "({reason})").unwrap();
+            msg
+        }
         GameOverReason::Adjudication(reason) => {
             writeln!(msg, "({reason})").unwrap();
             msg
@@ -115,6 +121,11 @@ pub trait Output<B: Board>: AbstractOutput {
     fn update_engine_info(&mut self, engine_name: &str, info: &SearchInfo<B>) {
         self.display_message(Info, &format!("{engine_name}: {info}"));
     }
+
+    /// Called periodically while a player is thinking, so that interactive outputs can show that the player is working.
+    fn show_thinking(&mut self, _m: &dyn GameState<B>) {
+        // do nothing
+    }
 }
 
 pub trait OutputBuilderOption<B: Board> {
@@ -161,6 +172,7 @@ pub fn required_outputs<B: Board>() -> OutputList<B> {
     )));
     #[allow(clippy::box_default)]
     res.push(Box::new(LoggerBuilder::default()));
+    res.push(Box::<FenTraceOutputBuilder>::default());
     res
 }
 
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use crate::general::board::Board;
+use crate::general::common::{NamedEntity, Res, StaticallyNamedEntity};
+use crate::output::text_output::TextStream;
+use crate::output::{AbstractOutput, Message, Output, OutputBox, OutputBuilder, OutputOpts};
+use crate::GameState;
+
+/// Prints the FEN of the current position each time it changes, and nothing else.
+/// One FEN per line makes it easy to pipe the output into external tools.
+#[derive(Debug)]
+pub struct FenTraceOutput {
+    stream: TextStream,
+    last_fen: Option<String>,
+}
+
+impl FenTraceOutput {
+    pub fn new(stream: TextStream) -> Self {
+        Self {
+            stream,
+            last_fen: None,
+        }
+    }
+
+    /// Returns the FEN of `pos` unless it's the same as the last returned FEN.
+    fn next_fen<B: Board>(&mut self, pos: &B) -> Option<String> {
+        let fen = pos.as_fen();
+        if self.last_fen.as_ref() == Some(&fen) {
+            return None;
+        }
+        self.last_fen = Some(fen.clone());
+        Some(fen)
+    }
+}
+
+impl NamedEntity for FenTraceOutput {
+    fn short_name(&self) -> String {
+        FenTraceOutputBuilder::static_short_name().to_string()
+    }
+
+    fn long_name(&self) -> String {
+        FenTraceOutputBuilder::static_long_name().to_string()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(FenTraceOutputBuilder::static_description())
+    }
+}
+
+impl AbstractOutput for FenTraceOutput {
+    fn output_name(&self) -> String {
+        self.stream.name()
+    }
+
+    fn display_message(&mut self, _typ: Message, _message: &str) {
+        // do nothing, the output should only consist of FENs
+    }
+}
+
+impl<B: Board> Output<B> for FenTraceOutput {
+    fn show(&mut self, m: &dyn GameState<B>, _opts: OutputOpts) {
+        if let Some(fen) = self.next_fen(&m.get_board()) {
+            _ = writeln!(self.stream.stream(), "{fen}");
+        }
+    }
+
+    fn as_string(&self, m: &dyn GameState<B>, _opts: OutputOpts) -> String {
+        m.get_board().as_fen()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct FenTraceOutputBuilder {
+    stream_name: String,
+}
+
+impl StaticallyNamedEntity for FenTraceOutputBuilder {
+    fn static_short_name() -> impl Display {
+        "fen_trace"
+    }
+
+    fn static_long_name() -> String {
+        "FEN Trace Output".to_string()
+    }
+
+    fn static_description() -> String {
+        "Prints the FEN of each new position on its own line. \
+        Accepts 'stdout' (the default), 'stderr', or a filename as option"
+            .to_string()
+    }
+}
+
+impl<B: Board> OutputBuilder<B> for FenTraceOutputBuilder {
+    fn for_engine(&mut self, _state: &dyn GameState<B>) -> Res<OutputBox<B>> {
+        let stream = TextStream::from_words(self.stream_name.split_whitespace(), "stdout")?;
+        Ok(Box::new(FenTraceOutput::new(stream)))
+    }
+
+    fn add_option(&mut self, option: String) -> Res<()> {
+        self.stream_name = option;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "chess")]
+mod tests {
+    use super::*;
+    use crate::games::chess::Chessboard;
+    use crate::general::board::Strictness::Strict;
+    use crate::general::moves::Move;
+    use std::io::stdout;
+
+    #[test]
+    fn fen_trace_test() {
+        let mut output = FenTraceOutput::new(TextStream::Stdout(stdout()));
+        let pos = Chessboard::default();
+        assert_eq!(output.next_fen(&pos), Some(pos.as_fen()));
+        assert_eq!(output.next_fen(&pos), None);
+        let mut trace = vec![];
+        let mut pos = pos;
+        for mov in ["e2e4", "e7e5"] {
+            pos = pos.make_move(Move::from_text(mov, &pos).unwrap()).unwrap();
+            trace.extend(output.next_fen(&pos));
+            assert_eq!(output.next_fen(&pos), None);
+        }
+        let expected = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        ];
+        assert_eq!(trace, expected);
+        for fen in expected {
+            assert!(Chessboard::from_fen(fen, Strict).is_ok());
+        }
+    }
+}
@@ -20,7 +20,7 @@
 
 use crate::games::{BoardHistory, Color};
 use crate::general::board::Board;
-use crate::general::board::Strictness::Relaxed;
+use crate::general::board::Strictness;
 use crate::general::common::{parse_bool_from_str, parse_int_from_str, Res};
 use crate::general::moves::ExtendedFormat::Standard;
 use crate::general::moves::Move;
@@ -31,7 +31,10 @@ use crate::output::pgn::TagPair::{
 };
 use crate::MatchStatus::*;
 use crate::ProgramStatus::Run;
-use crate::{AdjudicationReason, GameOverReason, GameResult, GameState, MatchResult, MatchState};
+use crate::{
+    AdjudicationReason, GameOverReason, GameResult, GameState, MatchResult, MatchState,
+    MatchStatus, NormalGameOverReason,
+};
 use anyhow::{anyhow, bail};
 use colored::Colorize;
 use std::fmt::Display;
@@ -44,21 +47,7 @@ pub fn match_to_pgn_string<B: Board>(m: &dyn GameState<B>) -> String {
         Over(r) => r.result.to_canonical_string(),
         _ => "*".to_string(),
     };
-    let status = m.match_status();
-    let termination = match &status {
-        NotStarted => "not started",
-        Ongoing => "unterminated",
-        Over(ref res) => match res.reason {
-            GameOverReason::Normal => "normal",
-            GameOverReason::Adjudication(ref reason) => match reason {
-                AdjudicationReason::TimeUp => "time forfeit",
-                AdjudicationReason::InvalidMove => "rules infraction",
-                AdjudicationReason::AbortedByUser => "abandoned",
-                AdjudicationReason::EngineError => "emergency",
-                AdjudicationReason::Adjudicator(ref reason) => reason,
-            },
-        },
-    };
+    let (termination, details) = termination(&m.match_status());
     let mut res = format!(
         "[Event \"{event}\"]\n\
         [Site \"{site}\"]\n\
@@ -85,24 +74,111 @@ pub fn match_to_pgn_string<B: Board>(m: &dyn GameState<B>) -> String {
         p1_name = B::Color::first(),
         p2_name = B::Color::second(),
     );
-    let mut board = m.initial_pos();
-    for (ply, mov) in m.move_history().iter().enumerate() {
+    res += &movetext(
+        m.initial_pos(),
+        m.move_history(),
+        details.as_deref(),
+        &result,
+    );
+    res
+}
+
+/// The value of the `Termination` tag, which must be one of the values defined by the PGN standard, and a more
+/// detailed description of why the game ended (if there is one), which gets written as a comment.
+fn termination(status: &MatchStatus) -> (&'static str, Option<String>) {
+    match status {
+        NotStarted | Ongoing => ("unterminated", None),
+        Over(res) => {
+            let termination = match &res.reason {
+                GameOverReason::Normal(_) => "normal",
+                GameOverReason::Adjudication(reason) => match reason {
+                    AdjudicationReason::TimeUp => "time forfeit",
+                    AdjudicationReason::InvalidMove(_) => "rules infraction",
+                    AdjudicationReason::AbortedByUser => "abandoned",
+                    AdjudicationReason::EngineError(_) => "emergency",
+                    AdjudicationReason::Adjudicator(_) => "adjudication",
+                },
+            };
+            let details = match &res.reason {
+                GameOverReason::Normal(NormalGameOverReason::Unspecified) => None,
+                GameOverReason::Normal(reason) => Some(reason.to_string()),
+                GameOverReason::Adjudication(reason) => Some(reason.to_string()),
+            };
+            (termination, details)
+        }
+    }
+}
+
+/// Each move of the first player starts a new line with the move number. Uses SAN for chess and the standard
+/// notation of the game otherwise. Ends with the result token.
+/// The `comment` gets written before the result.
+fn movetext<B: Board>(
+    initial_pos: B,
+    moves: &[B::Move],
+    comment: Option<&str>,
+    result: &str,
+) -> String {
+    let mut res = String::new();
+    let mut board = initial_pos;
+    for (ply, mov) in moves.iter().enumerate() {
         let mov_str = mov.extended_formatter(board, Standard);
-        if ply % 2 == 0 {
-            res += &format!("\n{}. {mov_str}", (ply + 1) / 2 + 1);
+        let move_nr = board.fullmove_ctr_1_based();
+        if board.active_player().is_first() {
+            res += &format!("\n{move_nr}. {mov_str}");
+        } else if ply == 0 {
+            res += &format!("\n{move_nr}... {mov_str}");
         } else {
-            if ply == 0 && !m.initial_pos().active_player().is_first() {
-                res += &format!("\n1... {mov_str}");
-            }
             res += &format!(" {mov_str}");
         }
         board = board.make_move(*mov).unwrap();
     }
+    if let Some(comment) = comment {
+        // a closing brace would end the comment early
+        res += &format!(" {{{}}}", comment.replace('}', ")"));
+    }
     res.push(' ');
-    res += &result;
+    res += result;
     res
 }
 
+impl<B: Board> MatchState<B> {
+    /// Unlike [`match_to_pgn_string`], this doesn't need a [`GameState`], so tags that a `MatchState` doesn't know
+    /// about, like the event or the player names, are set to `?`. The `SetUp` and `FEN` tags are only written if
+    /// the game didn't start from the default position.
+    pub fn to_pgn(&self) -> String {
+        let result = match &self.status {
+            Run(Over(r)) => r.result.to_canonical_string(),
+            _ => "*".to_string(),
+        };
+        let mut res = format!(
+            "[Event \"?\"]\n\
+            [Site \"?\"]\n\
+            [Date \"????.??.??\"]\n\
+            [Round \"?\"]\n\
+            [{p1} \"?\"]\n\
+            [{p2} \"?\"]\n\
+            [Result \"{result}\"]\n",
+            p1 = B::Color::first(),
+            p2 = B::Color::second(),
+        );
+        if self.pos_before_moves != B::default() {
+            res += &format!(
+                "[SetUp \"1\"]\n[FEN \"{}\"]\n",
+                self.pos_before_moves.as_fen()
+            );
+        }
+        res.push('\n');
+        res += movetext(self.pos_before_moves, &self.mov_hist, None, &result).trim_start();
+        res
+    }
+
+    /// Replays the moves of a PGN, starting from the position of the `FEN` tag or the default position.
+    /// All other tag pairs are ignored, use [`parse_pgn`] to get them as well.
+    pub fn from_pgn(pgn: &str, strictness: Strictness) -> Res<Self> {
+        Ok(parse_pgn(pgn, strictness)?.game)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct UnknownTagPair {
     pub tag: String,
@@ -200,7 +276,12 @@ impl TagPair {
             "WhiteType" => WhiteType(PlayerType::from_str(&value)?),
             "BlackType" => BlackType(PlayerType::from_str(&value)?),
             "FEN" => Fen(value.trim_ascii().to_string()),
-            "SetUp" => SetUp(parse_bool_from_str(value.trim_ascii(), "set up")?),
+            // the PGN standard uses "0" and "1"
+            "SetUp" => SetUp(match value.trim_ascii() {
+                "0" => false,
+                "1" => true,
+                value => parse_bool_from_str(value, "set up")?,
+            }),
             _ => Other(UnknownTagPair { tag, value }),
         })
     }
@@ -221,7 +302,7 @@ impl TagPair {
             TagPair::WhiteType(value) => value.to_string(),
             TagPair::BlackType(value) => value.to_string(),
             Other(value) => value.value.clone(),
-            TagPair::SetUp(value) => value.to_string(),
+            TagPair::SetUp(value) => u8::from(*value).to_string(),
             TagPair::Fen(value) => value.clone(),
         }
     }
@@ -238,16 +319,18 @@ struct PgnParser<'a, B: Board> {
     byte_idx: usize,
     original_input: &'a str,
     unread: Peekable<Chars<'a>>,
+    strictness: Strictness,
     res: PgnData<B>,
 }
 
 impl<'a, B: Board> PgnParser<'a, B> {
-    fn new(input: &'a str) -> Self {
+    fn new(input: &'a str, strictness: Strictness) -> Self {
         Self {
             first_in_line: true,
             byte_idx: 0,
             original_input: input,
             unread: input.chars().peekable(),
+            strictness,
             res: PgnData::default(),
         }
     }
@@ -352,7 +435,8 @@ impl<'a, B: Board> PgnParser<'a, B> {
             if c == '[' {
                 let tag_pair = self.parse_tag_pair()?;
                 if let TagPair::Fen(fen) = &tag_pair {
-                    self.res.game.board = B::from_fen(fen, Relaxed)?;
+                    self.res.game.board = B::from_fen(fen, self.strictness)?;
+                    self.res.game.pos_before_moves = self.res.game.board;
                 }
                 self.res.tag_pairs.push(tag_pair);
                 self.ignore_whitespace()?;
@@ -369,12 +453,20 @@ impl<'a, B: Board> PgnParser<'a, B> {
         if self.unread.peek().is_none() {
             return Ok(());
         }
+        // Numeric Annotation Glyphs like `$1` are ignored
+        if self.unread.peek().is_some_and(|&c| c == '$') {
+            self.eat();
+            while self.unread.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.eat();
+            }
+            return Ok(());
+        }
         let string = &self.original_input[self.byte_idx..];
         let next_word = string.split_ascii_whitespace().next().unwrap_or_default();
         if let Ok(result) = GameResult::from_str(next_word) {
             self.res.game.status = Run(Over(MatchResult {
                 result,
-                reason: GameOverReason::Normal,
+                reason: GameOverReason::Normal(NormalGameOverReason::Unspecified),
             }));
             for _ in 0..next_word.len() {
                 self.eat().unwrap();
@@ -393,18 +485,35 @@ impl<'a, B: Board> PgnParser<'a, B> {
             self.ignore_whitespace()?;
         }
         let string = &self.original_input[self.byte_idx..];
-        if let Run(Over(_)) = self.res.game.status {
-            bail!(
-                "The game has already ended, cannot parse additional moves at start of '{}'",
-                string.bold()
-            )
+        if let Run(status) = &self.res.game.status {
+            if let Err(err) = status.check_can_move() {
+                bail!(
+                    "Cannot parse additional moves at start of '{0}' because {err}",
+                    string.bold()
+                )
+            }
         }
+        let token = string
+            .split(|c: char| c.is_whitespace() || c == '{')
+            .next()
+            .unwrap_or_default();
         let prev_board = &self.res.game.board;
-        let (remaining, mov) = B::Move::parse_extended_text(string, prev_board)?;
+        // PGNs should use the standard notation (SAN for chess), but also accept the compact notation
+        let (remaining, mov) = match B::Move::parse_extended_text(string, prev_board) {
+            Ok(res) => res,
+            Err(err) => match B::Move::from_compact_text(token, prev_board) {
+                Ok(mov) if prev_board.is_move_pseudolegal(mov) => (&string[token.len()..], mov),
+                _ => bail!("Couldn't parse move '{}': {err}", token.red()),
+            },
+        };
         let Some(new_board) = prev_board.make_move(mov) else {
-            bail!("Illegal psuedolegal move '{}'", mov.to_string().red());
+            bail!(
+                "Illegal move '{}' (pseudolegal but not legal) in position '{prev_board}'",
+                token.red()
+            );
         };
         self.res.game.board_hist.push(prev_board);
+        self.res.game.repetitions.add(prev_board);
         self.res.game.mov_hist.push(mov);
         self.res.game.board = new_board;
         if let Some(res) = self
@@ -414,7 +523,7 @@ impl<'a, B: Board> PgnParser<'a, B> {
             .match_result_slow(&self.res.game.board_hist)
         {
             if let Run(st) = &mut self.res.game.status {
-                *st = Over(res);
+                st.end(res)?;
             }
         }
         for _ in 0..string.len() - remaining.len() {
@@ -437,8 +546,8 @@ impl<'a, B: Board> PgnParser<'a, B> {
     }
 }
 
-pub fn parse_pgn<B: Board>(pgn: &str) -> Res<PgnData<B>> {
-    let mut parser: PgnParser<'_, B> = PgnParser::new(pgn);
+pub fn parse_pgn<B: Board>(pgn: &str, strictness: Strictness) -> Res<PgnData<B>> {
+    let mut parser: PgnParser<'_, B> = PgnParser::new(pgn, strictness);
     parser.parse().map_err(|err| {
         anyhow!(
             "{err}. Unconsumed input: '{}'",
@@ -453,12 +562,13 @@ mod tests {
     use crate::games::chess::moves::ChessMove;
     use crate::games::chess::pieces::ChessPieceType::Bishop;
     use crate::games::chess::squares::ChessSquare;
-    use crate::games::chess::Chessboard;
+    use crate::games::chess::{ChessColor, Chessboard};
+    use crate::general::board::Strictness::{Relaxed, Strict};
 
     #[test]
     fn parse_one_ply_pgn() {
         let pgn = "1. e4";
-        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn);
+        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn, Relaxed);
         let data = parser.parse().unwrap();
         let pos = Chessboard::default();
         let pos = pos
@@ -480,7 +590,7 @@ mod tests {
     #[test]
     fn parse_two_ply_pgn() {
         let pgn = "{this}1e4{is} \n%a\nd5 {test}";
-        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn);
+        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn, Relaxed);
 
         let data = parser.parse().unwrap();
         let pos = Chessboard::default();
@@ -496,6 +606,152 @@ mod tests {
         assert!(data.tag_pairs.is_empty());
     }
 
+    #[test]
+    fn match_state_to_pgn_test() {
+        let mut game = MatchState::<Chessboard>::default();
+        for mov in ["e2e4", "e7e5", "g1f3"] {
+            let mov = ChessMove::from_compact_text(mov, &game.board).unwrap();
+            game.make_move(mov).unwrap();
+        }
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n"), "{pgn}");
+        assert!(pgn.contains("[White \"?\"]\n[Black \"?\"]\n[Result \"*\"]\n"));
+        assert!(!pgn.contains("FEN"), "{pgn}");
+        assert!(pgn.ends_with("\n\n1. e4 e5\n2. Nf3 *"), "{pgn}");
+        let parsed = parse_pgn::<Chessboard>(&pgn, Strict).unwrap();
+        assert_eq!(parsed.game.mov_hist, game.mov_hist);
+        assert_eq!(parsed.game.board, game.board);
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 17";
+        let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+        let mut game = MatchState {
+            board: pos,
+            pos_before_moves: pos,
+            ..Default::default()
+        };
+        for mov in ["e8g8", "e1c1", "f8f1"] {
+            let mov = ChessMove::from_compact_text(mov, &game.board).unwrap();
+            game.make_move(mov).unwrap();
+        }
+        game.status = Run(Over(MatchResult {
+            result: GameResult::P2Win,
+            reason: GameOverReason::Normal(NormalGameOverReason::Unspecified),
+        }));
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Result \"0-1\"]\n[SetUp \"1\"]\n"), "{pgn}");
+        assert!(
+            pgn.contains(&format!("[FEN \"{}\"]", pos.as_fen())),
+            "{pgn}"
+        );
+        assert!(pgn.ends_with("\n\n17... O-O\n18. O-O-O Rf1 0-1"), "{pgn}");
+        let parsed = parse_pgn::<Chessboard>(&pgn, Strict).unwrap();
+        assert_eq!(parsed.game.pos_before_moves, pos);
+        assert_eq!(parsed.game.mov_hist, game.mov_hist);
+        assert!(matches!(parsed.game.status, Run(Over(r)) if r.result == GameResult::P2Win));
+    }
+
+    #[test]
+    fn termination_test() {
+        let over = |reason| {
+            Over(MatchResult {
+                result: GameResult::Draw,
+                reason,
+            })
+        };
+        assert_eq!(termination(&Ongoing), ("unterminated", None));
+        assert_eq!(termination(&NotStarted), ("unterminated", None));
+        let normal = |reason| over(GameOverReason::Normal(reason));
+        assert_eq!(
+            termination(&normal(NormalGameOverReason::Stalemate)),
+            ("normal", Some("stalemate".to_string()))
+        );
+        assert_eq!(
+            termination(&normal(NormalGameOverReason::Unspecified)),
+            ("normal", None)
+        );
+        let adjudication = |reason| over(GameOverReason::Adjudication(reason));
+        for (reason, expected) in [
+            (AdjudicationReason::TimeUp, "time forfeit"),
+            (
+                AdjudicationReason::InvalidMove("e1e8".to_string()),
+                "rules infraction",
+            ),
+            (AdjudicationReason::AbortedByUser, "abandoned"),
+            (
+                AdjudicationReason::EngineError("crashed".to_string()),
+                "emergency",
+            ),
+            (
+                AdjudicationReason::Adjudicator("resigned".to_string()),
+                "adjudication",
+            ),
+        ] {
+            let details = reason.to_string();
+            assert_eq!(
+                termination(&adjudication(reason)),
+                (expected, Some(details))
+            );
+        }
+        // the details get written as a comment, which the parser ignores
+        let pos = Chessboard::default();
+        let text = movetext(pos, &[], Some("threefold {repetition}"), "1/2-1/2");
+        assert_eq!(text, " {threefold {repetition)} 1/2-1/2");
+        let pgn = format!("[Result \"1/2-1/2\"]\n\n1. e4 e5{text}");
+        let parsed = parse_pgn::<Chessboard>(&pgn, Strict).unwrap();
+        assert_eq!(parsed.game.mov_hist.len(), 2);
+    }
+
+    #[test]
+    fn match_state_from_pgn_test() {
+        let pgn = "[Event \"?\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\"]\n\n\
+            1... e5 {a comment} 2. Nf3 $1 Nc6 $14 {another comment}\n3. f1c4 Nf6 1-0";
+        let game = MatchState::<Chessboard>::from_pgn(pgn, Strict).unwrap();
+        let mut expected = MatchState {
+            board: game.pos_before_moves,
+            ..Default::default()
+        };
+        assert_eq!(game.pos_before_moves.active_player(), ChessColor::Black);
+        for mov in ["e7e5", "g1f3", "b8c6", "f1c4", "g8f6"] {
+            let mov = ChessMove::from_compact_text(mov, &expected.board).unwrap();
+            expected.make_move(mov).unwrap();
+        }
+        assert_eq!(game.mov_hist, expected.mov_hist);
+        assert_eq!(game.board_hist, expected.board_hist);
+        assert_eq!(game.board, expected.board);
+        assert_eq!(game.times_occurred(&game.pos_before_moves), 1);
+        assert!(matches!(game.status, Run(Over(r)) if r.result == GameResult::P1Win));
+
+        for (pgn, token) in [
+            ("1. e4 e5 2. Ke3", "Ke3"),
+            ("1. e4 e5 2. xyz Nc3", "xyz"),
+            ("[FEN \"8/8/8/8/8/8/8/R4K1k w - - 0 1\"]\n1. Kg2", "Kg2"),
+        ] {
+            let err = MatchState::<Chessboard>::from_pgn(pgn, Strict)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(token), "{err}");
+        }
+        // the halfmove clock is larger than the number of played plies
+        let pgn = "[FEN \"4k3/8/8/8/8/8/8/4K3 w - - 10 1\"]\n1. Kd1";
+        assert!(MatchState::<Chessboard>::from_pgn(pgn, Strict).is_err());
+        assert!(MatchState::<Chessboard>::from_pgn(pgn, Relaxed).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "mnk")]
+    fn mnk_match_state_to_pgn_test() {
+        use crate::games::mnk::MNKBoard;
+        let mut game = MatchState::<MNKBoard>::default();
+        for mov in ["b2", "a1"] {
+            let mov = <MNKBoard as Board>::Move::from_compact_text(mov, &game.board).unwrap();
+            game.make_move(mov).unwrap();
+        }
+        let pgn = game.to_pgn();
+        assert!(pgn.ends_with("\n\n1. b2 a1 *"), "{pgn}");
+        let parsed = parse_pgn::<MNKBoard>(&pgn, Strict).unwrap();
+        assert_eq!(parsed.game.mov_hist, game.mov_hist);
+    }
+
     #[test]
     // pgn adapted from https://en.wikipedia.org/wiki/Portable_Game_Notation
     fn parse_simple_pgn() {
@@ -523,7 +779,7 @@ hxg5 29.b3 Ke6 30.a3 Kd6 31.axb4 cxb4 32.Ra5 Nd5 33.f3 Bc8 34.Kf2 Bf5
  %}
 35.Ra7 g6 36.Ra6+ Kc5 37.Ke1{}Nf4 38.g3 Nxh3 39.Kd2 Kb5 40.Rd6 Kc5 41.Ra6
 Nf2 42.g4 Bd3 43.Re6 1/2-1/2"#;
-        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn);
+        let mut parser: PgnParser<'_, Chessboard> = PgnParser::new(pgn, Relaxed);
         let data = parser.parse().unwrap();
         assert_eq!(data.tag_pairs.len(), 7);
         assert!(matches!(data.tag_pairs[0], Event(_)));
@@ -13,12 +13,15 @@ use crate::GameState;
 use crate::MatchStatus::*;
 use anyhow::{anyhow, bail};
 use colored::Colorize;
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::Command;
 use std::fmt;
 use std::fs::File;
-use std::io::{stderr, stdout, Stderr, Stdout, Write};
+use std::io::{stderr, stdout, IsTerminal, Stderr, Stdout, Write};
 use std::mem::swap;
 use std::path::Path;
 use std::str::SplitWhitespace;
+use std::time::Duration;
 use strum_macros::EnumIter;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -86,6 +89,14 @@ impl TextStream {
             TextStream::Stderr(_) => "stderr".to_string(),
         }
     }
+
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            TextStream::File(_, _) => false,
+            TextStream::Stdout(out) => out.is_terminal(),
+            TextStream::Stderr(err) => err.is_terminal(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -107,6 +118,58 @@ impl TextWriter {
     }
 }
 
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Shows that a player is thinking by redrawing a spinner and the elapsed time in place.
+/// This needs control codes, so if the output isn't a terminal (e.g. because it's piped into a file),
+/// nothing gets shown at all.
+#[derive(Debug, Default)]
+pub struct ThinkingIndicator {
+    is_tty: bool,
+    frame: usize,
+    visible: bool,
+}
+
+impl ThinkingIndicator {
+    pub fn new(is_tty: bool) -> Self {
+        Self {
+            is_tty,
+            frame: 0,
+            visible: false,
+        }
+    }
+
+    /// Returns the text that replaces the current line with the next frame of the animation.
+    pub fn render(&mut self, name: &str, elapsed: Duration) -> String {
+        if !self.is_tty {
+            return String::new();
+        }
+        let spinner = SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()];
+        self.frame += 1;
+        self.visible = true;
+        format!(
+            "{clear}{spinner} {name} is thinking... {time:.1}s",
+            clear = Self::clear_line(),
+            time = elapsed.as_secs_f64()
+        )
+    }
+
+    /// Returns the text that removes the animation, or an empty string if it isn't currently shown.
+    pub fn clear(&mut self) -> String {
+        if !self.visible {
+            return String::new();
+        }
+        self.visible = false;
+        Self::clear_line()
+    }
+
+    fn clear_line() -> String {
+        let mut res = "\r".to_string();
+        Clear(ClearType::CurrentLine).write_ansi(&mut res).unwrap();
+        res
+    }
+}
+
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, EnumIter)]
 pub enum DisplayType {
     #[default]
@@ -264,16 +327,26 @@ struct TextOutput {
     writer: TextWriter,
     to_text: BoardToText,
     name: Option<String>,
+    thinking: ThinkingIndicator,
 }
 
 impl TextOutput {
     fn new(typ: DisplayType, is_engine: bool, writer: TextWriter, name: Option<String>) -> Self {
         Self {
             to_text: BoardToText { typ, is_engine },
+            thinking: ThinkingIndicator::new(writer.stream.is_terminal()),
             writer,
             name,
         }
     }
+
+    fn write_raw(&mut self, text: &str) {
+        if !text.is_empty() {
+            let stream = self.writer.stream.stream();
+            _ = write!(stream, "{text}");
+            _ = stream.flush();
+        }
+    }
 }
 
 impl NamedEntity for TextOutput {
@@ -300,14 +373,37 @@ impl AbstractOutput for TextOutput {
     }
 
     fn display_message(&mut self, typ: Message, message: &str) {
+        let clear = self.thinking.clear();
+        self.write_raw(&clear);
         self.writer.display_message(typ, message);
     }
 }
 
 impl<B: Board> Output<B> for TextOutput {
+    fn show(&mut self, m: &dyn GameState<B>, opts: OutputOpts) {
+        let clear = self.thinking.clear();
+        self.write_raw(&clear);
+        println!("{}", self.as_string(m, opts));
+    }
+
     fn as_string(&self, m: &dyn GameState<B>, opts: OutputOpts) -> String {
         self.to_text.as_string(m, opts)
     }
+
+    fn show_thinking(&mut self, m: &dyn GameState<B>) {
+        if self.to_text.typ == MsgOnly {
+            return;
+        }
+        let color = m.get_board().active_player();
+        let text = match m.thinking_since(color) {
+            Some(since) => {
+                let name = m.player_name(color).unwrap_or_else(|| color.to_string());
+                self.thinking.render(&name, since.elapsed())
+            }
+            None => self.thinking.clear(),
+        };
+        self.write_raw(&text);
+    }
 }
 
 #[derive(Default, Debug)]
@@ -545,7 +641,7 @@ fn write_horizontal_bar<B: RectangularBoard>(
     use fmt::Write;
     let sq_width = fmt.overwrite_width().unwrap_or(3);
     let flip = fmt.flip_board() && B::should_flip_visually();
-    let y_spacer = y % fmt.vertical_spacer_interval() == 0;
+    let y_spacer = y.is_multiple_of(fmt.vertical_spacer_interval());
     let mut res = "    ".to_string();
     let bar = if y == 0 || y == pos.get_height() {
         HEAVY_HORIZONTAL_BAR
@@ -646,7 +742,8 @@ pub fn display_board_pretty<B: RectangularBoard>(
         line += &with_color(
             HEAVY_VERTICAL_BAR,
             colors[y][pos.get_width() - 1],
-            pos.get_width() % fmt.horizontal_spacer_interval() == 0,
+            pos.get_width()
+                .is_multiple_of(fmt.horizontal_spacer_interval()),
         );
         res.push(line);
     }
@@ -807,3 +904,28 @@ impl<B: Board> BoardFormatter<B> for AdaptFormatter<B> {
         self.square_width
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thinking_indicator_test() {
+        let elapsed = Duration::from_millis(1234);
+        let mut piped = ThinkingIndicator::new(false);
+        for _ in 0..3 {
+            let text = piped.render("caps", elapsed);
+            assert!(!text.contains('\x1b'));
+            assert!(!text.contains('\r'));
+        }
+        assert!(piped.clear().is_empty());
+        let mut tty = ThinkingIndicator::new(true);
+        assert!(tty.clear().is_empty());
+        let first = tty.render("caps", elapsed);
+        assert!(first.starts_with("\r\x1b["));
+        assert!(first.ends_with("caps is thinking... 1.2s"));
+        assert_ne!(tty.render("caps", elapsed), first);
+        assert!(tty.clear().contains('\x1b'));
+        assert!(tty.clear().is_empty());
+    }
+}
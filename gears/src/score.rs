@@ -135,6 +135,19 @@ impl Score {
             .map(|n| (n as f32 / 2f32).ceil() as isize)
     }
 
+    /// A human-readable description of the number of moves (not plies) until mate, e.g. `mate in 3 moves`.
+    /// Returns `None` if this isn't a game won or lost score.
+    pub fn mate_description(self) -> Option<String> {
+        let moves = self.moves_until_game_won()?;
+        let num = moves.unsigned_abs();
+        let unit = if num == 1 { "move" } else { "moves" };
+        if moves > 0 {
+            Some(format!("mate in {num} {unit}"))
+        } else {
+            Some(format!("getting mated in {num} {unit}"))
+        }
+    }
+
     pub fn plies_until_game_over(self) -> Option<isize> {
         self.plies_until_game_won().map(isize::abs)
     }
@@ -188,6 +201,40 @@ pub const fn is_valid_score(score: ScoreT) -> bool {
     score >= SCORE_LOST.0 && score <= SCORE_WON.0
 }
 
+/// Converts a centipawn score to the expected win rate (where a draw counts as half a win) by applying a
+/// [logistic sigmoid](<https://en.wikipedia.org/wiki/Logistic_function>).
+/// The `scale` stretches the sigmoid horizontally, so a larger scale means that a larger score is necessary
+/// to count as "surely won".
+pub fn cp_to_winrate(cp: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + (-cp / scale).exp())
+}
+
+/// The inverse of [`cp_to_winrate`]. Returns an infinite score for a win rate of `0` or `1`.
+pub fn winrate_to_cp(winrate: f64, scale: f64) -> f64 {
+    scale * (winrate / (1.0 - winrate)).ln()
+}
+
+/// The expected win rate of a normalized score of 100 centipawns: The win probability is three times as large as the
+/// loss probability, i.e. the odds are 3:1.
+pub const NORMALIZED_PAWN_WINRATE: f64 = 0.75;
+
+/// The internal score that gets reported as 100 centipawns when normalizing scores with [`normalize_score`],
+/// given the `scale` of the sigmoid that relates internal scores to win rates.
+pub fn normalized_pawn_value(scale: f64) -> ScoreT {
+    (winrate_to_cp(NORMALIZED_PAWN_WINRATE, scale).round() as ScoreT).max(1)
+}
+
+/// Rescales `score` such that a score of `pawn_value` gets reported as 100 centipawns.
+/// Only normal scores are scaled (and clamped to stay normal), game-theoretic scores like mate scores are returned unchanged.
+pub fn normalize_score(score: Score, pawn_value: ScoreT) -> Score {
+    if score < MIN_NORMAL_SCORE || score > MAX_NORMAL_SCORE {
+        return score;
+    }
+    let normalized = (score.0 as i64 * 100 / pawn_value.max(1) as i64)
+        .clamp(MIN_NORMAL_SCORE.0 as i64, MAX_NORMAL_SCORE.0 as i64);
+    Score(normalized as ScoreT)
+}
+
 /// Uses a SWAR (SIMD Within A Register) technique to store and manipulate middlegame and endgame scores
 /// at the same time, by treating them as the lower and upper half of a single value.
 /// This improves performance, which is especially important because the eval of a typical a/b engine is hot.
@@ -294,6 +341,70 @@ mod tests {
     use rand::prelude::SliceRandom;
     use rand::rng;
 
+    #[test]
+    fn winrate_test() {
+        for scale in [1.0, 100.0, 123.4, 400.0] {
+            assert_eq!(cp_to_winrate(0.0, scale), 0.5);
+            assert_eq!(winrate_to_cp(0.5, scale), 0.0);
+            let mut prev = 0.0;
+            for cp in -2000..=2000 {
+                let cp = f64::from(cp);
+                let wr = cp_to_winrate(cp, scale);
+                assert!(wr >= prev, "{cp} {scale}");
+                assert!((0.0..=1.0).contains(&wr));
+                assert!((wr + cp_to_winrate(-cp, scale) - 1.0).abs() < 1e-12);
+                if cp.abs() < 10.0 * scale {
+                    assert!((winrate_to_cp(wr, scale) - cp).abs() < 1e-6, "{cp} {scale}");
+                }
+                prev = wr;
+            }
+            assert!(cp_to_winrate(scale, scale) > cp_to_winrate(scale, 2.0 * scale));
+        }
+    }
+
+    #[test]
+    fn normalize_score_test() {
+        let pawn_value = normalized_pawn_value(120.0);
+        assert_eq!(pawn_value, 132);
+        let scale = cp_to_winrate(f64::from(pawn_value), 120.0);
+        assert!((scale - NORMALIZED_PAWN_WINRATE).abs() < 0.001, "{scale}");
+        assert_eq!(normalize_score(Score(pawn_value), pawn_value), Score(100));
+        assert_eq!(
+            normalize_score(Score(-2 * pawn_value), pawn_value),
+            Score(-200)
+        );
+        assert_eq!(normalize_score(Score(0), pawn_value), Score(0));
+        assert_eq!(normalize_score(MAX_NORMAL_SCORE, 1), MAX_NORMAL_SCORE);
+        assert_eq!(normalize_score(MIN_NORMAL_SCORE, 1), MIN_NORMAL_SCORE);
+        for score in [
+            SCORE_WON,
+            SCORE_LOST,
+            SCORE_WON - 5,
+            SCORE_LOST + 8,
+            SCORE_TIME_UP,
+        ] {
+            assert_eq!(normalize_score(score, 2 * pawn_value), score);
+        }
+    }
+
+    #[test]
+    fn mate_description_test() {
+        assert_eq!(Score(0).mate_description(), None);
+        assert_eq!(Score(123).mate_description(), None);
+        assert_eq!(
+            (SCORE_WON - 1).mate_description().as_deref(),
+            Some("mate in 1 move")
+        );
+        assert_eq!(
+            (SCORE_WON - 5).mate_description().as_deref(),
+            Some("mate in 3 moves")
+        );
+        assert_eq!(
+            (SCORE_LOST + 4).mate_description().as_deref(),
+            Some("getting mated in 2 moves")
+        );
+    }
+
     #[test]
     fn tapered_test() {
         let mut v = vec![];
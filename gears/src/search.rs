@@ -1,7 +1,7 @@
 use crate::general::board::Board;
-use crate::general::common::{parse_fp_from_str, Res};
+use crate::general::common::{parse_fp_from_str, parse_int_from_str, Res};
 use crate::general::moves::Move;
-use crate::score::Score;
+use crate::score::{Score, ScoreT};
 use crate::search::MpvType::{MainOfMultiple, OnlyLine, SecondaryLine};
 use anyhow::{anyhow, bail};
 use colored::{ColoredString, Colorize};
@@ -145,22 +145,29 @@ impl NodeType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[must_use]
 pub struct SearchInfo<B: Board> {
     pub best_move_of_all_pvs: B::Move,
     pub depth: Depth,
     pub seldepth: Depth,
+    /// Wall-clock time since the start of the search.
     pub time: Duration,
+    /// The sum of the nodes searched by all threads.
     pub nodes: NodesLimit,
     pub pv_num: usize,
     pub max_num_pvs: usize,
     pub pv: Vec<B::Move>,
     pub score: Score,
+    /// Permill of used entries of the (shared) TT, estimated by sampling the first 1000 entries.
     pub hashfull: usize,
     pub pos: B,
     pub bound: Option<NodeType>,
     pub additional: Option<String>,
+    /// The internal score that corresponds to 100 normalized centipawns in the root position, see
+    /// [`normalize_score`](crate::score::normalize_score). `None` if the eval doesn't support normalization.
+    /// The `score` is always the internal score, normalizing is up to the output.
+    pub score_normalization: Option<ScoreT>,
 }
 
 impl<B: Board> Default for SearchInfo<B> {
@@ -179,11 +186,14 @@ impl<B: Board> Default for SearchInfo<B> {
             pos: B::default(),
             bound: None,
             additional: None,
+            score_normalization: None,
         }
     }
 }
 
 impl<B: Board> SearchInfo<B> {
+    /// The total number of nodes of all threads divided by the wall-clock time, so this doesn't depend on how
+    /// the work is distributed among threads.
     pub fn nps(&self) -> usize {
         let micros = self.time.as_micros() as f64;
         if micros == 0.0 {
@@ -237,6 +247,8 @@ impl<B: Board> Display for SearchInfo<B> {
 pub struct TimeControl {
     pub remaining: Duration,
     pub increment: Duration,
+    /// The number of moves until the time of the next period gets added for classical time controls like `40/300`,
+    /// or `None` for sudden death.
     pub moves_to_go: Option<usize>,
 }
 
@@ -251,6 +263,9 @@ impl Display for TimeControl {
         if self.is_infinite() {
             write!(f, "infinite")
         } else {
+            if let Some(moves) = self.moves_to_go {
+                write!(f, "{moves} moves in ")?;
+            }
             write!(
                 f,
                 "{0}ms + {1}ms",
@@ -261,16 +276,27 @@ impl Display for TimeControl {
     }
 }
 
+/// Parses the usual human-readable notation, in seconds: `<start>+<increment>` (e.g. `5+3`), `<start>` (e.g. `300`),
+/// optionally prefixed by the number of moves per period (e.g. `40/300` or `40/300+0.6`), or `inf` / `infinite` / `∞`.
 impl FromStr for TimeControl {
     type Err = anyhow::Error;
 
-    // assume that the start time and increment strings don't contain a `+`
-
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "infinite" || s == "∞" {
+        let s = s.trim();
+        if ["inf", "infinite", "∞"].contains(&s) {
             return Ok(TimeControl::infinite());
         }
-        // For now, don't support movestogo TODO: Add support
+        let (moves_to_go, s) = match s.split_once('/') {
+            None => (None, s),
+            Some((moves, rest)) => {
+                let moves: usize =
+                    parse_int_from_str(moves.trim(), "the number of moves per period")?;
+                if moves == 0 {
+                    bail!("The number of moves per period can't be zero")
+                }
+                (Some(moves), rest)
+            }
+        };
         let mut parts = s.split('+');
         let start_time = parts.next().ok_or_else(|| anyhow!("Empty TC"))?;
         let start_time = parse_fp_from_str::<f64>(start_time.trim(), "the start time")?.max(0.0);
@@ -281,15 +307,28 @@ impl FromStr for TimeControl {
                 parse_fp_from_str::<f64>(inc_str.trim(), "the increment")?.max(0.0),
             );
         }
-        Ok(TimeControl {
-            remaining: start_time,
-            increment,
-            moves_to_go: None,
-        })
+        if let Some(rest) = parts.next() {
+            bail!("Unexpected '+{rest}' after the increment of the time control")
+        }
+        Ok(TimeControl::new(start_time, increment, moves_to_go))
     }
 }
 
+/// The number of moves [`TimeControl::remaining_for_move`] assumes are left in a sudden death time control.
+pub const SUDDEN_DEATH_MOVES_LEFT: usize = 20;
+
 impl TimeControl {
+    /// `moves_to_go` is the number of moves per period for classical time controls like `40/300`,
+    /// and `None` for sudden death time controls.
+    pub fn new(remaining: Duration, increment: Duration, moves_to_go: Option<usize>) -> Self {
+        debug_assert_ne!(moves_to_go, Some(0));
+        TimeControl {
+            remaining,
+            increment,
+            moves_to_go,
+        }
+    }
+
     pub fn infinite() -> Self {
         TimeControl {
             remaining: Duration::MAX,
@@ -302,13 +341,35 @@ impl TimeControl {
         self.remaining >= Duration::MAX / 2
     }
 
-    pub fn update(&mut self, elapsed: Duration) {
-        if !self.is_infinite() {
-            self.remaining += self.increment;
-            self.remaining -= elapsed; // In this order to avoid computing negative intermediate values (which panics)
-                                       // TODO: This probably still panics when remaining + increment - elapsed is less than 0 but greater than -time_margin.
-                                       // TODO: Handle movestogo
+    /// Subtracts the time a move took and adds the increment. For classical time controls, `moves_to_go` counts down,
+    /// and once the current period is over, the time and number of moves of the next `period` get added.
+    /// Usually, `period` is the time control at the start of the game.
+    pub fn update(&mut self, elapsed: Duration, period: &TimeControl) {
+        if self.is_infinite() {
+            return;
+        }
+        self.remaining = (self.remaining + self.increment).saturating_sub(elapsed);
+        if let Some(moves) = self.moves_to_go {
+            if moves <= 1 {
+                self.remaining += period.remaining;
+                self.moves_to_go = period.moves_to_go;
+            } else {
+                self.moves_to_go = Some(moves - 1);
+            }
+        }
+    }
+
+    /// A simple per-move time budget: The remaining time is distributed evenly among the `moves_to_go` moves that are
+    /// left until the end of the current period. For sudden death, this assumes that [`SUDDEN_DEATH_MOVES_LEFT`] moves
+    /// are left. The increment is always added, but the budget never exceeds half of the remaining time,
+    /// so that a move that takes longer than planned, e.g. because of a slow engine or a slow connection,
+    /// doesn't lose on time.
+    pub fn remaining_for_move(&self) -> Duration {
+        if self.is_infinite() {
+            return Duration::MAX;
         }
+        let moves_left = self.moves_to_go.unwrap_or(SUDDEN_DEATH_MOVES_LEFT).max(1);
+        (self.remaining / moves_left as u32 + self.increment).min(self.remaining / 2)
     }
 
     pub fn remaining(&self, start: Option<Instant>) -> Duration {
@@ -320,6 +381,15 @@ impl TimeControl {
         }
     }
 
+    /// Multiplies the remaining time and the increment by `factor`, e.g. to give one player time odds.
+    /// An infinite time control stays infinite.
+    pub fn scale(&mut self, factor: f64) {
+        if !self.is_infinite() {
+            self.remaining = scale_duration(self.remaining, factor);
+            self.increment = scale_duration(self.increment, factor);
+        }
+    }
+
     pub fn remaining_to_string(&self, start: Option<Instant>) -> String {
         if self.is_infinite() {
             "infinite\n".to_string()
@@ -522,6 +592,14 @@ impl SearchLimit {
         self.fixed_time.min(self.tc.remaining)
     }
 
+    /// Scales the time control and the fixed time per move by `factor`, see [`TimeControl::scale`].
+    pub fn scale_time(&mut self, factor: f64) {
+        self.tc.scale(factor);
+        if !self.is_infinite_fixed_time() {
+            self.fixed_time = scale_duration(self.fixed_time, factor);
+        }
+    }
+
     pub fn is_infinite_fixed_time(&self) -> bool {
         is_duration_infinite(self.fixed_time)
     }
@@ -544,6 +622,160 @@ impl SearchLimit {
     }
 }
 
+fn scale_duration(duration: Duration, factor: f64) -> Duration {
+    Duration::try_from_secs_f64(duration.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+}
+
 pub fn is_duration_infinite(duration: Duration) -> bool {
     duration >= Duration::MAX / 2
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn nps_test() {
+        let mut info = SearchInfo::<crate::games::chess::Chessboard> {
+            time: Duration::from_millis(1500),
+            nodes: NodesLimit::new(3_000_000).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(info.nps(), 2_000_000);
+        info.time = Duration::from_micros(3);
+        info.nodes = NodesLimit::new(1).unwrap();
+        assert_eq!(info.nps(), 333_333);
+        info.time = Duration::ZERO;
+        assert_eq!(info.nps(), 0);
+    }
+
+    #[test]
+    fn time_odds_test() {
+        let tc = TimeControl::from_str("10+0.5").unwrap();
+        let mut limit = SearchLimit::tc(tc);
+        limit.scale_time(2.0);
+        assert_eq!(limit.tc.remaining, Duration::from_secs(20));
+        assert_eq!(limit.tc.increment, Duration::from_secs(1));
+        assert!(limit.is_infinite_fixed_time());
+        assert_eq!(
+            limit.max_move_time(),
+            2 * SearchLimit::tc(tc).max_move_time()
+        );
+        let mut limit = SearchLimit::per_move(Duration::from_millis(300));
+        limit.scale_time(0.5);
+        assert_eq!(limit.fixed_time, Duration::from_millis(150));
+        assert!(limit.tc.is_infinite());
+        let mut limit = SearchLimit::infinite();
+        limit.scale_time(3.0);
+        assert!(limit.is_infinite());
+    }
+
+    #[test]
+    fn parse_tc_test() {
+        let tc = TimeControl::from_str("5+3").unwrap();
+        assert_eq!(tc.remaining, Duration::from_secs(5));
+        assert_eq!(tc.increment, Duration::from_secs(3));
+        assert_eq!(tc.moves_to_go, None);
+        let tc = TimeControl::from_str("10+0.1").unwrap();
+        assert_eq!(tc.remaining, Duration::from_secs(10));
+        assert_eq!(tc.increment, Duration::from_millis(100));
+        let tc = TimeControl::from_str("300").unwrap();
+        assert_eq!(tc.remaining, Duration::from_secs(300));
+        assert_eq!(tc.increment, Duration::ZERO);
+        // increment only
+        let tc = TimeControl::from_str("0+2").unwrap();
+        assert_eq!(tc.remaining, Duration::ZERO);
+        assert_eq!(tc.increment, Duration::from_secs(2));
+        let tc = TimeControl::from_str("40/300").unwrap();
+        assert_eq!(tc.moves_to_go, Some(40));
+        assert_eq!(tc.remaining, Duration::from_secs(300));
+        assert_eq!(tc.increment, Duration::ZERO);
+        let tc = TimeControl::from_str("40/60+0.6").unwrap();
+        assert_eq!(tc.moves_to_go, Some(40));
+        assert_eq!(tc.remaining, Duration::from_secs(60));
+        assert_eq!(tc.increment, Duration::from_millis(600));
+        assert_eq!(tc.to_string(), "40 moves in 60000ms + 600ms");
+        for inf in ["inf", "infinite", "∞", " infinite "] {
+            assert!(TimeControl::from_str(inf).unwrap().is_infinite(), "{inf}");
+        }
+        for invalid in ["", "abc", "5+x", "0/60", "x/60", "5+3+1", "40/"] {
+            assert!(TimeControl::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn moves_to_go_test() {
+        let period = TimeControl::from_str("40/300").unwrap();
+        let mut tc = period;
+        for _ in 0..39 {
+            tc.update(Duration::from_secs(5), &period);
+        }
+        assert_eq!(tc.moves_to_go, Some(1));
+        assert_eq!(tc.remaining, Duration::from_secs(300 - 39 * 5));
+        tc.update(Duration::from_secs(5), &period);
+        assert_eq!(tc.moves_to_go, Some(40));
+        assert_eq!(tc.remaining, Duration::from_secs(600 - 40 * 5));
+        for _ in 0..10 {
+            tc.update(Duration::from_secs(5), &period);
+        }
+        assert_eq!(tc.moves_to_go, Some(30));
+        assert_eq!(tc.remaining, Duration::from_secs(600 - 50 * 5));
+        // a move that takes longer than the remaining time doesn't panic
+        let mut tc = TimeControl::from_str("1+0.5").unwrap();
+        tc.update(Duration::from_secs(2), &tc.clone());
+        assert_eq!(tc.remaining, Duration::ZERO);
+        assert_eq!(tc.moves_to_go, None);
+    }
+
+    #[test]
+    fn remaining_for_move_test() {
+        // 40 moves in 5 minutes, no increment
+        let classic = TimeControl::from_str("40/300").unwrap();
+        assert_eq!(
+            classic,
+            TimeControl::new(Duration::from_secs(300), Duration::ZERO, Some(40))
+        );
+        assert_eq!(classic.remaining_for_move(), Duration::from_millis(7500));
+        // `moves_to_go` counts down during the game
+        let mut tc = classic;
+        for _ in 0..30 {
+            tc.update(Duration::from_secs(9), &classic);
+        }
+        assert_eq!(tc.moves_to_go, Some(10));
+        assert_eq!(tc.remaining, Duration::from_secs(30));
+        assert_eq!(tc.remaining_for_move(), Duration::from_secs(3));
+        for _ in 0..9 {
+            tc.update(Duration::from_secs(1), &classic);
+        }
+        assert_eq!(tc.moves_to_go, Some(1));
+        assert_eq!(tc.remaining, Duration::from_secs(21));
+        // even for the last move of the period, half of the remaining time is kept as a safety margin
+        assert_eq!(tc.remaining_for_move(), Duration::from_millis(10_500));
+        // a new period has started
+        tc.update(Duration::from_secs(1), &classic);
+        assert_eq!(tc.moves_to_go, Some(40));
+        assert_eq!(tc.remaining_for_move(), Duration::from_millis(8000));
+
+        // 5 minutes + 3 seconds increment
+        let blitz = TimeControl::from_str("300+3").unwrap();
+        assert_eq!(
+            blitz,
+            TimeControl::new(Duration::from_secs(300), Duration::from_secs(3), None)
+        );
+        let budget = Duration::from_secs(300 / SUDDEN_DEATH_MOVES_LEFT as u64 + 3);
+        assert_eq!(blitz.remaining_for_move(), budget);
+        let mut tc = blitz;
+        tc.update(Duration::from_secs(3), &blitz);
+        assert_eq!(tc.remaining_for_move(), budget);
+        // the increment alone would exceed the remaining time
+        tc.remaining = Duration::from_secs(2);
+        assert_eq!(tc.remaining_for_move(), Duration::from_secs(1));
+        for remaining in [0, 1, 10, 1000, 100_000, 10_000_000] {
+            tc.remaining = Duration::from_millis(remaining);
+            assert!(tc.remaining_for_move() <= tc.remaining / 2);
+        }
+
+        assert_eq!(TimeControl::infinite().remaining_for_move(), Duration::MAX);
+    }
+}
@@ -134,10 +134,18 @@ pub enum EngineOptionName {
     UCIOpponent,
     UCIEngineAbout,
     UCIShowCurrLine,
+    UCIChess960,
     MoveOverhead,
     Strictness,
     SetEngine,
     SetEval,
+    DetectDraws,
+    SyncSearch,
+    MaxPvLength,
+    NormalizeScore,
+    Contempt,
+    AspirationWindow,
+    AspirationWidening,
     Other(String),
 }
 
@@ -160,10 +168,18 @@ impl NamedEntity for EngineOptionName {
             EngineOptionName::UCIOpponent => "The opponent. Currently only used to output the name in PGNs",
             EngineOptionName::UCIEngineAbout => "Information about the engine. Can't be changed, only queried",
             EngineOptionName::UCIShowCurrLine => "Every now and then, print the line currently being searched",
+            EngineOptionName::UCIChess960 => "Print castling moves as king takes rook (e.g. e1h1), which is necessary for Chess960. Otherwise, castling moves are printed as e.g. e1g1. Both are always accepted",
             EngineOptionName::MoveOverhead => "Subtract this from the remaining time each move to account for overhead of sending the move",
             EngineOptionName::Strictness => "Be more restrictive about the positions to accept. By default, many non-standard positions are accepted",
             EngineOptionName::SetEngine => "Change the current searcher, and optionally the eval. Similar effect to `uginewgame`",
             EngineOptionName::SetEval => "Change the current evaluation function without resetting the engine state, such as clearing the TT",
+            EngineOptionName::DetectDraws => "Don't search positions where the game is already drawn, e.g. because of insufficient material, and report a score of 0 instead",
+            EngineOptionName::SyncSearch => "Don't read the next command until a non-infinite search has finished. Intended for scripts",
+            EngineOptionName::MaxPvLength => "Print at most this many moves of the PV, 0 means no limit. Doesn't affect the search",
+            EngineOptionName::NormalizeScore => "Report normalized scores, where 100 cp means that the win probability is three times the loss probability (a 75% win rate). Only supported by some evals, doesn't affect mate scores",
+            EngineOptionName::Contempt => "How many centipawns a draw is worse than an equal position for the engine. Positive values avoid draws, negative values seek them. Only supported by some engines",
+            EngineOptionName::AspirationWindow => "Radius of the first aspiration window in centipawns. Only supported by some engines",
+            EngineOptionName::AspirationWidening => "Multiply the aspiration window radius by this after the score falls outside the window. Only supported by some engines",
             EngineOptionName::Other(name) => { return Some(format!("Custom option named '{name}'")) }
         };
         Some(res.to_string())
@@ -181,10 +197,18 @@ impl EngineOptionName {
             EngineOptionName::UCIOpponent => "UCI_Opponent",
             EngineOptionName::UCIEngineAbout => "UCI_EngineAbout",
             EngineOptionName::UCIShowCurrLine => "UCI_ShowCurrLine",
+            EngineOptionName::UCIChess960 => "UCI_Chess960",
             EngineOptionName::MoveOverhead => "MoveOverhead",
             EngineOptionName::Strictness => "Strict",
             EngineOptionName::SetEngine => "Engine",
             EngineOptionName::SetEval => "SetEval",
+            EngineOptionName::DetectDraws => "DetectDraws",
+            EngineOptionName::SyncSearch => "SyncSearch",
+            EngineOptionName::MaxPvLength => "MaxPVLength",
+            EngineOptionName::NormalizeScore => "NormalizeScore",
+            EngineOptionName::Contempt => "Contempt",
+            EngineOptionName::AspirationWindow => "AspirationWindow",
+            EngineOptionName::AspirationWidening => "AspirationWidening",
             EngineOptionName::Other(x) => x,
         }
     }
@@ -54,6 +54,13 @@ pub struct CommandLineArgs {
     /// Adjudicate matches where the number of moves exceeds this number as draws.
     pub max_moves: Option<NonZeroUsize>,
 
+    /// Adjudicate a match as draw as soon as neither player can reasonably win with the remaining material.
+    pub material_draw: bool,
+
+    /// Adjudicate a match as draw once a position has occurred three times. This only matters for games where
+    /// repetitions don't end the game by the rules, like ataxx.
+    pub repetition_draw: bool,
+
     /// The name of the event as displayed in a PGN
     pub event: Option<String>,
 
@@ -71,6 +78,12 @@ pub struct CommandLineArgs {
 
     pub start_pos: Option<String>,
 
+    /// Play this many random legal moves at the start of each match to diversify openings (defaults to 0).
+    pub random_plies: usize,
+
+    /// The seed used to generate random openings. If not set, a random seed is used.
+    pub seed: Option<u64>,
+
     /// If true, engines are restarted on failure (this still counts as a lost match).
     /// If false, the program simply exits.
     pub recover: bool,
@@ -86,6 +99,33 @@ pub struct CommandLineArgs {
     pub additional_outputs: Vec<OutputArgs>,
 }
 
+impl Default for CommandLineArgs {
+    fn default() -> Self {
+        Self {
+            game: Game::default(),
+            ui: "text".to_string(), // TODO: Change default
+            debug: false,
+            players: vec![],
+            concurrency: NonZeroUsize::new(1).unwrap(),
+            draw_adjudication: None,
+            resign_adjudication: None,
+            max_moves: None,
+            material_draw: false,
+            repetition_draw: false,
+            event: None,
+            site: None,
+            pgn_out: None,
+            fen_out: None,
+            wait_after_match: Duration::default(),
+            start_pos: None,
+            random_plies: 0,
+            seed: None,
+            recover: false,
+            additional_outputs: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct ClientEngineCliArgs {
     /// This name will be displayed in the GUI and be used for logfiles.
@@ -120,6 +160,9 @@ pub struct ClientEngineCliArgs {
     /// Limit the engine to the given number of seconds per move.
     pub move_time: Option<Duration>,
 
+    /// Multiply the time control and move time with this factor, e.g. to give this engine time odds (default: 1).
+    pub time_scale: Option<f64>,
+
     /// The engine is allowed to exceed the remaining time by this amount.
     pub time_margin: Option<TimeMargin>,
 
@@ -244,7 +287,13 @@ pub fn parse_engine<Iter: Iterator<Item = String>>(
             "mate" => res.mate = Some(Depth::try_new(parse_int_from_str(value?, "mate")?)?),
             "nodes" => res.nodes = Some(nonzero_u64(parse_int_from_str(value?, "nodes")?, "nodes")?),
             "ponder" => todo!("'ponder' isn't yet implemented"),
-            "tscale" => todo!("'tscale' isn't yet implemented"),
+            "tscale" => {
+                let scale: f64 = parse_fp_from_str(value?, "tscale (time scale)")?;
+                if !(scale > 0.0 && scale.is_finite()) {
+                    bail!("The time scale must be a positive number, not {scale}")
+                }
+                res.time_scale = Some(scale);
+            }
             x => match x.strip_prefix("option.") {
                 None => bail!("Unknown engine option {x}"),
                 Some(opt) => { res.custom_options.insert(x.to_string(), opt.to_string()); },
@@ -311,6 +360,7 @@ pub fn combine_engine_args(
     engine.proto = engine.proto.or(each.proto);
     engine.tc = engine.tc.or(each.tc);
     engine.move_time = engine.move_time.or(each.move_time);
+    engine.time_scale = engine.time_scale.or(each.time_scale);
     engine.time_margin = engine.time_margin.or(each.time_margin);
     engine.white_pov |= each.white_pov;
     engine.depth = engine.depth.or(each.depth);
@@ -332,24 +382,7 @@ pub fn parse_cli() -> Res<CommandLineArgs> {
         exit(0);
     }
 
-    let mut res = CommandLineArgs {
-        game: Game::default(),
-        ui: "text".to_string(), // TODO: Change default
-        debug: false,
-        players: vec![],
-        concurrency: NonZeroUsize::new(1).unwrap(),
-        draw_adjudication: None,
-        resign_adjudication: None,
-        max_moves: None,
-        event: None,
-        site: None,
-        pgn_out: None,
-        fen_out: None,
-        wait_after_match: Duration::default(),
-        start_pos: None,
-        recover: false,
-        additional_outputs: vec![],
-    };
+    let mut res = CommandLineArgs::default();
 
     let mut each = ClientEngineCliArgs::default();
 
@@ -375,6 +408,8 @@ pub fn parse_cli() -> Res<CommandLineArgs> {
             "-resign" => res.resign_adjudication = Some(parse_adjudication(&mut args, false)?),
             "-draw" => res.draw_adjudication = Some(parse_adjudication(&mut args, true)?),
             "-maxmoves" => res.max_moves = Some(get_next_nonzero_usize(&mut args, "maxmoves")?),
+            "-materialdraw" => res.material_draw = true,
+            "-repetitiondraw" => res.repetition_draw = true,
             "-tournament" => todo!(),
             "-event" => res.event = Some(get_next_arg(&mut args, "event")?),
             "-games" => todo!(),
@@ -384,14 +419,15 @@ pub fn parse_cli() -> Res<CommandLineArgs> {
             "-outcomeinterval" => todo!(),
             "-openings" => todo!(),
             "-bookmode" => todo!(),
-            "-pgnout" => todo!(),
+            "-pgnout" => res.pgn_out = Some(PathBuf::from(get_next_arg(&mut args, "pgnout")?)),
             "-epdout" | "-fenout" => todo!(),
             "-recover" => res.recover = true,
             "-noswap" => todo!(),
             "-reverse" => todo!(),
             "-seeds" => todo!(),
             "-site" => res.site = Some(get_next_arg(&mut args, "site")?),
-            "-srand" => todo!(),
+            "-srand" => res.seed = Some(get_next_int(&mut args, "srand")?),
+            "-randomplies" => res.random_plies = get_next_int(&mut args, "randomplies")?,
             "-wait" => {
                 res.wait_after_match =
                     Duration::from_millis(get_next_int::<i64>(&mut args, "wait")?.max(1) as u64);
@@ -7,7 +7,7 @@ use gears::games::mnk::MNKBoard;
 use gears::games::uttt::UtttBoard;
 use gears::games::OutputList;
 use gears::general::board::{Board, RectangularBoard};
-use gears::general::common::anyhow::anyhow;
+use gears::general::common::anyhow::{anyhow, bail};
 use gears::general::common::Description::WithDescription;
 use gears::general::common::{select_name_dyn, Res};
 use gears::output::{normal_outputs, required_outputs};
@@ -16,6 +16,7 @@ use gears::{create_selected_output_builders, output_builder_from_str, AnyRunnabl
 use crate::cli::{parse_cli, CommandLineArgs, HumanArgs, PlayerArgs};
 use crate::play::player::PlayerBuilder;
 use crate::play::ugi_client::RunClient;
+use crate::ui::match_input::MatchInputBuilder;
 use crate::ui::text_input::TextInputBuilder;
 use crate::ui::{InputBuilder, InputList};
 
@@ -34,6 +35,7 @@ fn main() {
 pub fn text_based_inputs<B: Board>() -> InputList<B> {
     vec![
         Box::new(TextInputBuilder::default()),
+        Box::new(MatchInputBuilder::default()),
         // TODO: Add SPRT input
     ]
 }
@@ -87,6 +89,8 @@ pub fn create_input_from_str<B: Board>(
 pub fn map_ui_to_input_and_output(ui: &str) -> (&str, &str) {
     match ui {
         "text" => ("text", "unicode"),
+        // only show the result
+        "match" => ("match", "info"),
         "gui" => todo!(),
         // "sprt" => (todo!(), "none"),
         x => (x, x),
@@ -105,9 +109,26 @@ pub fn create_match(args: CommandLineArgs) -> Res<AnyRunnable> {
 }
 
 pub fn create_client_match_for_game<B: Board>(
-    mut args: CommandLineArgs,
+    args: CommandLineArgs,
     uis: (OutputList<B>, InputList<B>),
 ) -> Res<AnyRunnable> {
+    Ok(create_run_client(args, uis)?)
+}
+
+/// Sets up the client with all outputs and players, but doesn't start a match yet.
+pub fn create_run_client<B: Board>(
+    mut args: CommandLineArgs,
+    uis: (OutputList<B>, InputList<B>),
+) -> Res<Box<RunClient<B>>> {
+    if args.ui == "match"
+        && (args.players.len() != 2
+            || args
+                .players
+                .iter()
+                .any(|p| !matches!(p, PlayerArgs::Engine(_))))
+    {
+        bail!("The 'match' ui requires two engines, set with '-engine'");
+    }
     while args.players.len() < 2 {
         args.players.push(PlayerArgs::Human(HumanArgs::default()));
     }
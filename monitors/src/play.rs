@@ -1,4 +1,6 @@
 pub mod adjudication;
+#[cfg(all(test, unix))]
+pub mod fake_engine;
 pub mod player;
 pub mod ugi_client;
 pub mod ugi_input;
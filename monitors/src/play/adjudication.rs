@@ -3,8 +3,8 @@ use gears::games::Color;
 use gears::general::board::Board;
 use gears::score::Score;
 use gears::{
-    player_res_to_match_res, AdjudicationReason, GameOver, GameOverReason, GameResult, GameState,
-    MatchResult, PlayerResult,
+    material_draw_result, player_res_to_match_res, repetition_draw_result, AdjudicationReason,
+    GameOver, GameOverReason, GameResult, GameState, MatchResult, PlayerResult,
 };
 
 pub trait Adjudication<B: Board> {
@@ -24,6 +24,8 @@ pub struct Adjudicator {
     resign: Option<ScoreAdjudication>,
     draw: Option<ScoreAdjudication>,
     max_moves_until_draw: usize,
+    material_draw: bool,
+    repetition_draw: bool,
 }
 
 impl Adjudicator {
@@ -32,11 +34,15 @@ impl Adjudicator {
         resign: Option<ScoreAdjudication>,
         draw: Option<ScoreAdjudication>,
         max_moves_until_draw: usize,
+        material_draw: bool,
+        repetition_draw: bool,
     ) -> Self {
         Self {
             resign,
             draw,
             max_moves_until_draw,
+            material_draw,
+            repetition_draw,
         }
     }
 
@@ -151,6 +157,18 @@ impl<B: Board> Adjudication<B> for Adjudicator {
         if state.contains_human() {
             return None;
         }
+        if self.material_draw {
+            if let Some(res) = material_draw_result(&state.the_match.board) {
+                return Some(res);
+            }
+        }
+        if self.repetition_draw {
+            let the_match = &state.the_match;
+            if let Some(res) = repetition_draw_result(the_match.repetitions.count(&the_match.board))
+            {
+                return Some(res);
+            }
+        }
         self.adjudicate_draw(state)
             .or_else(|| self.adjudicate_resignation(state))
     }
@@ -0,0 +1,72 @@
+//! A scripted UCI engine for tests. It's a shell script that plays a fixed sequence of moves and logs every message
+//! it receives, so tests can check what the client sent.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process;
+
+use crate::cli::ClientEngineCliArgs;
+
+#[derive(Debug)]
+#[must_use]
+pub struct FakeEngine {
+    dir: PathBuf,
+    pub args: ClientEngineCliArgs,
+}
+
+impl FakeEngine {
+    /// The `name` must be unique among all tests. The engine answers the nth `go` with the nth move of `moves`.
+    pub fn new(name: &str, moves: &[&str]) -> Self {
+        let dir = std::env::temp_dir().join(format!("monitors_{name}_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join(name);
+        let log = dir.join("received.log");
+        _ = fs::remove_file(&log);
+        fs::write(
+            &script,
+            format!(
+                r#"#!/bin/sh
+n=0
+while read -r line; do
+    echo "$line" >> "{log}"
+    case "$line" in
+        uci) echo "id name {name}"; echo "uciok" ;;
+        ugi) echo "id name {name}"; echo "ugiok" ;;
+        isready) echo "readyok" ;;
+        go*) n=$((n + 1)); echo "bestmove $(echo "{moves}" | cut -d ' ' -f $n)" ;;
+        quit) exit 0 ;;
+    esac
+done
+"#,
+                log = log.display(),
+                moves = moves.join(" ")
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let args = ClientEngineCliArgs {
+            display_name: Some(name.to_string()),
+            cmd: name.to_string(),
+            path: Some(script),
+            stderr: Some(dir.join("stderr.log")),
+            ..Default::default()
+        };
+        Self { dir, args }
+    }
+
+    /// All messages the engine has received so far, one per line.
+    pub fn received(&self) -> String {
+        fs::read_to_string(self.dir.join("received.log")).unwrap_or_default()
+    }
+
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+}
+
+impl Drop for FakeEngine {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -256,6 +256,9 @@ pub fn limit_to_ugi(
     if !btime.increment.is_zero() {
         write!(res, "binc {} ", btime.increment.as_millis())?;
     }
+    if let Some(moves) = limit.tc.moves_to_go {
+        write!(res, "movestogo {moves} ")?;
+    }
     if limit.nodes != NodesLimit::MAX {
         write!(res, "nodes {} ", limit.nodes)?;
     }
@@ -422,13 +425,16 @@ impl PlayerBuilder {
         let depth = args.depth.unwrap_or(Depth::MAX);
         let mate = args.mate.unwrap_or(Depth::MAX);
         let nodes = args.nodes.unwrap_or(NodesLimit::MAX);
-        let default_limit = SearchLimit {
+        let mut default_limit = SearchLimit {
             tc,
             fixed_time,
             depth,
             nodes,
             mate,
         };
+        if let Some(factor) = args.time_scale {
+            default_limit.scale_time(factor);
+        }
 
         // try to set uci/ugi mode based on the game, but possibly change that according to how the engine responds
         let proto = args.proto.unwrap_or_else(|| {
@@ -593,13 +599,15 @@ impl<B: Board> Player<B> {
                 if elapsed > limit.max_move_time().saturating_add(engine.time_margin.0) {
                     return true;
                 }
-                engine.current_match().limit.tc.update(elapsed);
+                let current_match = engine.current_match();
+                let period = current_match.original_limit.tc;
+                current_match.limit.tc.update(elapsed, &period);
             }
             Human(ref mut human) => {
                 if elapsed > human.tc.remaining {
                     return true;
                 }
-                human.tc.update(elapsed);
+                human.tc.update(elapsed, &human.original_tc);
             }
         }
         false
@@ -624,3 +632,33 @@ impl<B: Board> Player<B> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_to_go_test() {
+        let tc = TimeControl::from_str("40/300").unwrap();
+        let mut player: Player<Chessboard> = Human(HumanPlayer {
+            tc,
+            original_tc: tc,
+            name: "human".to_string(),
+            status: HumanPlayerStatus::Idle,
+        });
+        for _ in 0..41 {
+            player.start_clock();
+            assert!(!player.update_clock_and_check_for_time_loss());
+        }
+        let tc = player.get_time().unwrap();
+        assert_eq!(tc.moves_to_go, Some(39));
+        assert!(tc.remaining > Duration::from_secs(599), "{tc}");
+
+        let limit = SearchLimit::tc(tc);
+        let go = limit_to_ugi(limit, tc, TimeControl::from_str("5+1").unwrap()).unwrap();
+        assert!(go.contains("movestogo 39"), "{go}");
+        let limit = SearchLimit::tc(TimeControl::from_str("5+1").unwrap());
+        let go = limit_to_ugi(limit, limit.tc, limit.tc).unwrap();
+        assert!(!go.contains("movestogo"), "{go}");
+    }
+}
@@ -1,10 +1,16 @@
 use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::Write;
 use std::mem::swap;
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::thread::{sleep, Builder};
 use std::time::{Duration, Instant};
 
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use crossbeam_utils::sync::{Parker, Unparker};
+use rand::{rng, Rng};
 use strum::IntoEnumIterator;
 
 use crate::cli::CommandLineArgs;
@@ -17,19 +23,21 @@ use crate::play::ugi_input::BestMoveAction;
 use crate::play::ugi_input::BestMoveAction::Ignore;
 use crate::play::ugi_input::EngineStatus::*;
 use crate::ui::Input;
-use gears::games::{BoardHistory, Color, ZobristHistory};
-use gears::general::board::Board;
+use gears::games::{BoardHistory, Color, RepetitionTable, ZobristHistory};
 use gears::general::board::Strictness::Relaxed;
-use gears::general::common::anyhow::bail;
+use gears::general::board::{random_opening, Board};
+use gears::general::common::anyhow::{anyhow, bail};
 use gears::general::common::Res;
+use gears::output::pgn::match_to_pgn_string;
 use gears::output::Message::*;
 use gears::output::{Message, OutputBox, OutputBuilder, OutputOpts};
-use gears::search::{SearchInfo, TimeControl};
+use gears::search::{Depth, SearchInfo, TimeControl};
 use gears::MatchStatus::*;
 use gears::Quitting::*;
 use gears::{
-    output_builder_from_str, player_res_to_match_res, AbstractRun, AdjudicationReason, GameOver,
-    GameOverReason, GameResult, GameState, MatchResult, MatchStatus, PlayerResult, Quitting,
+    invalid_move_result, output_builder_from_str, player_res_to_match_res, rejected_move_message,
+    AbstractRun, AdjudicationReason, GameOver, GameOverReason, GameResult, GameState, MatchResult,
+    MatchStatus, PlayerResult, Quitting,
 };
 
 // TODO: Use tokio? Probably more efficient and it has non-blocking reads.
@@ -47,6 +55,9 @@ pub struct UgiMatchState<B: Board> {
     pub board: B,
     /// Needed for repetition detection
     pub board_history: ZobristHistory<B>,
+    /// How often each position has occurred, needed to adjudicate repetitions as draws in games where they
+    /// don't end the game by the rules
+    pub repetitions: RepetitionTable<B>,
     /// Needed to reconstruct the match, such as for the PGN export.
     pub move_history: Vec<B::Move>,
     /// useful for gui matches to allow a "restart" option
@@ -67,6 +78,7 @@ impl<B: Board> UgiMatchState<B> {
             status: NotStarted,
             board: initial_pos,
             board_history: ZobristHistory::default(),
+            repetitions: RepetitionTable::default(),
             move_history: vec![],
             initial_pos,
             event,
@@ -81,6 +93,8 @@ impl<B: Board> UgiMatchState<B> {
         self.move_history.clear();
         self.board_history.clear();
         self.board_history.push(&self.board);
+        self.repetitions.clear();
+        self.repetitions.add(&self.board);
         self.status = NotStarted;
     }
 
@@ -107,6 +121,12 @@ pub struct ClientState<B: Board> {
     pub recover: bool,
     /// In debug mode, everything gets logged.
     pub debug: bool,
+    /// The number of random moves to play at the start of each match
+    pub random_plies: usize,
+    /// Seed for the random opening, gets incremented after each match
+    pub seed: u64,
+    /// Append the PGN of each finished match to this file
+    pub pgn_out: Option<PathBuf>,
 }
 
 impl<B: Board> ClientState<B> {
@@ -238,11 +258,19 @@ pub struct Client<B: Board> {
     // quit the entire program (not just a single match)
     send_quit: Unparker,
     will_quit: bool,
+    /// Get notified when a match ends, see [`Self::on_game_over`].
+    game_over_listeners: Vec<Sender<MatchResult>>,
+    /// Receives the move of the hint that is currently being computed, see [`Self::request_hint`].
+    hint_listener: Option<Sender<B::Move>>,
+    /// Never sends anything, but dropping it disconnects the channel that [`WaitForClientDrop`] waits on.
+    /// This is the last member so that it gets dropped after the players.
+    _client_dropped: Sender<()>,
 }
 
 impl<B: Board> Client<B> {
     fn create(
         send_quit: Unparker,
+        client_dropped: Sender<()>,
         all_outputs: Vec<Box<dyn OutputBuilder<B>>>,
         args: &CommandLineArgs,
     ) -> Res<Arc<Mutex<Self>>> {
@@ -263,6 +291,8 @@ impl<B: Board> Client<B> {
             args.resign_adjudication,
             args.draw_adjudication,
             args.max_moves.unwrap_or(NonZeroUsize::MAX).get(),
+            args.material_draw,
+            args.repetition_draw,
         );
 
         let match_state = UgiMatchState::new(initial_pos, event, site);
@@ -274,6 +304,9 @@ impl<B: Board> Client<B> {
             wait_after_match: args.wait_after_match,
             recover: args.recover,
             debug: false,
+            random_plies: args.random_plies,
+            seed: args.seed.unwrap_or_else(|| rng().random()),
+            pgn_out: args.pgn_out.clone(),
         };
         let ugi_output = output_builder_from_str("ugi", &all_outputs)
             .expect("Couldn't create 'ugi' output")
@@ -286,6 +319,9 @@ impl<B: Board> Client<B> {
             ugi_output,
             send_quit,
             will_quit: false,
+            game_over_listeners: vec![],
+            hint_listener: None,
+            _client_dropped: client_dropped,
         })))
     }
 
@@ -364,6 +400,8 @@ impl<B: Board> Client<B> {
             self.abort_match();
         }
         self.will_quit = true;
+        // dropping the senders wakes up all threads that are waiting for the match to end
+        self.game_over_listeners.clear();
         self.send_quit.unpark();
     }
 
@@ -401,10 +439,60 @@ impl<B: Board> Client<B> {
     }
 
     pub fn game_over(&mut self, result: MatchResult) {
-        self.match_state().status = Over(result);
+        if let Err(err) = self.match_state().status.end(result.clone()) {
+            self.show_message(Warning, &err.to_string());
+            return;
+        }
         for output in &mut self.outputs {
             output.inform_game_over(&self.state);
         }
+        if let Err(err) = self.write_pgn() {
+            self.show_error(&err.to_string());
+        }
+        self.game_over_listeners
+            .retain(|listener| listener.send(result.clone()).is_ok());
+    }
+
+    /// Returns a channel that receives the result of each match once it's over, after the outputs have been informed
+    /// and the PGN has been written. The channel gets disconnected when the program quits.
+    pub fn on_game_over(&mut self) -> Receiver<MatchResult> {
+        let (sender, receiver) = unbounded();
+        if !self.will_quit {
+            self.game_over_listeners.push(sender);
+        }
+        receiver
+    }
+
+    /// Appends the PGN of the current match to the `pgn_out` file, if set.
+    fn write_pgn(&self) -> Res<()> {
+        let Some(path) = &self.state.pgn_out else {
+            return Ok(());
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| anyhow!("Couldn't open the PGN file '{}': {err}", path.display()))?;
+        writeln!(file, "{}\n", match_to_pgn_string(&self.state))?;
+        Ok(())
+    }
+
+    /// Ends the current match as a loss for the engine `id`, unless the match is already over (e.g. because the error
+    /// was an invalid move, which has already been adjudicated) or the engine isn't playing.
+    pub fn engine_error(&mut self, id: PlayerId, error: &str) {
+        if self.match_state().status != Ongoing {
+            return;
+        }
+        let Some(color) = self.get_color(id) else {
+            return;
+        };
+        let game_over = GameOver {
+            result: PlayerResult::Lose,
+            reason: GameOverReason::Adjudication(AdjudicationReason::EngineError(
+                error.to_string(),
+            )),
+        };
+        self.game_over(player_res_to_match_res(game_over, color));
     }
 
     pub fn update_info(&mut self, id: PlayerId, info: SearchInfo<B>) -> Res<()> {
@@ -419,6 +507,14 @@ impl<B: Board> Client<B> {
         Ok(())
     }
 
+    /// Lets interactive outputs indicate that the current player is still thinking.
+    /// This gets called periodically by a timer thread, see [`RunClient::run`].
+    pub fn show_thinking(&mut self) {
+        for output in &mut self.outputs {
+            output.show_thinking(&self.state);
+        }
+    }
+
     pub fn show_ugi_info_string(&mut self, id: PlayerId, info: &str) {
         self.show_message(
             Info,
@@ -433,24 +529,22 @@ impl<B: Board> Client<B> {
     /// Therefore, it does not show the current board after each move, does not test for the end of the match,
     /// and does not transfer control to the other player.
     pub fn play_move_internal(&mut self, mov: B::Move) -> Res<()> {
+        if let Err(err) = self.match_state().status.check_can_move() {
+            bail!("Can't play the move '{mov}' because {err}")
+        }
         if !self.board().is_move_pseudolegal(mov) {
             // can't use to_extended_text because that assumes pseudolegality internally
             bail!("The move '{mov}' is not pseudolegal in the current position",)
         }
         let Some(board) = self.board().make_move(mov) else {
-            let player_res = GameOver {
-                result: PlayerResult::Lose,
-                reason: GameOverReason::Adjudication(AdjudicationReason::InvalidMove),
-            };
-            self.game_over(player_res_to_match_res(
-                player_res,
-                self.active_player().unwrap(),
-            ));
-            bail!("Invalid move '{mov}' in position {}", self.board().as_fen(),)
+            let pos = *self.board();
+            self.game_over(invalid_move_result(&mov.to_string(), &pos));
+            bail!("Invalid move '{mov}' in position {}", pos.as_fen(),)
         };
 
         *self.board() = board;
         self.match_state().board_history.push(&board);
+        self.match_state().repetitions.add(&board);
         self.match_state().move_history.push(mov);
         Ok(())
     }
@@ -498,6 +592,15 @@ impl<B: Board> Client<B> {
         }
     }
 
+    /// Only loggers show rejected moves, so this is enabled by adding a `logger` output (e.g. with `--debug`).
+    /// The message lists the legal moves of the current position, which helps with debugging protocol or UI bugs.
+    pub fn log_rejected_move(&mut self, move_text: &str) {
+        let message = rejected_move_message(move_text, self.board());
+        for output in self.outputs.iter_mut().filter(|o| o.is_logger()) {
+            output.display_message(Message::Debug, &message);
+        }
+    }
+
     /// The player doesn't necessarily have to be playing a match right now.
     /// This can happen when initializing more than 2 players in tournament mode.
     pub fn send_ugi_message_to(&mut self, engine: PlayerId, message: &str) {
@@ -578,6 +681,57 @@ impl<B: Board> Client<B> {
         self.state.get_player_mut(color).start_clock();
     }
 
+    /// Asks the engine playing against the active human player to search the current position up to the given depth.
+    /// The engine doesn't get a `ThinkingSince` status because it's not its turn, and no clock is started.
+    /// Once the engine sends its `bestmove`, it gets displayed without being played, and the engine becomes idle again.
+    /// The move is also sent to the returned channel, which gets disconnected if the engine's move is invalid or the hint
+    /// is cancelled.
+    pub fn request_hint(&mut self, depth: Depth) -> Res<(PlayerId, Receiver<B::Move>)> {
+        let Some(active) = self.active_player() else {
+            bail!("The match isn't running")
+        };
+        if self.state.get_player(active).is_engine() {
+            bail!("The {active} player is an engine, hints are only available for human players")
+        }
+        let other = active.other();
+        if !self.state.get_player(other).is_engine() {
+            bail!("There is no engine that could compute a hint, both players are humans")
+        }
+        let engine = self.state.get_engine_mut(other);
+        if engine.status != Idle {
+            bail!(
+                "The engine '{0}' can't compute a hint because it's {1}",
+                engine.display_name,
+                engine.status
+            )
+        }
+        engine.status = Hint;
+        if let Some(current_match) = engine.current_match.as_mut() {
+            current_match.search_info = None;
+        }
+        let (sender, receiver) = unbounded();
+        self.hint_listener = Some(sender);
+        self.send_position(other);
+        self.send_ugi_message(other, &format!("go depth {}", depth.get()));
+        Ok((self.state.id(other), receiver))
+    }
+
+    /// Called once the engine has answered a [`Self::request_hint`], with `None` if its move was invalid.
+    pub(super) fn finish_hint(&mut self, mov: Option<B::Move>) {
+        if let (Some(listener), Some(mov)) = (self.hint_listener.take(), mov) {
+            _ = listener.send(mov);
+        }
+    }
+
+    /// Gives up on a hint requested with [`Self::request_hint`], e.g. because the engine doesn't respond.
+    pub fn cancel_hint(&mut self, engine: PlayerId) {
+        let engine = self.state.get_engine_from_id_mut(engine);
+        if engine.status == Hint {
+            engine.status = Idle;
+        }
+        self.hint_listener = None;
+    }
+
     pub fn stop_clock(&mut self, color: B::Color) {
         let player = self.state.get_player_mut(color);
         if player.update_clock_and_check_for_time_loss() {
@@ -717,9 +871,24 @@ impl<B: Board> Client<B> {
         for color in B::Color::iter() {
             self.state.get_player_mut(color).assign_to_match(color);
         }
+        self.play_random_opening();
         self.start_match();
     }
 
+    /// Plays `random_plies` random moves (if that isn't zero) so that consecutive matches don't all start
+    /// from the same position, which is useful for self-play.
+    fn play_random_opening(&mut self) {
+        if self.state.random_plies == 0 {
+            return;
+        }
+        let seed = self.state.seed;
+        self.state.seed = seed.wrapping_add(1);
+        for mov in random_opening(*self.board(), self.state.random_plies, seed) {
+            self.play_move_internal(mov)
+                .expect("random opening moves are legal");
+        }
+    }
+
     pub fn restart(&mut self) {
         self.new_match(self.state.the_match.p1, self.state.the_match.p2);
     }
@@ -730,14 +899,16 @@ impl<B: Board> Client<B> {
 
     /// Start a match from any starting position with the current players.
     pub fn start_match(&mut self) {
-        assert_eq!(self.match_state().status, NotStarted);
         for color in B::Color::iter() {
             if self.state.get_player(color).is_engine() {
                 self.send_uginewgame(color);
             }
         }
         self.show();
-        self.match_state().status = Ongoing;
+        self.match_state()
+            .status
+            .start()
+            .expect("Internal error: start_match() called on a match that has already started");
         let player = self.board().active_player();
         self.start_thinking(player);
     }
@@ -751,12 +922,33 @@ impl<B: Board> Client<B> {
     }
 }
 
+/// Blocks when it gets dropped until the [`Client`] has been dropped.
+///
+/// Dropping the client drops the engine players, which terminates their child processes. This must happen before the
+/// program exits, but the client isn't necessarily dropped by the main thread: The engine input threads only upgrade
+/// their `Weak` while they handle a message, and the message that ended the match might still be in the process of
+/// being handled.
+#[derive(Debug)]
+struct WaitForClientDrop(Receiver<()>);
+
+impl Drop for WaitForClientDrop {
+    fn drop(&mut self) {
+        // nothing ever gets sent, so this returns once the sender, which is part of the client, has been dropped
+        _ = self.0.recv();
+    }
+}
+
+/// How often the thinking indicator gets redrawn while a player is thinking.
+const THINKING_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct RunClient<B: Board> {
     pub client: Arc<Mutex<Client<B>>>,
     pub should_quit: Parker,
     /// This is what actually takes control of the program. It could be e.g. a GUI or a SPRT runner.
     pub input: Box<dyn Input<B>>,
+    /// Declared after `client` so that this `RunClient`'s `Arc` has already been dropped when this waits.
+    _wait_for_client_drop: WaitForClientDrop,
 }
 
 impl<B: Board> RunClient<B> {
@@ -766,11 +958,18 @@ impl<B: Board> RunClient<B> {
         args: &CommandLineArgs,
     ) -> Res<Self> {
         let should_quit = Parker::new();
-        let client = Client::create(should_quit.unparker().clone(), all_outputs, args)?;
+        let (client_dropped, wait_for_client_drop) = bounded(0);
+        let client = Client::create(
+            should_quit.unparker().clone(),
+            client_dropped,
+            all_outputs,
+            args,
+        )?;
         Ok(Self {
             client,
             should_quit,
             input,
+            _wait_for_client_drop: WaitForClientDrop(wait_for_client_drop),
         })
     }
 }
@@ -782,13 +981,27 @@ impl<B: Board> AbstractRun for RunClient<B> {
             guard.new_match(0, 1);
         }
         self.input.assume_control(self.client.clone());
+        // Redraw the thinking indicators independently of the engines, which might not send any info for a long time.
+        // Like the input threads, this only holds a `Weak` to the client and stops once the client has been dropped.
+        let weak = Arc::downgrade(&self.client);
+        Builder::new()
+            .name("Thinking indicator".to_string())
+            .spawn(move || loop {
+                sleep(THINKING_REFRESH_INTERVAL);
+                let Some(client) = weak.upgrade() else {
+                    return;
+                };
+                let Ok(mut client) = client.lock() else {
+                    return;
+                };
+                client.show_thinking();
+            })
+            .unwrap();
         self.should_quit.park();
         // The program has been closed.
-        // Calling the `drop` implementation of an engine player ensures that its child process is terminated.
-        // However, this only happens if no other thread is still holding an `Arc` to the client when this thread
-        // exits. Therefore, we wait for the input thread(s) to terminate before exiting the main thread.
+        // The input threads only hold a `Weak` to the client, so after joining them, only the engine input threads
+        // can still keep the client alive. Dropping `self` waits for that, see `WaitForClientDrop`.
         self.input.join_threads();
-        assert_eq!(Arc::strong_count(&self.client), 1);
         QuitMatch
     }
 }
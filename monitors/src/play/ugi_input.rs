@@ -19,6 +19,7 @@ use gears::crossterm::style::Stylize;
 use gears::general::board::Board;
 use gears::general::common::anyhow::{anyhow, bail};
 use gears::general::common::{parse_duration_ms, parse_int_from_str, tokens, Res, Tokens};
+use gears::general::moves::ExtendedFormat::Alternative;
 use gears::general::moves::Move;
 use gears::output::Message::*;
 use gears::score::{ScoreT, SCORE_LOST, SCORE_WON};
@@ -26,10 +27,7 @@ use gears::search::{Depth, NodesLimit, SearchInfo, SearchLimit};
 use gears::ugi::EngineOptionType::*;
 use gears::ugi::{EngineOption, EngineOptionName, UgiCheck, UgiCombo, UgiSpin, UgiString};
 use gears::MatchStatus::Over;
-use gears::{
-    player_res_to_match_res, AdjudicationReason, GameOver, GameOverReason, MatchStatus,
-    PlayerResult,
-};
+use gears::{invalid_move_result, parse_player_move, MatchStatus};
 // TODO: Does not currently handle engines that simply don't terminate the search (unless the user inputs 'stop')
 // (not receiving ugiok/uiok is handled, as is losing on time with a bestmove response,
 // but non-responding engines currently require user intervention)
@@ -61,6 +59,9 @@ pub enum EngineStatus {
     #[default]
     WaitingUgiOk,
     Halt(HandleBestMove),
+    /// Searching the current position for a human player, see [`Client::request_hint`].
+    /// The resulting best move is only displayed, not played.
+    Hint,
 }
 
 impl Display for EngineStatus {
@@ -73,7 +74,8 @@ impl Display for EngineStatus {
             WaitingUgiOk => "initializing, waiting for 'ugiok'".to_string(),
             Halt(_) => "quit".to_string(),
             Sync => "waiting for 'readyok')".to_string(),
-            Ping(start) => format!("thinking (since {} ms ago), waiting for the engine to answer 'isready' with 'readyok'", start.elapsed().as_millis())
+            Ping(start) => format!("thinking (since {} ms ago), waiting for the engine to answer 'isready' with 'readyok'", start.elapsed().as_millis()),
+            Hint => "computing a hint".to_string(),
         };
         write!(f, "{str}")
     }
@@ -92,7 +94,7 @@ impl EngineStatus {
     pub fn thinking_since(&mut self) -> Option<Instant> {
         match self {
             ThinkingSince(time) | Ping(time) | Halt(Play(time)) => Some(*time),
-            Idle | Sync | WaitingUgiOk | Halt(Ignore) => None,
+            Idle | Sync | WaitingUgiOk | Halt(Ignore) | Hint => None,
         }
     }
 }
@@ -168,6 +170,7 @@ impl<B: Board> InputThread<B> {
                     ));
                     if !client.state.recover {
                         // Only try to restart crashed engines if the user has explicitly enabled this (TODO: enable by default for the GUI)
+                        client.engine_error(id, error);
                         return false;
                     }
                 }
@@ -175,16 +178,7 @@ impl<B: Board> InputThread<B> {
                     client.lock().unwrap().show_error(&format!("Error: Could not restart engine '{name}' after it encountered an error: {err}"));
                     return false; // All hope is lost.
                 }
-                let mut client = client.lock().unwrap();
-                let player = client.state.get_engine_from_id(id); // make the borrow checker happy by getting the player again
-                let res = GameOver {
-                    result: PlayerResult::Lose,
-                    reason: GameOverReason::Adjudication(AdjudicationReason::EngineError),
-                };
-                if let Some(current_match) = player.current_match.as_ref() {
-                    let color = current_match.color;
-                    client.game_over(player_res_to_match_res(res, color));
-                }
+                client.lock().unwrap().engine_error(id, error);
             }
         }
         true
@@ -270,6 +264,7 @@ impl<B: Board> InputThread<B> {
                 WaitingUgiOk => {
                     Self::handle_ugi_initial_state(command, words.clone(), &mut client, self.id)
                 }
+                Hint => Self::handle_ugi_hint_state(command, words.clone(), &mut client, self.id),
                 Halt(handle_bestmove) => Self::handle_ugi_halt_state(
                     command,
                     words.clone(),
@@ -388,6 +383,21 @@ impl<B: Board> InputThread<B> {
         Ok(())
     }
 
+    fn handle_ugi_hint_state(
+        command: &str,
+        words: Tokens,
+        client: &mut MutexGuard<Client<B>>,
+        engine: PlayerId,
+    ) -> Res<()> {
+        match command {
+            "info" => Self::handle_info(words, client, engine),
+            "bestmove" => Self::handle_hint_bestmove(words, client, engine),
+            _ => {
+                bail!("Only 'info' or 'bestmove' are valid engine messages while computing a hint")
+            }
+        }
+    }
+
     fn handle_protocol(
         mut words: Tokens,
         client: &mut MutexGuard<Client<B>>,
@@ -444,12 +454,14 @@ impl<B: Board> InputThread<B> {
         engine: PlayerId,
     ) -> Res<()> {
         let engine = client.state.get_engine_from_id_mut(engine);
-        assert_eq!(engine.status, Sync); // Otherwise, this function wouldn't get called
         if let Some(next) = words.next() {
             bail!("Engine message doesn't end after 'readyok', the next word is {next}")
         }
-        if engine.status == Sync {
-            engine.status = Idle;
+        // `isready` can be sent while idle or while thinking, and `readyok` returns the engine to that state
+        match engine.status {
+            Sync => engine.status = Idle,
+            Ping(start) => engine.status = ThinkingSince(start),
+            _ => unreachable!("this function only gets called in the sync and ping states"),
         }
         Ok(())
     }
@@ -465,29 +477,50 @@ impl<B: Board> InputThread<B> {
         let engine = client.state.get_engine_mut(color);
         engine.status = Idle;
 
-        let Some(move_text) = words.next() else {
-            bail!("missing move after 'bestmove'")
-        };
-        match B::Move::from_text(move_text, client.board()) {
+        let pos = *client.board();
+        debug_assert_eq!(pos.active_player(), color);
+        let move_text = words.next().unwrap_or_default();
+        let chosen_mov = match parse_player_move(move_text, &pos) {
+            Ok(mov) => mov,
             Err(err) => {
-                let game_over = GameOver {
-                    result: PlayerResult::Lose,
-                    reason: GameOverReason::Adjudication(AdjudicationReason::InvalidMove),
-                };
-                client.game_over(player_res_to_match_res(game_over, color));
+                client.log_rejected_move(move_text);
+                client.game_over(invalid_move_result(move_text, &pos));
                 return Err(err);
             }
-            Ok(chosen_mov) => {
-                if let Err(err) = client.play_move(chosen_mov) {
-                    let game_over = GameOver {
-                        result: PlayerResult::Lose,
-                        reason: GameOverReason::Adjudication(AdjudicationReason::InvalidMove),
-                    };
-                    client.game_over(player_res_to_match_res(game_over, color));
-                    return Err(err);
-                }
-            }
-        }
+        };
+        client.play_move(chosen_mov)
+    }
+
+    /// Unlike [`Self::handle_bestmove`], this doesn't play the move or stop any clock, and an invalid
+    /// move doesn't end the match.
+    fn handle_hint_bestmove(
+        mut words: Tokens,
+        client: &mut MutexGuard<Client<B>>,
+        engine: PlayerId,
+    ) -> Res<()> {
+        let engine = client.state.get_engine_from_id_mut(engine);
+        engine.status = Idle;
+        let name = engine.display_name.clone();
+        let score = engine
+            .current_match
+            .as_ref()
+            .and_then(|m| m.search_info.as_ref())
+            .map(|info| format!(" (score: {})", info.score))
+            .unwrap_or_default();
+        let pos = *client.board();
+        let move_text = words.next().unwrap_or_default();
+        let mov = parse_player_move(move_text, &pos).inspect_err(|_| {
+            client.log_rejected_move(move_text);
+            client.finish_hint(None);
+        })?;
+        client.finish_hint(Some(mov));
+        client.show_message(
+            Info,
+            &format!(
+                "Hint by {name}: {0}{score}",
+                mov.to_extended_text(&pos, Alternative).bold()
+            ),
+        );
         Ok(())
     }
 
@@ -5,6 +5,7 @@ use gears::general::common::anyhow::bail;
 use gears::general::common::{EntityList, NamedEntity, Res, StaticallyNamedEntity};
 use std::sync::{Arc, Mutex};
 
+pub mod match_input;
 pub mod text_input;
 
 /// An `Input` tells the [`MatchState`] what to do. It isn't necessarily just a way for a human to enter input,
@@ -0,0 +1,277 @@
+use std::fmt::Display;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{sleep, Builder, JoinHandle};
+
+use crossbeam_channel::Receiver;
+
+use crate::play::ugi_client::Client;
+use crate::ui::{Input, InputBuilder};
+use gears::general::board::Board;
+use gears::general::common::{NamedEntity, StaticallyNamedEntity};
+use gears::MatchResult;
+use gears::MatchStatus::Over;
+
+/// Lets two engines play a single match without any user interaction, similar to `cutechess-cli`.
+/// The program quits once the match is over.
+#[derive(Debug, Default)]
+pub(super) struct MatchInput {
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MatchInput {
+    fn wait_for_game_over<B: Board>(
+        client: Weak<Mutex<Client<B>>>,
+        game_over: Receiver<MatchResult>,
+    ) {
+        // The channel gets disconnected if the program quits before the match is over
+        if game_over.recv().is_err() {
+            return;
+        }
+        let Some(client) = client.upgrade() else {
+            return;
+        };
+        // The outputs have already been informed, and the PGN has already been written
+        let wait = client.lock().unwrap().state.wait_after_match;
+        sleep(wait);
+        client.lock().unwrap().quit_program();
+    }
+}
+
+impl StaticallyNamedEntity for MatchInput {
+    fn static_short_name() -> impl Display {
+        "match"
+    }
+
+    fn static_long_name() -> String {
+        "Engine match".to_string()
+    }
+
+    fn static_description() -> String {
+        "Lets two engines play a match without user interaction and quits once it's over"
+            .to_string()
+    }
+}
+
+impl<B: Board> Input<B> for MatchInput {
+    fn assume_control(&mut self, ugi_client: Arc<Mutex<Client<B>>>) {
+        let game_over = {
+            let mut client = ugi_client.lock().unwrap();
+            let game_over = client.on_game_over();
+            // The match can already be over, e.g. if the engines couldn't be started
+            if matches!(client.state.the_match.status, Over(_)) {
+                client.quit_program();
+            }
+            game_over
+        };
+        // Only keep a `Weak` so that this thread doesn't keep the client alive
+        let client = Arc::downgrade(&ugi_client);
+        self.handle = Some(
+            Builder::new()
+                .name("Match input thread".to_string())
+                .spawn(move || Self::wait_for_game_over(client, game_over))
+                .unwrap(),
+        );
+    }
+
+    fn join_threads(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().expect("The input thread panicked");
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MatchInputBuilder {}
+
+impl NamedEntity for MatchInputBuilder {
+    fn short_name(&self) -> String {
+        MatchInput::static_short_name().to_string()
+    }
+
+    fn long_name(&self) -> String {
+        MatchInput::static_long_name().to_string()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(MatchInput::static_description())
+    }
+}
+
+impl<B: Board> InputBuilder<B> for MatchInputBuilder {
+    fn build(&self) -> Box<dyn Input<B>> {
+        Box::new(MatchInput::default())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::cli::CommandLineArgs;
+    use crate::cli::PlayerArgs::Engine;
+    use crate::play::fake_engine::FakeEngine;
+    use crate::{create_run_client, normal_uis};
+    use gears::cli::Game;
+    use gears::games::ataxx::AtaxxBoard;
+    use gears::games::chess::Chessboard;
+    use gears::output::logger::LoggerBuilder;
+    use gears::search::TimeControl;
+    use gears::AbstractRun;
+    use itertools::Itertools;
+    use std::fs;
+    use std::num::NonZeroU64;
+    use std::str::FromStr;
+
+    #[test]
+    fn match_test() {
+        let white = FakeEngine::new("match_test_white", &["f2f3", "g2g4"]);
+        let black = FakeEngine::new("match_test_black", &["e7e5", "d8h4"]);
+        let pgn = white.file("match.pgn");
+        let args = CommandLineArgs {
+            ui: "match".to_string(),
+            players: vec![Engine(white.args.clone()), Engine(black.args.clone())],
+            pgn_out: Some(pgn.clone()),
+            ..Default::default()
+        };
+        let mut client = create_run_client(args, normal_uis::<Chessboard>()).unwrap();
+        // returns once the match is over
+        _ = client.run();
+        let pgn = fs::read_to_string(pgn).unwrap();
+        assert!(pgn.contains("[Result \"0-1\"]"), "{pgn}");
+        assert!(pgn.contains("[Termination \"normal\"]"), "{pgn}");
+        assert!(pgn.contains("2. g4 Qh4# {checkmate} 0-1"), "{pgn}");
+        let received = black.received();
+        assert!(received.contains("moves f2f3 e7e5 g2g4\ngo"), "{received}");
+    }
+
+    #[test]
+    fn engine_limits_test() {
+        let mut white = FakeEngine::new("engine_limits_test_white", &["e2e4", "d2d4"]);
+        let mut black = FakeEngine::new("engine_limits_test_black", &["e7e5", "d7d5"]);
+        let tc = TimeControl::from_str("10+0.5").unwrap();
+        // time odds: white gets twice as much time as black
+        white.args.tc = Some(tc);
+        white.args.time_scale = Some(2.0);
+        // black plays with a fixed number of nodes per move
+        black.args.tc = Some(tc);
+        black.args.nodes = NonZeroU64::new(1000);
+        let args = CommandLineArgs {
+            ui: "match".to_string(),
+            players: vec![Engine(white.args.clone()), Engine(black.args.clone())],
+            ..Default::default()
+        };
+        let mut client = create_run_client(args, normal_uis::<Chessboard>()).unwrap();
+        // the fake engines run out of moves, so this ends the match after a few moves
+        _ = client.run();
+        let go_commands = |engine: &FakeEngine| {
+            engine
+                .received()
+                .lines()
+                .filter(|line| line.starts_with("go"))
+                .map(str::to_string)
+                .collect_vec()
+        };
+        let white_go = go_commands(&white);
+        assert!(
+            white_go[0].starts_with("go wtime 20000 winc 1000 btime 10000 binc 500"),
+            "{white_go:?}"
+        );
+        assert!(
+            white_go.iter().all(|go| !go.contains("nodes")),
+            "{white_go:?}"
+        );
+        let black_go = go_commands(&black);
+        assert!(
+            black_go[0].contains(" btime 10000 binc 500 "),
+            "{black_go:?}"
+        );
+        assert!(
+            black_go.iter().all(|go| go.contains(" nodes 1000")),
+            "{black_go:?}"
+        );
+    }
+
+    /// Returns the PGN of an ataxx match where both engines move a piece back and forth.
+    fn repetition_match(name: &str, repetition_draw: bool) -> String {
+        let x = FakeEngine::new(
+            &format!("{name}_x"),
+            &["a1a3", "a3a1", "a1a3", "a3a1", "a1a3"],
+        );
+        let o = FakeEngine::new(
+            &format!("{name}_o"),
+            &["a7a5", "a5a7", "a7a5", "a5a7", "a7a5"],
+        );
+        let pgn = x.file("match.pgn");
+        let args = CommandLineArgs {
+            game: Game::Ataxx,
+            ui: "match".to_string(),
+            players: vec![Engine(x.args.clone()), Engine(o.args.clone())],
+            pgn_out: Some(pgn.clone()),
+            repetition_draw,
+            ..Default::default()
+        };
+        let mut client = create_run_client(args, normal_uis::<AtaxxBoard>()).unwrap();
+        _ = client.run();
+        fs::read_to_string(pgn).unwrap()
+    }
+
+    #[test]
+    fn repetition_draw_test() {
+        // unlike chess, ataxx doesn't end the game on repetitions, so this can only be an adjudication
+        let pgn = repetition_match("repetition_draw_test", true);
+        assert!(pgn.contains("[Result \"1/2-1/2\"]"), "{pgn}");
+        assert!(pgn.contains("[Termination \"adjudication\"]"), "{pgn}");
+        assert!(
+            pgn.contains(
+                "4. a3a1 a5a7 {Matchmaker adjudication: The position has occurred three times}"
+            ),
+            "{pgn}"
+        );
+        // the adjudication is opt-in, so without it, the game continues until the first engine runs out of moves
+        let pgn = repetition_match("no_repetition_draw_test", false);
+        assert!(
+            !pgn.contains("The position has occurred three times"),
+            "{pgn}"
+        );
+        assert!(pgn.contains("[Result \"0-1\"]"), "{pgn}");
+        assert!(pgn.contains("5. a1a3 a7a5"), "{pgn}");
+    }
+
+    #[test]
+    fn rejected_move_log_test() {
+        let white = FakeEngine::new("rejected_move_test_white", &["e2e5"]);
+        let black = FakeEngine::new("rejected_move_test_black", &[]);
+        let log = white.file("client.log");
+        let pgn = white.file("match.pgn");
+        let args = CommandLineArgs {
+            ui: "match".to_string(),
+            players: vec![Engine(white.args.clone()), Engine(black.args.clone())],
+            pgn_out: Some(pgn.clone()),
+            ..Default::default()
+        };
+        let mut client = create_run_client(args, normal_uis::<Chessboard>()).unwrap();
+        client
+            .client
+            .lock()
+            .unwrap()
+            .add_output(Box::new(LoggerBuilder::new(log.to_str().unwrap())))
+            .unwrap();
+        _ = client.run();
+        let log = fs::read_to_string(log).unwrap();
+        let entry = log
+            .lines()
+            .find(|line| line.contains("Rejected move 'e2e5'"))
+            .unwrap_or_else(|| panic!("{log}"));
+        let legal = entry.split_once("Legal moves: ").unwrap().1;
+        assert!(legal.split_whitespace().any(|mov| mov == "e2e4"), "{entry}");
+        let pgn = fs::read_to_string(pgn).unwrap();
+        // white made an invalid move, so black wins
+        assert!(pgn.contains("[Result \"0-1\"]"), "{pgn}");
+        assert!(pgn.contains("[Termination \"rules infraction\"]"), "{pgn}");
+        let start_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(
+            pgn.contains(&format!(
+                "{{Invalid move 'e2e5' in position '{start_fen}'}} 0-1"
+            )),
+            "{pgn}"
+        );
+    }
+}
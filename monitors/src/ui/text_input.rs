@@ -3,7 +3,9 @@ use std::io::stdin;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, MutexGuard, Weak};
 use std::thread::{Builder, JoinHandle};
+use std::time::Duration;
 
+use crossbeam_channel::RecvTimeoutError;
 use itertools::Itertools;
 use rand::rng;
 
@@ -28,11 +30,15 @@ use gears::general::moves::ExtendedFormat::Alternative;
 use gears::general::moves::Move;
 use gears::output::Message::{Info, Warning};
 use gears::output::OutputOpts;
-use gears::search::TimeControl;
+use gears::search::{Depth, TimeControl};
 use gears::ugi::{parse_ugi_position_part, EngineOption};
 use gears::MatchStatus::{Ongoing, Over};
 use gears::{output_builder_from_str, GameState};
 
+const DEFAULT_HINT_DEPTH: Depth = Depth::new_unchecked(8);
+
+const HINT_TIMEOUT: Duration = Duration::from_secs(5);
+
 // TODO: Unify with motors `Command`, probably move to gears
 struct TextSelection<F> {
     names: Vec<&'static str>,
@@ -123,6 +129,7 @@ impl<B: Board> TextInputThread<B> {
                 sel_descr(vec!["info"], |client, words| Self::handle_info(client, words), "Print general information about the given player, e.g. 'info' or 'info black'"),
                 sel_descr(vec!["set_player"], |client, words| Self::handle_set_player(client, words), "Set a player, e.g. 'set_player white human'."),
                 sel_descr(vec!["load_player"], |_, _| panic!("This should've been handled manually'"), "Load a new player, which will then be available to play, such as by using 'set_player'"),
+                sel_descr(vec!["hint"], |_, _| panic!("This should've been handled manually'"), "Asks the opposing engine for a quick suggestion for the current human player, e.g. 'hint' or 'hint 10' (the optional number is the search depth)"),
                 sel_descr(vec!["position"], |client, words| Self::handle_position(client, words), "Set the current position, e.g. 'position fen <fen>'"),
                 sel_descr(vec!["tc", "time"], |client, words| Self::handle_tc(client, words), "Set the time control of a player, given in seconds, e.g. 'tc white 300+3' or 'tc black 8+0.08'"),
                 sel_descr(vec!["ugi", "uci", "send_ugi", "send_uci"], |client, words| Self::handle_send_ugi(client, words), "Manually send a UGI command to an engine, e.g 'ugi white go depth 3'. Note that this can very easily crash the engine and is only intended as a developer tool."),
@@ -180,6 +187,9 @@ impl<B: Board> TextInputThread<B> {
             // Shouldn't be a part of the `commands` vec because it has a different signature (takes an `Arc<Mutex<Client>>`
             // instead of a `MutexGuard<Client>`).
             Self::handle_load_player(ugi_client.clone(), &mut words)?;
+        } else if command.eq_ignore_ascii_case("hint") {
+            // Also handled manually because it needs to unlock the client while waiting for the engine.
+            Self::handle_hint(ugi_client.clone(), &mut words)?;
         } else {
             let mut client = ugi_client.lock().unwrap();
             match B::Move::from_text(input, client.board()) {
@@ -187,9 +197,10 @@ impl<B: Board> TextInputThread<B> {
                     let Some(active_player) = client.active_player() else {
                         bail!("Ignoring move because the game is over".to_string())
                     };
-                    client
-                        .play_move(mov)
-                        .map_err(|err| anyhow!("Ignoring input: {err}"))?;
+                    client.play_move(mov).map_err(|err| {
+                        client.log_rejected_move(input);
+                        anyhow!("Ignoring input: {err}")
+                    })?;
                     // `play_move` will have stopped the clock by now.
                     assert!(client
                         .state
@@ -337,9 +348,12 @@ impl<B: Board> TextInputThread<B> {
             bail!("Unrecognized word '{word}' after position command, expected either 'moves' or nothing")
         }
         for mov in words {
-            let mov = B::Move::from_compact_text(mov, client.board())
-                .map_err(|err| anyhow!("Couldn't parse move: {err}"))?;
-            client.play_move_internal(mov)?;
+            let parsed = B::Move::from_compact_text(mov, client.board())
+                .map_err(|err| anyhow!("Couldn't parse move: {err}"));
+            if let Err(err) = parsed.and_then(|m| client.play_move_internal(m)) {
+                client.log_rejected_move(mov);
+                return Err(err);
+            }
         }
         Ok(())
     }
@@ -411,6 +425,40 @@ impl<B: Board> TextInputThread<B> {
         Ok(())
     }
 
+    fn handle_hint(ugi_client: Arc<Mutex<Client<B>>>, words: &mut Tokens) -> Res<()> {
+        let depth = match words.next() {
+            None => DEFAULT_HINT_DEPTH,
+            Some(word) => Depth::try_new(parse_int_from_str(word, "hint depth")?)?,
+        };
+        _ = Self::compute_hint(&ugi_client, depth)?;
+        Ok(())
+    }
+
+    /// Blocks until the engine has answered, which means that no moves can be input in the meantime.
+    /// The engine's input thread needs to lock the client to handle the `bestmove`, which then also displays it,
+    /// so the client must not be locked while waiting.
+    fn compute_hint(ugi_client: &Arc<Mutex<Client<B>>>, depth: Depth) -> Res<B::Move> {
+        let (engine, hint) = ugi_client.lock().unwrap().request_hint(depth)?;
+        let mut sent_stop = false;
+        loop {
+            match hint.recv_timeout(HINT_TIMEOUT) {
+                Ok(mov) => return Ok(mov),
+                Err(RecvTimeoutError::Disconnected) => {
+                    bail!("The engine didn't answer with a valid move")
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let mut client = ugi_client.lock().unwrap();
+                    if sent_stop {
+                        client.cancel_hint(engine);
+                        bail!("The engine didn't answer in time")
+                    }
+                    client.send_ugi_message_to(engine, "stop");
+                    sent_stop = true;
+                }
+            }
+        }
+    }
+
     fn handle_ui(mut client: MutexGuard<Client<B>>, words: &mut Tokens) -> Res<()> {
         match words.next() {
             None => {
@@ -572,3 +620,31 @@ impl<B: Board> InputBuilder<B> for TextInputBuilder {
         Box::new(TextInput::default())
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cli::CommandLineArgs;
+    use crate::play::fake_engine::FakeEngine;
+    use crate::{create_run_client, normal_uis};
+    use gears::games::chess::Chessboard;
+
+    #[test]
+    fn hint_test() {
+        let engine = FakeEngine::new("hint_test_engine", &["e2e4"]);
+        let args = CommandLineArgs {
+            players: vec![Human(HumanArgs::default()), Engine(engine.args.clone())],
+            ..Default::default()
+        };
+        // keep the `RunClient` alive, dropping it waits until the client has been dropped
+        let run_client = create_run_client(args, normal_uis::<Chessboard>()).unwrap();
+        let client = run_client.client.clone();
+        client.lock().unwrap().new_match(0, 1);
+        let mov = TextInputThread::compute_hint(&client, Depth::new_unchecked(3)).unwrap();
+        assert_eq!(mov.compact_text(false), "e2e4");
+        assert!(engine.received().contains("go depth 3"));
+        // the hint only gets displayed, not played
+        assert_eq!(*client.lock().unwrap().board(), Chessboard::default());
+        client.lock().unwrap().quit_program();
+    }
+}
@@ -0,0 +1,214 @@
+//! Self-play data generation for the tuner.
+//!
+//! The engine plays against itself with a low depth / node budget, and the resulting positions are written to
+//! disk as annotated FENs in the format expected by `pliers`, i.e. `<fen> [<wdl>]`, optionally followed by the
+//! search score. Both the wdl and the score are from white's (i.e., the first player's) perspective.
+
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use gears::games::{BoardHistory, Color, ZobristHistory};
+use gears::general::board::{random_opening, Board};
+use gears::general::common::anyhow::anyhow;
+use gears::general::common::Res;
+use gears::general::moves::Move;
+use gears::search::{Depth, NodesLimit, SearchLimit};
+use gears::GameResult;
+use gears::Quitting::QuitProgram;
+use gears::{AbstractRun, Quitting};
+
+use crate::io::cli::EngineOpts;
+use crate::search::tt::TT;
+use crate::search::{Engine, EvalList, SearchParams, SearcherList};
+use crate::{create_engine_box_from_str, Mode};
+
+pub const DEFAULT_DATAGEN_DEPTH: Depth = Depth::new_unchecked(5);
+
+/// Settings for the `datagen` mode.
+#[derive(Debug, Clone)]
+pub struct DatagenOpts {
+    /// The directory where the shards get written to. Created if it doesn't exist.
+    pub out: PathBuf,
+    /// The number of self-play games.
+    pub games: usize,
+    /// Depth limit for each search. If neither this nor `nodes` is set, [`DEFAULT_DATAGEN_DEPTH`] is used.
+    pub depth: Option<Depth>,
+    /// Node limit for each search.
+    pub nodes: Option<NodesLimit>,
+    /// The maximum number of positions per shard. Each shard is a separate file.
+    pub shard_size: usize,
+    /// Also write the search score after the wdl.
+    pub with_score: bool,
+    /// The number of random moves played at the start of each game, see [`random_opening`].
+    pub random_plies: usize,
+    /// Games that last longer than this are adjudicated as draws.
+    pub max_plies: usize,
+    /// The seed of the random opening of the first game; gets incremented for each game.
+    pub seed: u64,
+}
+
+impl Default for DatagenOpts {
+    fn default() -> Self {
+        Self {
+            out: PathBuf::from("datagen"),
+            games: 100,
+            depth: None,
+            nodes: None,
+            shard_size: 100_000,
+            with_score: false,
+            random_plies: 8,
+            max_plies: 400,
+            seed: 0,
+        }
+    }
+}
+
+/// The wdl from the first player's perspective, as used by the `White` perspective in `pliers`.
+fn wdl(result: GameResult) -> &'static str {
+    match result {
+        GameResult::P1Win => "1.0",
+        GameResult::P2Win => "0.0",
+        _ => "0.5",
+    }
+}
+
+/// Plays a single self-play game and returns the annotated FENs of all positions that weren't filtered out.
+/// Positions where the side to move is in check or where the best move is tactical (such as a capture) are skipped,
+/// as are the positions of the random opening.
+pub fn play_datagen_game<B: Board>(
+    engine: &mut dyn Engine<B>,
+    opts: &DatagenOpts,
+    seed: u64,
+) -> Vec<String> {
+    let mut limit = SearchLimit::infinite();
+    if let Some(nodes) = opts.nodes {
+        limit.nodes = nodes;
+    }
+    if let Some(depth) = opts.depth {
+        limit.depth = depth;
+    } else if opts.nodes.is_none() {
+        limit.depth = DEFAULT_DATAGEN_DEPTH;
+    }
+    let mut pos = B::default();
+    let mut history = ZobristHistory::default();
+    for mov in random_opening(pos, opts.random_plies, seed) {
+        history.push(&pos);
+        pos = pos.make_move(mov).expect("random opening moves are legal");
+    }
+    engine.forget();
+    let tt = TT::default();
+    // (fen, score from white's perspective)
+    let mut positions = vec![];
+    let result = loop {
+        if let Some(res) = pos.match_result_slow(&history) {
+            break res.result;
+        }
+        if history.len() >= opts.max_plies {
+            break GameResult::Draw;
+        }
+        let res = engine.search(SearchParams::new_unshared(
+            pos,
+            limit,
+            history.clone(),
+            tt.clone(),
+        ));
+        let mov = res.chosen_move;
+        if !pos.is_in_check() && !mov.is_tactical(&pos) {
+            let score = res.score.unwrap_or_default();
+            let score = if pos.active_player().is_first() {
+                score
+            } else {
+                -score
+            };
+            positions.push((pos.as_fen(), score));
+        }
+        history.push(&pos);
+        let Some(new_pos) = pos.make_move(mov) else {
+            // Shouldn't happen, but avoids crashing the whole datagen run.
+            break GameResult::Draw;
+        };
+        pos = new_pos;
+    };
+    let wdl = wdl(result);
+    positions
+        .into_iter()
+        .map(|(fen, score)| {
+            if opts.with_score {
+                format!("{fen} [{wdl}] {}", score.0)
+            } else {
+                format!("{fen} [{wdl}]")
+            }
+        })
+        .collect()
+}
+
+fn write_shard(dir: &Path, idx: usize, lines: &[String]) -> Res<()> {
+    let path = dir.join(format!("shard_{idx}.txt"));
+    let file = File::create(&path)
+        .map_err(|err| anyhow!("Couldn't create '{}': {err}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    for line in lines {
+        writeln!(writer, "{line}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Plays `opts.games` games and writes the positions to shards in `opts.out`. Returns the number of positions.
+pub fn run_datagen<B: Board>(engine: &mut dyn Engine<B>, opts: &DatagenOpts) -> Res<usize> {
+    create_dir_all(&opts.out)
+        .map_err(|err| anyhow!("Couldn't create '{}': {err}", opts.out.display()))?;
+    let mut shard = vec![];
+    let mut num_shards = 0;
+    let mut num_positions = 0;
+    for game in 0..opts.games {
+        let lines = play_datagen_game(engine, opts, opts.seed.wrapping_add(game as u64));
+        num_positions += lines.len();
+        shard.extend(lines);
+        while shard.len() >= opts.shard_size.max(1) {
+            let rest = shard.split_off(opts.shard_size.max(1));
+            write_shard(&opts.out, num_shards, &shard)?;
+            num_shards += 1;
+            shard = rest;
+        }
+    }
+    if !shard.is_empty() {
+        write_shard(&opts.out, num_shards, &shard)?;
+    }
+    Ok(num_positions)
+}
+
+#[derive(Debug)]
+pub(super) struct DatagenRun<B: Board> {
+    engine: Box<dyn Engine<B>>,
+    opts: DatagenOpts,
+}
+
+impl<B: Board> DatagenRun<B> {
+    pub fn create(
+        options: &EngineOpts,
+        all_searchers: &SearcherList<B>,
+        all_evals: &EvalList<B>,
+    ) -> Res<Self> {
+        let Mode::Datagen(opts) = options.mode.clone() else {
+            unreachable!()
+        };
+        let engine = create_engine_box_from_str(&options.engine, all_searchers, all_evals)?;
+        Ok(Self { engine, opts })
+    }
+}
+
+impl<B: Board> AbstractRun for DatagenRun<B> {
+    fn run(&mut self) -> Quitting {
+        match run_datagen(self.engine.as_mut(), &self.opts) {
+            Ok(num) => println!(
+                "Wrote {num} positions from {0} games to '{1}'",
+                self.opts.games,
+                self.opts.out.display()
+            ),
+            Err(err) => eprintln!("Datagen failed: {err}"),
+        }
+        QuitProgram
+    }
+}
@@ -4,8 +4,10 @@ use gears::general::board::Board;
 use std::fmt::Debug;
 use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
-use gears::general::common::StaticallyNamedEntity;
+use gears::general::common::anyhow::bail;
+use gears::general::common::{Res, StaticallyNamedEntity};
 use gears::score::{PhaseType, PhasedScore, Score, ScoreT};
+use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType};
 
 pub mod rand_eval;
 
@@ -27,6 +29,13 @@ pub trait Eval<B: Board>: Debug + Send + StaticallyNamedEntity + DynClone + 'sta
         self.eval(new_pos, ply)
     }
 
+    /// Evaluates many unrelated positions at once, such as all positions of a dataset, with `ply` set to 0.
+    /// The default simply calls [`Self::eval`] for each position, but evals with expensive setup (e.g. networks)
+    /// can override this to amortize the setup. The result contains one score per position, in the same order.
+    fn eval_batch(&mut self, positions: &[&B]) -> Vec<Score> {
+        positions.iter().map(|pos| self.eval(pos, 0)).collect()
+    }
+
     /// How much larger do we expect variation in piece scores to be than variation in eval scores?
     /// This is used for coloring the eval score in the pretty 'eval' command, which removes each piece
     /// and prints the resulting eval delta. The value returned by this function doesn't have to be
@@ -36,6 +45,38 @@ pub trait Eval<B: Board>: Debug + Send + StaticallyNamedEntity + DynClone + 'sta
     fn piece_scale(&self) -> ScoreT {
         2
     }
+
+    /// The internal score that should be reported as 100 centipawns in this position when the `NormalizeScore` option
+    /// is set, see [`normalize_score`](gears::score::normalize_score). This usually depends on the material left.
+    /// Returns `None` by default, which means that scores don't get normalized.
+    fn score_normalization(&self, _pos: &B) -> Option<ScoreT> {
+        None
+    }
+
+    /// A human-readable breakdown of the static eval into its terms, printed by the `eval` command.
+    /// Evals that aren't made up of individual terms (or just don't bother) can keep the default implementation.
+    fn explain(&self, _pos: &B) -> String {
+        "no breakdown available".to_string()
+    }
+
+    /// Options that can be changed with `setoption`, such as the seed of the random eval.
+    /// These get listed together with the searcher's options. Most evals don't have any options.
+    fn options(&self) -> Vec<EngineOption> {
+        vec![]
+    }
+
+    /// Sets one of the options returned by [`Self::options`]. `old_value` is the current value and should be updated.
+    fn set_option(
+        &mut self,
+        option: &EngineOptionName,
+        _old_value: &mut EngineOptionType,
+        value: &str,
+    ) -> Res<()> {
+        bail!(
+            "The eval '{0}' doesn't support setting the option '{option}' to '{value}'",
+            self.long_name()
+        )
+    }
 }
 
 #[expect(type_alias_bounds)]
@@ -10,6 +10,7 @@ use strum_macros::EnumIter;
 
 pub mod king_gambot;
 pub mod lite;
+pub mod lite_trace;
 pub mod lite_values;
 pub mod material_only;
 pub mod piston;
@@ -27,8 +28,6 @@ pub enum FileOpenness {
 
 pub type DiagonalOpenness = FileOpenness;
 
-pub const CHESS_PHASE_VALUES: [usize; NUM_CHESS_PIECES] = [0, 1, 1, 2, 4, 0];
-
 pub const NUM_PSQT_FEATURES: usize = NUM_CHESS_PIECES * NUM_SQUARES;
 
 pub const NUM_PAWN_SHIELD_CONFIGURATIONS: usize = (1 << 6) + (1 << 4) + (1 << 4);
@@ -87,7 +86,7 @@ pub fn pawn_shield_idx(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::eval::chess::lite::LiTEval;
+    use crate::eval::chess::lite::{shield_pawn_deficit, LiTEval};
     use crate::eval::chess::material_only::MaterialOnlyEval;
     use crate::eval::chess::piston::PistonEval;
     use crate::eval::Eval;
@@ -98,7 +97,9 @@ mod tests {
     use gears::games::DimT;
     use gears::general::bitboards::RawBitboard;
     use gears::general::board::Board;
+    use gears::general::board::Strictness::Strict;
     use gears::score::Score;
+    use itertools::Itertools;
     use strum::IntoEnumIterator;
 
     #[test]
@@ -149,7 +150,7 @@ mod tests {
         }
         let mut res = 0;
 
-        let file_deltas = if king.file() % 8 == 0 {
+        let file_deltas = if king.file().is_multiple_of(8) {
             res += 1 << 6;
             vec![0, 1]
         } else if king.file() % 8 == 7 {
@@ -210,4 +211,86 @@ mod tests {
         generic_eval_test::<PistonEval>();
         generic_eval_test::<LiTEval>();
     }
+
+    #[test]
+    fn score_normalization_test() {
+        let eval = LiTEval::default();
+        let startpos = eval.score_normalization(&Chessboard::default()).unwrap();
+        let lucena = Chessboard::from_name("lucena").unwrap();
+        let lucena = eval.score_normalization(&lucena).unwrap();
+        // the same advantage is worth more in the endgame
+        assert!(startpos > lucena, "{startpos} {lucena}");
+        assert!((100..200).contains(&startpos), "{startpos}");
+        assert!((100..200).contains(&lucena), "{lucena}");
+        assert!(MaterialOnlyEval::default()
+            .score_normalization(&Chessboard::default())
+            .is_none());
+    }
+
+    #[test]
+    fn explain_test() {
+        for pos in Chessboard::bench_positions() {
+            let mut eval = LiTEval::default();
+            let explanation = eval.explain(&pos);
+            for term in ["Material + PSQT", "King safety", "Mobility", "Total"] {
+                assert!(explanation.contains(term), "{explanation}");
+            }
+            let score = eval.eval(&pos, 0);
+            assert!(
+                explanation.contains(&format!("Final eval: {} (", score.0)),
+                "{pos} {score} {explanation}"
+            );
+        }
+        assert_eq!(
+            MaterialOnlyEval::default().explain(&Chessboard::default()),
+            "no breakdown available"
+        );
+    }
+
+    #[test]
+    fn king_safety_test() {
+        let sheltered =
+            Chessboard::from_fen("r4rk1/5ppp/8/8/8/8/5PPP/R4RK1 w - - 0 1", Strict).unwrap();
+        let exposed =
+            Chessboard::from_fen("r4rk1/5ppp/8/8/8/8/PPP5/R4RK1 w - - 0 1", Strict).unwrap();
+        let mut eval = LiTEval::default();
+        assert!(eval.eval(&exposed, 0) < eval.eval(&sheltered, 0));
+        let deficit = |pos: &Chessboard, color| {
+            shield_pawn_deficit(
+                pos.colored_piece_bb(color, Pawn),
+                pos.king_square(color),
+                color,
+            )
+        };
+        assert_eq!(deficit(&sheltered, White), Some(0));
+        assert_eq!(deficit(&sheltered, Black), Some(0));
+        assert_eq!(deficit(&exposed, White), Some(3));
+        assert_eq!(deficit(&exposed, Black), Some(0));
+        // pawns that have advanced by one square still shelter the king
+        let pos = Chessboard::from_fen("6k1/6p1/5p1p/8/8/8/8/K7 w - - 0 1", Strict).unwrap();
+        assert_eq!(deficit(&pos, Black), Some(0));
+        // only two files next to a king in the corner
+        assert_eq!(deficit(&pos, White), Some(2));
+        // uncastled kings don't get this penalty
+        assert_eq!(deficit(&Chessboard::default(), White), None);
+    }
+
+    fn eval_batch_test<E: Eval<Chessboard> + Default>() {
+        let positions = Chessboard::bench_positions();
+        let refs = positions.iter().collect_vec();
+        let batch = E::default().eval_batch(&refs);
+        assert_eq!(batch.len(), positions.len());
+        let mut eval = E::default();
+        for (pos, score) in positions.iter().zip(batch) {
+            assert_eq!(eval.eval(pos, 0), score, "{pos}");
+        }
+        assert!(E::default().eval_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn batch_eval_test() {
+        eval_batch_test::<MaterialOnlyEval>();
+        eval_batch_test::<PistonEval>();
+        eval_batch_test::<LiTEval>();
+    }
 }
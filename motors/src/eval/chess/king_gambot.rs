@@ -121,6 +121,14 @@ impl LiteValues for KingGambotValues {
         }
     }
 
+    fn shield_pawn_deficit(missing: usize) -> PhasedScore {
+        Lite::shield_pawn_deficit(missing)
+    }
+
+    fn king_adjacent_openness(openness: FileOpenness) -> PhasedScore {
+        Lite::king_adjacent_openness(openness)
+    }
+
     fn pawn_protection(piece: ChessPieceType) -> PhasedScore {
         Lite::pawn_protection(piece)
     }
@@ -1,12 +1,13 @@
 use std::fmt::Display;
+use std::iter::once;
 use strum::IntoEnumIterator;
 
 use crate::eval::chess::lite_values::*;
 use crate::eval::chess::{pawn_shield_idx, DiagonalOpenness, FileOpenness};
 use gears::games::chess::moves::ChessMove;
+use gears::games::chess::pieces::ChessPieceType;
 use gears::games::chess::pieces::ChessPieceType::*;
-use gears::games::chess::pieces::{ChessPieceType, NUM_CHESS_PIECES};
-use gears::games::chess::squares::ChessSquare;
+use gears::games::chess::squares::{ChessSquare, C_FILE_NO, F_FILE_NO, H_FILE_NO};
 use gears::games::chess::ChessColor::{Black, White};
 use gears::games::chess::{ChessColor, Chessboard, SliderMove};
 use gears::games::Color;
@@ -20,16 +21,16 @@ use gears::general::board::Board;
 use gears::general::common::StaticallyNamedEntity;
 use gears::general::moves::Move;
 use gears::general::squares::RectangularCoordinates;
-use gears::score::{PhaseType, PhasedScore, Score, ScoreT};
+use gears::score::{normalized_pawn_value, PhaseType, PhasedScore, Score, ScoreT};
 
 use crate::eval::chess::king_gambot::KingGambotValues;
 use crate::eval::chess::lite::FileOpenness::{Closed, Open, SemiClosed, SemiOpen};
+use crate::eval::chess::lite_trace::{EvalTerm, TracedValues};
 use crate::eval::{Eval, ScoreType, SingleFeatureScore};
 
 #[derive(Debug, Default, Copy, Clone)]
 struct EvalState<Tuned: LiteValues> {
     hash: ZobristHash,
-    phase: PhaseType,
     // scores are stored from the perspective of the white player
     psqt_score: Tuned::Score,
     pawn_shield_score: Tuned::Score,
@@ -49,8 +50,15 @@ pub type KingGambot = GenericLiTEval<KingGambotValues>;
 pub const TEMPO: Score = Score(10);
 // TODO: Differentiate between rooks and kings in front of / behind pawns?
 
-/// Includes a phase for the empty piece to simplify the implementation
-const PIECE_PHASE: [PhaseType; NUM_CHESS_PIECES + 1] = [0, 1, 1, 2, 4, 0, 0];
+/// The scale of the sigmoid that converts scores to win rates, for the middlegame and endgame.
+/// The tuner uses a single scale of 120 for all positions, but advantages in the endgame
+/// are easier to convert, so the same score corresponds to a larger win rate.
+const WINRATE_SCALE_MG: f64 = 135.0;
+const WINRATE_SCALE_EG: f64 = 100.0;
+
+fn phase(pos: &Chessboard) -> PhaseType {
+    pos.game_phase() as PhaseType
+}
 
 fn openness(
     ray: ChessBitboard,
@@ -98,6 +106,40 @@ pub fn anti_diagonal_openness(
     )
 }
 
+fn adjacent_files(file: DimT) -> impl Iterator<Item = DimT> {
+    [
+        file.checked_sub(1),
+        Some(file + 1).filter(|&f| f <= H_FILE_NO),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// The number of files on or next to the king's file without one of our pawns on the two squares in front of the king.
+/// Returns `None` unless the king is castled, i.e. on its back rank on one of the wings.
+pub fn shield_pawn_deficit(
+    our_pawns: ChessBitboard,
+    king_square: ChessSquare,
+    color: ChessColor,
+) -> Option<usize> {
+    let back_rank = if color == White { 0 } else { 7 };
+    let file = king_square.file();
+    if king_square.rank() != back_rank || (C_FILE_NO < file && file < F_FILE_NO) {
+        return None;
+    }
+    let shield_ranks = if color == White { [1, 2] } else { [6, 5] };
+    let missing = once(file)
+        .chain(adjacent_files(file))
+        .filter(|&file| {
+            shield_ranks.iter().all(|&rank| {
+                let square = ChessSquare::from_rank_file(rank, file);
+                !our_pawns.is_bit_set_at(square.bb_idx())
+            })
+        })
+        .count();
+    Some(missing)
+}
+
 impl<Tuned: LiteValues> StaticallyNamedEntity for GenericLiTEval<Tuned> {
     fn static_short_name() -> impl Display
     where
@@ -153,11 +195,22 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
         score
     }
 
-    fn pawn_shield_for(pos: &Chessboard, color: ChessColor) -> SingleFeatureScore<Tuned::Score> {
+    /// Everything that only depends on the pawns and the king, so that it can be cached together with the pawn shield.
+    fn pawn_shield_for(pos: &Chessboard, color: ChessColor) -> Tuned::Score {
         let our_pawns = pos.colored_piece_bb(color, Pawn);
+        let their_pawns = pos.colored_piece_bb(color.other(), Pawn);
         let king_square = pos.king_square(color);
         let idx = pawn_shield_idx(our_pawns, king_square, color);
-        Tuned::default().pawn_shield(color, idx)
+        let mut score = Tuned::Score::from(Tuned::default().pawn_shield(color, idx));
+        // only castled kings, otherwise this would e.g. encourage a lone king to stay on the edge
+        if let Some(missing) = shield_pawn_deficit(our_pawns, king_square, color) {
+            score += Tuned::shield_pawn_deficit(missing);
+            for file in adjacent_files(king_square.file()) {
+                let openness = file_openness(file, our_pawns, their_pawns);
+                score += Tuned::king_adjacent_openness(openness);
+            }
+        }
+        score
     }
 
     fn pawn_shield(pos: &Chessboard) -> Tuned::Score {
@@ -299,11 +352,10 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
         mov: ChessMove,
         captured: ChessPieceType,
         new_pos: &Chessboard,
-    ) -> (Tuned::Score, PhaseType) {
+    ) -> Tuned::Score {
         let moving_player = old_pos.active_player();
         // the current player has been flipped
         let mut delta = Tuned::Score::default();
-        let mut phase_delta = PhaseType::default();
         let piece = mov.piece_type();
         delta -= self.tuned.psqt(mov.src_square(), piece, moving_player);
         if mov.is_castle() {
@@ -323,7 +375,6 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
             delta += self
                 .tuned
                 .psqt(mov.dest_square(), mov.promo_piece(), moving_player);
-            phase_delta += PIECE_PHASE[mov.promo_piece() as usize];
         }
         if mov.is_ep() {
             delta += self.tuned.psqt(
@@ -336,27 +387,17 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
             delta += self
                 .tuned
                 .psqt(mov.dest_square(), captured, moving_player.other());
-            phase_delta -= PIECE_PHASE[captured as usize];
         }
         // the position is always evaluated from white's perspective
-        (
-            match moving_player {
-                White => delta,
-                Black => -delta,
-            },
-            phase_delta,
-        )
+        match moving_player {
+            White => delta,
+            Black => -delta,
+        }
     }
 
     fn eval_from_scratch(&self, pos: &Chessboard) -> (EvalState<Tuned>, Tuned::Score) {
         let mut state = EvalState::default();
 
-        let mut phase = 0;
-        for piece in ChessPieceType::non_king_pieces() {
-            phase += pos.piece_bb(piece).num_ones() as isize * PIECE_PHASE[piece as usize];
-        }
-        state.phase = phase;
-
         let psqt_score = self.psqt(pos);
         state.psqt_score = psqt_score.clone();
         let pawn_shield_score = Self::pawn_shield(pos);
@@ -370,9 +411,9 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
     }
 
     pub fn do_eval(&self, pos: &Chessboard) -> <Tuned::Score as ScoreType>::Finalized {
-        let (state, score) = self.eval_from_scratch(pos);
+        let (_, score) = self.eval_from_scratch(pos);
         score.finalize(
-            state.phase,
+            phase(pos),
             24,
             pos.active_player(),
             <Tuned::Score as ScoreType>::Finalized::default(),
@@ -404,16 +445,14 @@ impl<Tuned: LiteValues> GenericLiTEval<Tuned> {
             );
             debug_assert_eq!(&old_pos.make_move(mov).unwrap(), new_pos);
             let captured = mov.captured(old_pos);
-            let (psqt_delta, phase_delta) = self.psqt_delta(old_pos, mov, captured, new_pos);
-            state.psqt_score += psqt_delta;
-            state.phase += phase_delta;
+            state.psqt_score += self.psqt_delta(old_pos, mov, captured, new_pos);
             debug_assert_eq!(
                 state.psqt_score,
                 self.psqt(new_pos),
                 "{0} {1} {2} {old_pos} {new_pos} {mov}",
                 state.psqt_score,
                 self.psqt(new_pos),
-                self.psqt_delta(old_pos, mov, captured, new_pos).0,
+                self.psqt_delta(old_pos, mov, captured, new_pos),
             );
             // TODO: Test if this is actually faster -- getting the captured piece is quite expensive
             // (but this could be remedied by reusing that info from `psqt_delta`, or by using a redundant mailbox)
@@ -441,7 +480,7 @@ fn eval_lite<Tuned: LiteValues<Score = PhasedScore>>(
     this.stack.clear();
     let (state, score) = this.eval_from_scratch(pos);
     this.stack.push(state);
-    score.finalize(state.phase, 24, pos.active_player(), TEMPO)
+    score.finalize(phase(pos), 24, pos.active_player(), TEMPO)
 }
 
 fn eval_lite_incremental<Tuned: LiteValues<Score = PhasedScore>>(
@@ -456,7 +495,55 @@ fn eval_lite_incremental<Tuned: LiteValues<Score = PhasedScore>>(
     let entry = this.stack[ply - 1];
     let (entry, score) = this.incremental(entry, old_pos, mov, new_pos);
     this.stack.resize(ply + 1, entry);
-    score.finalize(entry.phase, 24, new_pos.active_player(), TEMPO)
+    score.finalize(phase(new_pos), 24, new_pos.active_player(), TEMPO)
+}
+
+/// Prints a table of the eval terms, similar to Stockfish's `eval` command.
+/// The terms come from running the eval with [`TracedValues`], so they always add up to the actual eval.
+/// Each color's terms are from that color's perspective, the total is from white's perspective.
+fn explain_lite<Tuned: LiteValues<Score = PhasedScore>>(
+    this: &GenericLiTEval<Tuned>,
+    pos: &Chessboard,
+) -> String {
+    let traced = GenericLiTEval {
+        stack: vec![],
+        tuned: TracedValues(this.tuned),
+    };
+    let trace = traced.do_eval(pos);
+    let cell = |score: PhasedScore| format!("{0:>6} {1:>6}", score.mg().0, score.eg().0);
+    let separator = format!("{0:-<20}+{0:-<15}+{0:-<15}+{0:-<15}\n", "");
+    let mut res = format!(
+        "{0:>19} | {1:^13} | {2:^13} | {3:^13}\n{4:>19} | {5} | {5} | {5}\n{separator}",
+        "Term", "White", "Black", "Total", "", "    MG     EG"
+    );
+    let mut total = PhasedScore::default();
+    for term in EvalTerm::iter() {
+        total += trace.total(term);
+        res += &format!(
+            "{0:>19} | {1} | {2} | {3}\n",
+            term.name(),
+            cell(trace.white(term)),
+            cell(trace.black(term)),
+            cell(trace.total(term))
+        );
+    }
+    debug_assert_eq!(total, this.eval_from_scratch(pos).1);
+    res += &separator;
+    res += &format!(
+        "{0:>19} | {1:>13} | {1:>13} | {2}\n\n",
+        "Total",
+        "",
+        cell(total)
+    );
+    let phase = phase(pos);
+    let tapered = total.taper(phase, 24);
+    let color = pos.active_player();
+    let final_score = total.finalize(phase, 24, color, TEMPO);
+    res += &format!(
+        "Phase: {phase} / 24, tapered: {0} (white's perspective)\nFinal eval: {1} ({color}'s perspective, including tempo)\n",
+        tapered.0, final_score.0
+    );
+    res
 }
 
 impl Eval<Chessboard> for LiTEval {
@@ -479,11 +566,21 @@ impl Eval<Chessboard> for LiTEval {
     fn piece_scale(&self) -> ScoreT {
         5
     }
+
+    fn explain(&self, pos: &Chessboard) -> String {
+        explain_lite(self, pos)
+    }
+
+    fn score_normalization(&self, pos: &Chessboard) -> Option<ScoreT> {
+        let phase = phase(pos) as f64 / 24.0;
+        let scale = WINRATE_SCALE_MG * phase + WINRATE_SCALE_EG * (1.0 - phase);
+        Some(normalized_pawn_value(scale))
+    }
 }
 
 impl Eval<Chessboard> for KingGambot {
     fn eval(&mut self, pos: &Chessboard, ply: usize) -> Score {
-        self.tuned.us = if ply % 2 == 0 {
+        self.tuned.us = if ply.is_multiple_of(2) {
             pos.active_player()
         } else {
             pos.inactive_player()
@@ -504,4 +601,8 @@ impl Eval<Chessboard> for KingGambot {
     fn piece_scale(&self) -> ScoreT {
         5
     }
+
+    fn explain(&self, pos: &Chessboard) -> String {
+        explain_lite(self, pos)
+    }
 }
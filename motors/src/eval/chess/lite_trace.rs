@@ -0,0 +1,333 @@
+/*
+ *  Motors, a collection of board game engines.
+ *  Copyright (C) 2024 ToTheAnd
+ *
+ *  Motors is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  Motors is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with Motors. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A trace that splits the LiTE eval into its terms, used by the `eval` command.
+//!
+//! This works just like the `TraceTrait` implementation `SparseTrace` in `pliers`: The trace implements [`ScoreType`],
+//! so running the unchanged eval function with [`TracedValues`] instead of the normal values records which term each
+//! feature score belongs to. `pliers` itself can't be used here because it depends on this crate.
+
+use crate::eval::chess::lite_values::LiteValues;
+use crate::eval::chess::FileOpenness;
+use crate::eval::ScoreType;
+use gears::games::chess::pieces::ChessPieceType;
+use gears::games::chess::squares::ChessSquare;
+use gears::games::chess::ChessColor;
+use gears::games::Color;
+use gears::general::common::StaticallyNamedEntity;
+use gears::score::{PhaseType, PhasedScore};
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use strum::EnumCount;
+use strum_macros::{EnumCount, EnumIter};
+
+/// The rows of the `eval` table. Each [`LiteValues`] feature belongs to exactly one of them.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, EnumIter, EnumCount)]
+pub enum EvalTerm {
+    #[default]
+    Psqt,
+    Pawns,
+    PassedPawns,
+    Bishops,
+    Rooks,
+    KingSafety,
+    Mobility,
+    Threats,
+}
+
+impl EvalTerm {
+    pub fn name(self) -> &'static str {
+        match self {
+            EvalTerm::Psqt => "Material + PSQT",
+            EvalTerm::Pawns => "Pawns",
+            EvalTerm::PassedPawns => "Passed pawns",
+            EvalTerm::Bishops => "Bishops",
+            EvalTerm::Rooks => "Rooks",
+            EvalTerm::KingSafety => "King safety",
+            EvalTerm::Mobility => "Mobility",
+            EvalTerm::Threats => "Threats",
+        }
+    }
+}
+
+/// The score of a single feature, together with the term it belongs to.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct TermScore {
+    term: EvalTerm,
+    score: PhasedScore,
+}
+
+impl Mul<usize> for TermScore {
+    type Output = Self;
+
+    fn mul(mut self, rhs: usize) -> Self::Output {
+        self.score *= rhs;
+        self
+    }
+}
+
+/// The per-term scores of both players.
+///
+/// Like the normal score, the trace is always from the perspective of the player whose features currently get added,
+/// and the eval negates it to switch to the other player. So `ours` holds the terms of that player and `theirs` holds
+/// the negated terms of the other player; negating the trace swaps them. Once the eval is done, the trace is from
+/// white's perspective.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct EvalTrace {
+    ours: [PhasedScore; EvalTerm::COUNT],
+    theirs: [PhasedScore; EvalTerm::COUNT],
+}
+
+impl EvalTrace {
+    /// The term's score for white, from white's perspective.
+    pub fn white(&self, term: EvalTerm) -> PhasedScore {
+        self.ours[term as usize]
+    }
+
+    /// The term's score for black, from black's perspective.
+    pub fn black(&self, term: EvalTerm) -> PhasedScore {
+        -self.theirs[term as usize]
+    }
+
+    /// The term's score from white's perspective.
+    pub fn total(&self, term: EvalTerm) -> PhasedScore {
+        self.white(term) - self.black(term)
+    }
+}
+
+impl From<TermScore> for EvalTrace {
+    fn from(value: TermScore) -> Self {
+        let mut res = Self::default();
+        res.ours[value.term as usize] = value.score;
+        res
+    }
+}
+
+impl Add for EvalTrace {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for EvalTrace {
+    fn add_assign(&mut self, rhs: Self) {
+        for i in 0..EvalTerm::COUNT {
+            self.ours[i] += rhs.ours[i];
+            self.theirs[i] += rhs.theirs[i];
+        }
+    }
+}
+
+impl Add<TermScore> for EvalTrace {
+    type Output = Self;
+
+    fn add(mut self, rhs: TermScore) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign<TermScore> for EvalTrace {
+    fn add_assign(&mut self, rhs: TermScore) {
+        self.ours[rhs.term as usize] += rhs.score;
+    }
+}
+
+impl Sub for EvalTrace {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl SubAssign for EvalTrace {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self += -rhs;
+    }
+}
+
+impl Sub<TermScore> for EvalTrace {
+    type Output = Self;
+
+    fn sub(mut self, rhs: TermScore) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl SubAssign<TermScore> for EvalTrace {
+    fn sub_assign(&mut self, rhs: TermScore) {
+        self.ours[rhs.term as usize] -= rhs.score;
+    }
+}
+
+impl Neg for EvalTrace {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            ours: self.theirs.map(|score| -score),
+            theirs: self.ours.map(|score| -score),
+        }
+    }
+}
+
+impl Mul<usize> for EvalTrace {
+    type Output = Self;
+
+    fn mul(self, rhs: usize) -> Self::Output {
+        Self {
+            ours: self.ours.map(|score| score * rhs),
+            theirs: self.theirs.map(|score| score * rhs),
+        }
+    }
+}
+
+impl ScoreType for EvalTrace {
+    type Finalized = Self;
+    type SingleFeatureScore = TermScore;
+
+    /// Tapering happens when printing the trace, so this simply returns the trace.
+    fn finalize<C: Color>(
+        self,
+        _phase: PhaseType,
+        _max_phase: PhaseType,
+        _color: C,
+        _tempo: Self::Finalized,
+    ) -> Self::Finalized {
+        self
+    }
+}
+
+/// Wraps the eval values `Tuned` such that each feature also records its [`EvalTerm`].
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TracedValues<Tuned: LiteValues<Score = PhasedScore>>(pub Tuned);
+
+impl<Tuned: LiteValues<Score = PhasedScore>> StaticallyNamedEntity for TracedValues<Tuned> {
+    fn static_short_name() -> impl Display
+    where
+        Self: Sized,
+    {
+        Tuned::static_short_name()
+    }
+
+    fn static_long_name() -> String
+    where
+        Self: Sized,
+    {
+        Tuned::static_long_name()
+    }
+
+    fn static_description() -> String
+    where
+        Self: Sized,
+    {
+        Tuned::static_description()
+    }
+}
+
+fn traced(term: EvalTerm, score: PhasedScore) -> TermScore {
+    TermScore { term, score }
+}
+
+impl<Tuned: LiteValues<Score = PhasedScore>> LiteValues for TracedValues<Tuned> {
+    type Score = EvalTrace;
+
+    fn psqt(&self, square: ChessSquare, piece: ChessPieceType, color: ChessColor) -> TermScore {
+        traced(EvalTerm::Psqt, self.0.psqt(square, piece, color))
+    }
+
+    fn passed_pawn(square: ChessSquare) -> TermScore {
+        traced(EvalTerm::PassedPawns, Tuned::passed_pawn(square))
+    }
+
+    fn unsupported_pawn() -> TermScore {
+        traced(EvalTerm::Pawns, Tuned::unsupported_pawn())
+    }
+
+    fn doubled_pawn() -> TermScore {
+        traced(EvalTerm::Pawns, Tuned::doubled_pawn())
+    }
+
+    fn bishop_pair() -> TermScore {
+        traced(EvalTerm::Bishops, Tuned::bishop_pair())
+    }
+
+    fn bad_bishop(num_pawns: usize) -> TermScore {
+        traced(EvalTerm::Bishops, Tuned::bad_bishop(num_pawns))
+    }
+
+    fn rook_openness(openness: FileOpenness) -> TermScore {
+        traced(EvalTerm::Rooks, Tuned::rook_openness(openness))
+    }
+
+    fn king_openness(openness: FileOpenness) -> TermScore {
+        traced(EvalTerm::KingSafety, Tuned::king_openness(openness))
+    }
+
+    fn bishop_openness(openness: FileOpenness, len: usize) -> TermScore {
+        traced(EvalTerm::Bishops, Tuned::bishop_openness(openness, len))
+    }
+
+    fn pawn_shield(&self, color: ChessColor, config: usize) -> TermScore {
+        traced(EvalTerm::KingSafety, self.0.pawn_shield(color, config))
+    }
+
+    fn shield_pawn_deficit(missing: usize) -> TermScore {
+        traced(EvalTerm::KingSafety, Tuned::shield_pawn_deficit(missing))
+    }
+
+    fn king_adjacent_openness(openness: FileOpenness) -> TermScore {
+        traced(
+            EvalTerm::KingSafety,
+            Tuned::king_adjacent_openness(openness),
+        )
+    }
+
+    fn pawn_protection(piece: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Pawns, Tuned::pawn_protection(piece))
+    }
+
+    fn pawn_attack(piece: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Threats, Tuned::pawn_attack(piece))
+    }
+
+    fn mobility(piece: ChessPieceType, mobility: usize) -> TermScore {
+        traced(EvalTerm::Mobility, Tuned::mobility(piece, mobility))
+    }
+
+    fn threats(attacking: ChessPieceType, targeted: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Threats, Tuned::threats(attacking, targeted))
+    }
+
+    fn defended(protecting: ChessPieceType, target: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Mobility, Tuned::defended(protecting, target))
+    }
+
+    fn king_zone_attack(attacking: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Threats, Tuned::king_zone_attack(attacking))
+    }
+
+    fn can_give_check(piece: ChessPieceType) -> TermScore {
+        traced(EvalTerm::Threats, Tuned::can_give_check(piece))
+    }
+}
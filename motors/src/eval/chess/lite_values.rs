@@ -223,6 +223,9 @@ const PAWN_SHIELDS: [PhasedScore; NUM_PAWN_SHIELD_CONFIGURATIONS] = [
     p(0, 0),     /*0b1110*/
     p(21, -44),  /*0b1111*/
 ];
+const SHIELD_PAWN_DEFICIT: [PhasedScore; 4] = [p(0, 0), p(0, 0), p(0, 0), p(0, 0)];
+const KING_ADJACENT_OPEN_FILE: PhasedScore = p(0, 0);
+const KING_ADJACENT_SEMIOPEN_FILE: PhasedScore = p(0, 0);
 
 #[rustfmt::skip]
 const PASSED_PAWNS: [PhasedScore; NUM_SQUARES] = [
@@ -485,6 +488,12 @@ pub trait LiteValues:
 
     fn pawn_shield(&self, color: ChessColor, config: usize) -> SingleFeatureScore<Self::Score>;
 
+    /// `missing` is the number of files (at most 3) in front of the castled king without a pawn to shelter it.
+    fn shield_pawn_deficit(missing: usize) -> SingleFeatureScore<Self::Score>;
+
+    /// Applied once for each file next to the file of the castled king.
+    fn king_adjacent_openness(openness: FileOpenness) -> SingleFeatureScore<Self::Score>;
+
     fn pawn_protection(piece: ChessPieceType) -> SingleFeatureScore<Self::Score>;
 
     fn pawn_attack(piece: ChessPieceType) -> SingleFeatureScore<Self::Score>;
@@ -591,6 +600,18 @@ impl LiteValues for Lite {
         PAWN_SHIELDS[config]
     }
 
+    fn shield_pawn_deficit(missing: usize) -> PhasedScore {
+        SHIELD_PAWN_DEFICIT[missing]
+    }
+
+    fn king_adjacent_openness(openness: FileOpenness) -> PhasedScore {
+        match openness {
+            FileOpenness::Open => KING_ADJACENT_OPEN_FILE,
+            FileOpenness::SemiOpen => KING_ADJACENT_SEMIOPEN_FILE,
+            FileOpenness::Closed | FileOpenness::SemiClosed => PhasedScore::default(),
+        }
+    }
+
     fn pawn_protection(piece: ChessPieceType) -> PhasedScore {
         PAWN_PROTECTION[piece as usize]
     }
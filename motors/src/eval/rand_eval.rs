@@ -1,25 +1,55 @@
 use gears::general::board::Board;
-use rand::{rng, Rng};
-use std::fmt::Display;
+use rand::rngs::StdRng;
+use rand::{rng, RngCore};
+use std::fmt::{Debug, Display};
 
 use crate::eval::Eval;
-use gears::general::common::StaticallyNamedEntity;
+use gears::general::common::anyhow::bail;
+use gears::general::common::{parse_int_from_str, Res, SeedRng, StaticallyNamedEntity};
 use gears::score::{Score, ScoreT, MAX_NORMAL_SCORE, MIN_NORMAL_SCORE};
+use gears::ugi::EngineOptionName::Other;
+use gears::ugi::EngineOptionType::Spin;
+use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType, UgiSpin};
 
+/// The rng is only used in non-deterministic mode. Using [`XorShiftRng`](gears::general::common::XorShiftRng)
+/// makes the scores for a given seed reproducible.
 #[derive(Debug, Clone)]
-pub struct RandEval {
+pub struct RandEval<R: SeedRng = StdRng> {
     deterministic: bool,
+    /// Set through the `Seed` option. In deterministic mode, this changes the score of each position,
+    /// otherwise it seeds the rng. A seed of `None` keeps the default behavior.
+    seed: Option<u64>,
+    rng: R,
 }
 
-impl Default for RandEval {
+impl<R: SeedRng> Default for RandEval<R> {
     fn default() -> Self {
         Self {
             deterministic: true,
+            seed: None,
+            rng: R::seed_from_u64(rng().next_u64()),
         }
     }
 }
 
-impl StaticallyNamedEntity for RandEval {
+impl<R: SeedRng> RandEval<R> {
+    pub fn new(deterministic: bool, seed: Option<u64>) -> Self {
+        let mut res = Self {
+            deterministic,
+            ..Self::default()
+        };
+        res.set_seed(seed);
+        res
+    }
+
+    /// Only called when explicitly requested, so searching doesn't reseed the rng.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed.unwrap_or_else(|| rng().next_u64()));
+    }
+}
+
+impl<R: SeedRng + Debug> StaticallyNamedEntity for RandEval<R> {
     fn static_short_name() -> impl Display
     where
         Self: Sized,
@@ -42,19 +72,97 @@ impl StaticallyNamedEntity for RandEval {
     }
 }
 
-impl<B: Board> Eval<B> for RandEval {
+impl<B: Board, R: SeedRng + Debug + Clone + Send + 'static> Eval<B> for RandEval<R> {
     fn eval(&mut self, pos: &B, _ply: usize) -> Score {
         if self.deterministic {
             // deterministic and faster than seeding a rng while still being good enough
-            let random = (pos.zobrist_hash().0
-                % (MAX_NORMAL_SCORE.0 as i64 - MIN_NORMAL_SCORE.0 as i64 + 1) as u64)
-                as i64;
+            let hash = match self.seed {
+                // multiply to avoid simply flipping the same bits of every hash
+                Some(seed) => pos.zobrist_hash().0 ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+                None => pos.zobrist_hash().0,
+            };
+            let random =
+                (hash % (MAX_NORMAL_SCORE.0 as i64 - MIN_NORMAL_SCORE.0 as i64 + 1) as u64) as i64;
             Score((random + MIN_NORMAL_SCORE.0 as i64) as ScoreT)
             // too slow (there's probably a way to do this faster while using the rng crate, but the above is good enough)
             // StdRng::seed_from_u64(pos.zobrist_hash().0)
             //     .random_range(MIN_NORMAL_SCORE.0..=MAX_NORMAL_SCORE.0),
         } else {
-            Score(rng().random_range(MIN_NORMAL_SCORE.0..=MAX_NORMAL_SCORE.0))
+            Score(
+                self.rng
+                    .random_range(MIN_NORMAL_SCORE.0..=MAX_NORMAL_SCORE.0),
+            )
+        }
+    }
+
+    fn options(&self) -> Vec<EngineOption> {
+        vec![EngineOption {
+            name: Other("Seed".to_string()),
+            value: Spin(UgiSpin {
+                val: self.seed.unwrap_or(0).min(i64::MAX as u64) as i64,
+                default: Some(0),
+                min: Some(0),
+                max: Some(i64::MAX),
+            }),
+        }]
+    }
+
+    /// A seed of 0 restores the default behavior.
+    fn set_option(
+        &mut self,
+        option: &EngineOptionName,
+        old_value: &mut EngineOptionType,
+        value: &str,
+    ) -> Res<()> {
+        let Spin(spin) = old_value else {
+            bail!("The random eval doesn't support setting the option '{option}' to '{value}'")
+        };
+        let seed: u64 = parse_int_from_str(value.trim(), "seed")?;
+        spin.val = seed.min(i64::MAX as u64) as i64;
+        self.set_seed((seed != 0).then_some(seed));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gears::games::chess::Chessboard;
+    use gears::general::common::XorShiftRng;
+
+    #[test]
+    fn seed_test() {
+        let positions = Chessboard::bench_positions();
+        let evals = |eval: &mut RandEval| {
+            positions
+                .iter()
+                .map(|pos| eval.eval(pos, 0))
+                .collect::<Vec<_>>()
+        };
+        let unseeded = evals(&mut RandEval::default());
+        assert_eq!(unseeded, evals(&mut RandEval::default()));
+        for deterministic in [true, false] {
+            let seeded = evals(&mut RandEval::new(deterministic, Some(42)));
+            assert_eq!(seeded, evals(&mut RandEval::new(deterministic, Some(42))));
+            assert_ne!(seeded, evals(&mut RandEval::new(deterministic, Some(43))));
+            assert_ne!(seeded, unseeded);
         }
+        let mut eval = RandEval::default();
+        let option = Eval::<Chessboard>::options(&eval).pop().unwrap();
+        let mut value = option.value;
+        Eval::<Chessboard>::set_option(&mut eval, &option.name, &mut value, "42").unwrap();
+        assert_eq!(value.value_to_str(), "42");
+        assert_eq!(evals(&mut eval), evals(&mut RandEval::new(true, Some(42))));
+        Eval::<Chessboard>::set_option(&mut eval, &option.name, &mut value, "0").unwrap();
+        assert_eq!(evals(&mut eval), unseeded);
+    }
+
+    #[test]
+    fn xorshift_test() {
+        // with the xorshift rng, the random scores don't depend on the version of `rand`
+        let pos = Chessboard::default();
+        let mut eval = RandEval::<XorShiftRng>::new(false, Some(42));
+        let scores = (0..3).map(|_| eval.eval(&pos, 0)).collect::<Vec<_>>();
+        assert_eq!(scores, [Score(-9655), Score(16935), Score(17408)]);
     }
 }
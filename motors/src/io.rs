@@ -33,15 +33,15 @@ use crate::io::ugi_output::{color_for_score, pretty_score, score_gradient, suffi
 use crate::io::Protocol::{Interactive, UGI};
 use crate::io::SearchType::*;
 use crate::search::multithreading::EngineWrapper;
-use crate::search::tt::{TTEntry, DEFAULT_HASH_SIZE_MB, TT};
-use crate::search::{run_bench_with, EvalList, SearchParams, SearcherList};
+use crate::search::tt::{TTEntry, DEFAULT_HASH_SIZE_MB, MAX_HASH_SIZE_MB, TT};
+use crate::search::{forced_draw_result, run_bench_with, EvalList, SearchParams, SearcherList};
 use crate::{
     create_engine_box_from_str, create_engine_from_str, create_eval_from_str, create_match,
 };
 use colored::Color::Red;
 use colored::Colorize;
 use gears::cli::select_game;
-use gears::games::{ColoredPiece, OutputList, ZobristHistory};
+use gears::games::{ColoredPiece, OutputList, RepetitionTable, ZobristHistory};
 use gears::general::board::Strictness::{Relaxed, Strict};
 use gears::general::board::{Board, Strictness, UnverifiedBoard};
 use gears::general::common::anyhow::{anyhow, bail};
@@ -53,13 +53,14 @@ use gears::general::common::{
 use gears::general::common::{Res, Tokens};
 use gears::general::moves::ExtendedFormat::{Alternative, Standard};
 use gears::general::moves::Move;
-use gears::general::perft::{perft_for, split_perft};
+use gears::general::perft::{perft, perft_for, perft_parallel, split_perft};
 use gears::output::logger::LoggerBuilder;
 use gears::output::pgn::parse_pgn;
 use gears::output::text_output::{display_color, AdaptFormatter};
 use gears::output::Message::*;
 use gears::output::{Message, OutputBox, OutputBuilder, OutputOpts};
-use gears::search::{Depth, SearchLimit, TimeControl};
+use gears::score::Score;
+use gears::search::{Depth, SearchInfo, SearchLimit, TimeControl};
 use gears::ugi::EngineOptionName::*;
 use gears::ugi::EngineOptionType::*;
 use gears::ugi::{EngineOption, EngineOptionName, UgiCheck, UgiCombo, UgiSpin, UgiString};
@@ -72,6 +73,7 @@ use gears::{
 };
 use itertools::Itertools;
 use std::cell::RefCell;
+use std::cmp::Reverse;
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::fs;
 use std::ops::{Deref, DerefMut};
@@ -84,6 +86,8 @@ use strum::IntoEnumIterator;
 
 const DEFAULT_MOVE_OVERHEAD_MS: u64 = 50;
 
+const DEFAULT_ANALYSIS_DEPTH: Depth = Depth::new_unchecked(8);
+
 // TODO: Ensure this conforms to <https://expositor.dev/uci/doc/uci-draft-1.pdf>
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -129,7 +133,6 @@ struct EngineGameState<B: Board> {
     game_name: String,
     protocol: Protocol,
     debug_mode: bool,
-    ponder_limit: Option<SearchLimit>,
     engine: EngineWrapper<B>,
     /// This doesn't have to be the UGI engine name. It often isn't, especially when two engines with
     /// the same name play against each other, such as in a SPRT. It should be unique, however
@@ -172,6 +175,10 @@ pub struct EngineUGI<B: Board> {
     strictness: Strictness,
     multi_pv: usize,
     allow_ponder: bool,
+    detect_draws: bool,
+    sync_search: bool,
+    id_name: Option<String>,
+    id_author: Option<String>,
 }
 
 impl<B: Board> AbstractRun for EngineUGI<B> {
@@ -288,6 +295,7 @@ impl<B: Board> EngineUGI<B> {
             status: Run(NotStarted),
             mov_hist: vec![],
             board_hist: ZobristHistory::default(),
+            repetitions: RepetitionTable::default(),
             pos_before_moves: B::default(),
             last_played_color: B::Color::default(),
         };
@@ -297,7 +305,6 @@ impl<B: Board> EngineUGI<B> {
             game_name: B::game_name(),
             protocol,
             debug_mode: opts.debug,
-            ponder_limit: None,
             engine,
             display_name,
             opponent_name: None,
@@ -326,6 +333,10 @@ impl<B: Board> EngineUGI<B> {
             strictness: Relaxed,
             multi_pv: 1,
             allow_ponder: false,
+            detect_draws: false,
+            sync_search: false,
+            id_name: opts.id_name,
+            id_author: opts.id_author,
         })
     }
 
@@ -337,7 +348,7 @@ impl<B: Board> EngineUGI<B> {
         self.state.protocol == Interactive
     }
 
-    fn output(&self) -> MutexGuard<UgiOutput<B>> {
+    fn output(&self) -> MutexGuard<'_, UgiOutput<B>> {
         self.output.lock().unwrap()
     }
 
@@ -363,13 +374,21 @@ impl<B: Board> EngineUGI<B> {
         if self.state.protocol == Interactive && !interactive {
             self.state.protocol = UGI; // Will be overwritten shortly, and isn't really used much anyway
         }
+        self.input_loop(&mut input)
+    }
+
+    /// Reads and handles commands until the program should quit.
+    /// If the input can't be read anymore, which usually means that stdin has been closed because the parent
+    /// process died, this is treated like a `quit` command.
+    fn input_loop(&mut self, input: &mut Input<B>) -> Quitting {
         loop {
             input.set_interactive(self.state.protocol == Interactive, self);
             let input = match input.get_line(self) {
                 Ok(input) => input,
                 Err(err) => {
                     self.write_message(Error, &err.to_string());
-                    break;
+                    self.quit_on_closed_input();
+                    return QuitProgram;
                 }
             };
 
@@ -401,7 +420,19 @@ impl<B: Board> EngineUGI<B> {
                 }
             }
         }
-        QuitProgram
+    }
+
+    /// Like `quit`, but also waits (for a limited time) until a running search has printed its `bestmove`,
+    /// because the program will exit after this.
+    fn quit_on_closed_input(&mut self) {
+        if let Err(err) = self.handle_quit(QuitProgram) {
+            self.write_message(Error, &format!("Error while quitting: {err}"));
+        }
+        let atomic = self.state.engine.main_atomic_search_data();
+        let start = Instant::now();
+        while atomic.currently_searching() && start.elapsed() < Duration::from_secs(1) {
+            sleep(Duration::from_millis(1));
+        }
     }
 
     fn write_ugi(&mut self, message: &str) {
@@ -585,11 +616,15 @@ impl<B: Board> EngineUGI<B> {
 
     fn id(&self) -> String {
         let info = self.state.engine.get_engine_info();
-        format!(
-            "id name Motors -- Game {0} -- Engine {1}\nid author ToTheAnd",
-            B::game_name(),
-            info.long_name(),
-        )
+        let name = self.id_name.clone().unwrap_or_else(|| {
+            format!(
+                "Motors -- Game {0} -- Engine {1}",
+                B::game_name(),
+                info.long_name()
+            )
+        });
+        let author = self.id_author.as_deref().unwrap_or("ToTheAnd");
+        format!("id name {name}\nid author {author}")
     }
 
     fn handle_engine_print(&mut self) -> Res<()> {
@@ -685,8 +720,22 @@ impl<B: Board> EngineUGI<B> {
             SetEval => {
                 self.handle_set_eval(&mut tokens(&value))?;
             }
+            DetectDraws => {
+                self.detect_draws = parse_bool_from_str(&value, "detect draws")?;
+            }
+            SyncSearch => {
+                self.sync_search = parse_bool_from_str(&value, "synchronous search")?;
+            }
+            MaxPvLength => {
+                let max_len: usize = parse_int_from_str(&value, "max pv length")?;
+                self.output().max_pv_len = (max_len > 0).then_some(max_len);
+            }
+            NormalizeScore => {
+                self.output().normalize_score = parse_bool_from_str(&value, "normalize score")?;
+            }
 
-            Hash | Threads | UciElo | UCIEngineAbout | Other(_) => {
+            Hash | Threads | UciElo | UCIEngineAbout | UCIChess960 | Contempt
+            | AspirationWindow | AspirationWidening | Other(_) => {
                 let value = value.trim().to_string();
                 self.state
                     .engine
@@ -769,13 +818,8 @@ impl<B: Board> EngineUGI<B> {
                 return self.bench(opts.limit, &bench_positions);
             }
             Perft => {
-                let positions = if opts.complete {
-                    B::bench_positions()
-                } else {
-                    vec![self.state.board]
-                };
-                self.output()
-                    .write_ugi(&perft_for(opts.limit.depth, &positions).to_string())
+                let report = self.perft_report(opts.limit.depth, opts.complete, opts.threads);
+                self.write_ugi(&report);
             }
             SplitPerft => {
                 if opts.limit.depth.get() == 0 {
@@ -788,6 +832,91 @@ impl<B: Board> EngineUGI<B> {
         Ok(())
     }
 
+    /// Runs perft on the current position and lists the number of leaf nodes after each legal move as well as the
+    /// total, like `splitperft`. If `complete` is set, this instead sums up the perft results of all bench positions.
+    /// Otherwise, if `threads` is set, this only prints the total, which gets computed with that many threads.
+    fn perft_report(&self, depth: Depth, complete: bool, threads: Option<usize>) -> String {
+        if complete {
+            perft_for(depth, &B::bench_positions()).to_string()
+        } else if threads.is_some() {
+            perft_parallel(depth, self.state.board, threads).to_string()
+        } else if depth.get() == 0 {
+            perft(depth, self.state.board).to_string()
+        } else {
+            split_perft(depth, self.state.board).to_string()
+        }
+    }
+
+    /// Searches the current position with a fresh instance of the current engine, using as many PVs as there are
+    /// legal moves, and lists every legal move with the score it got in the last completed iteration, best first.
+    /// Moves that didn't get a score (e.g. because the engine doesn't support multipv) are listed last.
+    fn analysis_report(&self, depth: Depth) -> Res<String> {
+        let pos = self.state.board;
+        let legal_moves = pos.legal_moves_slow().into_iter().collect_vec();
+        if legal_moves.is_empty() {
+            bail!(
+                "There are no legal moves in the current position ('{}')",
+                pos.as_fen()
+            );
+        }
+        let engine = self.state.engine.get_engine_info().short_name();
+        let mut engine =
+            create_engine_box_from_str(&engine, &self.searcher_factories, &self.eval_factories)?;
+        // (first move of the pv, score, depth)
+        let scores = Arc::new(Mutex::new(Vec::<(B::Move, Score, Depth)>::new()));
+        let callback_scores = scores.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<B>| {
+                let Some(&mov) = info.pv.first() else {
+                    return;
+                };
+                let mut scores = callback_scores.lock().unwrap();
+                scores.retain(|(m, _, _)| *m != mov);
+                scores.push((mov, info.score, info.depth));
+            }));
+        let params = SearchParams::new_unshared(
+            pos,
+            SearchLimit::depth(depth),
+            self.state.board_hist.clone(),
+            TT::default(),
+        )
+        .additional_pvs(legal_moves.len() - 1);
+        _ = engine.search(params);
+        let mut scores = scores.lock().unwrap().clone();
+        scores.sort_by_key(|(_, score, _)| Reverse(*score));
+        let mut res = vec![];
+        for (i, (mov, score, depth)) in scores.iter().enumerate() {
+            let mov = mov.to_extended_text(&pos, Alternative);
+            res.push(format!(
+                "{0:>3}. {mov:<8} {score:>10}  (depth {1})",
+                i + 1,
+                depth.get()
+            ));
+        }
+        for mov in legal_moves {
+            if !scores.iter().any(|(m, _, _)| *m == mov) {
+                let mov = mov.to_extended_text(&pos, Alternative);
+                res.push(format!(
+                    "{0:>3}. {mov:<8} {1:>10}",
+                    res.len() + 1,
+                    "no score"
+                ));
+            }
+        }
+        Ok(res.join("\n"))
+    }
+
+    fn handle_analysis(&mut self, words: &mut Tokens) -> Res<()> {
+        let depth = match words.next() {
+            Some(word) => Depth::try_new(parse_int_from_str(word, "depth")?)?,
+            None => DEFAULT_ANALYSIS_DEPTH,
+        };
+        let report = self.analysis_report(depth)?;
+        self.write_ugi(&report);
+        Ok(())
+    }
+
     fn start_search(&mut self, opts: GoState<B>) -> Res<()> {
         self.write_message(
             Debug,
@@ -805,12 +934,22 @@ impl<B: Board> EngineUGI<B> {
             // this keeps the current history even if we're searching a different position, but that's probably not a problem
             // and doing a normal search from a custom position isn't even implemented at the moment -- TODO: implement?
             Normal => {
-                // It doesn't matter if we got a ponderhit or a miss, we simply abort the ponder search and start a new search.
-                if self.state.ponder_limit.is_some() {
-                    self.state.ponder_limit = None;
+                // A `ponderhit` doesn't start a new search, so this can only be a ponder miss. Abort the ponder search.
+                if self.state.engine.is_pondering() {
                     // TODO: Maybe do this all the time to make sure two `go` commands after another work -- write testcase for that
                     self.state.engine.send_stop(true); // aborts the pondering without printing a search result
                 }
+                let infinite = opts.limit.is_infinite();
+                // `bestmove` must not be sent before `stop` in an infinite search, so this only works for finite searches.
+                // Ponder searches are handled below and always search.
+                if self.detect_draws && !infinite {
+                    if let Some(res) = forced_draw_result(opts.board, &opts.board_hist) {
+                        self.write_ugi("info depth 0 score cp 0");
+                        self.write_ugi("info string drawn");
+                        self.output().write_search_res(res);
+                        return Ok(());
+                    }
+                }
                 self.state.engine.start_search(
                     opts.board,
                     opts.limit,
@@ -820,12 +959,16 @@ impl<B: Board> EngineUGI<B> {
                     false,
                     opts.threads,
                 )?;
+                // An infinite search can only be ended by `stop`, so it would never return
+                if self.sync_search && !infinite {
+                    self.state.engine.wait_for_search();
+                }
             }
             SearchType::Ponder => {
-                self.state.ponder_limit = Some(opts.limit);
+                // limits are ignored until `ponderhit`, which turns this into a normal search
                 self.state.engine.start_search(
                     opts.board,
-                    SearchLimit::infinite(), //always allocate infinite time for pondering
+                    opts.limit,
                     opts.board_hist,
                     opts.search_moves,
                     opts.multi_pv, // don't ignore multi_pv in pondering mode
@@ -868,10 +1011,11 @@ impl<B: Board> EngineUGI<B> {
                 let mut eval =
                     create_eval_from_str(&eval_name.short_name(), &self.eval_factories)?.build();
                 let eval_score = eval.eval(&state.board, 0);
+                let breakdown = eval.explain(&state.board);
                 let diagram = show_eval_pos(state.board, state.last_move(), eval);
                 diagram
                     + &format!(
-                        "Eval Score: {}\n",
+                        "{breakdown}\nEval Score: {}\n",
                         pretty_score(eval_score, None, None, &score_gradient(), true, false)
                     )
             } else {
@@ -1117,7 +1261,7 @@ impl<B: Board> EngineUGI<B> {
             self.output.clone(),
             TT::new_with_bytes(self.state.engine.next_tt().size_in_bytes()),
         )?;
-        let hash = self.state.engine.next_tt().size_in_mb();
+        let hash = self.state.engine.hash_size_mb();
         let threads = self.state.engine.num_threads();
         self.state.engine.send_quit()?;
         // This resets some engine options, but that's probably for the better since the new engine might not support those.
@@ -1171,7 +1315,7 @@ impl<B: Board> EngineUGI<B> {
 
     fn load_pgn(&mut self, words: &mut Tokens) -> Res<()> {
         let file_text = fs::read_to_string(words.join(" "))?;
-        let pgn_data = parse_pgn::<B>(&file_text)?;
+        let pgn_data = parse_pgn::<B>(&file_text, Relaxed)?;
         self.state.position_state = pgn_data.game;
         self.print_board(OutputOpts::default());
         Ok(())
@@ -1198,9 +1342,10 @@ impl<B: Board> EngineUGI<B> {
         Ok(match words.join(" ").to_ascii_lowercase().as_str() {
             "" => {
                 let mut res = format!(
-                    "{0} playing {1}\n",
+                    "{0} playing {1} (option hash {2:016x})\n",
                     self.state.engine.get_engine_info().short_name(),
-                    self.state.game_name()
+                    self.state.game_name(),
+                    self.options_hash()
                 );
                 for o in options {
                     Self::write_single_option(&o, &mut res);
@@ -1229,6 +1374,22 @@ impl<B: Board> EngineUGI<B> {
         })
     }
 
+    /// A hash of the current value of every option, which can be used to tag cached results with the configuration
+    /// that produced them. Unlike [`DefaultHasher`](std::hash::DefaultHasher), this is guaranteed to be the same
+    /// across runs, platforms and Rust versions, and it doesn't depend on the order of the options.
+    pub fn options_hash(&self) -> u64 {
+        let options = self
+            .get_options()
+            .iter()
+            .map(|o| format!("{}={}", o.name, o.value.value_to_str()))
+            .sorted()
+            .join("\n");
+        // 64 bit FNV-1a
+        options.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
     fn write_ugi_options(&self) -> String {
         self.get_options()
             .iter()
@@ -1259,10 +1420,10 @@ impl<B: Board> EngineUGI<B> {
                 Hash => EngineOption {
                     name: Hash,
                     value: Spin(UgiSpin {
-                        val: self.state.engine.next_tt().size_in_mb() as i64,
+                        val: self.state.engine.hash_size_mb() as i64,
                         default: Some(DEFAULT_HASH_SIZE_MB as i64),
                         min: Some(0),
-                        max: Some(10_000_000), // use at most 10 terabytes (should be enough for anybody™)
+                        max: Some(MAX_HASH_SIZE_MB as i64),
                     }),
                 },
                 Threads => EngineOption {
@@ -1284,7 +1445,7 @@ impl<B: Board> EngineUGI<B> {
                 MultiPv => EngineOption {
                     name: MultiPv,
                     value: Spin(UgiSpin {
-                        val: 1,
+                        val: self.multi_pv as i64,
                         default: Some(1),
                         min: Some(1),
                         max: Some(256),
@@ -1361,7 +1522,40 @@ impl<B: Board> EngineUGI<B> {
                             .collect_vec(),
                     }),
                 },
-                Other(_) => continue,
+                DetectDraws => EngineOption {
+                    name: DetectDraws,
+                    value: Check(UgiCheck {
+                        val: self.detect_draws,
+                        default: Some(false),
+                    }),
+                },
+                SyncSearch => EngineOption {
+                    name: SyncSearch,
+                    value: Check(UgiCheck {
+                        val: self.sync_search,
+                        default: Some(false),
+                    }),
+                },
+                MaxPvLength => EngineOption {
+                    name: MaxPvLength,
+                    value: Spin(UgiSpin {
+                        val: self.output().max_pv_len.unwrap_or(0) as i64,
+                        default: Some(0),
+                        min: Some(0),
+                        max: Some(1000),
+                    }),
+                },
+                NormalizeScore => EngineOption {
+                    name: NormalizeScore,
+                    value: Check(UgiCheck {
+                        val: self.output().normalize_score,
+                        default: Some(false),
+                    }),
+                },
+                // only listed by engines that support it
+                UCIChess960 | Contempt | AspirationWindow | AspirationWidening | Other(_) => {
+                    continue
+                }
             });
         }
         res.extend(self.state.engine.get_engine_info().additional_options());
@@ -1466,3 +1660,583 @@ fn show_eval_pos<B: Board>(pos: B, last: Option<B::Move>, eval: Box<dyn Eval<B>>
     };
     pos.display_pretty(&mut formatter)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chess")]
+    fn test_opts() -> EngineOpts {
+        let mut opts = EngineOpts::for_game(gears::cli::Game::Chess, false);
+        opts.interactive = false;
+        opts
+    }
+
+    #[cfg(feature = "chess")]
+    fn test_ugi_with(
+        opts: EngineOpts,
+        evals: EvalList<gears::games::chess::Chessboard>,
+    ) -> EngineUGI<gears::games::chess::Chessboard> {
+        use crate::{list_chess_outputs, list_chess_searchers};
+        EngineUGI::create(
+            opts,
+            vec![],
+            list_chess_outputs(),
+            list_chess_searchers(),
+            evals,
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "chess")]
+    fn test_ugi() -> EngineUGI<gears::games::chess::Chessboard> {
+        test_ugi_with(test_opts(), crate::list_chess_evals())
+    }
+
+    /// Logs the UGI communication to a new file so that tests can inspect what the engine has sent.
+    #[cfg(feature = "chess")]
+    fn log_to_file<B: Board>(ugi: &mut EngineUGI<B>, name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("motors_{name}_{}.log", std::process::id()));
+        ugi.handle_input(&format!("log {}", path.display()))
+            .unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn sync_search_test() {
+        use gears::games::chess::Chessboard;
+
+        let mut ugi = test_ugi();
+        ugi.handle_input("setoption name SyncSearch value true")
+            .unwrap();
+        ugi.handle_input("position startpos").unwrap();
+        ugi.handle_input("go depth 5").unwrap();
+        // the search has already finished and sent its best move before the next command gets handled
+        let atomic = ugi.state.engine.main_atomic_search_data();
+        assert!(!atomic.currently_searching());
+        let pos = Chessboard::default();
+        assert!(pos.is_move_legal(atomic.best_move()));
+        ugi.handle_input("go depth 3").unwrap();
+        assert!(!ugi
+            .state
+            .engine
+            .main_atomic_search_data()
+            .currently_searching());
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn detect_draws_test() {
+        let mut ugi = test_ugi();
+        ugi.handle_input("setoption name DetectDraws value true")
+            .unwrap();
+        ugi.handle_input("setoption name SyncSearch value true")
+            .unwrap();
+        ugi.handle_input("position fen 8/8/3k4/8/8/4K3/8/8 w - - 0 1")
+            .unwrap();
+        // the drawn position doesn't get searched
+        ugi.handle_input("go depth 50").unwrap();
+        let atomic = ugi.state.engine.main_atomic_search_data();
+        assert!(!atomic.currently_searching());
+        assert_eq!(atomic.nodes(), 0);
+        // but infinite and ponder searches can't send a `bestmove` before `stop`
+        for go in ["go infinite", "go ponder"] {
+            ugi.handle_input(go).unwrap();
+            assert!(ugi
+                .state
+                .engine
+                .main_atomic_search_data()
+                .currently_searching());
+            ugi.handle_input("stop").unwrap();
+            ugi.state.engine.wait_for_search();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn perft_current_position_test() {
+        use gears::games::chess::Chessboard;
+        use gears::general::board::board_from_name;
+
+        let mut ugi = test_ugi();
+        let kiwipete = board_from_name::<Chessboard>("kiwipete").unwrap();
+        ugi.handle_input(&format!("position fen {}", kiwipete.as_fen()))
+            .unwrap();
+        ugi.handle_input("perft 3").unwrap();
+        let report = ugi.perft_report(Depth::new_unchecked(3), false, None);
+        let mut lines = report.lines();
+        assert!(lines.next().unwrap().contains("nodes 97862 "), "{report}");
+        // one line per legal move
+        assert_eq!(lines.count(), 48);
+        let report = ugi.perft_report(Depth::new_unchecked(0), false, None);
+        assert!(report.contains("nodes 1 "), "{report}");
+        ugi.handle_input("go perft depth 3 threads 4").unwrap();
+        let report = ugi.perft_report(Depth::new_unchecked(3), false, Some(4));
+        assert_eq!(report.lines().count(), 1);
+        assert!(report.contains("nodes 97862 "), "{report}");
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn stop_before_search_starts_test() {
+        use gears::games::chess::Chessboard;
+        use std::thread::sleep;
+
+        let mut ugi = test_ugi();
+        let pos = Chessboard::default();
+        for go in ["go infinite", "go depth 30", "go movetime 100000"] {
+            for _ in 0..20 {
+                // `ucinewgame` gets handled by the search thread, so the `stop` can arrive before that thread
+                // has even started the search
+                ugi.handle_input("ucinewgame").unwrap();
+                ugi.handle_input("position startpos").unwrap();
+                ugi.handle_input(go).unwrap();
+                ugi.handle_input("stop").unwrap();
+                let atomic = ugi.state.engine.main_atomic_search_data();
+                let start = Instant::now();
+                while atomic.currently_searching() {
+                    assert!(start.elapsed().as_secs() < 5, "'{go}' didn't stop");
+                    sleep(Duration::from_millis(1));
+                }
+                assert!(pos.is_move_legal(atomic.best_move()), "{go}");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn searchmoves_test() {
+        let mut ugi = test_ugi();
+        ugi.handle_input("setoption name SyncSearch value true")
+            .unwrap();
+        let fen = "4k3/8/8/8/8/8/4r3/4K3 w - - 0 1";
+        ugi.handle_input(&format!("position fen {fen}")).unwrap();
+        // e1d2 is pseudolegal, but not legal
+        let err = ugi
+            .handle_input("go searchmoves e1f1 e1d2 depth 2")
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't legal"), "{err}");
+        assert!(ugi.handle_input("go searchmoves e1f1 xyz depth 2").is_err());
+        assert!(ugi.handle_input("go searchmoves depth 2").is_err());
+        // the best move would be e1e2, which captures the rook
+        ugi.handle_input("go searchmoves e1f1 e1d1 multipv 5 depth 3")
+            .unwrap();
+        let best_move = ugi.state.engine.main_atomic_search_data().best_move();
+        assert!(["e1f1", "e1d1"].contains(&best_move.to_string().as_str()));
+        ugi.handle_input("go searchmoves e1f1 e1e2 depth 3")
+            .unwrap();
+        let best_move = ugi.state.engine.main_atomic_search_data().best_move();
+        assert_eq!(best_move.to_string(), "e1e2");
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn ponderhit_test() {
+        use gears::games::chess::Chessboard;
+        use std::thread::sleep;
+
+        let mut ugi = test_ugi();
+        let log_file = log_to_file(&mut ugi, "ponderhit_test");
+        let bestmoves = || {
+            let log = fs::read_to_string(&log_file).unwrap();
+            log.lines().filter(|line| line.contains("bestmove")).count()
+        };
+        assert!(ugi.handle_input("ponderhit").is_err());
+        ugi.handle_input("position startpos moves e2e4").unwrap();
+        ugi.handle_input("go ponder depth 3").unwrap();
+        let atomic = ugi.state.engine.main_atomic_search_data();
+        // The depth limit still applies while pondering, so the search finishes depth 3 long before the ponderhit.
+        // But it waits for the ponderhit before sending its best move.
+        sleep(Duration::from_millis(100));
+        assert!(atomic.currently_searching());
+        assert!(ugi.state.engine.is_pondering());
+        assert_eq!(bestmoves(), 0);
+        ugi.handle_input("ponderhit").unwrap();
+        assert!(!ugi.state.engine.is_pondering());
+        ugi.state.engine.wait_for_search();
+        assert_eq!(bestmoves(), 1);
+        // the search continued instead of starting over, so there is only one iteration for each depth
+        let log = fs::read_to_string(&log_file).unwrap();
+        assert_eq!(
+            log.lines()
+                .filter(|line| line.contains("info depth 1 "))
+                .count(),
+            1,
+            "{log}"
+        );
+        assert!(atomic.ponderhit_time().is_some());
+        let pos = Chessboard::default().make_move_str("e2e4", Strict).unwrap();
+        assert!(pos.is_move_legal(atomic.best_move()));
+        assert!(ugi.handle_input("ponderhit").is_err());
+
+        // a ponder miss: the GUI sends a new `go` command while the engine is still pondering
+        ugi.handle_input("go ponder movetime 100000").unwrap();
+        ugi.handle_input("position startpos moves d2d4").unwrap();
+        ugi.handle_input("go depth 2").unwrap();
+        ugi.state.engine.wait_for_search();
+        assert!(!ugi.state.engine.is_pondering());
+        fs::remove_file(log_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn max_pv_length_test() {
+        use gears::games::chess::Chessboard;
+
+        // The PV lengths of all `info` lines and the final `bestmove` line
+        let search = |max_pv_len: usize| {
+            let mut ugi = test_ugi();
+            let log_file = log_to_file(&mut ugi, &format!("max_pv_length_test_{max_pv_len}"));
+            ugi.handle_input(&format!("setoption name MaxPVLength value {max_pv_len}"))
+                .unwrap();
+            let pos = Chessboard::from_name("kiwipete").unwrap();
+            ugi.handle_input(&format!("position fen {}", pos.as_fen()))
+                .unwrap();
+            ugi.handle_input("go depth 8").unwrap();
+            ugi.state.engine.wait_for_search();
+            let log = fs::read_to_string(&log_file).unwrap();
+            fs::remove_file(log_file).unwrap();
+            let pv_lens = log
+                .lines()
+                .filter_map(|line| line.split_once(" pv "))
+                .map(|(_, pv)| pv.split_whitespace().count())
+                .collect_vec();
+            let bestmove = log.lines().find(|line| line.contains("bestmove")).unwrap();
+            (pv_lens, bestmove.to_string())
+        };
+        // 0 means that the PV isn't capped
+        let (uncapped, _) = search(0);
+        assert!(uncapped.iter().any(|len| *len > 3), "{uncapped:?}");
+        let (capped, _) = search(3);
+        assert!(!capped.is_empty());
+        assert!(capped.iter().all(|len| *len <= 3), "{capped:?}");
+        // the search still uses the full PV, so the ponder move gets sent even if only the first move gets printed
+        let (capped, bestmove) = search(1);
+        assert!(capped.iter().all(|len| *len == 1), "{capped:?}");
+        assert!(bestmove.contains(" ponder "), "{bestmove}");
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn fail_hard_test() {
+        // The (score, alpha, beta) of each root search, taken from the debug messages of the aspiration windows,
+        // which always get logged
+        let root_searches = |fail_hard: bool| {
+            let mut ugi = test_ugi();
+            let log_file = log_to_file(&mut ugi, &format!("fail_hard_test_{fail_hard}"));
+            ugi.handle_input(&format!("setoption name FailHard value {fail_hard}"))
+                .unwrap();
+            // a tiny aspiration window makes the root fail high and low
+            ugi.handle_input("setoption name AspirationWindow value 1")
+                .unwrap();
+            ugi.handle_input("position startpos moves e2e4 d7d5")
+                .unwrap();
+            ugi.handle_input("go depth 8").unwrap();
+            ugi.state.engine.wait_for_search();
+            let log = fs::read_to_string(&log_file).unwrap();
+            fs::remove_file(log_file).unwrap();
+            log.lines()
+                .filter_map(|line| {
+                    let (_, rest) = line.split_once(", score ")?;
+                    let (score, rest) = rest.split_once(", radius ")?;
+                    let (_, rest) = rest.split_once("interval (")?;
+                    let (alpha, rest) = rest.split_once(", ")?;
+                    let (beta, _) = rest.split_once(')')?;
+                    let parse = |s: &str| s.parse::<i32>().unwrap();
+                    Some((parse(score), parse(alpha), parse(beta)))
+                })
+                .collect_vec()
+        };
+        let soft = root_searches(false);
+        assert!(
+            soft.iter()
+                .any(|(score, alpha, beta)| score < alpha || score > beta),
+            "{soft:?}"
+        );
+        let hard = root_searches(true);
+        assert!(!hard.is_empty());
+        assert!(
+            hard.iter()
+                .all(|(score, alpha, beta)| (alpha..=beta).contains(&score)),
+            "{hard:?}"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn isready_during_search_test() {
+        use gears::games::chess::Chessboard;
+        use std::thread::sleep;
+
+        let mut ugi = test_ugi();
+        ugi.handle_input("position startpos").unwrap();
+        ugi.handle_input("go infinite").unwrap();
+        let atomic = ugi.state.engine.main_atomic_search_data();
+        for i in 0..5 {
+            // the search runs in its own thread, so `isready` is answered without waiting for the search
+            let start = Instant::now();
+            ugi.handle_input("isready").unwrap();
+            assert!(start.elapsed() < Duration::from_millis(50), "{i}");
+            // answering `isready` doesn't end the search, so no `bestmove` has been sent
+            assert!(atomic.currently_searching());
+            sleep(Duration::from_millis(i * 10));
+        }
+        ugi.handle_input("stop").unwrap();
+        ugi.state.engine.wait_for_search();
+        assert!(Chessboard::default().is_move_legal(atomic.best_move()));
+        ugi.handle_input("isready").unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn closed_input_test() {
+        use crate::io::input::Input;
+        use gears::games::chess::Chessboard;
+        use std::io::Cursor;
+
+        for text in [
+            "",
+            "position startpos\n",
+            "position startpos\ngo infinite\n",
+        ] {
+            let mut ugi = test_ugi();
+            let mut input = Input::from_reader(Box::new(Cursor::new(text)));
+            assert_eq!(ugi.input_loop(&mut input), QuitProgram);
+            assert!(matches!(ugi.state.status, Quit(QuitProgram)));
+            let atomic = ugi.state.engine.main_atomic_search_data();
+            assert!(!atomic.currently_searching(), "{text}");
+            if text.contains("go") {
+                assert!(Chessboard::default().is_move_legal(atomic.best_move()));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn analysis_test() {
+        let mut ugi = test_ugi();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/4K3/8/8/8/8/6R1 w - - 0 1",
+        ] {
+            ugi.handle_input(&format!("position fen {fen}")).unwrap();
+            let report = ugi.analysis_report(Depth::new_unchecked(3)).unwrap();
+            let num_moves = ugi.state.board.num_legal_moves();
+            assert_eq!(report.lines().count(), num_moves, "{report}");
+            assert!(!report.contains("no score"), "{report}");
+        }
+        // the mate in one is the best move
+        let first = ugi
+            .analysis_report(Depth::new_unchecked(3))
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(first.contains("mate 1"), "{first}");
+        ugi.handle_input("position fen 7k/5KQ1/8/8/8/8/8/8 b - - 0 1")
+            .unwrap();
+        assert!(ugi.analysis_report(Depth::new_unchecked(3)).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn setoption_before_isready_test() {
+        let mut ugi = test_ugi();
+        ugi.handle_input("uci").unwrap();
+        for mb in [1, 4, 2] {
+            ugi.handle_input(&format!("setoption name Hash value {mb}"))
+                .unwrap();
+            ugi.handle_input("isready").unwrap();
+            assert_eq!(ugi.state.engine.hash_size_mb(), mb);
+            let bytes = ugi.state.engine.next_tt().size_in_bytes();
+            assert!(
+                bytes <= mb * 1_000_000 && bytes > mb * 1_000_000 / 2,
+                "{bytes}"
+            );
+        }
+        let threads = ugi.state.engine.get_engine_info().max_threads().min(2);
+        ugi.handle_input(&format!("setoption name Threads value {threads}"))
+            .unwrap();
+        ugi.handle_input("isready").unwrap();
+        assert_eq!(ugi.state.engine.num_threads(), threads);
+        // the engine still works after changing the options
+        ugi.handle_input("go depth 2").unwrap();
+        ugi.state.engine.wait_for_search();
+        ugi.handle_input("isready").unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn options_hash_test() {
+        let mut ugi = test_ugi();
+        ugi.handle_input("uci").unwrap();
+        let default_hash = ugi.options_hash();
+        assert_eq!(ugi.options_hash(), default_hash);
+        let changes = [
+            ("Hash", "2".to_string(), DEFAULT_HASH_SIZE_MB.to_string()),
+            ("Ponder", "true".to_string(), "false".to_string()),
+            ("MultiPV", "3".to_string(), "1".to_string()),
+            ("UCI_ShowCurrLine", "true".to_string(), "false".to_string()),
+            (
+                "MoveOverhead",
+                "123".to_string(),
+                DEFAULT_MOVE_OVERHEAD_MS.to_string(),
+            ),
+            ("Strict", "true".to_string(), "false".to_string()),
+            ("DetectDraws", "true".to_string(), "false".to_string()),
+            ("SyncSearch", "true".to_string(), "false".to_string()),
+            ("MaxPVLength", "10".to_string(), "0".to_string()),
+            ("NormalizeScore", "true".to_string(), "false".to_string()),
+            ("Contempt", "20".to_string(), "0".to_string()),
+            ("AspirationWindow", "5".to_string(), "20".to_string()),
+            ("AspirationWidening", "5".to_string(), "3".to_string()),
+        ];
+        let mut seen = vec![default_hash];
+        for (name, value, default) in changes {
+            ugi.handle_input(&format!("setoption name {name} value {value}"))
+                .unwrap();
+            ugi.handle_input("isready").unwrap();
+            let hash = ugi.options_hash();
+            assert!(!seen.contains(&hash), "{name}");
+            seen.push(hash);
+            ugi.handle_input(&format!("setoption name {name} value {default}"))
+                .unwrap();
+            ugi.handle_input("isready").unwrap();
+            assert_eq!(ugi.options_hash(), default_hash, "{name}");
+            // setting the same value again reproduces the hash
+            ugi.handle_input(&format!("setoption name {name} value {value}"))
+                .unwrap();
+            ugi.handle_input("isready").unwrap();
+            assert_eq!(ugi.options_hash(), hash, "{name}");
+            ugi.handle_input(&format!("setoption name {name} value {default}"))
+                .unwrap();
+        }
+        ugi.handle_input("isready").unwrap();
+        assert_eq!(ugi.options_hash(), default_hash);
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn id_override_test() {
+        for (name, author) in [(None, None), (Some("My Fork"), Some("Someone Else"))] {
+            let mut opts = test_opts();
+            opts.id_name = name.map(ToString::to_string);
+            opts.id_author = author.map(ToString::to_string);
+            let mut ugi = test_ugi_with(opts, crate::list_chess_evals());
+            let log_file = log_to_file(&mut ugi, &format!("id_override_test_{}", name.is_some()));
+            ugi.handle_input("uci").unwrap();
+            ugi.handle_input("isready").unwrap();
+            let log = fs::read_to_string(&log_file).unwrap();
+            let id_line = |prefix: &str| {
+                let lines = log
+                    .lines()
+                    .filter(|line| line.contains(prefix))
+                    .collect_vec();
+                assert_eq!(lines.len(), 1, "{log}");
+                lines[0][lines[0].find(prefix).unwrap()..].to_string()
+            };
+            if let Some(name) = name {
+                assert_eq!(id_line("id name"), format!("id name {name}"));
+                assert_eq!(
+                    id_line("id author"),
+                    format!("id author {}", author.unwrap())
+                );
+            } else {
+                assert!(id_line("id name").starts_with("id name Motors"), "{log}");
+                assert_eq!(id_line("id author"), "id author ToTheAnd");
+            }
+            // the engine can still be used normally after the handshake
+            assert!(log.contains("uciok"), "{log}");
+            assert!(log.contains("readyok"), "{log}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn eval_options_test() {
+        use crate::eval::Eval;
+        use crate::search::EvalBuilder;
+        use gears::games::chess::Chessboard;
+        use gears::general::common::StaticallyNamedEntity;
+        use gears::score::Score;
+        use gears::ugi::EngineOptionType;
+        use gears::ugi::EngineOptionType::Spin;
+        use std::fmt::Display;
+        use std::sync::atomic::AtomicI64;
+        use std::sync::atomic::Ordering::SeqCst;
+
+        static OFFSET: AtomicI64 = AtomicI64::new(0);
+
+        #[derive(Debug, Default, Clone)]
+        struct OffsetEval {}
+
+        impl StaticallyNamedEntity for OffsetEval {
+            fn static_short_name() -> impl Display {
+                "offset"
+            }
+            fn static_long_name() -> String {
+                "Offset Test Eval".to_string()
+            }
+            fn static_description() -> String {
+                "An eval with an offset option".to_string()
+            }
+        }
+
+        impl Eval<Chessboard> for OffsetEval {
+            fn eval(&mut self, _pos: &Chessboard, _ply: usize) -> Score {
+                Score(OFFSET.load(SeqCst) as i32)
+            }
+
+            fn options(&self) -> Vec<EngineOption> {
+                vec![EngineOption {
+                    name: Other("Offset".to_string()),
+                    value: Spin(UgiSpin {
+                        val: 0,
+                        default: Some(0),
+                        min: Some(-100),
+                        max: Some(100),
+                    }),
+                }]
+            }
+
+            fn set_option(
+                &mut self,
+                _option: &EngineOptionName,
+                old_value: &mut EngineOptionType,
+                value: &str,
+            ) -> Res<()> {
+                let Spin(spin) = old_value else {
+                    unreachable!()
+                };
+                spin.val = parse_int_from_str(value, "offset")?;
+                OFFSET.store(spin.val, SeqCst);
+                Ok(())
+            }
+        }
+
+        let mut evals = crate::list_chess_evals();
+        evals.push(Box::new(EvalBuilder::<Chessboard, OffsetEval>::default()));
+        let mut opts = test_opts();
+        opts.engine = "caps-offset".to_string();
+        let mut ugi = test_ugi_with(opts, evals);
+        let options = ugi.write_ugi_options();
+        assert!(
+            options.contains("option name Offset type spin default 0 min -100 max 100"),
+            "{options}"
+        );
+        // the searcher's own options are still there
+        assert!(options.contains("option name FailHard"), "{options}");
+        ugi.handle_input("setoption name Offset value 42").unwrap();
+        let start = Instant::now();
+        while OFFSET.load(SeqCst) != 42 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            sleep(Duration::from_millis(1));
+        }
+        // switching to an eval without options removes the option
+        ugi.handle_input("setoption name SetEval value lite")
+            .unwrap();
+        assert!(!ugi.write_ugi_options().contains("Offset"));
+    }
+}
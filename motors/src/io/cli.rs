@@ -15,18 +15,21 @@
  *  You should have received a copy of the GNU General Public License
  *  along with Motors. If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::datagen::DatagenOpts;
 use crate::Mode;
 use colored::Colorize;
 use gears::cli::{get_next_arg, get_next_int, parse_output, ArgIter, Game};
-use gears::general::common::anyhow::bail;
+use gears::general::common::anyhow::{anyhow, bail};
 use gears::general::common::{parse_int_from_str, Res};
-use gears::search::Depth;
+use gears::search::{Depth, NodesLimit};
 use gears::OutputArgs;
 use std::env;
+use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::Mode::{Bench, Engine, Perft};
+use crate::Mode::{Bench, Datagen, Engine, EvalFens, Perft};
 
 #[derive(Debug, Clone)]
 #[must_use]
@@ -45,6 +48,11 @@ pub struct EngineOpts {
     pub pos_name: Option<String>,
 
     pub mode: Mode,
+
+    /// Overrides the `id name` sent in response to `uci` / `ugi`, e.g. for forks or tournament entries.
+    pub id_name: Option<String>,
+    /// Overrides the `id author` sent in response to `uci` / `ugi`.
+    pub id_author: Option<String>,
 }
 
 impl EngineOpts {
@@ -57,6 +65,8 @@ impl EngineOpts {
             interactive: true,
             pos_name: None,
             mode: Engine,
+            id_name: None,
+            id_author: None,
         }
     }
 }
@@ -76,14 +86,107 @@ fn parse_depth(args: &mut ArgIter) -> Res<Option<Depth>> {
     Ok(None)
 }
 
-fn parse_bench(args: &mut ArgIter) -> Res<Option<Depth>> {
-    parse_depth(args)
+/// `bench [depth] [nodes] [--movetime <ms>]`, where the node limit can also be given as `--nodes <n>`.
+fn parse_bench(args: &mut ArgIter) -> Res<(Option<Depth>, Option<NodesLimit>, Option<Duration>)> {
+    let depth = parse_depth(args)?;
+    let mut nodes = None;
+    if let Some(next) = args.peek() {
+        let val = if next == "-n" || next == "--nodes" {
+            args.next();
+            Some(get_next_int(args, "bench nodes")?)
+        } else if let Ok(val) = parse_int_from_str(next, "bench nodes") {
+            args.next();
+            Some(val)
+        } else {
+            None
+        };
+        if let Some(val) = val {
+            nodes = Some(
+                NodesLimit::new(val)
+                    .ok_or_else(|| anyhow!("The bench node limit must be greater than zero"))?,
+            );
+        }
+    }
+    let mut movetime = None;
+    if args
+        .peek()
+        .is_some_and(|next| next == "-t" || next == "--movetime")
+    {
+        args.next();
+        let millis: u64 = get_next_int(args, "bench movetime")?;
+        if millis == 0 {
+            bail!("The bench movetime must be greater than zero");
+        }
+        movetime = Some(Duration::from_millis(millis));
+    }
+    Ok((depth, nodes, movetime))
 }
 
 fn parse_perft(args: &mut ArgIter) -> Res<Option<Depth>> {
     parse_depth(args)
 }
 
+fn parse_eval_fens(args: &mut ArgIter) -> Res<(Option<PathBuf>, Option<Depth>)> {
+    let file = match args.peek() {
+        // a number is the depth, not a file name
+        Some(next) if !next.starts_with('-') && next.parse::<usize>().is_err() => {
+            Some(PathBuf::from(args.next().unwrap()))
+        }
+        _ => None,
+    };
+    Ok((file, parse_depth(args)?))
+}
+
+fn parse_datagen(args: &mut ArgIter) -> Res<DatagenOpts> {
+    let mut res = DatagenOpts::default();
+    if let Some(next) = args.peek() {
+        if !next.starts_with('-') {
+            res.out = PathBuf::from(args.next().unwrap());
+        }
+    }
+    while let Some(next) = args.peek() {
+        match next.trim_start_matches('-') {
+            "games" => {
+                args.next();
+                res.games = get_next_int(args, "games")?;
+            }
+            "depth" => {
+                args.next();
+                res.depth = Some(Depth::try_new(get_next_int(args, "depth")?)?);
+            }
+            "nodes" => {
+                args.next();
+                res.nodes = Some(
+                    NodesLimit::new(get_next_int(args, "nodes")?)
+                        .ok_or_else(|| anyhow!("The node limit must be greater than zero"))?,
+                );
+            }
+            "shard-size" => {
+                args.next();
+                res.shard_size = get_next_int(args, "shard size")?;
+            }
+            "random-plies" => {
+                args.next();
+                res.random_plies = get_next_int(args, "random plies")?;
+            }
+            "max-plies" => {
+                args.next();
+                res.max_plies = get_next_int(args, "max plies")?;
+            }
+            "seed" => {
+                args.next();
+                res.seed = get_next_int(args, "seed")?;
+            }
+            "score" => {
+                args.next();
+                res.with_score = true;
+            }
+            _ => break,
+        }
+    }
+    Ok(res)
+}
+
 fn parse_option(args: &mut ArgIter, opts: &mut EngineOpts) -> Res<()> {
     let mut key = args.next().unwrap_or_default().clone();
     // since we already accept -<long> in monitors for cutechess compatibility,
@@ -92,16 +195,29 @@ fn parse_option(args: &mut ArgIter, opts: &mut EngineOpts) -> Res<()> {
         key.remove(0);
     }
     match key.as_str() {
-        "bench" | "-bench" | "-b" | "b" => opts.mode = Bench(parse_bench(args)?, true),
-        "bench-simple" | "-bench-simple" | "-bs" | "bs" => opts.mode = Bench(parse_bench(args)?, false),
+        "bench" | "-bench" | "-b" | "b" => {
+            let (depth, nodes, movetime) = parse_bench(args)?;
+            opts.mode = Bench(depth, nodes, movetime, true);
+        }
+        "bench-simple" | "-bench-simple" | "-bs" | "bs" => {
+            let (depth, nodes, movetime) = parse_bench(args)?;
+            opts.mode = Bench(depth, nodes, movetime, false);
+        }
         "perft" | "-perft" | "-p" => opts.mode = Perft(parse_perft(args)?),
+        "eval-fens" | "-eval-fens" | "-ef" => {
+            let (file, depth) = parse_eval_fens(args)?;
+            opts.mode = EvalFens(file, depth);
+        }
+        "datagen" | "-datagen" => opts.mode = Datagen(parse_datagen(args)?),
         "-engine" | "-e" => opts.engine = get_next_arg(args, "engine")?,
         "-game" | "-g" => opts.game = Game::from_str(&get_next_arg(args, "engine")?.to_lowercase())?,
         "-debug" | "-d" => opts.debug = true,
         "-non-interactive" => opts.interactive = false,
+        "-id-name" => opts.id_name = Some(get_next_arg(args, "id name")?),
+        "-id-author" => opts.id_author = Some(get_next_arg(args, "id author")?),
         "-additional-output" | "-output" | "-o" => parse_output(args, &mut opts.outputs)?,
         "-help" => { print_help(); exit(0); },
-        x => bail!("Unrecognized option '{x}'. Only 'bench', 'bench-simple', 'perft', '--engine', '--game', '--debug' and '--outputs' are valid.")
+        x => bail!("Unrecognized option '{x}'. Only 'bench', 'bench-simple', 'perft', 'eval-fens', 'datagen', '--engine', '--game', '--debug', '--non-interactive', '--id-name', '--id-author' and '--outputs' are valid.")
     }
     Ok(())
 }
@@ -131,6 +247,14 @@ fn print_help() {
     \n--{3} can be used to determine how the engine prints extra information; it's mostly useful for development but can also be used to export PGNs, for example.\
     \n--{4} and --{5} are useful for testing the engine and move generation speed, respectively,\
     `bench` is also useful to get a \"hash\" of the search tree explored by the engine.\
+    Both `bench` and `bench-simple` accept an optional depth and node limit, e.g. `bench 8 50000`,\
+    and a fixed time per position in milliseconds instead of the default depth, e.g. `bench --movetime 100`.\
+    \n--{8} reads FENs from the given file or from stdin and prints each FEN followed by a tab and the static eval in centipawns,\
+    or the score of a search if a depth is given, e.g. `eval-fens fens.txt --depth 3`.\
+    \n--{9} generates training data for the tuner through self-play and writes it to the given directory,\
+    e.g. `datagen data --games 1000 --depth 6 --score`. The other options are `--nodes`, `--shard-size`, `--random-plies`,\
+    `--max-plies` and `--seed`.\
+    \n--{10} and --{11} change the name and author the engine reports in response to `uci` or `ugi`.\
     Typing '{6}' while the program is running will also show help messages",
              "game".bold(),
              "engine".bold(),
@@ -140,5 +264,9 @@ fn print_help() {
              "perft".bold(),
              "help".bold(),
              "non-interactive".bold(),
+             "eval-fens".bold(),
+             "datagen".bold(),
+             "id-name".bold(),
+             "id-author".bold(),
     )
 }
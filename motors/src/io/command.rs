@@ -141,7 +141,9 @@ impl<B: Board> Default for AutoCompleteFunc<B> {
     }
 }
 
-pub struct SubCommandsFn<B: Board>(Option<Box<dyn Fn(ACState<B>) -> SubCommandList<B>>>);
+type SubCommandsFnBox<B> = Box<dyn Fn(ACState<B>) -> SubCommandList<B>>;
+
+pub struct SubCommandsFn<B: Board>(Option<SubCommandsFnBox<B>>);
 
 impl<B: Board> Debug for SubCommandsFn<B> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -359,23 +361,15 @@ pub fn ugi_commands<B: Board>() -> CommandList<EngineUGI<B>> {
         ugi_command!(
             ponderhit,
             All,
-            "Stop pondering and start a normal search",
-            |ugi, _, cmd| {
-                let mut go_state = GoState::new(ugi, Normal, ugi.move_overhead);
-                go_state.limit = ugi.state.ponder_limit.ok_or_else(|| {
-                    anyhow!(
-                        "The engine received a '{}' command but wasn't pondering",
-                        cmd.bold()
-                    )
-                })?;
-                ugi.start_search(go_state)
-            }
+            "Stop pondering and continue the search as a normal search",
+            |ugi, _, _| ugi.state.engine.ponderhit()
         ),
         ugi_command!(
             isready,
             All,
-            "Queries if the engine is ready. The engine responds with 'readyok'",
+            "Queries if the engine is ready. The engine responds with 'readyok' once all previously set options have been applied",
             |ugi, _, _| {
+                ugi.state.engine.wait_until_ready()?;
                 ugi.write_ugi("readyok");
                 Ok(())
             }
@@ -553,7 +547,7 @@ pub fn ugi_commands<B: Board>() -> CommandList<EngineUGI<B>> {
         ugi_command!(
             perft,
             Custom,
-            "Internal movegen test on current / bench positions",
+            "Internal movegen test on current / bench positions, also lists the number of nodes after each move",
             |ugi, words, _| ugi.handle_go(Perft, words),
             -> |_state: ACState<B>| go_options(Some(Perft)),
             recurse = true
@@ -574,6 +568,12 @@ pub fn ugi_commands<B: Board>() -> CommandList<EngineUGI<B>> {
             -> |_| go_options::<B>(Some(Bench)),
             recurse = true
         ),
+        ugi_command!(
+            analysis | analyze,
+            Custom,
+            "Searches the current position and lists all legal moves with their scores, best first. Optionally takes a depth",
+            |ugi, words, _| ugi.handle_analysis(words)
+        ),
         ugi_command!(
             eval | e | static_eval,
             Custom,
@@ -828,7 +828,16 @@ pub fn go_options<B: Board>(mode: Option<SearchType>) -> CommandList<GoState<B>>
                 "Only consider the specified moves",
                 |opts, words, _| {
                     let mut search_moves = vec![];
-                    while let Some(mov) = words.peek().and_then(|m| B::Move::from_text(m, &opts.board).ok()) {
+                    // the list of moves ends at the next go option, anything else has to be a legal move
+                    while let Some(word) = words.peek() {
+                        let mov = match B::Move::from_text(word, &opts.board) {
+                            Ok(mov) => mov,
+                            Err(_) if go_options::<B>(Some(opts.search_type)).iter().any(|o| o.matches(word)) => break,
+                            Err(err) => bail!("Invalid move '{0}' after 'searchmoves': {err}", word.red()),
+                        };
+                        if !opts.board.is_move_legal(mov) {
+                            bail!("The move '{0}' after 'searchmoves' isn't legal in position '{1}'", word.red(), opts.board.as_fen());
+                        }
                         words.next().unwrap();
                         search_moves.push(mov);
                     }
@@ -854,7 +863,7 @@ pub fn go_options<B: Board>(mode: Option<SearchType>) -> CommandList<GoState<B>>
             command!(GoState<B>,
                 threads | t,
                 Custom,
-                "Search with n threads in parallel, temporarily overwriting the 'threads' engine option",
+                "Search with n threads in parallel, temporarily overwriting the 'threads' engine option. Also works for perft",
                 |opts, words, _| {
                     opts.threads = Some(parse_int(words, "threads")?);
                     Ok(())
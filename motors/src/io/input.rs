@@ -16,6 +16,8 @@
  *  along with Motors. If not, see <https://www.gnu.org/licenses/>.
  */
 use crate::io::command::{ugi_commands, CommandAutocomplete};
+#[cfg(test)]
+use crate::io::input::InputEnum::Reader;
 use crate::io::input::InputEnum::{Interactive, NonInteractive};
 use crate::io::EngineUGI;
 use colored::Colorize;
@@ -25,6 +27,9 @@ use gears::general::common::anyhow::{anyhow, bail};
 use gears::general::common::Res;
 use gears::output::OutputOpts;
 use inquire::Text;
+use std::fmt::Debug;
+#[cfg(test)]
+use std::io::BufRead;
 use std::io::{stdin, stdout, IsTerminal};
 
 trait GetLine<B: Board> {
@@ -104,10 +109,37 @@ impl<B: Board> GetLine<B> for NonInteractiveInput {
     }
 }
 
+/// Reads from an arbitrary source instead of stdin, e.g. a file or an in-memory buffer.
+#[cfg(test)]
+struct ReaderInput {
+    reader: Box<dyn BufRead>,
+}
+
+#[cfg(test)]
+impl Debug for ReaderInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReaderInput")
+    }
+}
+
+#[cfg(test)]
+impl<B: Board> GetLine<B> for ReaderInput {
+    fn get_line(&mut self, _ugi: &mut EngineUGI<B>) -> Res<String> {
+        let mut input = String::new();
+        let count = self.reader.read_line(&mut input)?;
+        if count == 0 {
+            bail!("Read 0 bytes. Terminating the program.")
+        }
+        Ok(input)
+    }
+}
+
 #[derive(Debug)]
 enum InputEnum<B: Board> {
     Interactive(InteractiveInput<B>),
     NonInteractive(NonInteractiveInput),
+    #[cfg(test)]
+    Reader(ReaderInput),
 }
 
 #[derive(Debug)]
@@ -129,7 +161,19 @@ impl<B: Board> Input<B> {
         (Self { typ }, interactive)
     }
 
+    /// Reads the input from `reader` instead of stdin. This is never interactive.
+    #[cfg(test)]
+    pub fn from_reader(reader: Box<dyn BufRead>) -> Self {
+        Self {
+            typ: Reader(ReaderInput { reader }),
+        }
+    }
+
     pub fn set_interactive(&mut self, value: bool, ugi: &mut EngineUGI<B>) {
+        #[cfg(test)]
+        if matches!(self.typ, Reader(_)) {
+            return;
+        }
         if value {
             if !matches!(self.typ, Interactive(_)) {
                 self.typ = Interactive(InteractiveInput::new(ugi));
@@ -152,6 +196,10 @@ impl<B: Board> Input<B> {
             NonInteractive(n) => n
                 .get_line(ugi)
                 .map_err(|err| anyhow!("Couldn't read input: {err}")),
+            #[cfg(test)]
+            Reader(r) => r
+                .get_line(ugi)
+                .map_err(|err| anyhow!("Couldn't read input: {err}")),
         }
     }
 }
@@ -28,7 +28,7 @@ use gears::general::move_list::MoveList;
 use gears::general::moves::ExtendedFormat::Standard;
 use gears::general::moves::Move;
 use gears::output::{Message, OutputBox, OutputOpts};
-use gears::score::{Score, SCORE_LOST, SCORE_WON};
+use gears::score::{normalize_score, Score, SCORE_LOST, SCORE_WON};
 use gears::search::MpvType::{MainOfMultiple, OnlyLine, SecondaryLine};
 use gears::search::NodeType::*;
 use gears::search::{MpvType, NodeType, SearchInfo, SearchResult};
@@ -48,6 +48,10 @@ pub struct UgiOutput<B: Board> {
     alt_grad: BasisGradient,
     progress_bar: Option<ProgressBar>,
     pub(super) show_currline: bool,
+    /// Only the first `max_pv_len` moves of the PV get printed, if set. Some GUIs struggle with very long PVs.
+    pub(super) max_pv_len: Option<usize>,
+    /// Report normalized scores if the eval supports it, see [`normalize_score`].
+    pub(super) normalize_score: bool,
 }
 
 impl<B: Board> Default for UgiOutput<B> {
@@ -65,6 +69,8 @@ impl<B: Board> Default for UgiOutput<B> {
                 .unwrap(),
             progress_bar: None,
             show_currline: true,
+            max_pv_len: None,
+            normalize_score: false,
         }
     }
 }
@@ -109,6 +115,9 @@ impl<B: Board> UgiOutput<B> {
             move_text = move_text.color(color_for_score(score, &self.gradient));
         }
         let mut msg = format!("Chosen move: {move_text}",);
+        if let Some(mate) = res.score.and_then(Score::mate_description) {
+            msg += &format!(" ({mate})");
+        }
         if let Some(ponder) = res.ponder_move() {
             let new_pos = res
                 .pos
@@ -208,8 +217,10 @@ impl<B: Board> UgiOutput<B> {
         );
     }
 
-    pub fn write_search_info(&mut self, info: SearchInfo<B>) {
+    pub fn write_search_info(&mut self, mut info: SearchInfo<B>) {
         self.clear_progress_bar();
+        self.cap_pv(&mut info);
+        self.normalize_score(&mut info);
         let exact = info.bound == Some(Exact);
         if !self.pretty {
             self.write_ugi(&info.to_string());
@@ -223,7 +234,7 @@ impl<B: Board> UgiOutput<B> {
             && self
                 .previous_exact_info
                 .as_ref()
-                .is_some_and(|i| i.depth != info.depth - 1)
+                .is_some_and(|i| i.depth.get() + 1 != info.depth.get())
         {
             self.previous_exact_info = None;
         }
@@ -322,6 +333,20 @@ impl<B: Board> UgiOutput<B> {
         }
     }
 
+    /// This only affects the output; the search still uses the full PV, e.g. to determine the ponder move.
+    fn cap_pv(&self, info: &mut SearchInfo<B>) {
+        if let Some(max_len) = self.max_pv_len {
+            info.pv.truncate(max_len);
+        }
+    }
+
+    /// Like [`Self::cap_pv`], this only affects the output and not the search.
+    fn normalize_score(&self, info: &mut SearchInfo<B>) {
+        if let Some(pawn_value) = info.score_normalization.filter(|_| self.normalize_score) {
+            info.score = normalize_score(info.score, pawn_value);
+        }
+    }
+
     fn clear_progress_bar(&mut self) {
         if let Some(bar) = &self.progress_bar {
             bar.finish_and_clear();
@@ -545,3 +570,37 @@ fn pretty_variation<B: Board>(
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn normalize_score_test() {
+        use gears::games::chess::Chessboard;
+        use gears::score::{MIN_SCORE_WON, SCORE_WON};
+
+        let mut output = UgiOutput::<Chessboard>::default();
+        let mut info = SearchInfo::<Chessboard> {
+            score: Score(300),
+            score_normalization: Some(150),
+            ..Default::default()
+        };
+        output.normalize_score(&mut info);
+        assert_eq!(info.score, Score(300));
+        output.normalize_score = true;
+        output.normalize_score(&mut info);
+        assert_eq!(info.score, Score(200));
+        assert!(info.to_string().contains(" score cp 200 "), "{info}");
+        for score in [SCORE_WON - 3, MIN_SCORE_WON, -SCORE_WON] {
+            info.score = score;
+            output.normalize_score(&mut info);
+            assert_eq!(info.score, score);
+        }
+        info.score = Score(300);
+        info.score_normalization = None;
+        output.normalize_score(&mut info);
+        assert_eq!(info.score, Score(300));
+    }
+}
@@ -1,8 +1,12 @@
+use std::fs::File;
+use std::io::{stdin, BufRead, BufReader};
 use std::marker::PhantomData;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use dyn_clone::clone_box;
-use rand::rngs::StdRng;
+use itertools::Itertools;
 
 use gears::cli::{ArgIter, Game};
 #[cfg(feature = "ataxx")]
@@ -15,16 +19,18 @@ use gears::games::mnk::MNKBoard;
 use gears::games::uttt::UtttBoard;
 use gears::games::OutputList;
 use gears::general::board::Board;
+use gears::general::board::Strictness::Relaxed;
 use gears::general::common::anyhow::anyhow;
 use gears::general::common::Description::WithDescription;
-use gears::general::common::{select_name_dyn, Res};
-use gears::general::perft::perft;
+use gears::general::common::{select_name_dyn, Res, XorShiftRng};
+use gears::general::perft::{perft, PerftRes};
 use gears::output::normal_outputs;
-use gears::search::{Depth, SearchLimit};
+use gears::search::{Depth, NodesLimit, SearchLimit};
 use gears::Quitting::*;
 use gears::{create_selected_output_builders, AbstractRun, AnyRunnable, OutputArgs, Quitting};
 use std::fmt::{Display, Formatter};
 
+use crate::datagen::{DatagenOpts, DatagenRun};
 #[cfg(feature = "ataxx")]
 use crate::eval::ataxx::bate::Bate;
 use crate::eval::chess::lite::KingGambot;
@@ -51,29 +57,38 @@ use crate::search::generic::random_mover::RandomMover;
 use crate::search::multithreading::EngineWrapper;
 use crate::search::tt::TT;
 use crate::search::{
-    run_bench_with, AbstractEvalBuilder, AbstractSearcherBuilder, Engine, EvalBuilder, EvalList,
-    SearcherBuilder, SearcherList,
+    run_bench_with, AbstractEvalBuilder, AbstractSearcherBuilder, BenchResult, Engine, EvalBuilder,
+    EvalList, SearcherBuilder, SearcherList,
 };
-use crate::Mode::{Bench, Perft};
+use crate::Mode::{Bench, Datagen, EvalFens, Perft};
 
+pub mod datagen;
 pub mod eval;
 pub mod io;
 pub mod search;
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Clone)]
 pub enum Mode {
     #[default]
     Engine,
-    Bench(Option<Depth>, bool),
+    /// The optional depth, node limit and movetime, and whether to do a second node-limited search per position
+    /// with the engine's default bench nodes if neither a node limit nor a movetime was given.
+    Bench(Option<Depth>, Option<NodesLimit>, Option<Duration>, bool),
     Perft(Option<Depth>),
+    /// Read FENs from the file (or stdin) and print their static evals, or search scores if a depth is given.
+    EvalFens(Option<PathBuf>, Option<Depth>),
+    /// Generate training data for the tuner through self-play.
+    Datagen(DatagenOpts),
 }
 
 impl Display for Mode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Mode::Engine => write!(f, "engine"),
-            Bench(_, _) => write!(f, "bench"),
+            Bench(..) => write!(f, "bench"),
             Perft(_) => write!(f, "perft"),
+            EvalFens(_, _) => write!(f, "fen evaluation"),
+            Datagen(_) => write!(f, "datagen"),
         }
     }
 }
@@ -82,6 +97,8 @@ impl Display for Mode {
 struct BenchRun<B: Board> {
     engine: Box<dyn Engine<B>>,
     depth: Option<Depth>,
+    nodes: Option<NodesLimit>,
+    movetime: Option<Duration>,
     with_nodes: bool,
 }
 
@@ -91,33 +108,50 @@ impl<B: Board> BenchRun<B> {
         all_searchers: &SearcherList<B>,
         all_evals: &EvalList<B>,
     ) -> Res<Self> {
-        let Bench(depth, with_nodes) = options.mode else {
+        let Bench(depth, nodes, movetime, with_nodes) = options.mode else {
             unreachable!()
         };
         let engine = create_engine_box_from_str(&options.engine, all_searchers, all_evals)?;
         Ok(Self {
             engine,
             depth,
+            nodes,
+            movetime,
             with_nodes,
         })
     }
 }
 
-impl<B: Board> AbstractRun for BenchRun<B> {
-    fn run(&mut self) -> Quitting {
+impl<B: Board> BenchRun<B> {
+    /// Uses the depth given on the command line, or the engine's default bench depth if there wasn't one.
+    /// An explicit node limit caps the depth-limited search instead of adding a second search.
+    /// A movetime bench isn't depth-limited unless a depth was given explicitly.
+    fn bench_res(&mut self) -> BenchResult {
         let engine = self.engine.as_mut();
-        let nodes = if self.with_nodes {
+        let mut limit = if let Some(movetime) = self.movetime {
+            let mut limit = SearchLimit::per_move(movetime);
+            if let Some(depth) = self.depth {
+                limit.depth = depth;
+            }
+            limit
+        } else {
+            SearchLimit::depth(self.depth.unwrap_or(engine.default_bench_depth()))
+        };
+        let second_limit = if let Some(nodes) = self.nodes {
+            limit.nodes = nodes;
+            None
+        } else if self.with_nodes && self.movetime.is_none() {
             Some(SearchLimit::nodes(engine.default_bench_nodes()))
         } else {
             None
         };
-        let depth = self.depth.unwrap_or(engine.default_bench_depth());
-        let res = run_bench_with(
-            engine,
-            SearchLimit::depth(depth),
-            nodes,
-            &B::bench_positions(),
-        );
+        run_bench_with(engine, limit, second_limit, &B::bench_positions())
+    }
+}
+
+impl<B: Board> AbstractRun for BenchRun<B> {
+    fn run(&mut self) -> Quitting {
+        let res = self.bench_res();
         println!("{res}");
         QuitProgram
     }
@@ -136,18 +170,97 @@ impl<B: Board> PerftRun<B> {
             ..Self::default()
         }
     }
+
+    /// Uses the depth given on the command line, or the game's default perft depth if there wasn't one.
+    fn perft_res(&self) -> PerftRes {
+        let pos = B::default();
+        let depth = self.depth.unwrap_or(pos.default_perft_depth());
+        perft(depth, pos)
+    }
 }
 
 impl<B: Board> AbstractRun for PerftRun<B> {
     fn run(&mut self) -> Quitting {
-        let pos = B::default();
-        let depth = self.depth.unwrap_or(pos.default_perft_depth());
-        let res = perft(depth, pos);
+        let res = self.perft_res();
         println!("{res}");
         QuitProgram
     }
 }
 
+#[derive(Debug)]
+struct EvalFensRun<B: Board> {
+    engine: Box<dyn Engine<B>>,
+    input: Option<PathBuf>,
+    depth: Option<Depth>,
+}
+
+impl<B: Board> EvalFensRun<B> {
+    pub fn create(
+        options: &EngineOpts,
+        all_searchers: &SearcherList<B>,
+        all_evals: &EvalList<B>,
+    ) -> Res<Self> {
+        let EvalFens(input, depth) = options.mode.clone() else {
+            unreachable!()
+        };
+        let engine = create_engine_box_from_str(&options.engine, all_searchers, all_evals)?;
+        Ok(Self {
+            engine,
+            input,
+            depth,
+        })
+    }
+}
+
+impl<B: Board> AbstractRun for EvalFensRun<B> {
+    fn run(&mut self) -> Quitting {
+        let input: Box<dyn BufRead> = match &self.input {
+            None => Box::new(stdin().lock()),
+            Some(path) => match File::open(path) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(err) => {
+                    eprintln!("Couldn't open '{}': {err}", path.display());
+                    return QuitProgram;
+                }
+            },
+        };
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let fen = line.trim();
+            if fen.is_empty() {
+                continue;
+            }
+            match eval_fen(self.engine.as_mut(), fen, self.depth) {
+                Ok(res) => println!("{res}"),
+                Err(err) => eprintln!("Couldn't evaluate '{fen}': {err}"),
+            }
+        }
+        QuitProgram
+    }
+}
+
+/// Returns `<fen>\t<score>`, where the score is the static eval from the perspective of the side to move or,
+/// if `depth` is set, the score of a search with that depth.
+pub fn eval_fen<B: Board>(
+    engine: &mut dyn Engine<B>,
+    fen: &str,
+    depth: Option<Depth>,
+) -> Res<String> {
+    let pos = B::from_fen(fen, Relaxed)?;
+    let score = match depth {
+        None => engine.static_eval(pos, 0),
+        Some(depth) => {
+            engine.forget();
+            let res = engine.search_with_new_tt(pos, SearchLimit::depth(depth));
+            res.score
+                .ok_or_else(|| anyhow!("The search didn't return a score"))?
+        }
+    };
+    Ok(format!("{fen}\t{}", score.0))
+}
+
 // TODO: A lot of this repetitiveness could be avoided with a macro
 
 pub fn create_searcher_from_str<B: Board>(
@@ -213,6 +326,21 @@ pub fn create_engine_box_from_str<B: Board>(
     Ok(searcher_builder.build(eval_builder.as_ref()))
 }
 
+/// Returns the names of all `searcher-eval` combinations for the given searchers and evals,
+/// which can then be passed to [`create_engine_from_str`] to build the engine, e.g. after selecting it from a menu.
+/// The default combination comes last. Combinations that can't be built are left out, so every returned name is valid.
+pub fn list_engine_names<B: Board>(
+    searchers: &SearcherList<B>,
+    evals: &EvalList<B>,
+) -> Vec<String> {
+    searchers
+        .iter()
+        .cartesian_product(evals.iter())
+        .map(|(searcher, eval)| format!("{}-{}", searcher.short_name(), eval.short_name()))
+        .filter(|name| create_engine_box_from_str(name, searchers, evals).is_ok())
+        .collect()
+}
+
 pub fn create_match_for_game<B: Board>(
     mut args: EngineOpts,
     searchers: SearcherList<B>,
@@ -220,7 +348,7 @@ pub fn create_match_for_game<B: Board>(
     outputs: OutputList<B>,
 ) -> Res<AnyRunnable> {
     match args.mode {
-        Bench(_, _) => Ok(Box::new(BenchRun::create(&args, &searchers, &evals)?)),
+        Bench(..) => Ok(Box::new(BenchRun::create(&args, &searchers, &evals)?)),
         Mode::Engine => {
             if args.debug {
                 args.outputs.push(OutputArgs::new("logger".to_string()));
@@ -234,6 +362,8 @@ pub fn create_match_for_game<B: Board>(
             )?))
         }
         Perft(depth) => Ok(Box::new(PerftRun::<B>::create(depth))),
+        EvalFens(_, _) => Ok(Box::new(EvalFensRun::create(&args, &searchers, &evals)?)),
+        Datagen(_) => Ok(Box::new(DatagenRun::create(&args, &searchers, &evals)?)),
     }
 }
 
@@ -263,7 +393,8 @@ pub fn list_mnk_outputs() -> OutputList<MNKBoard> {
 
 #[must_use]
 pub fn generic_evals<B: Board>() -> EvalList<B> {
-    vec![Box::new(EvalBuilder::<B, RandEval>::default())]
+    // use the xorshift rng so that setting the `Seed` option gives the same results with every version of `rand`
+    vec![Box::new(EvalBuilder::<B, RandEval<XorShiftRng>>::default())]
 }
 
 #[cfg(feature = "chess")]
@@ -307,7 +438,7 @@ pub fn list_mnk_evals() -> EvalList<MNKBoard> {
 pub fn generic_searchers<B: Board>() -> SearcherList<B> {
     vec![
         #[cfg(feature = "random_mover")]
-        Box::new(SearcherBuilder::<B, RandomMover<B, StdRng>>::default()),
+        Box::new(SearcherBuilder::<B, RandomMover<B, XorShiftRng>>::default()),
         #[cfg(feature = "gaps")]
         Box::new(SearcherBuilder::<B, Gaps<B>>::default()),
     ]
@@ -379,7 +510,7 @@ pub fn create_match(args: EngineOpts) -> Res<AnyRunnable> {
 pub fn run_program_with_args(args: ArgIter) -> Res<()> {
     let args =
         parse_cli(args).map_err(|err| anyhow!("Failed to parse command line arguments: {err}"))?;
-    let mode = args.mode;
+    let mode = args.mode.clone();
     let mut the_match =
         create_match(args).map_err(|err| anyhow!("Couldn't start the {mode}: {err}"))?;
     _ = the_match.run();
@@ -391,3 +522,115 @@ pub fn run_program() -> Res<()> {
     args.next(); // remove the program name
     run_program_with_args(args)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn list_chess_engine_names_test() {
+        let searchers = list_chess_searchers();
+        let evals = list_chess_evals();
+        let names = list_engine_names(&searchers, &evals);
+        assert_eq!(names.len(), searchers.len() * evals.len());
+        #[cfg(feature = "caps")]
+        assert!(names.last().unwrap().eq_ignore_ascii_case("caps-lite"));
+        for name in names {
+            let engine = create_engine_box_from_str(&name, &searchers, &evals);
+            assert!(engine.is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn perft_depth_override_test() {
+        let run = PerftRun::<Chessboard>::create(Some(Depth::new_unchecked(2)));
+        let res = run.perft_res();
+        assert_eq!(res.depth, Depth::new_unchecked(2));
+        assert_eq!(res.nodes, 400);
+        let res = PerftRun::<Chessboard>::create(None).perft_res();
+        assert_eq!(res.depth, Chessboard::default().default_perft_depth());
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn bench_depth_override_test() {
+        let mut opts = EngineOpts::for_game(Game::Chess, false);
+        for depth in [2, 3] {
+            let depth = Depth::new_unchecked(depth);
+            opts.mode = Bench(Some(depth), None, None, false);
+            let mut run =
+                BenchRun::create(&opts, &list_chess_searchers(), &list_chess_evals()).unwrap();
+            let res = run.bench_res();
+            assert_eq!(res.depth, Some(depth));
+            assert!(res.max_depth >= depth);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn bench_nodes_override_test() {
+        let mut opts = EngineOpts::for_game(Game::Chess, false);
+        let depth = Depth::new_unchecked(8);
+        let nodes = NodesLimit::new(50_000).unwrap();
+        opts.mode = Bench(Some(depth), Some(nodes), None, true);
+        let mut run =
+            BenchRun::create(&opts, &list_chess_searchers(), &list_chess_evals()).unwrap();
+        let res = run.bench_res();
+        assert_eq!(res.depth, Some(depth));
+        assert_eq!(res.nodes_limit, Some(nodes));
+        let num_positions = Chessboard::bench_positions().len() as u64;
+        assert!(res.nodes <= num_positions * nodes.get(), "{res}");
+        assert!(res
+            .to_string()
+            .starts_with("depth 8, node limit 50000, max depth "));
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn bench_movetime_test() {
+        let mut opts = EngineOpts::for_game(Game::Chess, false);
+        let movetime = Duration::from_millis(5);
+        opts.mode = Bench(None, None, Some(movetime), true);
+        let mut run =
+            BenchRun::create(&opts, &list_chess_searchers(), &list_chess_evals()).unwrap();
+        let res = run.bench_res();
+        assert_eq!(res.depth, None);
+        assert_eq!(res.movetime, Some(movetime));
+        let num_positions = Chessboard::bench_positions().len() as u32;
+        assert!(res.time >= movetime * num_positions, "{res}");
+        let res = res.to_string();
+        assert!(res.starts_with("movetime 5 ms, max depth "), "{res}");
+        assert!(res.contains(" nps, hash "), "{res}");
+        // depth-based benches don't mention the movetime
+        opts.mode = Bench(Some(Depth::new_unchecked(1)), None, None, false);
+        let mut run =
+            BenchRun::create(&opts, &list_chess_searchers(), &list_chess_evals()).unwrap();
+        assert!(run
+            .bench_res()
+            .to_string()
+            .starts_with("depth 1, max depth "));
+    }
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn eval_fen_test() {
+        let mut engine =
+            create_engine_box_from_str("caps-lite", &list_chess_searchers(), &list_chess_evals())
+                .unwrap();
+        // white is up a queen
+        let fen = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        for depth in [None, Some(Depth::new_unchecked(3))] {
+            let res = eval_fen(engine.as_mut(), fen, depth).unwrap();
+            let (res_fen, score) = res.split_once('\t').unwrap();
+            assert_eq!(res_fen, fen);
+            assert!(score.parse::<i32>().unwrap() > 300, "{res}");
+            let flipped = fen.replace(" w ", " b ");
+            let res = eval_fen(engine.as_mut(), &flipped, depth).unwrap();
+            let score = res.split_once('\t').unwrap().1;
+            assert!(score.parse::<i32>().unwrap() < -300, "{res}");
+        }
+        assert!(eval_fen(engine.as_mut(), "not a fen", None).is_err());
+    }
+}
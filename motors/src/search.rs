@@ -24,6 +24,7 @@ use gears::search::{
     Depth, NodeType, NodesLimit, SearchInfo, SearchLimit, SearchResult, TimeControl,
 };
 use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType};
+use gears::PlayerResult::Draw;
 use itertools::Itertools;
 use rand::prelude::StdRng;
 use rand::SeedableRng;
@@ -56,6 +57,8 @@ pub struct EngineInfo {
     default_bench_depth: Depth,
     default_bench_nodes: NodesLimit,
     options: HashMap<EngineOptionName, EngineOptionType>,
+    /// The subset of `options` that belongs to the eval, see [`Eval::options`].
+    eval_options: Vec<EngineOptionName>,
     max_threads: usize,
     pub internal_state_description: Option<String>,
 }
@@ -110,16 +113,31 @@ impl EngineInfo {
             std::thread::available_parallelism().unwrap_or(NonZeroUsize::new(1024).unwrap());
         let max_threads = max_threads.unwrap_or(usize::MAX).min(num_cores.get());
         let options = HashMap::from_iter(options.into_iter().map(|o| (o.name, o.value)));
-        Self {
+        let mut res = Self {
             engine: Name::new(engine),
-            eval: Some(Name::new(eval)),
+            eval: None,
             version: version.to_string(),
             default_bench_depth,
             default_bench_nodes,
             max_threads,
             options,
+            eval_options: vec![],
             internal_state_description: None,
+        };
+        res.set_eval(eval);
+        res
+    }
+
+    /// Replaces the eval's name and options with those of the new `eval`.
+    pub fn set_eval<B: Board>(&mut self, eval: &dyn Eval<B>) {
+        for name in self.eval_options.drain(..) {
+            self.options.remove(&name);
+        }
+        for option in eval.options() {
+            self.eval_options.push(option.name.clone());
+            self.options.insert(option.name, option.value);
         }
+        self.eval = Some(Name::new(eval));
     }
 
     pub fn engine(&self) -> &Name {
@@ -163,6 +181,10 @@ pub struct BenchResult {
     pub time: Duration,
     pub max_depth: Depth,
     pub depth: Option<Depth>,
+    /// The node limit of each search, if there was one.
+    pub nodes_limit: Option<NodesLimit>,
+    /// The fixed time per search, if there was one.
+    pub movetime: Option<Duration>,
     pub pv_score_hash: u64,
 }
 
@@ -172,6 +194,8 @@ impl Default for BenchResult {
             nodes: 0,
             time: Duration::default(),
             depth: None,
+            nodes_limit: None,
+            movetime: None,
             max_depth: Depth::try_new(0).unwrap(),
             pv_score_hash: 0,
         }
@@ -186,9 +210,20 @@ impl Display for BenchResult {
         } else {
             String::new()
         };
+        let nodes_limit = if let Some(nodes) = self.nodes_limit {
+            format!("node limit {nodes}, ")
+        } else {
+            String::new()
+        };
+        // Only shown for movetime benches so that the output of depth-based benches stays the same
+        let movetime = if let Some(time) = self.movetime {
+            format!("movetime {} ms, ", time.as_millis())
+        } else {
+            String::new()
+        };
         writeln!(
             f,
-            "{depth}max depth {0}, time {2} ms, {1} nodes, {3} nps, hash {4:X}",
+            "{depth}{nodes_limit}{movetime}max depth {0}, time {2} ms, {1} nodes, {3} nps, hash {4:X}",
             self.max_depth.get(),
             Colorize::bold(self.nodes.to_string().as_str()),
             self.time.as_millis().to_string().color(Red),
@@ -226,6 +261,10 @@ impl<B: Board, const LIMIT: usize> Pv<B, LIMIT> {
         self.list.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.list.clear();
     }
@@ -454,6 +493,9 @@ pub trait Engine<B: Board>: StaticallyNamedEntity + Send + 'static {
         Self: Sized,
     {
         let state = self.search_state();
+        if state.pondering() {
+            return state.stop_flag();
+        }
         let limit = self.limit();
         // Do the less expensive checks first to avoid querying the time in each node
         // loads an atomic, so calling this function twice probably won't be optimized
@@ -482,6 +524,9 @@ pub trait Engine<B: Board>: StaticallyNamedEntity + Send + 'static {
         Self: Sized,
     {
         let state = self.search_state();
+        if state.pondering() {
+            return false;
+        }
         state.start_time().elapsed() >= soft_limit
             || state.depth().get() as isize > max_soft_depth
             || state.best_score() >= Score(SCORE_WON.0 - mate_depth.get() as ScoreT)
@@ -493,6 +538,21 @@ pub trait Engine<B: Board>: StaticallyNamedEntity + Send + 'static {
         self.search_state_dyn().to_search_info()
     }
 
+    /// Calls `callback` once per completed depth with the [`SearchInfo`] of the last line, which contains e.g. the depth,
+    /// score, nodes and PV. This is meant for embedding the engine without parsing its UGI output.
+    /// This is an [iteration callback](AbstractSearchState::add_iteration_callback) that ignores aspiration window
+    /// fail highs and fail lows, aborted iterations and all but the last line of a multipv search.
+    /// Without multipv, the last line is the main line.
+    fn on_new_depth(&mut self, callback: IterationCallback<B>) {
+        self.search_state_mut_dyn().add_iteration_callback(Box::new(
+            move |info: &SearchInfo<B>| {
+                if info.bound == Some(NodeType::Exact) && info.pv_num + 1 == info.max_num_pvs {
+                    callback(info);
+                }
+            },
+        ));
+    }
+
     /// Sets an option with the name 'option' to the value 'value'.
     fn set_option(
         &mut self,
@@ -514,6 +574,11 @@ pub trait Engine<B: Board>: StaticallyNamedEntity + Send + 'static {
 
     fn set_eval(&mut self, eval: Box<dyn Eval<B>>);
 
+    /// The eval used by this searcher, if any. Used to forward the eval's options, see [`Eval::set_option`].
+    fn eval_mut(&mut self) -> Option<&mut dyn Eval<B>> {
+        None
+    }
+
     /// The simplest version of the search function, ignores history-related rules like repetitions.
     fn search_with_new_tt(&mut self, pos: B, limit: SearchLimit) -> SearchResult<B> {
         self.search_with_tt(pos, limit, TT::default())
@@ -531,7 +596,12 @@ pub trait Engine<B: Board>: StaticallyNamedEntity + Send + 'static {
     /// Start a new search and return the best move and score.
     /// 'parameters' contains information like the board history and allows the search to output intermediary results.
     fn search(&mut self, search_params: SearchParams<B>) -> SearchResult<B> {
+        let normalization = self
+            .eval_mut()
+            .and_then(|eval| eval.score_normalization(&search_params.pos));
         self.search_state_mut_dyn().new_search(search_params);
+        self.search_state_mut_dyn()
+            .set_score_normalization(normalization);
         let res = self.do_search();
         self.search_state_mut_dyn().end_search(res);
         res
@@ -692,6 +762,9 @@ impl<B: Board> CustomInfo<B> for NoCustomInfo {
     fn hard_forget_except_tt(&mut self) {}
 }
 
+/// The radius of the first aspiration window, which gets used after the first iteration.
+pub const DEFAULT_AW_INITIAL_RADIUS: ScoreT = 20;
+
 #[derive(Debug, Clone)]
 struct PVData<B: Board> {
     alpha: Score,
@@ -707,7 +780,7 @@ impl<B: Board> Default for PVData<B> {
         Self {
             alpha: MIN_ALPHA,
             beta: MAX_BETA,
-            radius: Score(20),
+            radius: Score(DEFAULT_AW_INITIAL_RADIUS),
             pv: Pv::default(),
             score: NO_SCORE_YET,
             bound: None,
@@ -723,11 +796,44 @@ pub trait AbstractSearchState<B: Board> {
     fn to_bench_res(&self) -> BenchResult;
     fn to_search_info(&self) -> SearchInfo<B>;
     fn aggregated_statistics(&self) -> &Statistics;
+    /// Passes the current [`SearchInfo`] to all iteration callbacks, including the one that writes the UGI output.
     fn send_search_info(&self);
+    /// Like [`Self::send_search_info`], but doesn't write the UGI output. This is used for results that aren't
+    /// interesting enough to print, like fail highs and fail lows of short aspiration window searches.
+    fn notify_iteration_callbacks(&self);
+    /// Registers a callback that gets invoked with the [`SearchInfo`] of each iterative deepening iteration
+    /// (once per line for multipv searches), including iterations that fail high or low and aborted iterations,
+    /// which can be recognized by their [`SearchInfo::bound`]. This allows UIs to e.g. live-plot the search without
+    /// parsing UGI output. Callbacks are kept across searches until [`Self::clear_iteration_callbacks`] is called.
+    fn add_iteration_callback(&mut self, callback: IterationCallback<B>);
+    /// Removes all iteration callbacks.
+    fn clear_iteration_callbacks(&mut self);
+    /// Sets the [`SearchInfo::score_normalization`] of the current search, which depends on the eval and the root position.
+    fn set_score_normalization(&mut self, pawn_value: Option<ScoreT>);
     /// Engine-specific info, like the contents of history tables.
     fn write_internal_info(&self) -> Option<String>;
 }
 
+/// Gets called with the [`SearchInfo`] of each iteration, see [`AbstractSearchState::add_iteration_callback`].
+pub type IterationCallback<B> = Box<dyn Fn(&SearchInfo<B>) + Send>;
+
+#[derive(Default)]
+struct IterationCallbacks<B: Board> {
+    /// Writes the UGI output. Only set for the main thread, and replaced at the start of each search.
+    ugi: Option<IterationCallback<B>>,
+    custom: Vec<IterationCallback<B>>,
+}
+
+impl<B: Board> Debug for IterationCallbacks<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} iteration callback(s)", self.custom.len())?;
+        if self.ugi.is_some() {
+            write!(f, " and UGI output")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct SearchState<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> {
     search_stack: Vec<E>,
@@ -740,6 +846,8 @@ pub struct SearchState<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> {
     last_msg_time: Instant,
     statistics: Statistics,
     aggregated_statistics: Statistics, // statistics aggregated over all searches of the current match
+    iteration_callbacks: IterationCallbacks<B>,
+    score_normalization: Option<ScoreT>,
 }
 
 impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> AbstractSearchState<B>
@@ -753,7 +861,7 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> AbstractSearchState<B>
         }
         if hard {
             self.custom.hard_forget_except_tt();
-            self.params.atomic.reset(false);
+            self.params.atomic.clear_results();
         } else {
             self.custom.new_search();
         }
@@ -793,6 +901,7 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> AbstractSearchState<B>
         if num_moves == 1 && parameters.limit.is_only_time_based() {
             parameters.limit.depth = Depth::new_unchecked(1);
         }
+        self.iteration_callbacks.ugi = parameters.thread_type.output_callback();
         self.params = parameters;
         // it's possible that a stop command has already been received and handled, which means the stop flag
         // can already be set
@@ -832,26 +941,14 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> AbstractSearchState<B>
             time: self.start_time().elapsed(),
             max_depth: self.depth(),
             depth: None,
+            nodes_limit: None,
+            movetime: None,
             pv_score_hash: hash,
         }
     }
 
     fn to_search_info(&self) -> SearchInfo<B> {
-        SearchInfo {
-            best_move_of_all_pvs: self.best_move(),
-            depth: self.depth(),
-            seldepth: self.seldepth(),
-            time: self.start_time().elapsed(),
-            nodes: NodesLimit::new(self.uci_nodes()).unwrap(),
-            pv_num: self.current_pv_num,
-            max_num_pvs: self.params.num_multi_pv,
-            pv: self.current_mpv_pv().into(),
-            score: self.current_pv_data().score,
-            hashfull: self.estimate_hashfull(),
-            pos: self.params.pos,
-            bound: self.current_pv_data().bound,
-            additional: Self::additional(),
-        }
+        self.search_info_of_line(self.current_pv_num)
     }
 
     fn aggregated_statistics(&self) -> &Statistics {
@@ -859,10 +956,36 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> AbstractSearchState<B>
     }
 
     fn send_search_info(&self) {
-        if let Some(mut output) = self.search_params().thread_type.output() {
-            let info = self.to_search_info();
-            output.write_search_info(info);
+        let callbacks = &self.iteration_callbacks;
+        if callbacks.ugi.is_none() && callbacks.custom.is_empty() {
+            return;
+        }
+        let info = self.to_search_info();
+        for callback in callbacks.ugi.iter().chain(&callbacks.custom) {
+            callback(&info);
+        }
+    }
+
+    fn notify_iteration_callbacks(&self) {
+        if self.iteration_callbacks.custom.is_empty() {
+            return;
         }
+        let info = self.to_search_info();
+        for callback in &self.iteration_callbacks.custom {
+            callback(&info);
+        }
+    }
+
+    fn add_iteration_callback(&mut self, callback: IterationCallback<B>) {
+        self.iteration_callbacks.custom.push(callback);
+    }
+
+    fn clear_iteration_callbacks(&mut self) {
+        self.iteration_callbacks.custom.clear();
+    }
+
+    fn set_score_normalization(&mut self, pawn_value: Option<ScoreT>) {
+        self.score_normalization = pawn_value;
     }
 
     fn write_internal_info(&self) -> Option<String> {
@@ -876,6 +999,12 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
         self.search_params().atomic.stop_flag()
     }
 
+    /// True while searching after `go ponder` but before `ponderhit`. Search limits don't apply while pondering.
+    fn pondering(&self) -> bool {
+        self.search_params().atomic.pondering()
+    }
+
+    /// All threads share the same TT, so this samples it only once, independent of the number of threads.
     fn estimate_hashfull(&self) -> usize {
         self.tt().estimate_hashfull::<B>()
     }
@@ -920,6 +1049,15 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
         self.search_params().atomic.nodes()
     }
 
+    /// The number of nodes searched by all threads combined. Only the main thread knows about the other threads,
+    /// so for auxiliary threads, this is the same as [`Self::uci_nodes`].
+    fn aggregated_nodes(&self) -> u64 {
+        match &self.search_params().thread_type {
+            Main(data) => data.aggregated_nodes(),
+            Auxiliary => self.uci_nodes(),
+        }
+    }
+
     fn tt(&self) -> &TT {
         &self.search_params().tt
     }
@@ -968,9 +1106,9 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
 
     /// this will block if
     /// a) this is a main thread (i.e., it actually outputs), and
-    /// b) the search is an infinite search from `go infinite` but not `ponder`, and
-    /// c) the search hasn't been cancelled yet. It will wait until the search has been cancelled.
-    /// Auxiliary threads and ponder searches both return instantly from this function, without printing anything.
+    /// b) the search is an infinite search from `go infinite`, or a ponder search that hasn't received a `ponderhit`, and
+    /// c) the search hasn't been cancelled yet. It will wait until the search has been cancelled (or the ponderhit).
+    /// Auxiliary threads return instantly from this function, without printing anything.
     /// If the search result has chosen a null move, this instead outputs a warning and a random legal move.
     fn send_search_res(&mut self, res: SearchResult<B>) {
         let search_params = self.search_params();
@@ -985,6 +1123,10 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
                 spin_loop();
             }
         }
+        // a ponder search may only send its best move after a `ponderhit` or `stop`
+        while self.search_params().atomic.pondering() && !self.search_params().atomic.stop_flag() {
+            spin_loop();
+        }
         let pos = self.search_params().pos;
         let mut output = data.output.lock().unwrap();
         if res.chosen_move == B::Move::default() {
@@ -992,6 +1134,7 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
             let chosen_move = pos.random_legal_move(&mut rng).unwrap_or_default();
             if chosen_move != B::Move::default() {
                 debug_assert!(pos.is_move_legal(chosen_move));
+                self.search_params().atomic.set_best_move(chosen_move);
                 output.write_message(Warning, "Not even a single iteration finished");
                 output.write_search_res(SearchResult::<B>::move_only(chosen_move, pos));
                 return;
@@ -1026,6 +1169,28 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
             excluded_moves: vec![],
             current_pv_num: 0,
             last_msg_time: start_time,
+            iteration_callbacks: IterationCallbacks::default(),
+            score_normalization: None,
+        }
+    }
+
+    fn search_info_of_line(&self, pv_num: usize) -> SearchInfo<B> {
+        let pv_data = &self.multi_pvs[pv_num];
+        SearchInfo {
+            best_move_of_all_pvs: self.best_move(),
+            depth: self.depth(),
+            seldepth: self.seldepth(),
+            time: self.start_time().elapsed(),
+            nodes: NodesLimit::new(self.aggregated_nodes()).unwrap(),
+            pv_num,
+            max_num_pvs: self.params.num_multi_pv,
+            pv: pv_data.pv.as_slice().into(),
+            score: pv_data.score,
+            hashfull: self.estimate_hashfull(),
+            pos: self.params.pos,
+            bound: pv_data.bound,
+            additional: Self::additional(),
+            score_normalization: self.score_normalization,
         }
     }
 
@@ -1054,8 +1219,12 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
         &mut self.params
     }
 
+    /// For a ponder search, this is the time of the `ponderhit`, so that time limits only start counting then.
     fn start_time(&self) -> Instant {
-        self.start_time
+        self.params
+            .atomic
+            .ponderhit_time()
+            .unwrap_or(self.start_time)
     }
 
     /// If the 'statistics' feature is enabled, this collects additional statistics.
@@ -1091,6 +1260,21 @@ impl<B: Board, E: SearchStackEntry<B>, C: CustomInfo<B>> SearchState<B, E, C> {
     }
 }
 
+/// Returns a result with a score of zero if the game has already been drawn, e.g. because of insufficient material,
+/// a repetition or the 50 move rule, so that it's possible to skip searching clearly drawn positions while analyzing.
+/// Since there is nothing to search, the chosen move is simply the first legal move.
+/// Returns `None` if the game isn't drawn or if there are no legal moves, which is left to the search to handle.
+pub fn forced_draw_result<B: Board>(
+    pos: B,
+    history: &ZobristHistory<B>,
+) -> Option<SearchResult<B>> {
+    if pos.player_result_no_movegen(history) != Some(Draw) {
+        return None;
+    }
+    let chosen_move = pos.legal_moves_slow().into_iter().next()?;
+    Some(SearchResult::new(chosen_move, Score(0), None, pos))
+}
+
 // TODO: Necessary?
 pub fn run_bench<B: Board>(
     engine: &mut dyn Engine<B>,
@@ -1125,6 +1309,12 @@ pub fn run_bench_with<B: Board>(
     if limit.depth != SearchLimit::infinite().depth {
         total.depth = Some(limit.depth);
     }
+    if limit.nodes != SearchLimit::infinite().nodes {
+        total.nodes_limit = Some(limit.nodes);
+    }
+    if !limit.is_infinite_fixed_time() {
+        total.movetime = Some(limit.fixed_time);
+    }
     total.pv_score_hash = hasher.finish();
     if cfg!(feature = "statistics") {
         eprintln!(
@@ -1160,6 +1350,27 @@ fn single_bench<B: Board>(
 mod tests {
     use super::*;
     use gears::general::moves::Move;
+    use std::sync::Mutex;
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn forced_draw_test() {
+        use gears::games::chess::moves::ChessMove;
+        use gears::games::chess::Chessboard;
+        use gears::general::board::Strictness::Strict;
+        let history = ZobristHistory::default();
+        let pos = Chessboard::from_fen("8/8/3k4/8/8/4K3/8/8 w - - 0 1", Strict).unwrap();
+        let res = forced_draw_result(pos, &history).unwrap();
+        assert_eq!(res.score, Some(Score(0)));
+        assert!(pos.is_move_legal(res.chosen_move));
+        let pos = Chessboard::from_fen("8/8/3k4/8/8/4K3/4Q3/8 w - - 99 80", Strict).unwrap();
+        assert!(forced_draw_result(pos, &history).is_none());
+        assert!(forced_draw_result(Chessboard::default(), &history).is_none());
+        let mov = ChessMove::from_text("Qh2", &pos).unwrap();
+        let new_pos = pos.make_move(mov).unwrap();
+        let res = forced_draw_result(new_pos, &history).unwrap();
+        assert_eq!(res.score, Some(Score(0)));
+    }
 
     // A testcase that any engine should pass
     pub fn generic_engine_test<B: Board, E: Engine<B>>(mut engine: E) {
@@ -1210,4 +1421,132 @@ mod tests {
             // assert_eq!(engine.search_state().internal_node_count(), 1_234); // TODO: Assert exact match
         }
     }
+
+    /// Tests that the iteration callback gets invoked once per completed iteration (and multipv line), with
+    /// non-decreasing depths. Fail highs and fail lows also get reported, but they don't have an exact bound.
+    pub fn iteration_callback_test<B: Board, E: Engine<B>>(mut engine: E, depth: usize) {
+        let infos = Arc::new(Mutex::new(vec![]));
+        let infos_clone = infos.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<B>| {
+                infos_clone
+                    .lock()
+                    .unwrap()
+                    .push((info.depth.get(), info.pv_num, info.bound))
+            }));
+        let completed = |infos: &[(usize, usize, Option<NodeType>)]| {
+            infos
+                .iter()
+                .filter(|(.., bound)| *bound == Some(NodeType::Exact))
+                .map(|(d, pv_num, _)| (*d, *pv_num))
+                .collect_vec()
+        };
+        for p in B::bench_positions() {
+            infos.lock().unwrap().clear();
+            _ = engine.search_with_new_tt(p, SearchLimit::depth_(depth));
+            let infos = infos.lock().unwrap();
+            assert!(infos.is_sorted_by_key(|(d, ..)| *d), "{p}");
+            let completed = completed(&infos);
+            assert!(completed.len() <= depth, "{p}");
+            for (i, (d, pv_num)) in completed.iter().enumerate() {
+                assert_eq!(*d, i + 1, "{p}");
+                assert_eq!(*pv_num, 0, "{p}");
+            }
+        }
+        infos.lock().unwrap().clear();
+        let params =
+            SearchParams::for_pos(B::default(), SearchLimit::depth_(depth)).additional_pvs(1);
+        _ = engine.search(params);
+        let expected = (1..=depth).flat_map(|d| [(d, 0), (d, 1)]).collect_vec();
+        assert_eq!(completed(&infos.lock().unwrap()), expected);
+
+        engine.search_state_mut_dyn().clear_iteration_callbacks();
+        infos.lock().unwrap().clear();
+        _ = engine.search_with_new_tt(B::default(), SearchLimit::depth_(depth));
+        assert!(infos.lock().unwrap().is_empty());
+    }
+
+    /// Tests that multipv searches report one line per distinct root move and that the first line matches the
+    /// search result. If `exact` is set, each line's score must also be the score of a search restricted to its
+    /// root move, which only holds for engines that don't prune.
+    pub fn multi_pv_test<B: Board, E: Engine<B>>(mut engine: E, depth: usize, exact: bool) {
+        let infos = Arc::new(Mutex::new(vec![]));
+        let infos_clone = infos.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<B>| {
+                // `B::Move` doesn't have to be `Send`, so store the moves as text
+                let mov = info.pv.first().map(|mov| mov.to_string());
+                if info.bound == Some(NodeType::Exact) {
+                    infos_clone.lock().unwrap().push((
+                        info.depth.get(),
+                        info.pv_num,
+                        mov,
+                        info.score,
+                    ))
+                }
+            }));
+        let depths = Arc::new(Mutex::new(vec![]));
+        let depths_clone = depths.clone();
+        engine.on_new_depth(Box::new(move |info: &SearchInfo<B>| {
+            depths_clone
+                .lock()
+                .unwrap()
+                .push((info.depth.get(), info.pv_num))
+        }));
+        let num_pvs = 3;
+        for p in B::bench_positions() {
+            if p.legal_moves_slow().num_moves() < num_pvs {
+                continue;
+            }
+            infos.lock().unwrap().clear();
+            depths.lock().unwrap().clear();
+            let params = SearchParams::new_unshared(
+                p,
+                SearchLimit::depth_(depth),
+                ZobristHistory::default(),
+                TT::default(),
+            )
+            .additional_pvs(num_pvs - 1);
+            let res = engine.search(params);
+            // don't hold the lock while searching, the callback needs it
+            let lines = infos
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(d, ..)| *d == depth)
+                .map(|(_, pv_num, mov, score)| (*pv_num, mov.clone().unwrap(), *score))
+                .collect_vec();
+            assert_eq!(lines.len(), num_pvs, "{p}");
+            // `on_new_depth` only reports the last line of each depth
+            assert_eq!(
+                *depths.lock().unwrap(),
+                (1..=depth).map(|d| (d, num_pvs - 1)).collect_vec(),
+                "{p}"
+            );
+            assert_eq!(lines[0].1, res.chosen_move.to_string(), "{p}");
+            assert_eq!(Some(lines[0].2), res.score, "{p}");
+            assert!(lines.iter().map(|(_, mov, _)| mov).all_unique(), "{p}");
+            for (i, (pv_num, mov, score)) in lines.iter().enumerate() {
+                assert_eq!(*pv_num, i, "{p}");
+                if !exact {
+                    continue;
+                }
+                if i > 0 {
+                    assert!(*score <= lines[i - 1].2, "{p}");
+                }
+                let mov = B::Move::from_compact_text(mov, &p).unwrap();
+                let params = SearchParams::new_unshared(
+                    p,
+                    SearchLimit::depth_(depth),
+                    ZobristHistory::default(),
+                    TT::default(),
+                )
+                .restrict_moves(vec![mov]);
+                assert_eq!(engine.search(params).score, Some(*score), "{p}");
+            }
+        }
+        engine.search_state_mut_dyn().clear_iteration_callbacks();
+    }
 }
@@ -1,5 +1,4 @@
 use std::cmp::min;
-use std::mem::take;
 use std::time::{Duration, Instant};
 
 use crate::eval::chess::lite::LiTEval;
@@ -13,12 +12,13 @@ use crate::search::tt::TTEntry;
 use crate::search::*;
 use derive_more::{Deref, DerefMut, Index, IndexMut};
 use gears::arrayvec::ArrayVec;
+use gears::games::chess::kpk::{init_kpk_bitbase, kpk_result};
 use gears::games::chess::moves::{ChessMove, ChessMoveFlags};
 use gears::games::chess::pieces::ChessPieceType::Pawn;
 use gears::games::chess::see::SeeScore;
 use gears::games::chess::squares::ChessSquare;
-use gears::games::chess::{ChessColor, Chessboard, MAX_CHESS_MOVES_IN_POS};
-use gears::games::{n_fold_repetition, BoardHistory, ZobristHash, ZobristHistory};
+use gears::games::chess::{ChessColor, ChessSettings, Chessboard, MAX_CHESS_MOVES_IN_POS};
+use gears::games::{BoardHistory, ZobristHash};
 use gears::general::bitboards::RawBitboard;
 use gears::general::common::Description::NoDescription;
 use gears::general::common::{
@@ -36,9 +36,9 @@ use gears::score::{
 use gears::search::NodeType::*;
 use gears::search::*;
 use gears::ugi::EngineOptionName::*;
-use gears::ugi::EngineOptionType::Check;
-use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType, UgiCheck};
-use gears::PlayerResult::{Lose, Win};
+use gears::ugi::EngineOptionType::{Check, Spin};
+use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType, UgiCheck, UgiSpin};
+use gears::PlayerResult::{Draw, Lose, Win};
 use itertools::Itertools;
 
 /// The maximum value of the `depth` parameter, i.e. the maximum number of Iterative Deepening iterations.
@@ -46,6 +46,18 @@ const DEPTH_SOFT_LIMIT: Depth = Depth::new_unchecked(225);
 /// The maximum value of the `ply` parameter, i.e. the maximum depth (in plies) before qsearch is reached
 const DEPTH_HARD_LIMIT: Depth = Depth::new_unchecked(255);
 
+/// Aspiration windows with a larger radius than this get replaced by a full window.
+const AW_FULL_WINDOW_RADIUS: ScoreT = 1000;
+
+const MAX_AW_WIDENING_FACTOR: ScoreT = 10;
+
+/// Added to the static eval of positions that the KPK bitbase says are won. Large enough to outweigh any eval difference,
+/// but still far away from mate scores, which the bitbase can't provide.
+const KPK_WIN_BONUS: ScoreT = 10_000;
+
+/// Large enough for any reasonable contempt, but small enough that draw scores can't be confused with mate scores.
+const MAX_CONTEMPT: ScoreT = 1000;
+
 /// Qsearch can't go more than 30 plies deep, so this prevents out of bounds accesses
 const SEARCH_STACK_LEN: usize = DEPTH_HARD_LIMIT.get() + 30;
 
@@ -106,6 +118,9 @@ impl Default for CaptHist {
 /// Used for Countermove History (CMH, 1 ply ago) and Follow-up Move History (FMH, 2 plies ago).
 /// Unlike the main quiet history heuristic, this in indexed by the previous piece, previous target square,
 /// current piece, current target square, and color.
+/// At ply `n`, the previous move is `search_stack[n - 1].last_tried_move()` for the CMH, which is the opponent's
+/// move that led to the current position, and `search_stack[n - 2].last_tried_move()` for the FMH, which is our
+/// own previous move. Updating and scoring must use the same convention.
 #[derive(Debug, Clone, Deref, DerefMut, Index, IndexMut)]
 struct ContHist(Vec<i32>); // Can't store this on the stack because it's too large.
 
@@ -142,7 +157,6 @@ pub struct CapsCustomInfo {
     /// our previous move instead of the opponent's previous move, i.e. the move 2 plies ago instead of 1 ply ago.
     follow_up_move_hist: ContHist,
     capt_hist: CaptHist,
-    original_board_hist: ZobristHistory<Chessboard>,
     nmp_disabled: [bool; 2],
     depth_hard_limit: usize,
 }
@@ -155,8 +169,8 @@ impl CapsCustomInfo {
 
 impl CustomInfo<Chessboard> for CapsCustomInfo {
     fn new_search(&mut self) {
-        debug_assert_eq!(self.nmp_disabled[0], false);
-        debug_assert_eq!(self.nmp_disabled[1], false);
+        debug_assert!(!self.nmp_disabled[0]);
+        debug_assert!(!self.nmp_disabled[1]);
         // don't update history values, malus and gravity already take care of that
     }
 
@@ -263,6 +277,16 @@ type DefaultEval = LiTEval;
 pub struct Caps {
     state: CapsState,
     eval: Box<dyn Eval<Chessboard>>,
+    /// Development option: If set, `negamax` and `qsearch` clamp their return value to the `[alpha, beta]` window
+    /// (fail-hard) instead of returning the best score found (fail-soft, the default).
+    /// TT entries always store the unclamped score, which is a valid bound in both modes, so switching this mid-game is fine.
+    fail_hard: bool,
+    /// How much worse than an equal position a draw is for the engine, see [`Self::draw_score`].
+    contempt: ScoreT,
+    /// The radius of the first aspiration window, which gets used after the first iteration, see [`Self::aspiration`].
+    aw_initial_radius: ScoreT,
+    /// How much the aspiration window radius gets multiplied by after a fail high or fail low.
+    aw_widening_factor: ScoreT,
 }
 
 impl Default for Caps {
@@ -302,9 +326,14 @@ impl Engine<Chessboard> for Caps {
     type CustomInfo = CapsCustomInfo;
 
     fn with_eval(eval: Box<dyn Eval<Chessboard>>) -> Self {
+        init_kpk_bitbase();
         Self {
             state: SearchState::new(Depth::new_unchecked(SEARCH_STACK_LEN)),
             eval,
+            fail_hard: false,
+            contempt: 0,
+            aw_initial_radius: DEFAULT_AW_INITIAL_RADIUS,
+            aw_widening_factor: cc::aw_widening_factor(),
         }
     }
 
@@ -334,12 +363,46 @@ impl Engine<Chessboard> for Caps {
 
     fn engine_info(&self) -> EngineInfo {
         let mut options = vec![EngineOption {
-            name: Other("UCI_Chess960".to_string()),
+            name: UCIChess960,
             value: Check(UgiCheck {
-                val: true,
-                default: Some(true),
+                val: ChessSettings::chess960_notation(),
+                default: Some(false),
             }),
         }];
+        options.push(EngineOption {
+            name: Other("FailHard".to_string()),
+            value: Check(UgiCheck {
+                val: self.fail_hard,
+                default: Some(false),
+            }),
+        });
+        options.push(EngineOption {
+            name: Contempt,
+            value: Spin(UgiSpin {
+                val: self.contempt as i64,
+                default: Some(0),
+                min: Some(-MAX_CONTEMPT as i64),
+                max: Some(MAX_CONTEMPT as i64),
+            }),
+        });
+        options.push(EngineOption {
+            name: AspirationWindow,
+            value: Spin(UgiSpin {
+                val: self.aw_initial_radius as i64,
+                default: Some(DEFAULT_AW_INITIAL_RADIUS as i64),
+                min: Some(1),
+                max: Some(AW_FULL_WINDOW_RADIUS as i64),
+            }),
+        });
+        options.push(EngineOption {
+            name: AspirationWidening,
+            value: Spin(UgiSpin {
+                val: self.aw_widening_factor as i64,
+                default: Some(cc::aw_widening_factor() as i64),
+                min: Some(1),
+                max: Some(MAX_AW_WIDENING_FACTOR as i64),
+            }),
+        });
         options.append(&mut cc::ugi_options());
         EngineInfo::new(
             self,
@@ -353,7 +416,10 @@ impl Engine<Chessboard> for Caps {
     }
 
     fn time_up(&self, tc: TimeControl, fixed_time: Duration, start_time: Instant) -> bool {
-        debug_assert!(self.state.uci_nodes() % DEFAULT_CHECK_TIME_INTERVAL == 0);
+        debug_assert!(self
+            .state
+            .uci_nodes()
+            .is_multiple_of(DEFAULT_CHECK_TIME_INTERVAL));
         let elapsed = start_time.elapsed();
         // divide by 4 unless moves to go is very small, but don't divide by 1 (or zero) to avoid timeouts
         // TODO: Compute at the start of the search instead of every time:
@@ -374,13 +440,61 @@ impl Engine<Chessboard> for Caps {
         value: String,
     ) -> Res<()> {
         let name = option.name().to_string();
+        if option == Contempt {
+            let Spin(spin) = old_value else {
+                unreachable!()
+            };
+            let value: ScoreT = parse_int_from_str(&value, "contempt")?;
+            if !(-MAX_CONTEMPT..=MAX_CONTEMPT).contains(&value) {
+                bail!("Contempt must be between -{MAX_CONTEMPT} and {MAX_CONTEMPT}, not {value}");
+            }
+            spin.val = value as i64;
+            self.contempt = value;
+            return Ok(());
+        }
+        if option == AspirationWindow {
+            let Spin(spin) = old_value else {
+                unreachable!()
+            };
+            let value: ScoreT = parse_int_from_str(&value, &name)?;
+            if !(1..=AW_FULL_WINDOW_RADIUS).contains(&value) {
+                bail!("{name} must be between 1 and {AW_FULL_WINDOW_RADIUS}, not {value}");
+            }
+            spin.val = value as i64;
+            self.aw_initial_radius = value;
+            return Ok(());
+        }
+        if option == AspirationWidening {
+            let Spin(spin) = old_value else {
+                unreachable!()
+            };
+            let value: ScoreT = parse_int_from_str(&value, &name)?;
+            if !(1..=MAX_AW_WIDENING_FACTOR).contains(&value) {
+                bail!("{name} must be between 1 and {MAX_AW_WIDENING_FACTOR}, not {value}");
+            }
+            spin.val = value as i64;
+            self.aw_widening_factor = value;
+            return Ok(());
+        }
+        if option == UCIChess960 {
+            let Check(check) = old_value else {
+                unreachable!()
+            };
+            let value = parse_bool_from_str(&value, "UCI_Chess960")?;
+            check.val = value;
+            // This changes the notation for the entire process, which is fine because each UCI engine runs in its
+            // own process. It also means that there's no point in storing it per engine.
+            ChessSettings::set_chess960_notation(value);
+            return Ok(());
+        }
         if let Other(name) = &option {
-            if name.eq_ignore_ascii_case("uci_chess960") {
+            if name.eq_ignore_ascii_case("failhard") {
                 let Check(check) = old_value else {
                     unreachable!()
                 };
-                let value = parse_bool_from_str(&value, "UCI_Chess960")?;
+                let value = parse_bool_from_str(&value, "FailHard")?;
                 check.val = value;
+                self.fail_hard = value;
                 return Ok(());
             }
             if let Ok(val) = parse_int_from_str(&value, "spsa option value") {
@@ -409,6 +523,10 @@ impl Engine<Chessboard> for Caps {
         self.eval = eval;
     }
 
+    fn eval_mut(&mut self) -> Option<&mut dyn Eval<Chessboard>> {
+        Some(self.eval.as_mut())
+    }
+
     fn do_search(&mut self) -> SearchResult<Chessboard> {
         let mut limit = self.state.params.limit;
         let pos = self.state.params.pos;
@@ -441,10 +559,6 @@ impl Engine<Chessboard> for Caps {
             soft = soft_limit.as_millis(),
             ignored = self.state.excluded_moves.len(),
         ));
-        // Use 3fold repetition detection for positions before and including the root node and 2fold for positions during search.
-        self.state.custom.original_board_hist = take(&mut self.state.search_params_mut().history);
-        self.state.custom.original_board_hist.push(&pos);
-
         self.iterative_deepening(pos, soft_limit)
     }
 }
@@ -465,10 +579,16 @@ impl Caps {
         soft_limit: Duration,
     ) -> SearchResult<Chessboard> {
         let max_depth = DEPTH_SOFT_LIMIT.min(self.limit().depth).isize();
+        if max_depth == 0 {
+            return self.depth_zero_search(pos);
+        }
         let multi_pv = self.state.multi_pv();
         let mut soft_limit_scale = 1.0;
 
         self.state.multi_pvs.resize(multi_pv, PVData::default());
+        for pv_data in &mut self.state.multi_pvs {
+            pv_data.radius = Score(self.aw_initial_radius);
+        }
         let mut chosen_at_depth =
             EagerNonAllocMoveList::<Chessboard, { DEPTH_SOFT_LIMIT.get() }>::default();
 
@@ -528,6 +648,40 @@ impl Caps {
         self.state.search_result()
     }
 
+    /// `go depth 0` doesn't search at all; some GUIs use it to query the static eval of a position.
+    /// The reported score is the static eval of `pos`, and the chosen move is the one that leaves the opponent
+    /// with the worst static eval. If there are no legal moves, this returns the null move like a normal search would.
+    fn depth_zero_search(&mut self, pos: Chessboard) -> SearchResult<Chessboard> {
+        let score = self.eval.eval(&pos, 0);
+        self.state.atomic().set_depth(0);
+        self.state.atomic().count_node();
+        let mut best = None;
+        for mov in pos.legal_moves_slow() {
+            if self.state.excluded_moves.contains(&mov) {
+                continue;
+            }
+            let child = pos.make_move(mov).unwrap();
+            let child_score = -self.eval.eval(&child, 1);
+            if best.is_none_or(|(_, best_score)| child_score > best_score) {
+                best = Some((mov, child_score));
+            }
+        }
+        let Some((chosen_move, _)) = best else {
+            return self.state.search_result();
+        };
+        let atomic = &self.state.params.atomic;
+        atomic.set_best_move(chosen_move);
+        atomic.set_ponder_move(None);
+        atomic.set_score(score);
+        self.state.current_pv_num = 0;
+        let pv_data = self.state.current_pv_data_mut();
+        pv_data.pv.reset_to_move(chosen_move);
+        pv_data.score = score;
+        pv_data.bound = Some(Exact);
+        self.state.send_search_info();
+        self.state.search_result()
+    }
+
     /// Aspiration Windows (AW): Assume that the score will be close to the score from the previous iteration
     /// of Iterative Deepening, so use alpha, beta bounds around that score to prune more aggressively.
     /// This means that it's possible for the root to fail low (or high), which is always something to consider:
@@ -589,7 +743,7 @@ impl Caps {
             self.state.multi_pvs[self.state.current_pv_num].score = pv_score;
             // adding ` && node_type != FailLow` gains elo, which is weird because this only prevents incomplete search iterations that have
             // already changed the PV from affecting the chosen move.
-            if pv.len() > 0 && node_type != FailLow {
+            if !pv.is_empty() && node_type != FailLow {
                 if self.state.current_pv_num == 0 {
                     let chosen_move = pv.get(0).unwrap();
                     let ponder_move = pv.get(1);
@@ -618,17 +772,19 @@ impl Caps {
             }
             if cfg!(debug_assertions) {
                 if pos.player_result_slow(&self.state.params.history).is_some() {
-                    assert_eq!(pv.len(), 0);
+                    // the root still gets searched, but all child nodes return immediately
+                    assert!(pv.len() <= 1, "{pos} {0}", pv.len());
                 } else {
                     match node_type {
                         FailHigh => debug_assert_eq!(pv.len(), 1, "{pos} {node_type}"),
                         Exact => debug_assert!(
                             // currently, it's possible to reduce the PV through IIR when the TT entry of a PV node gets overwritten,
-                            // but that should be relatively rare. In the future, a better replacement policy might make this actually sound
+                            // which can happen at more than one node of the PV. In the future, a better replacement policy might make this actually sound
                             self.state.multi_pv() > 1
-                                || pv.len() + pv.len() / 4
+                                || pv.len() + depth as usize / 4
                                     >= self.state.custom.depth_hard_limit.min(depth as usize)
-                                || pv_score.is_won_lost_or_draw_score(),
+                                || pv_score.is_won_or_lost()
+                                || self.pv_ends_in_known_result(pos, pv_score),
                             "{depth} {0} {pv_score} {1}",
                             pv.len(),
                             self.state.uci_nodes()
@@ -654,21 +810,92 @@ impl Caps {
                 let delta = pv_score.0.abs_diff(alpha.0);
                 let delta = delta.min(pv_score.0.abs_diff(beta.0));
                 let delta = delta.min(cc::aw_delta_max()) as i32;
-                window_radius.0 = SCORE_WON
-                    .0
-                    .min(window_radius.0 * cc::aw_widening_factor() + delta);
+                // always grow the window, even if the widening factor is 1 and the score is exactly on the bound
+                let widened =
+                    (window_radius.0 * self.aw_widening_factor + delta).max(window_radius.0 + 1);
+                window_radius.0 = SCORE_WON.0.min(widened);
+            }
+            // Only widen the window in the direction of the failure; the other bound is still valid.
+            // Once the window has become very large, there's no point in continuing to use it, so use a full window.
+            match node_type {
+                _ if window_radius.0 >= AW_FULL_WINDOW_RADIUS => {
+                    *alpha = MIN_ALPHA;
+                    *beta = MAX_BETA;
+                }
+                FailLow => *alpha = (pv_score - *window_radius).max(MIN_ALPHA),
+                FailHigh => *beta = (pv_score + *window_radius).min(MAX_BETA),
+                Exact => {
+                    *alpha = (pv_score - *window_radius).max(MIN_ALPHA);
+                    *beta = (pv_score + *window_radius).min(MAX_BETA);
+                }
             }
-            *alpha = (pv_score - *window_radius).max(MIN_ALPHA);
-            *beta = (pv_score + *window_radius).min(MAX_BETA);
 
             if node_type == Exact {
                 return true;
             } else if asp_start_time.elapsed().as_millis() >= 1000 {
                 self.state.send_search_info();
+            } else {
+                self.state.notify_iteration_callbacks();
             }
         }
     }
 
+    /// Positions with nothing but the kings and a single pawn have an exact result in the KPK bitbase.
+    /// Won positions still use the static eval so that the search makes progress towards promoting the pawn.
+    /// The result is stored in the TT as an exact score.
+    fn kpk_score(&mut self, pos: Chessboard, ply: usize, depth: isize) -> Option<Score> {
+        let score = match kpk_result(&pos)? {
+            Win => self.eval(pos, ply) + Score(KPK_WIN_BONUS),
+            Lose => self.eval(pos, ply) - Score(KPK_WIN_BONUS),
+            Draw => self.draw_score(ply),
+        };
+        let tt_entry: TTEntry<Chessboard> = TTEntry::new(
+            pos.zobrist_hash(),
+            score,
+            ChessMove::default(),
+            depth,
+            Exact,
+        );
+        self.state.tt_mut().store(tt_entry, ply);
+        Some(score)
+    }
+
+    /// The score of a drawn position `ply` plies away from the root, from the perspective of the side to move there.
+    /// With a positive contempt, the engine considers draws to be worse than an equal position, and its opponent
+    /// considers them to be better by the same amount. This is never applied at the root, and never to mate scores.
+    fn draw_score(&self, ply: usize) -> Score {
+        if ply == 0 {
+            Score(0)
+        } else if ply.is_multiple_of(2) {
+            Score(-self.contempt)
+        } else {
+            Score(self.contempt)
+        }
+    }
+
+    /// Whether the root PV ends in a position that returns its score without being searched, i.e. a draw or a KPK
+    /// position, so that the PV can be shorter than the depth. Draws are recognized by comparing against the draw score
+    /// at the ply where the PV ends, and KPK positions by looking at the last position of the PV.
+    /// Only used for debug assertions.
+    fn pv_ends_in_known_result(&self, pos: Chessboard, pv_score: Score) -> bool {
+        let pv = &self.state.search_stack[0].pv;
+        let ply = pv.len();
+        // `draw_score` is from the perspective of the side to move at `ply`, `pv_score` from the root's perspective
+        let draw_score = if ply.is_multiple_of(2) {
+            self.draw_score(ply)
+        } else {
+            -self.draw_score(ply)
+        };
+        if pv_score == draw_score {
+            return true;
+        }
+        let mut pos = pos;
+        for i in 0..ply {
+            pos = pos.make_move(pv.get(i).unwrap()).unwrap();
+        }
+        kpk_result(&pos).is_some()
+    }
+
     /// Recursive search function, the most important part of the engine. If the computed score of the current position
     /// lies within the open interval `(alpha, beta)`, return the score. Otherwise, the returned score might not be exact,
     /// but could be closer to the window than the true score. On top of that, there are **many** additional techniques
@@ -676,8 +903,33 @@ impl Caps {
     /// that a re-search with the same depth returns a different score. Because of PVS, `alpha` is `beta - 1` in almost
     /// all nodes, and most nodes either get cut off before reaching the move loop or produce a beta cutoff after
     /// the first move.
-    #[allow(clippy::too_many_lines)]
+    /// If the `FailHard` option is set, the returned score is clamped to `[alpha, beta]`.
     fn negamax(
+        &mut self,
+        pos: Chessboard,
+        ply: usize,
+        depth: isize,
+        alpha: Score,
+        beta: Score,
+        expected_node_type: NodeType,
+    ) -> Option<Score> {
+        let score = self.negamax_impl(pos, ply, depth, alpha, beta, expected_node_type)?;
+        Some(self.apply_bounds(score, alpha, beta))
+    }
+
+    /// In fail-hard mode, a score outside the window is replaced by the bound it failed against.
+    /// A fail low score `<= alpha` is an upper bound, and `alpha` is a weaker (but still correct) upper bound;
+    /// the same holds for fail high scores and `beta`. So this never invalidates the result of a node.
+    fn apply_bounds(&self, score: Score, alpha: Score, beta: Score) -> Score {
+        if self.fail_hard {
+            score.clamp(alpha, beta)
+        } else {
+            score
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn negamax_impl(
         &mut self,
         pos: Chessboard,
         ply: usize,
@@ -712,19 +964,22 @@ impl Caps {
             }
         }
 
-        let ply_100_ctr = pos.halfmove_repetition_clock();
+        // The history contains the positions of the game, followed by the positions of the current search tree.
+        // Inside the search tree, the first repetition already counts as a draw because the opponent could simply
+        // repeat the position again. A position from before the root must have occurred three times, as in the game.
         if !root
             && (pos.is_50mr_draw()
                 || pos.has_insufficient_material()
-                || n_fold_repetition(2, &self.state.params.history, &pos, ply_100_ctr)
-                || n_fold_repetition(
-                    3,
-                    &self.state.custom.original_board_hist,
-                    &pos,
-                    ply_100_ctr.saturating_sub(ply),
-                ))
+                || pos.is_draw_by_repetition(&self.state.params.history, ply))
         {
-            return Some(Score(0));
+            return Some(self.draw_score(ply));
+        }
+        // The root isn't probed because the search still has to find a move that keeps the result. Instead, each
+        // root move gets probed at ply 1, so the root score is the bitbase result as well.
+        if !root {
+            if let Some(score) = self.kpk_score(pos, ply, depth) {
+                return Some(score);
+            }
         }
         let in_check = pos.is_in_check();
         // Check extensions. Increase the depth by 1 if in check.
@@ -905,7 +1160,10 @@ impl Caps {
             }
         }
 
-        if self.state.uci_nodes() % DEFAULT_CHECK_TIME_INTERVAL == 0
+        if self
+            .state
+            .uci_nodes()
+            .is_multiple_of(DEFAULT_CHECK_TIME_INTERVAL)
             && self.state.last_msg_time.elapsed().as_millis() >= 1000
         {
             let score = self.qsearch(pos, alpha, beta, ply);
@@ -1105,7 +1363,9 @@ impl Caps {
                     unreachable!()
                 };
                 current.pv.extend(best_move, &child.pv);
-                if depth > 1 && score < beta && !score.is_won_lost_or_draw_score() {
+                // Draws return without storing a TT entry, but KPK positions store an exact entry
+                let child_is_draw = score == -self.draw_score(ply + 1);
+                if depth > 1 && score < beta && !score.is_won_or_lost() && !child_is_draw {
                     debug_assert_eq!(
                         self.state
                             .tt()
@@ -1139,7 +1399,10 @@ impl Caps {
         );
 
         if self.state.search_stack[ply].tried_moves.is_empty() {
-            return Some(game_result_to_score(pos.no_moves_result_if(in_check), ply));
+            return Some(match pos.no_moves_result_if(in_check) {
+                Draw => self.draw_score(ply),
+                res => game_result_to_score(res, ply),
+            });
         }
 
         let tt_entry: TTEntry<Chessboard> = TTEntry::new(
@@ -1245,8 +1508,22 @@ impl Caps {
     }
 
     /// Search only "tactical" moves to quieten down the position before calling eval
-    fn qsearch(&mut self, pos: Chessboard, mut alpha: Score, beta: Score, ply: usize) -> Score {
+    fn qsearch(&mut self, pos: Chessboard, alpha: Score, beta: Score, ply: usize) -> Score {
+        let score = self.qsearch_impl(pos, alpha, beta, ply);
+        self.apply_bounds(score, alpha, beta)
+    }
+
+    fn qsearch_impl(
+        &mut self,
+        pos: Chessboard,
+        mut alpha: Score,
+        beta: Score,
+        ply: usize,
+    ) -> Score {
         self.state.statistics.count_node_started(Qsearch);
+        if let Some(score) = self.kpk_score(pos, ply, 0) {
+            return score;
+        }
         // updating seldepth only in qsearch meaningfully increased performance and was even measurable in a [0, 10] SPRT.
         self.state.atomic().update_seldepth(ply);
         // The stand pat check. Since we're not looking at all moves, it's very likely that there's a move we didn't
@@ -1442,6 +1719,7 @@ impl MoveScorer<Chessboard, Caps> for CapsMoveScorer {
 #[cfg(test)]
 mod tests {
     use gears::games::chess::Chessboard;
+    use gears::games::Color;
     use gears::general::board::Strictness::{Relaxed, Strict};
     #[cfg(not(debug_assertions))]
     use gears::general::moves::ExtendedFormat::Standard;
@@ -1451,7 +1729,8 @@ mod tests {
     use crate::eval::chess::material_only::MaterialOnlyEval;
     use crate::eval::chess::piston::PistonEval;
     use crate::eval::rand_eval::RandEval;
-    use crate::search::tests::generic_engine_test;
+    use crate::search::tests::{generic_engine_test, iteration_callback_test, multi_pv_test};
+    use std::sync::{Arc, Mutex};
 
     use super::*;
 
@@ -1542,6 +1821,287 @@ mod tests {
         depth_1_nodes_test(Caps::for_eval::<KingGambot>(), tt.clone());
     }
 
+    #[test]
+    fn iteration_callback_caps_test() {
+        iteration_callback_test(Caps::for_eval::<LiTEval>(), 6);
+        // a tiny aspiration window makes the root fail high and low, which must also be reported
+        let mut engine = Caps::for_eval::<LiTEval>();
+        engine
+            .set_option(
+                AspirationWindow,
+                &mut Spin(UgiSpin::default()),
+                "1".to_string(),
+            )
+            .unwrap();
+        let bounds = Arc::new(Mutex::new(vec![]));
+        let bounds_clone = bounds.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<Chessboard>| {
+                bounds_clone.lock().unwrap().push(info.bound)
+            }));
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        _ = engine.search_with_new_tt(pos, SearchLimit::depth_(8));
+        let bounds = bounds.lock().unwrap();
+        assert!(bounds.contains(&Some(FailHigh)) || bounds.contains(&Some(FailLow)));
+        assert_eq!(bounds.iter().filter(|b| **b == Some(Exact)).count(), 8);
+    }
+
+    #[test]
+    fn on_new_depth_test() {
+        let best_moves = Arc::new(Mutex::new(vec![]));
+        let best_moves_clone = best_moves.clone();
+        let mut engine = Caps::for_eval::<LiTEval>();
+        engine.on_new_depth(Box::new(move |info: &SearchInfo<Chessboard>| {
+            assert!(info.nodes.get() > 0);
+            assert!(info.seldepth >= info.depth);
+            assert_eq!(info.pv.first(), Some(&info.best_move_of_all_pvs));
+            best_moves_clone.lock().unwrap().push((
+                info.depth.get(),
+                info.best_move_of_all_pvs,
+                info.score,
+            ))
+        }));
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        let res = engine.search_with_new_tt(pos, SearchLimit::depth_(5));
+        let best_moves = best_moves.lock().unwrap();
+        assert_eq!(
+            best_moves.iter().map(|(depth, ..)| *depth).collect_vec(),
+            (1..=5).collect_vec()
+        );
+        let (_, last_move, last_score) = best_moves.last().unwrap();
+        assert_eq!(*last_move, res.chosen_move);
+        assert_eq!(Some(*last_score), res.score);
+        assert!(best_moves.iter().all(|(_, mov, _)| pos.is_move_legal(*mov)));
+    }
+
+    #[test]
+    fn on_new_depth_with_fail_highs_test() {
+        // a tiny aspiration window makes the root fail high and low, but each depth must still be reported once
+        let mut engine = Caps::for_eval::<LiTEval>();
+        engine
+            .set_option(
+                AspirationWindow,
+                &mut Spin(UgiSpin::default()),
+                "1".to_string(),
+            )
+            .unwrap();
+        let bounds = Arc::new(Mutex::new(vec![]));
+        let bounds_clone = bounds.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<Chessboard>| {
+                bounds_clone.lock().unwrap().push(info.bound)
+            }));
+        let depths = Arc::new(Mutex::new(vec![]));
+        let depths_clone = depths.clone();
+        engine.on_new_depth(Box::new(move |info: &SearchInfo<Chessboard>| {
+            depths_clone.lock().unwrap().push(info.depth.get())
+        }));
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        _ = engine.search_with_new_tt(pos, SearchLimit::depth_(8));
+        let bounds = bounds.lock().unwrap();
+        assert!(bounds.iter().any(|b| *b != Some(Exact)));
+        assert_eq!(*depths.lock().unwrap(), (1..=8).collect_vec());
+    }
+
+    #[test]
+    fn multi_pv_caps_test() {
+        multi_pv_test(Caps::for_eval::<LiTEval>(), 5, false);
+    }
+
+    #[test]
+    fn kqk_mate_distance_test() {
+        // Mate in 3, so that there are only a few searches. Because of pruning, this still needs depth 14 to find the mate.
+        let mut pos = Chessboard::from_fen("8/8/8/3K4/8/8/3Q4/k7 w - - 0 1", Strict).unwrap();
+        // the mate distance in moves must be bounded and decrease after each move of the winning side
+        let mut prev_mate = 4;
+        while !pos.legal_moves_slow().is_empty() {
+            let mut engine = Caps::for_eval::<LiTEval>();
+            let res = engine.search_with_new_tt(pos, SearchLimit::depth_(14));
+            let score = res.score.unwrap();
+            if pos.active_player().is_first() {
+                let mate = score.moves_until_game_won().unwrap();
+                assert!(mate > 0 && mate < prev_mate, "{mate} {prev_mate} {pos}");
+                assert!(score.mate_description().unwrap().starts_with("mate in"));
+                prev_mate = mate;
+            } else if let Some(mate) = score.moves_until_game_won() {
+                assert!(mate < 0 && -mate < prev_mate, "{mate} {prev_mate} {pos}");
+            }
+            pos = pos.make_move(res.chosen_move).unwrap();
+        }
+        assert_eq!(prev_mate, 1);
+        assert!(pos.is_in_check(), "{pos}");
+    }
+
+    #[test]
+    fn kpk_bitbase_test() {
+        let mut engine = Caps::for_eval::<LiTEval>();
+        // black to move loses because white gets the opposition
+        let pos = Chessboard::from_fen("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1", Strict).unwrap();
+        let score = engine
+            .search_with_new_tt(pos, SearchLimit::depth_(2))
+            .score
+            .unwrap();
+        assert!(score <= Score(-KPK_WIN_BONUS / 2), "{score}");
+        // with white to move, it's a draw
+        let pos = pos.flip_side_to_move().unwrap();
+        let score = engine
+            .search_with_new_tt(pos, SearchLimit::depth_(2))
+            .score
+            .unwrap();
+        assert_eq!(score, Score(0));
+        // most moves throw away the win
+        let pos = Chessboard::from_fen("3k4/8/4K3/4P3/8/8/8/8 w - - 0 1", Strict).unwrap();
+        let res = engine.search_with_new_tt(pos, SearchLimit::depth_(4));
+        assert!(res.score.unwrap() >= Score(KPK_WIN_BONUS / 2));
+        let new_pos = pos.make_move(res.chosen_move).unwrap();
+        assert_eq!(kpk_result(&new_pos), Some(Lose), "{}", res.chosen_move);
+    }
+
+    #[test]
+    fn depth_zero_test() {
+        let infos = Arc::new(Mutex::new(vec![]));
+        let infos_clone = infos.clone();
+        let mut engine = Caps::for_eval::<LiTEval>();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<Chessboard>| {
+                infos_clone.lock().unwrap().push(info.to_string())
+            }));
+        for pos in Chessboard::bench_positions() {
+            infos.lock().unwrap().clear();
+            let eval = engine.static_eval(pos, 0);
+            let res = engine.search_with_new_tt(pos, SearchLimit::depth_(0));
+            if pos.legal_moves_slow().is_empty() {
+                assert!(res.chosen_move.is_null());
+                continue;
+            }
+            assert!(pos.is_move_legal(res.chosen_move), "{pos}");
+            assert_eq!(res.score, Some(eval));
+            let infos = infos.lock().unwrap();
+            assert_eq!(infos.len(), 1);
+            let expected = format!("info depth 0 seldepth 0 multipv 1 score cp {} ", eval.0);
+            assert!(infos[0].starts_with(&expected), "{0}", infos[0]);
+            assert!(infos[0].ends_with(&format!(" pv {}", res.chosen_move)));
+        }
+    }
+
+    #[test]
+    fn fail_hard_test() {
+        // (fen, best move, plies until mate)
+        let positions = [
+            ("4k3/8/4K3/8/8/8/8/6R1 w - - 0 1", "g1g8", Some(1)),
+            ("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "d1d8", Some(1)),
+            (
+                "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+                "h5f7",
+                Some(1),
+            ),
+            ("k7/8/1K6/8/8/8/8/7R w - - 0 1", "h1h8", Some(1)),
+            ("7k/8/5KQ1/8/8/8/8/8 w - - 0 1", "g6g7", Some(1)),
+            ("3k4/8/8/8/8/8/3q4/3QK3 w - - 0 1", "d1d2", None),
+        ];
+        for (fen, best_move, mate) in positions {
+            let pos = Chessboard::from_fen(fen, Strict).unwrap();
+            let mut results = vec![];
+            for fail_hard in [false, true] {
+                let mut engine = Caps::for_eval::<LiTEval>();
+                engine.fail_hard = fail_hard;
+                let res = engine.search_with_new_tt(pos, SearchLimit::depth_(5));
+                results.push(res);
+            }
+            let [soft, hard] = results.as_slice() else {
+                unreachable!()
+            };
+            assert_eq!(soft.chosen_move, hard.chosen_move, "{fen}");
+            let best_move = ChessMove::from_compact_text(best_move, &pos).unwrap();
+            assert_eq!(soft.chosen_move, best_move, "{fen}");
+            for res in results {
+                let score = res.score.unwrap();
+                assert_eq!(score.plies_until_game_won(), mate, "{fen} {score}");
+                if mate.is_none() {
+                    assert!(score >= Score(500), "{fen} {score}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn contempt_test() {
+        // Trading rooks leads to a dead draw, but so does everything else
+        let pos = Chessboard::from_fen("3r4/4k3/8/8/8/8/8/3RK3 w - - 0 1", Strict).unwrap();
+        let trade = ChessMove::from_compact_text("d1d8", &pos).unwrap();
+        let mut results = vec![];
+        for contempt in [-100, 0, 100] {
+            let mut engine = Caps::for_eval::<LiTEval>();
+            engine
+                .set_option(
+                    Contempt,
+                    &mut Spin(UgiSpin::default()),
+                    contempt.to_string(),
+                )
+                .unwrap();
+            assert_eq!(engine.contempt, contempt);
+            results.push(engine.search_with_new_tt(pos, SearchLimit::depth_(7)));
+        }
+        let [seek_draw, neutral, avoid_draw] = results.as_slice() else {
+            unreachable!()
+        };
+        assert_eq!(seek_draw.chosen_move, trade);
+        assert_eq!(seek_draw.score, Some(Score(100)));
+        assert_eq!(neutral.chosen_move, trade);
+        assert_eq!(neutral.score, Some(Score(0)));
+        assert_ne!(avoid_draw.chosen_move, trade);
+        let score = avoid_draw.score.unwrap();
+        assert!(score > Score(-100) && !score.is_won_or_lost(), "{score}");
+        let mut engine = Caps::default();
+        assert!(engine
+            .set_option(
+                Contempt,
+                &mut Spin(UgiSpin::default()),
+                "100000".to_string()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn aspiration_window_option_test() {
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        let limit = SearchLimit::depth_(6);
+        let expected = Caps::for_eval::<LiTEval>().search_with_new_tt(pos, limit);
+        for (radius, factor) in [(1, 1), (1, 2), (3, 10), (1000, 1)] {
+            let mut engine = Caps::for_eval::<LiTEval>();
+            engine
+                .set_option(
+                    AspirationWindow,
+                    &mut Spin(UgiSpin::default()),
+                    radius.to_string(),
+                )
+                .unwrap();
+            engine
+                .set_option(
+                    AspirationWidening,
+                    &mut Spin(UgiSpin::default()),
+                    factor.to_string(),
+                )
+                .unwrap();
+            assert_eq!(engine.aw_initial_radius, radius);
+            assert_eq!(engine.aw_widening_factor, factor);
+            let res = engine.search_with_new_tt(pos, limit);
+            assert_eq!(res.chosen_move, expected.chosen_move, "{radius} {factor}");
+            assert!(!res.score.unwrap().is_won_or_lost());
+        }
+        // the options are per engine, so changing them didn't affect the default of new engines
+        assert_eq!(Caps::default().aw_widening_factor, cc::aw_widening_factor());
+        let mut engine = Caps::default();
+        for (option, value) in [(AspirationWindow, "0"), (AspirationWidening, "11")] {
+            assert!(engine
+                .set_option(option, &mut Spin(UgiSpin::default()), value.to_string())
+                .is_err());
+        }
+    }
+
     // TODO: Eventually, make sure that GAPS also passed this
     fn depth_1_nodes_test(mut engine: Caps, tt: TT) {
         for pos in Chessboard::bench_positions() {
@@ -1683,4 +2243,40 @@ mod tests {
             assert_eq!(res.chosen_move.to_string(), best_move);
         }
     }
+
+    #[test]
+    fn continuation_hist_test() {
+        let mut engine = Caps::default();
+        let startpos = Chessboard::default();
+        let e4 = ChessMove::from_text("e2e4", &startpos).unwrap();
+        let pos = startpos.make_move(e4).unwrap();
+        let e5 = ChessMove::from_text("e7e5", &pos).unwrap();
+        let pos = pos.make_move(e5).unwrap();
+        let nf3 = ChessMove::from_text("g1f3", &pos).unwrap();
+        let a3 = ChessMove::from_text("a2a3", &pos).unwrap();
+        let stack = &mut engine.state.search_stack;
+        stack[0].tried_moves = [e4].into_iter().collect();
+        stack[1].tried_moves = [e5].into_iter().collect();
+        // `a3` was searched first and didn't cause a cutoff, then `nf3` did
+        stack[2].tried_moves = [a3, nf3].into_iter().collect();
+        let color = pos.active_player();
+        engine.update_histories_and_killer(&pos, nf3, 5, 2, color);
+
+        let custom = &engine.state.custom;
+        assert!(custom.history[nf3.from_to_square()] > 0);
+        assert!(custom.history[a3.from_to_square()] < 0);
+        assert_eq!(engine.state.search_stack[2].killer, nf3);
+        // the countermove history uses the opponent's last move, the follow-up history our own last move
+        assert!(custom.countermove_hist.score(nf3, e5, color) > 0);
+        assert!(custom.countermove_hist.score(a3, e5, color) < 0);
+        assert_eq!(custom.countermove_hist.score(nf3, e4, color), 0);
+        assert!(custom.follow_up_move_hist.score(nf3, e4, color) > 0);
+        assert!(custom.follow_up_move_hist.score(a3, e4, color) < 0);
+        assert_eq!(custom.follow_up_move_hist.score(nf3, e5, color), 0);
+
+        // ignore the killer, only compare the history scores
+        engine.state.search_stack[2].killer = ChessMove::default();
+        let scorer = CapsMoveScorer { board: pos, ply: 2 };
+        assert!(scorer.score_move(nf3, &engine.state) > scorer.score_move(a3, &engine.state));
+    }
 }
@@ -1,3 +1,5 @@
+use itertools::Itertools;
+use std::cmp::Reverse;
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 
@@ -7,8 +9,9 @@ use gears::general::board::Board;
 use crate::eval::rand_eval::RandEval;
 use crate::eval::Eval;
 use crate::search::statistics::SearchType::MainSearch;
+use crate::search::tt::TTEntry;
 use crate::search::{
-    AbstractSearchState, EmptySearchStackEntry, Engine, EngineInfo, NoCustomInfo, SearchState,
+    AbstractSearchState, Engine, EngineInfo, NoCustomInfo, Pv, SearchStackEntry, SearchState,
     SearchStateFor,
 };
 use gears::general::common::StaticallyNamedEntity;
@@ -18,11 +21,29 @@ use gears::search::{Depth, NodesLimit, SearchResult, TimeControl};
 
 const MAX_DEPTH: Depth = Depth::new_unchecked(100);
 
+const SEARCH_STACK_LEN: usize = MAX_DEPTH.get() + 1;
+
 type DefaultEval = RandEval;
 
+#[derive(Debug, Default, Clone)]
+pub struct GapsSearchStackEntry<B: Board> {
+    pv: Pv<B, SEARCH_STACK_LEN>,
+    last_played_move: Option<B::Move>,
+}
+
+impl<B: Board> SearchStackEntry<B> for GapsSearchStackEntry<B> {
+    fn pv(&self) -> Option<&[B::Move]> {
+        Some(self.pv.as_slice())
+    }
+
+    fn last_played_move(&self) -> Option<B::Move> {
+        self.last_played_move
+    }
+}
+
 #[derive(Debug)]
 pub struct Gaps<B: Board> {
-    state: SearchState<B, EmptySearchStackEntry, NoCustomInfo>,
+    state: SearchState<B, GapsSearchStackEntry<B>, NoCustomInfo>,
     eval: Box<dyn Eval<B>>,
 }
 
@@ -53,7 +74,7 @@ impl<B: Board> StaticallyNamedEntity for Gaps<B> {
 }
 
 impl<B: Board> Engine<B> for Gaps<B> {
-    type SearchStackEntry = EmptySearchStackEntry;
+    type SearchStackEntry = GapsSearchStackEntry<B>;
     type CustomInfo = NoCustomInfo;
 
     fn with_eval(eval: Box<dyn Eval<B>>) -> Self {
@@ -108,6 +129,10 @@ impl<B: Board> Engine<B> for Gaps<B> {
         self.eval = eval;
     }
 
+    fn eval_mut(&mut self) -> Option<&mut dyn Eval<B>> {
+        Some(self.eval.as_mut())
+    }
+
     fn do_search(&mut self) -> SearchResult<B> {
         let mut limit = self.state.params.limit;
         let max_depth = MAX_DEPTH.min(limit.depth).isize();
@@ -137,8 +162,10 @@ impl<B: Board> Engine<B> for Gaps<B> {
                 // only set now so that incomplete iterations are discarded
                 let best_mpv_move = self.state.current_pv_data().pv.get(0).unwrap_or_default();
                 if pv_num == 0 {
+                    let ponder_move = self.state.current_pv_data().pv.get(1);
                     self.state.atomic().set_score(iteration_score);
                     self.state.atomic().set_best_move(best_mpv_move);
+                    self.state.atomic().set_ponder_move(ponder_move);
                 }
                 self.search_state().send_search_info();
                 self.state.excluded_moves.push(best_mpv_move);
@@ -149,11 +176,7 @@ impl<B: Board> Engine<B> for Gaps<B> {
             self.state.statistics.next_id_iteration();
         }
 
-        SearchResult::move_and_score(
-            self.state.atomic().best_move(),
-            self.state.atomic().score(),
-            pos,
-        )
+        self.state.search_result()
     }
 }
 
@@ -167,22 +190,50 @@ impl<B: Board> Gaps<B> {
         mut alpha: Score,
         beta: Score,
     ) -> Score {
+        let original_alpha = alpha;
         debug_assert!(alpha < beta);
         debug_assert!(ply <= MAX_DEPTH.get() * 2);
         debug_assert!(depth <= MAX_DEPTH.isize());
         self.state.statistics.count_node_started(MainSearch);
+        self.state.search_stack[ply].pv.clear();
 
+        // Inside the search tree, the first repetition already counts as a draw. The game-level rule used by
+        // `player_result_no_movegen` requires a threefold repetition, which only matters for repetitions of earlier positions.
+        if ply > 0 && pos.is_draw_by_repetition(&self.state.params.history, ply) {
+            return Score(0);
+        }
         if let Some(res) = pos.player_result_no_movegen(&self.state.params.history) {
             return game_result_to_score(res, ply);
         }
+        // Check extensions. This works for all games because `is_in_check` is always false for games without checks.
+        // Keeping `ply + depth` below the max depth ensures that the search stack is large enough.
+        let depth = if pos.is_in_check() && ply + (depth.max(0) as usize) < MAX_DEPTH.get() {
+            depth + 1
+        } else {
+            depth
+        };
         if depth <= 0 {
             return self.eval.eval(&pos, ply);
         }
 
         let mut best_score = SCORE_LOST;
+        let mut best_move = None;
         let mut num_children = 0;
 
-        for mov in pos.pseudolegal_moves() {
+        // the TT is only used for move ordering
+        let tt_move = self
+            .state
+            .tt()
+            .load::<B>(pos.zobrist_hash(), ply)
+            .and_then(|entry| entry.mov.check_pseudolegal(&pos));
+        // `sorted_by_key` is stable, so moves with the same score keep their movegen order
+        let moves = pos
+            .pseudolegal_moves()
+            .into_iter()
+            .filter(|&mov| Some(mov) != tt_move)
+            .sorted_by_key(|&mov| Reverse(pos.move_order_score(mov)));
+
+        for mov in tt_move.into_iter().chain(moves) {
             let new_pos = pos.make_move(mov);
             if new_pos.is_none() {
                 continue; // illegal pseudolegal move
@@ -196,6 +247,7 @@ impl<B: Board> Gaps<B> {
             self.state.atomic().count_node();
 
             self.state.params.history.push(&pos);
+            self.state.search_stack[ply].last_played_move = Some(mov);
 
             let score = -self.negamax(new_pos.unwrap(), ply + 1, depth - 1, -beta, -alpha);
 
@@ -210,10 +262,16 @@ impl<B: Board> Gaps<B> {
             }
             alpha = alpha.max(score);
             best_score = score;
+            best_move = Some(mov);
+            let (current, child) = self.state.search_stack.split_at_mut(ply + 1);
+            current[ply].pv.extend(mov, &child[0].pv);
             if ply == 0 {
                 // don't set score here because it's set in `do_search`, which handles situations like the position
                 // being checkmate
-                self.state.current_pv_data_mut().pv.reset_to_move(mov);
+                let pv = &self.state.search_stack[0].pv;
+                self.state.multi_pvs[self.state.current_pv_num]
+                    .pv
+                    .assign_from(pv);
             }
             if score < beta {
                 continue;
@@ -222,7 +280,7 @@ impl<B: Board> Gaps<B> {
         }
         let node_type = if best_score >= beta {
             FailHigh
-        } else if best_score <= alpha {
+        } else if best_score <= original_alpha {
             FailLow
         } else {
             Exact
@@ -230,6 +288,10 @@ impl<B: Board> Gaps<B> {
         self.state
             .statistics
             .count_complete_node(MainSearch, node_type, depth, ply, num_children);
+        if let Some(mov) = best_move {
+            let entry = TTEntry::<B>::new(pos.zobrist_hash(), best_score, mov, depth, node_type);
+            self.state.tt_mut().store(entry, ply);
+        }
         if num_children == 0 {
             game_result_to_score(pos.no_moves_result(), ply)
         } else {
@@ -243,10 +305,16 @@ mod tests {
     use super::*;
     use crate::eval::chess::lite::LiTEval;
     use crate::eval::mnk::base::BasicMnkEval;
-    use crate::search::tests::generic_engine_test;
+    use crate::search::tests::{generic_engine_test, iteration_callback_test, multi_pv_test};
+    use crate::search::tt::TT;
     use gears::games::ataxx::AtaxxBoard;
     use gears::games::chess::Chessboard;
     use gears::games::mnk::MNKBoard;
+    use gears::general::board::Strictness::Strict;
+    use gears::general::moves::Move;
+    use gears::search::{SearchInfo, SearchLimit};
+    use itertools::Itertools;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn generic_test() {
@@ -254,4 +322,74 @@ mod tests {
         generic_engine_test::<MNKBoard, Gaps<MNKBoard>>(Gaps::for_eval::<BasicMnkEval>());
         generic_engine_test::<AtaxxBoard, Gaps<AtaxxBoard>>(Gaps::for_eval::<RandEval>());
     }
+
+    fn pv_test_for<B: Board>(mut engine: Gaps<B>) {
+        let infos = Arc::new(Mutex::new(vec![]));
+        let infos_clone = infos.clone();
+        engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<B>| {
+                // `B::Move` doesn't have to be `Send`, so store the moves as text
+                let pv = info.pv.iter().map(|mov| mov.to_string()).collect_vec();
+                infos_clone.lock().unwrap().push((info.depth.get(), pv))
+            }));
+        let pos = B::default();
+        let res = engine.search_with_new_tt(pos, SearchLimit::depth_(3));
+        let infos = infos.lock().unwrap();
+        assert_eq!(infos.len(), 3);
+        for (i, (depth, pv)) in infos.iter().enumerate() {
+            assert_eq!(*depth, i + 1);
+            assert_eq!(pv.len(), i + 1);
+            let mut p = pos;
+            for mov in pv {
+                p = p
+                    .make_move(B::Move::from_compact_text(mov, &p).unwrap())
+                    .unwrap();
+            }
+        }
+        let pv = &infos.last().unwrap().1;
+        assert_eq!(pv[0], res.chosen_move.to_string());
+        assert_eq!(pv.get(1).cloned(), res.ponder_move.map(|m| m.to_string()));
+    }
+
+    #[test]
+    fn pv_test() {
+        pv_test_for(Gaps::<Chessboard>::for_eval::<LiTEval>());
+        pv_test_for(Gaps::<MNKBoard>::for_eval::<BasicMnkEval>());
+        pv_test_for(Gaps::<AtaxxBoard>::for_eval::<RandEval>());
+    }
+
+    #[test]
+    fn check_extension_test() {
+        // Without check extensions, a depth 1 search wouldn't see that the opponent has no legal moves after Ra8#
+        let pos = Chessboard::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", Strict).unwrap();
+        let res = Gaps::for_eval::<LiTEval>().search_with_new_tt(pos, SearchLimit::depth_(1));
+        assert_eq!(res.chosen_move.to_string(), "a1a8");
+        assert_eq!(res.score.unwrap().moves_until_game_won(), Some(1));
+    }
+
+    #[test]
+    fn iteration_callback_gaps_test() {
+        iteration_callback_test::<Chessboard, Gaps<Chessboard>>(Gaps::for_eval::<LiTEval>(), 3);
+        iteration_callback_test::<MNKBoard, Gaps<MNKBoard>>(Gaps::for_eval::<BasicMnkEval>(), 3);
+    }
+
+    #[test]
+    fn multi_pv_gaps_test() {
+        multi_pv_test::<Chessboard, Gaps<Chessboard>>(Gaps::for_eval::<LiTEval>(), 2, true);
+        multi_pv_test::<MNKBoard, Gaps<MNKBoard>>(Gaps::for_eval::<BasicMnkEval>(), 3, true);
+        multi_pv_test::<AtaxxBoard, Gaps<AtaxxBoard>>(Gaps::for_eval::<RandEval>(), 2, true);
+    }
+
+    #[test]
+    fn shared_tt_test() {
+        // the TT is passed in with the search params, so it's shared between threads and the `Hash` option applies
+        let tt = TT::new_with_mb(1).unwrap();
+        let pos = Chessboard::default();
+        let mut engine = Gaps::for_eval::<LiTEval>();
+        _ = engine.search_with_tt(pos, SearchLimit::depth_(3), tt.clone());
+        assert!(tt.estimate_hashfull::<Chessboard>() > 0);
+        let entry = tt.load::<Chessboard>(pos.zobrist_hash(), 0).unwrap();
+        assert!(entry.mov.check_pseudolegal(&pos).is_some());
+    }
 }
@@ -1,9 +1,8 @@
-use itertools::Itertools;
 use std::fmt::{Debug, Display, Formatter};
 use std::time::{Duration, Instant};
 
 use gears::general::board::Board;
-use rand::{rng, Rng, RngCore, SeedableRng};
+use rand::{rng, RngCore};
 
 use crate::eval::rand_eval::RandEval;
 use crate::eval::Eval;
@@ -11,17 +10,21 @@ use crate::search::{
     AbstractSearchState, EmptySearchStackEntry, Engine, EngineInfo, NoCustomInfo, SearchState,
     SearchStateFor,
 };
-use gears::general::common::StaticallyNamedEntity;
+use gears::general::common::anyhow::bail;
+use gears::general::common::{
+    parse_int_from_str, NamedEntity, Res, SeedRng, StaticallyNamedEntity,
+};
 use gears::score::Score;
 use gears::search::NodeType::Exact;
 use gears::search::{Depth, NodesLimit, SearchInfo, SearchResult, TimeControl};
-
-pub trait SeedRng: Rng + SeedableRng {}
-
-impl<T> SeedRng for T where T: Rng + SeedableRng {}
+use gears::ugi::EngineOptionName::Other;
+use gears::ugi::EngineOptionType::Spin;
+use gears::ugi::{EngineOption, EngineOptionName, EngineOptionType, UgiSpin};
 
 pub struct RandomMover<B: Board, R: SeedRng> {
     pub rng: R,
+    /// Set by [`Self::with_seed`] or the `Seed` option. If this is `None`, the rng has been seeded randomly.
+    seed: Option<u64>,
     state: SearchState<B, EmptySearchStackEntry, NoCustomInfo>,
 }
 
@@ -34,10 +37,28 @@ impl<B: Board, R: SeedRng> Debug for RandomMover<B, R> {
 impl<B: Board, R: SeedRng> Default for RandomMover<B, R> {
     fn default() -> Self {
         Self {
-            rng: R::seed_from_u64(rng().next_u64()),
+            seed: None,
+            ..Self::with_seed(rng().next_u64())
+        }
+    }
+}
+
+impl<B: Board, R: SeedRng> RandomMover<B, R> {
+    /// With a deterministic RNG like [`XorShiftRng`], the same seed always results in the same moves.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: R::seed_from_u64(seed),
+            seed: Some(seed),
             state: SearchState::new(Depth::new_unchecked(1)),
         }
     }
+
+    /// Reseeds the rng, which is only done when explicitly requested through the `Seed` option or `ucinewgame`,
+    /// so that the moves of a game only depend on the seed. A seed of `None` seeds the rng randomly.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed.unwrap_or_else(|| rng().next_u64()));
+    }
 }
 
 // impl<B: Board, R: SeedableRng + Rng + Clone + Send + 'static> RandomMover<B, R> {
@@ -84,12 +105,20 @@ impl<B: Board, R: SeedRng + Clone + Send + 'static> Engine<B> for RandomMover<B,
     fn engine_info(&self) -> EngineInfo {
         let mut res = EngineInfo::new(
             self,
-            &RandEval::default(),
+            &<RandEval>::default(),
             "0.1.0",
             Depth::new_unchecked(1),
             NodesLimit::new(1).unwrap(),
             Some(1),
-            vec![],
+            vec![EngineOption {
+                name: Other("Seed".to_string()),
+                value: Spin(UgiSpin {
+                    val: self.seed.unwrap_or(0).min(i64::MAX as u64) as i64,
+                    default: Some(0),
+                    min: Some(0),
+                    max: Some(i64::MAX),
+                }),
+            }],
         );
         res.eval = None;
         res
@@ -99,19 +128,62 @@ impl<B: Board, R: SeedRng + Clone + Send + 'static> Engine<B> for RandomMover<B,
         // do nothing
     }
 
+    /// A seed of 0 means that the rng gets seeded randomly.
+    fn set_option(
+        &mut self,
+        option: EngineOptionName,
+        old_value: &mut EngineOptionType,
+        value: String,
+    ) -> Res<()> {
+        if let Other(name) = &option {
+            if name.eq_ignore_ascii_case("seed") {
+                let Spin(spin) = old_value else {
+                    unreachable!()
+                };
+                let seed: u64 = parse_int_from_str(value.trim(), "seed")?;
+                spin.val = seed.min(i64::MAX as u64) as i64;
+                self.set_seed((seed != 0).then_some(seed));
+                return Ok(());
+            }
+        }
+        bail!(
+            "The searcher '{0}' doesn't support setting the option '{option}' to '{value}'",
+            self.long_name()
+        )
+    }
+
+    /// Reseeds the rng if a seed has been set, so that each new game with the same seed results in the same moves.
+    fn forget(&mut self) {
+        self.state.forget(true);
+        if self.seed.is_some() {
+            self.set_seed(self.seed);
+        }
+    }
+
     fn do_search(&mut self) -> SearchResult<B> {
         self.state.statistics.next_id_iteration();
         let pos = self.state.params.pos;
 
-        let moves = pos
-            .legal_moves_slow()
-            .into_iter()
-            .filter(|m| self.state.excluded_moves.contains(m))
-            .collect_vec();
-        let best_move = if moves.is_empty() {
+        let excluded = &self.state.excluded_moves;
+        let is_candidate = |mov: &B::Move| !excluded.contains(mov);
+        // visit the legal moves twice instead of collecting them, which avoids allocating a list of moves
+        let mut num_candidates = 0;
+        pos.for_each_legal_move(|mov| num_candidates += usize::from(is_candidate(&mov)));
+        let best_move = if num_candidates == 0 {
             pos.random_legal_move(&mut self.rng).unwrap_or_default()
         } else {
-            moves[self.rng.random_range(0..moves.len())]
+            let chosen = self.rng.random_range(0..num_candidates);
+            let mut idx = 0;
+            let mut best_move = B::Move::default();
+            pos.for_each_legal_move(|mov| {
+                if is_candidate(&mov) {
+                    if idx == chosen {
+                        best_move = mov;
+                    }
+                    idx += 1;
+                }
+            });
+            best_move
         };
         self.state.atomic().set_best_move(best_move);
         SearchResult::move_only(best_move, pos)
@@ -148,6 +220,7 @@ impl<B: Board, R: SeedRng + Clone + Send + 'static> Engine<B> for RandomMover<B,
             pos: self.search_state().params.pos,
             bound: Some(Exact),
             additional: None,
+            score_normalization: None,
         }
     }
 
@@ -163,3 +236,67 @@ impl<B: Board, R: SeedRng + Clone + Send + 'static> Engine<B> for RandomMover<B,
         &mut self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gears::games::chess::Chessboard;
+    use gears::general::common::XorShiftRng;
+    use gears::search::SearchLimit;
+
+    fn play_random_game(seed: u64, plies: usize) -> Vec<String> {
+        let mut engine = RandomMover::<Chessboard, XorShiftRng>::with_seed(seed);
+        let mut pos = Chessboard::default();
+        let mut moves = vec![];
+        for _ in 0..plies {
+            let mov = engine
+                .search_with_new_tt(pos, SearchLimit::depth_(1))
+                .chosen_move;
+            moves.push(mov.to_string());
+            pos = pos.make_move(mov).unwrap();
+        }
+        moves
+    }
+
+    #[test]
+    fn seed_option_test() {
+        let play = |engine: &mut RandomMover<Chessboard, XorShiftRng>| {
+            let mut pos = Chessboard::default();
+            let mut moves = vec![];
+            for _ in 0..10 {
+                let mov = engine
+                    .search_with_new_tt(pos, SearchLimit::depth_(1))
+                    .chosen_move;
+                moves.push(mov.to_string());
+                pos = pos.make_move(mov).unwrap();
+            }
+            moves
+        };
+        let mut engine = RandomMover::<Chessboard, XorShiftRng>::default();
+        let mut option = engine.engine_info().options[&Other("Seed".to_string())].clone();
+        assert_eq!(option.value_to_str(), "0");
+        engine
+            .set_option(Other("Seed".to_string()), &mut option, "42".to_string())
+            .unwrap();
+        assert_eq!(option.value_to_str(), "42");
+        let moves = play(&mut engine);
+        assert_eq!(moves, play_random_game(42, 10));
+        // searching again continues the game instead of reseeding
+        assert_ne!(play(&mut engine), moves);
+        // but starting a new game reseeds
+        engine.forget();
+        assert_eq!(play(&mut engine), moves);
+        assert!(engine
+            .set_option(Other("Seed".to_string()), &mut option, "-1".to_string())
+            .is_err());
+        assert_eq!(option.value_to_str(), "42");
+    }
+
+    #[test]
+    fn fixed_seed_test() {
+        let moves = play_random_game(42, 20);
+        assert_eq!(moves, play_random_game(42, 20));
+        assert_ne!(moves, play_random_game(43, 20));
+        assert_eq!(moves[..6], ["c2c3", "d7d5", "d2d4", "g7g5", "c3c4", "b7b5"]);
+    }
+}
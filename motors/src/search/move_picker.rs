@@ -1,8 +1,8 @@
 use crate::search::move_picker::MovePickerState::*;
 use crate::search::{Engine, MoveScore, MoveScorer, SearchStateFor};
-use gears::arrayvec::{ArrayVec, IntoIter};
+use gears::arrayvec::ArrayVec;
 use gears::general::board::Board;
-use gears::general::move_list::MoveList;
+use gears::general::move_list::MoveSink;
 use gears::general::moves::Move;
 use itertools::Itertools;
 
@@ -12,16 +12,6 @@ pub type ScoredMove<B: Board> = (B::Move, MoveScore);
 #[expect(type_alias_bounds)]
 type ScoredMoveList<B: Board, const MAX_LEN: usize> = ArrayVec<ScoredMove<B>, MAX_LEN>;
 
-struct UnscoredMoveIter<B: Board, const MAX_LEN: usize>(IntoIter<ScoredMove<B>, MAX_LEN>);
-
-impl<B: Board, const MAX_LEN: usize> Iterator for UnscoredMoveIter<B, MAX_LEN> {
-    type Item = B::Move;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|(mov, _score)| mov)
-    }
-}
-
 #[derive(Debug)]
 struct MoveListScorer<'a, B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer: MoveScorer<B, E>> {
     list: &'a mut ScoredMoveList<B, MAX_LEN>,
@@ -30,19 +20,8 @@ struct MoveListScorer<'a, B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer:
     excluded: B::Move,
 }
 
-impl<B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer: MoveScorer<B, E>> IntoIterator
+impl<B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer: MoveScorer<B, E>> MoveSink<B>
     for MoveListScorer<'_, B, E, MAX_LEN, Scorer>
-{
-    type Item = B::Move;
-    type IntoIter = UnscoredMoveIter<B, MAX_LEN>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        UnscoredMoveIter(self.list.take().into_iter())
-    }
-}
-
-impl<'a, B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer: MoveScorer<B, E>> MoveList<B>
-    for MoveListScorer<'a, B, E, MAX_LEN, Scorer>
 {
     fn add_move(&mut self, mov: B::Move) {
         if self.excluded != mov {
@@ -50,28 +29,6 @@ impl<'a, B: Board, E: Engine<B>, const MAX_LEN: usize, Scorer: MoveScorer<B, E>>
             self.list.push((mov, score));
         }
     }
-
-    fn num_moves(&self) -> usize {
-        self.list.len()
-    }
-
-    fn swap_remove_move(&mut self, idx: usize) -> B::Move {
-        self.list.swap_remove(idx).0
-    }
-
-    fn iter_moves(&self) -> impl Iterator<Item = &B::Move> {
-        self.list.iter().map(|(mov, _)| mov)
-    }
-
-    fn remove(&mut self, to_remove: B::Move) {
-        if let Some((idx, _)) = self.list.iter().find_position(|(mov, _)| *mov == to_remove) {
-            self.swap_remove_move(idx);
-        }
-    }
-
-    fn filter_moves<F: Fn(&mut B::Move) -> bool>(&mut self, predicate: F) {
-        self.list.retain(|(mov, _)| predicate(mov));
-    }
 }
 
 enum MovePickerState<B: Board, const MAX_LEN: usize> {
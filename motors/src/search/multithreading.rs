@@ -6,14 +6,14 @@ use crate::search::multithreading::SearchType::{Infinite, Normal, Ponder};
 use crate::search::tt::{TTEntry, TT};
 use crate::search::{
     AbstractEvalBuilder, AbstractSearchState, AbstractSearcherBuilder, Engine, EngineInfo,
-    SearchParams,
+    IterationCallback, SearchParams,
 };
 use colored::Colorize;
 use dyn_clone::clone_box;
 use gears::games::ZobristHistory;
 use gears::general::board::Board;
 use gears::general::common::anyhow::{anyhow, bail};
-use gears::general::common::{parse_int_from_str, Name, NamedEntity, Res};
+use gears::general::common::{parse_int_from_str, NamedEntity, Res};
 use gears::general::moves::Move;
 use gears::output::Message::*;
 use gears::score::{Score, NO_SCORE_YET};
@@ -22,6 +22,7 @@ use gears::ugi::EngineOptionName;
 use gears::ugi::EngineOptionName::{Hash, Threads};
 use portable_atomic::AtomicUsize;
 use std::hint::spin_loop;
+use std::iter::once;
 use std::marker::PhantomData;
 use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
 use std::sync::atomic::{AtomicBool, AtomicI32, AtomicIsize, AtomicU64};
@@ -40,6 +41,8 @@ pub enum EngineReceives<B: Board> {
     Search(SearchParams<B>),
     SetEval(Box<dyn Eval<B>>),
     Print(Arc<Mutex<EngineInfo>>),
+    /// Answers once all previously sent messages have been handled, see [`EngineWrapper::wait_until_ready`].
+    Ping(Sender<()>),
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -80,9 +83,15 @@ impl<B: Board> MainThreadData<B> {
         self.search_type = SearchType::new(ponder, limit);
         for data in &mut self.atomic_search_data {
             data.reset(true);
+            data.pondering.store(ponder, Relaxed);
         }
         Ok(())
     }
+
+    /// The sum of the nodes searched so far by all threads. Each thread only counts its own nodes.
+    pub fn aggregated_nodes(&self) -> u64 {
+        self.atomic_search_data.iter().map(|a| a.nodes()).sum()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -95,12 +104,26 @@ pub enum SearchThreadType<B: Board> {
 }
 
 impl<B: Board> SearchThreadType<B> {
-    pub fn output(&self) -> Option<MutexGuard<UgiOutput<B>>> {
+    pub fn output(&self) -> Option<MutexGuard<'_, UgiOutput<B>>> {
         match self {
             Main(MainThreadData { output, .. }) => Some(output.lock().unwrap()),
             Auxiliary => None,
         }
     }
+
+    /// An iteration callback that writes the [`SearchInfo`](gears::search::SearchInfo) to the UGI output, if this is
+    /// the main thread.
+    pub fn output_callback(&self) -> Option<IterationCallback<B>> {
+        match self {
+            Main(MainThreadData { output, .. }) => {
+                let output = output.clone();
+                Some(Box::new(move |info| {
+                    output.lock().unwrap().write_search_info(info.clone())
+                }))
+            }
+            Auxiliary => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -122,6 +145,10 @@ pub struct AtomicSearchState<B: Board> {
     // hasn't yet been stopped, this is set to false; the thread may still spin until it receives a stop.
     currently_searching: AtomicBool,
     pub suppress_best_move: AtomicBool,
+    // Set while the engine is pondering, which means that search limits are ignored. A `ponderhit` clears this flag
+    // and stores the current time, which the search then uses instead of its actual start time.
+    pondering: AtomicBool,
+    ponderhit_time: Mutex<Option<Instant>>,
     nodes: AtomicU64,
     depth: AtomicIsize,
     seldepth: AtomicUsize,
@@ -137,6 +164,8 @@ impl<B: Board> Default for AtomicSearchState<B> {
             should_stop: AtomicBool::new(false),
             currently_searching: AtomicBool::new(false),
             suppress_best_move: AtomicBool::new(false),
+            pondering: AtomicBool::new(false),
+            ponderhit_time: Mutex::new(None),
             nodes: AtomicU64::new(0),
             depth: AtomicIsize::new(0),
             seldepth: AtomicUsize::new(0),
@@ -149,18 +178,27 @@ impl<B: Board> Default for AtomicSearchState<B> {
 }
 
 impl<B: Board> AtomicSearchState<B> {
-    // called on 'ucinewgame' and on starting a new search
+    // called by the UGI thread when starting a new search
     pub fn reset(&self, starting_search: bool) {
         // all stores can be Relaxed because we're overwriting all members
+        self.clear_results();
+        self.set_searching(starting_search);
+        self.suppress_best_move.store(false, Relaxed);
+        self.pondering.store(false, Relaxed);
+        *self.ponderhit_time.lock().unwrap() = None;
+        self.should_stop.store(false, Relaxed);
+    }
+
+    /// Called by the search thread on 'ucinewgame'. Unlike [`Self::reset`], this doesn't touch the `stop` and
+    /// `searching` flags: The UGI thread sets those before sending a new search to the search thread, so a
+    /// 'ucinewgame' that is still queued could otherwise overwrite a `stop` that raced ahead of the search.
+    pub fn clear_results(&self) {
         self.set_score(NO_SCORE_YET);
         self.set_ponder_move(None);
         self.set_best_move(B::Move::default());
         self.seldepth.store(0, Relaxed); // don't use `update_seldepth` as that uses `fetch_max`.
         self.set_depth(0);
         self.nodes.store(0, Relaxed);
-        self.set_searching(starting_search);
-        self.suppress_best_move.store(false, Relaxed);
-        self.should_stop.store(false, Relaxed);
     }
 
     pub fn stop_flag(&self) -> bool {
@@ -178,6 +216,23 @@ impl<B: Board> AtomicSearchState<B> {
         self.currently_searching.store(val, Relaxed);
     }
 
+    /// True while the engine is pondering, i.e. between `go ponder` and `ponderhit` (or `stop`).
+    pub fn pondering(&self) -> bool {
+        self.pondering.load(Acquire)
+    }
+
+    /// The time at which the engine received a `ponderhit`, or `None` if the current search wasn't a ponder search
+    /// or if the engine is still pondering.
+    pub fn ponderhit_time(&self) -> Option<Instant> {
+        *self.ponderhit_time.lock().unwrap()
+    }
+
+    /// Turns the current ponder search into a normal search. Called by the UGI thread upon receiving `ponderhit`.
+    fn ponderhit(&self) {
+        *self.ponderhit_time.lock().unwrap() = Some(Instant::now());
+        self.pondering.store(false, Release);
+    }
+
     pub fn nodes(&self) -> u64 {
         self.nodes.load(Relaxed)
     }
@@ -275,11 +330,15 @@ impl<B: Board, E: Engine<B>> EngineThread<B, E> {
                 Threads => panic!("This should have already been handled by the engine owner"),
                 _ => {
                     let mut guard = info.lock().unwrap();
+                    let is_eval_option = guard.eval_options.contains(&name);
                     let Some(val) = guard.options.get_mut(&name) else {
                         bail!("The engine '{0}' doesn't provide the option '{1}', so it can't be set to value '{2}'",
                             guard.engine.short_name().bold(), name.to_string().red(), value.bold());
                     };
-                    self.engine.set_option(name, val, value)?
+                    match self.engine.eval_mut() {
+                        Some(eval) if is_eval_option => eval.set_option(&name, val, &value)?,
+                        _ => self.engine.set_option(name, val, value)?,
+                    }
                 }
             },
             Search(params) => {
@@ -296,6 +355,7 @@ impl<B: Board, E: Engine<B>> EngineThread<B, E> {
                 });
                 engine_info.lock().unwrap().internal_state_description = Some(info);
             }
+            Ping(sender) => sender.send(()).map_err(|err| anyhow!(err.to_string()))?,
         };
         Ok(false)
     }
@@ -335,8 +395,11 @@ pub struct EngineWrapper<B: Board> {
     searcher_builder: Box<dyn AbstractSearcherBuilder<B>>,
     eval_builder: Box<dyn AbstractEvalBuilder<B>>,
     main_thread_data: MainThreadData<B>,
-    // If we receive a `setoption name Hash` while searching, we only apply that to the next search
+    // Setting the `Hash` option stops the current search, so this is only different from the TT used by the search
+    // threads if `set_tt` has been called during a search.
     tt_for_next_search: TT,
+    // The size set through the `Hash` option, which can be larger than the actual size of the TT
+    hash_size_mb: usize,
     // It's possible to temporarily add or remove threads
     overwrite_num_threads: Option<usize>,
 }
@@ -364,6 +427,7 @@ impl<B: Board> EngineWrapper<B> {
         eval_builder: Box<dyn AbstractEvalBuilder<B>>,
     ) -> Self {
         let atomic = Arc::new(AtomicSearchState::default());
+        let hash_size_mb = tt.size_in_mb();
         let (main, info) = searcher_builder.build_in_new_thread(eval_builder.build());
         let main_thread_data = MainThreadData {
             atomic_search_data: vec![atomic],
@@ -378,6 +442,7 @@ impl<B: Board> EngineWrapper<B> {
             eval_builder,
             main_thread_data,
             tt_for_next_search: tt,
+            hash_size_mb,
             overwrite_num_threads: None,
         }
     }
@@ -463,6 +528,12 @@ impl<B: Board> EngineWrapper<B> {
         self.tt_for_next_search.clone()
     }
 
+    /// The value of the `Hash` option. Because the number of TT entries gets rounded down to a power of two,
+    /// this can be larger than `next_tt().size_in_mb()`.
+    pub fn hash_size_mb(&self) -> usize {
+        self.hash_size_mb
+    }
+
     pub fn resize_threads(&mut self, count: usize) {
         self.auxiliary.resize_with(count - 1, || {
             self.searcher_builder
@@ -488,10 +559,12 @@ impl<B: Board> EngineWrapper<B> {
             Ok(())
         } else if name == Hash {
             let value: usize = parse_int_from_str(&value, "hash size in mb")?;
-            let size = value * 1_000_000;
-            // first, give back the memory of the old TT to avoid spikes in memory usage
+            // The search threads hold a reference to the TT, so stop them before giving back the memory of the old TT
+            // to avoid spikes in memory usage. This doesn't suppress the `bestmove` of the aborted search.
+            self.send_stop(false);
             self.set_tt(TT::minimal());
-            self.set_tt(TT::new_with_bytes(size));
+            self.set_tt(TT::new_with_mb(value)?);
+            self.hash_size_mb = value;
             Ok(())
         } else {
             for aux in &mut self.auxiliary {
@@ -521,7 +594,7 @@ impl<B: Board> EngineWrapper<B> {
             aux.send(SetEval(clone_box(eval.as_ref())))
                 .map_err(|err| anyhow!(err.to_string()))?;
         }
-        self.get_engine_info().eval = Some(Name::new(eval.as_ref()));
+        self.get_engine_info().set_eval(eval.as_ref());
         self.main
             .send(SetEval(eval))
             .map_err(|err| anyhow!(err.to_string()))
@@ -557,6 +630,58 @@ impl<B: Board> EngineWrapper<B> {
         self.overwrite_num_threads = None;
     }
 
+    /// True if the engine is currently running a ponder search that hasn't received a `ponderhit` yet.
+    pub fn is_pondering(&self) -> bool {
+        let atomic = &self.main_thread_data.atomic_search_data[0];
+        atomic.currently_searching.load(Acquire) && atomic.pondering()
+    }
+
+    /// Turns the current ponder search into a normal search with the limit given to `go ponder`. The search keeps
+    /// everything it has computed so far, but its time limits only start counting now.
+    pub fn ponderhit(&mut self) -> Res<()> {
+        if !self.is_pondering() {
+            bail!("The engine received a 'ponderhit' command but wasn't pondering");
+        }
+        for atomic in &self.main_thread_data.atomic_search_data {
+            atomic.ponderhit();
+        }
+        Ok(())
+    }
+
+    /// Blocks until the main search thread has finished the current search, which includes sending the best move.
+    /// Returns immediately if no search is running.
+    pub fn wait_for_search(&self) {
+        let atomic = &self.main_thread_data.atomic_search_data[0];
+        if !atomic.currently_searching.load(Acquire) {
+            return;
+        }
+        // The main thread only handles messages between searches, so it answers once the search has finished
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        if self.main.send(Ping(sender)).is_ok() {
+            _ = receiver.recv();
+        }
+    }
+
+    /// Blocks until all search threads have handled all messages sent so far, such as options set with
+    /// [`Self::set_option`]. Returns immediately while searching, because the threads only handle messages between searches.
+    /// This is what allows answering `isready` with `readyok` during a search without stopping it:
+    /// The search threads never have to poll for input, the UGI thread answers directly.
+    pub fn wait_until_ready(&self) -> Res<()> {
+        if self.main_atomic_search_data().currently_searching() {
+            return Ok(());
+        }
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        for thread in self.auxiliary.iter().chain(once(&self.main)) {
+            thread
+                .send(Ping(sender.clone()))
+                .map_err(|err| anyhow!(err.to_string()))?;
+        }
+        for _ in 0..=self.auxiliary.len() {
+            receiver.recv().map_err(|err| anyhow!(err.to_string()))?;
+        }
+        Ok(())
+    }
+
     pub fn send_quit(&mut self) -> Res<()> {
         self.send_stop(false);
         for o in &mut self.auxiliary {
@@ -576,7 +701,7 @@ impl<B: Board> EngineWrapper<B> {
             .map_err(|err| anyhow!(err.to_string()))
     }
 
-    pub fn get_engine_info(&self) -> MutexGuard<EngineInfo> {
+    pub fn get_engine_info(&self) -> MutexGuard<'_, EngineInfo> {
         self.main_thread_data.engine_info.lock().unwrap()
     }
 
@@ -596,3 +721,69 @@ impl<B: Board> EngineWrapper<B> {
         self.main_thread_data.atomic_search_data[0].clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "caps")]
+    fn aggregated_nodes_test() {
+        use crate::search::chess::caps::Caps;
+        use gears::games::chess::Chessboard;
+        use gears::search::SearchInfo;
+        use std::thread::spawn;
+
+        let main_atomic = Arc::new(AtomicSearchState::<Chessboard>::default());
+        let aux_atomic = Arc::new(AtomicSearchState::<Chessboard>::default());
+        let mut main_engine = Caps::default();
+        let mut data = MainThreadData {
+            atomic_search_data: vec![main_atomic.clone(), aux_atomic.clone()],
+            output: Arc::new(Mutex::new(UgiOutput::default())),
+            engine_info: Arc::new(Mutex::new(main_engine.engine_info())),
+            search_type: Normal,
+        };
+        let limit = SearchLimit::depth_(10);
+        data.new_search(false, &limit).unwrap();
+        let params = SearchParams::create(
+            Chessboard::default(),
+            limit,
+            ZobristHistory::default(),
+            TT::default(),
+            None,
+            0,
+            main_atomic.clone(),
+            Main(data),
+        );
+        let aux_params = params.auxiliary(aux_atomic.clone());
+        let aux_thread = spawn(move || {
+            _ = Caps::default().search(aux_params);
+        });
+
+        let infos = Arc::new(Mutex::new(vec![]));
+        let infos_clone = infos.clone();
+        let main_clone = main_atomic.clone();
+        let aux_clone = aux_atomic.clone();
+        main_engine
+            .search_state_mut_dyn()
+            .add_iteration_callback(Box::new(move |info: &SearchInfo<Chessboard>| {
+                let main_nodes = main_clone.nodes();
+                let aux_nodes = aux_clone.nodes();
+                infos_clone
+                    .lock()
+                    .unwrap()
+                    .push((info.nodes.get(), main_nodes, aux_nodes));
+            }));
+        _ = main_engine.search(params);
+        aux_thread.join().unwrap();
+
+        let infos = infos.lock().unwrap();
+        let &(nodes, main_nodes, aux_nodes) = infos.last().unwrap();
+        // the aux thread may have searched a few more nodes since the info was created
+        assert!(nodes > main_nodes, "{nodes} {main_nodes}");
+        assert!(
+            nodes <= main_nodes + aux_nodes,
+            "{nodes} {main_nodes} {aux_nodes}"
+        );
+    }
+}
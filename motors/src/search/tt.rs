@@ -7,6 +7,7 @@ use std::ptr::addr_of;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
 
+use colored::Colorize;
 use portable_atomic::AtomicU128;
 use static_assertions::const_assert_eq;
 use strum_macros::FromRepr;
@@ -15,6 +16,8 @@ use strum_macros::FromRepr;
 use gears::games::chess::Chessboard;
 use gears::games::ZobristHash;
 use gears::general::board::Board;
+use gears::general::common::anyhow::{anyhow, bail};
+use gears::general::common::Res;
 use gears::general::moves::{Move, UntrustedMove};
 use gears::score::{Score, ScoreT, SCORE_WON};
 use gears::search::NodeType;
@@ -138,7 +141,10 @@ const_assert_eq!(size_of::<TTEntry<Chessboard>>(), size_of::<AtomicTTEntry>());
 
 pub const DEFAULT_HASH_SIZE_MB: usize = 16;
 
-/// Note that resizing the TT during search will wait until the search is finished
+/// Use at most 10 terabytes (should be enough for anybody™)
+pub const MAX_HASH_SIZE_MB: usize = 10_000_000;
+
+/// Note that setting the `Hash` option stops the current search before allocating a new TT
 /// (all threads will receive a new arc)
 // TODO: TT handle
 #[derive(Clone, Debug)]
@@ -163,6 +169,29 @@ impl TT {
         Self(tt)
     }
 
+    /// Creates a fresh TT for the `Hash` option. The number of entries is rounded down to a power of two, so the TT
+    /// never uses more memory than requested. Unlike [`Self::new_with_bytes`], this returns an `Err` instead of aborting
+    /// if the requested size is too large or the memory can't be allocated.
+    pub fn new_with_mb(size_in_mb: usize) -> Res<Self> {
+        if size_in_mb > MAX_HASH_SIZE_MB {
+            bail!(
+                "The hash size can be at most {MAX_HASH_SIZE_MB} MB, not {}",
+                size_in_mb.to_string().red()
+            )
+        }
+        let requested_entries = 1.max(size_in_mb * 1_000_000 / size_of::<AtomicTTEntry>());
+        let num_entries = 1 << requested_entries.ilog2();
+        let mut arr = Vec::new();
+        arr.try_reserve_exact(num_entries).map_err(|err| {
+            anyhow!(
+                "Couldn't allocate a TT of {} MB: {err}",
+                size_in_mb.to_string().red()
+            )
+        })?;
+        arr.resize_with(num_entries, AtomicU128::default);
+        Ok(Self(arr.into_boxed_slice().into()))
+    }
+
     pub fn size_in_entries(&self) -> usize {
         self.0.len()
     }
@@ -264,16 +293,20 @@ impl TT {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::eval::chess::lite::LiTEval;
+    use crate::io::ugi_output::UgiOutput;
     use crate::search::chess::caps::Caps;
-    use crate::search::multithreading::AtomicSearchState;
-    use crate::search::{Engine, SearchParams};
+    use crate::search::multithreading::{AtomicSearchState, EngineWrapper};
+    use crate::search::{Engine, EvalBuilder, SearchParams, SearcherBuilder};
     use gears::games::chess::moves::ChessMove;
     use gears::games::ZobristHistory;
     use gears::score::{MAX_NORMAL_SCORE, MIN_NORMAL_SCORE};
     use gears::search::NodeType::Exact;
     use gears::search::{Depth, SearchLimit};
+    use gears::ugi::EngineOptionName::Hash;
     use rand::distr::Uniform;
     use rand::{rng, Rng, RngCore};
+    use std::sync::Mutex;
     use std::thread::{sleep, spawn};
     use std::time::Duration;
 
@@ -281,8 +314,7 @@ mod test {
     #[cfg(feature = "chess")]
     fn test_packing() {
         let board = Chessboard::from_name("kiwipete").unwrap();
-        let mut i = 1;
-        for mov in board.pseudolegal_moves() {
+        for (mov, i) in board.pseudolegal_moves().into_iter().zip(1..) {
             let entry: TTEntry<Chessboard> = TTEntry::new(
                 board.zobrist_hash(),
                 Score(i * i * (i % 2 * 2 - 1)),
@@ -292,7 +324,6 @@ mod test {
             );
             let converted = entry.to_packed();
             assert_eq!(TTEntry::from_packed(converted), entry);
-            i += 1;
         }
     }
 
@@ -367,6 +398,49 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(feature = "caps")]
+    fn hash_option_test() {
+        let output = Arc::new(Mutex::new(UgiOutput::new(false)));
+        let mut engine = EngineWrapper::new(
+            TT::default(),
+            output,
+            Box::new(SearcherBuilder::<Chessboard, Caps>::new()),
+            Box::new(EvalBuilder::<Chessboard, LiTEval>::default()),
+        );
+        let pos = Chessboard::from_name("kiwipete").unwrap();
+        let mut prev_entries = 0;
+        for mb in [0, 1, 3, 16, 17, 100] {
+            engine.set_option(Hash, mb.to_string()).unwrap();
+            assert_eq!(engine.hash_size_mb(), mb);
+            let mut tt = engine.next_tt();
+            let entries = tt.size_in_entries();
+            assert!(entries.is_power_of_two(), "{entries}");
+            assert!(entries >= prev_entries);
+            assert!(tt.size_in_bytes() <= 16.max(mb * 1_000_000));
+            assert!(tt.size_in_bytes() * 2 > mb * 1_000_000);
+            prev_entries = entries;
+            // the new TT doesn't contain any old entries
+            assert!(tt.load::<Chessboard>(pos.zobrist_hash(), 0).is_none());
+            for (mov, i) in pos.pseudolegal_moves().into_iter().zip(0..) {
+                let entry: TTEntry<Chessboard> =
+                    TTEntry::new(pos.zobrist_hash(), Score(i), mov, i as isize, Exact);
+                tt.store(entry, 0);
+                assert_eq!(tt.load(pos.zobrist_hash(), 0), Some(entry));
+            }
+        }
+        assert!(engine
+            .set_option(Hash, (MAX_HASH_SIZE_MB + 1).to_string())
+            .is_err());
+        assert!(engine.set_option(Hash, "-1".to_string()).is_err());
+        assert_eq!(engine.hash_size_mb(), 100);
+        let limit = SearchLimit::depth_(3);
+        let res = Caps::default().search_with_tt(pos, limit, engine.next_tt());
+        assert!(pos.is_move_legal(res.chosen_move));
+        let entry = engine.tt_entry(&pos).unwrap();
+        assert_eq!(entry.mov.check_legal(&pos), Some(res.chosen_move));
+    }
+
     #[test]
     #[cfg(feature = "chess")]
     fn shared_tt_test() {
@@ -0,0 +1,39 @@
+//! The `UCI_Chess960` option changes the castling notation of the entire process, see
+//! [`ChessSettings::set_chess960_notation`]. This test is therefore in its own test binary, which runs in its own
+//! process, so that it can't interfere with the other tests.
+#![cfg(feature = "caps")]
+
+use gears::games::chess::moves::ChessMove;
+use gears::games::chess::{ChessSettings, Chessboard};
+use gears::general::board::Board;
+use gears::general::moves::Move;
+use gears::ugi::EngineOptionName::UCIChess960;
+use motors::search::chess::caps::Caps;
+use motors::search::Engine;
+
+#[test]
+fn chess960_option_test() {
+    let mut engine = Caps::default();
+    let info = engine.engine_info();
+    let option = info.additional_options();
+    let option = option.iter().find(|o| o.name == UCIChess960).unwrap();
+    let mut value = option.value.clone();
+    assert_eq!(value.to_string(), "type check default false");
+    let pos = Chessboard::from_name("kiwipete").unwrap();
+    let mov = ChessMove::from_compact_text("e1g1", &pos).unwrap();
+    assert_eq!(mov.to_string(), "e1g1");
+    assert!(pos.as_fen().contains(" w KQkq "));
+    engine
+        .set_option(UCIChess960, &mut value, "true".to_string())
+        .unwrap();
+    assert_eq!(value.value_to_str(), "true");
+    assert!(ChessSettings::chess960_notation());
+    assert_eq!(mov.to_string(), "e1h1");
+    assert!(pos.as_fen().contains(" w HAha "));
+    engine
+        .set_option(UCIChess960, &mut value, "false".to_string())
+        .unwrap();
+    assert_eq!(value.value_to_str(), "false");
+    assert_eq!(mov.to_string(), "e1g1");
+    assert!(pos.as_fen().contains(" w KQkq "));
+}
@@ -16,6 +16,7 @@ use gears::general::board::Board;
 use std::fmt;
 use std::fmt::Formatter;
 
+pub mod ataxx;
 pub mod chess;
 
 /// Returns a [`Vec`] of [`bool`] where the ith entry is [`true`] iff the absolute difference between `weights[i]` and
@@ -440,10 +441,10 @@ fn tune_scaling_factor<B: Board, D: Datapoint, E: Eval<B>>(
             because the eval fails to accurately predict the used datasets. You can always fall back to hand-picking an \
             eval scale in case this doesn't work, or try again with different datasets");
         let (dir, _loss) = grad_for_eval_scale(weights, batch, scale);
-        if prev_dir.is_none() {
-            prev_dir = Some(dir);
-        } else if prev_dir.unwrap() != dir {
-            break;
+        match prev_dir {
+            None => prev_dir = Some(dir),
+            Some(prev) if prev != dir => break,
+            _ => {}
         }
         match dir {
             Up => scale *= 2.0,
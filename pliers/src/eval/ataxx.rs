@@ -0,0 +1,3 @@
+//! Contains ataxx evaluation functions.
+
+pub mod frontier_eval;
@@ -0,0 +1,100 @@
+//! An ataxx eval that tunes material, the number of pieces that can be captured, and the number of empty squares
+//! next to own pieces.
+
+use crate::eval::{Eval, WeightsInterpretation};
+use crate::gd::{NonTaperedDatapoint, Weight, Weights};
+use crate::load_data::NoFilter;
+use crate::trace::{BasicTrace, SimpleTrace, TraceTrait};
+use gears::games::ataxx::{AtaxxBoard, AtaxxColor};
+use gears::games::Color;
+use gears::general::bitboards::{Bitboard, RawBitboard};
+use std::fmt::Formatter;
+use strum::IntoEnumIterator;
+
+const MATERIAL: usize = 0;
+const VULNERABLE: usize = 1;
+const LIBERTIES: usize = 2;
+
+const NUM_FEATURES: usize = 3;
+
+const FEATURE_NAMES: [&str; NUM_FEATURES] = ["material", "vulnerable", "liberties"];
+
+/// An ataxx eval that tunes material, the number of pieces that can be captured, and the number of empty squares
+/// next to own pieces.
+///
+/// A piece is vulnerable if it has an empty neighbor that the opponent can move to, i.e. if the opponent could
+/// convert it with a single move. Liberties are empty squares that are adjacent to at least one own piece,
+/// i.e. squares where the player can clone to.
+#[derive(Debug, Default)]
+pub struct FrontierEval {}
+
+impl WeightsInterpretation for FrontierEval {
+    fn display(&self) -> fn(&mut Formatter, &Weights, &[Weight]) -> std::fmt::Result {
+        |f: &mut Formatter<'_>, weights: &Weights, _old_weights: &[Weight]| {
+            for (idx, name) in FEATURE_NAMES.iter().enumerate() {
+                writeln!(f, "{name}:\t{0}", weights[idx])?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Eval<AtaxxBoard> for FrontierEval {
+    fn num_weights() -> usize {
+        NUM_FEATURES
+    }
+
+    fn num_features() -> usize {
+        Self::num_weights()
+    }
+
+    type D = NonTaperedDatapoint;
+    type Filter = NoFilter;
+
+    fn feature_trace(pos: &AtaxxBoard) -> impl TraceTrait {
+        let mut trace = SimpleTrace::for_features(Self::num_features());
+        let empty = pos.empty_bb();
+        for color in AtaxxColor::iter() {
+            let ours = pos.color_bb(color);
+            let theirs = pos.color_bb(color.other());
+            trace.increment_by(MATERIAL, color, ours.num_ones() as isize);
+            let reachable_by_them = theirs.extended_moore_neighbors(2) & empty;
+            let vulnerable = ours & reachable_by_them.moore_neighbors();
+            trace.increment_by(VULNERABLE, color, vulnerable.num_ones() as isize);
+            let liberties = ours.moore_neighbors() & empty;
+            trace.increment_by(LIBERTIES, color, liberties.num_ones() as isize);
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gd::Outcome;
+    use gears::general::board::Board;
+    use gears::general::board::Strictness::Strict;
+
+    #[test]
+    pub fn startpos_test() {
+        let board = AtaxxBoard::default();
+        let features = FrontierEval::feature_trace(&board);
+        assert_eq!(features.as_features(0).len(), 0);
+    }
+
+    #[test]
+    pub fn features_test() {
+        // x has two pieces in the corner, o has a single piece that can leap next to them and vice versa
+        let board = AtaxxBoard::from_fen("xx5/7/2o4/7/7/7/7 x 0 1", Strict).unwrap();
+        let features = FrontierEval::extract_features(&board, Outcome::new(1.0), 1.0).features;
+        let values = features
+            .iter()
+            .map(|f| (f.idx(), f.float()))
+            .collect::<Vec<_>>();
+        // x: 2 pieces, both vulnerable, 4 liberties; o: 1 piece, vulnerable, 8 liberties
+        assert_eq!(
+            values,
+            vec![(MATERIAL, 1.0), (VULNERABLE, 1.0), (LIBERTIES, -4.0)]
+        );
+    }
+}
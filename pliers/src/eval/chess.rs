@@ -4,12 +4,10 @@ use crate::eval::write_phased_with_width;
 use crate::gd::{Float, Weight};
 use crate::load_data::{Filter, ParseResult};
 use crate::trace::{BasicTrace, SimpleTrace, TraceNFeatures};
-use gears::games::chess::pieces::{ChessPieceType, NUM_CHESS_PIECES};
+use gears::games::chess::pieces::{ChessPieceType, MAX_CHESS_PHASE, NUM_CHESS_PIECES};
 use gears::games::chess::squares::{ChessSquare, NUM_SQUARES};
 use gears::games::chess::ChessColor::White;
 use gears::games::chess::{ChessColor, Chessboard};
-use gears::general::bitboards::RawBitboard;
-use motors::eval::chess::CHESS_PHASE_VALUES;
 use std::fmt::Formatter;
 use strum::IntoEnumIterator;
 
@@ -42,10 +40,7 @@ const NUM_PSQT_FEATURES: usize = NUM_CHESS_PIECES * NUM_SQUARES;
 /// The start position has a phase value of 24, and a position without any non-pawn, non-king pieces
 /// has a phase value of zero.
 pub fn chess_phase(pos: &Chessboard) -> Float {
-    let phase: usize = ChessPieceType::non_king_pieces()
-        .map(|piece| pos.piece_bb(piece).num_ones() * CHESS_PHASE_VALUES[piece as usize])
-        .sum();
-    phase as Float / 24.0
+    pos.game_phase() as Float / MAX_CHESS_PHASE as Float
 }
 
 fn to_feature_idx(piece: ChessPieceType, color: ChessColor, square: ChessSquare) -> usize {
@@ -41,6 +41,8 @@ pub enum LiteFeatureSubset {
     KingOpenness,
     BishopOpenness,
     PawnShield,
+    ShieldPawnDeficit,
+    KingAdjacentOpenness,
     PassedPawn,
     UnsupportedPawn,
     DoubledPawn,
@@ -63,6 +65,8 @@ impl FeatureSubSet for LiteFeatureSubset {
             KingOpenness => 3,
             BishopOpenness => 4 * 8,
             PawnShield => NUM_PAWN_SHIELD_CONFIGURATIONS,
+            ShieldPawnDeficit => 4,
+            KingAdjacentOpenness => 2,
             PassedPawn => NUM_SQUARES,
             UnsupportedPawn => 1,
             DoubledPawn => 1,
@@ -145,6 +149,17 @@ impl FeatureSubSet for LiteFeatureSubset {
                 }
                 return writeln!(f, "];");
             }
+            ShieldPawnDeficit => {
+                write!(f, "const SHIELD_PAWN_DEFICIT: [PhasedScore; 4] = ")?;
+            }
+            KingAdjacentOpenness => {
+                for (i, openness) in ["OPEN", "SEMIOPEN"].iter().enumerate() {
+                    write!(f, "const KING_ADJACENT_{openness}_FILE: PhasedScore = ")?;
+                    write_phased(f, weights, self.start_idx() + i, special)?;
+                    writeln!(f, ";")?;
+                }
+                return Ok(());
+            }
             PassedPawn => {
                 writeln!(f, "\n#[rustfmt::skip]")?;
                 write!(f, "const PASSED_PAWNS: [PhasedScore; NUM_SQUARES] = ")?;
@@ -310,6 +325,19 @@ impl LiteValues for LiTETrace {
         SingleFeature::new(PawnShield, config)
     }
 
+    fn shield_pawn_deficit(missing: usize) -> SingleFeature {
+        debug_assert!(missing <= 3);
+        SingleFeature::new(ShieldPawnDeficit, missing)
+    }
+
+    fn king_adjacent_openness(openness: FileOpenness) -> SingleFeature {
+        match openness {
+            Open => SingleFeature::new(KingAdjacentOpenness, 0),
+            SemiOpen => SingleFeature::new(KingAdjacentOpenness, 1),
+            Closed | SemiClosed => SingleFeature::no_feature(KingAdjacentOpenness),
+        }
+    }
+
     fn pawn_protection(piece: ChessPieceType) -> SingleFeature {
         SingleFeature::new(PawnProtection, piece as usize)
     }
@@ -4,6 +4,10 @@ use crate::eval::{count_occurrences, display, interpolate, WeightsInterpretation
 use crate::trace::TraceTrait;
 use derive_more::{Add, AddAssign, Deref, DerefMut, Display, Div, Mul, Sub, SubAssign};
 use gears::crossterm::style::Stylize;
+use gears::general::common::anyhow;
+use gears::general::common::anyhow::anyhow;
+use gears::score::cp_to_winrate;
+use gears::GameResult;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use rayon::prelude::*;
@@ -54,6 +58,36 @@ impl WrScore {
     }
 }
 
+/// Converts a [`GameResult`] from the first player's perspective, like the `From<GameResult>` impls for `f32` and `f64`:
+/// A win is `1`, a loss `0` and a draw `0.5`. Aborted games don't have an outcome.
+impl TryFrom<GameResult> for WrScore {
+    type Error = anyhow::Error;
+
+    fn try_from(result: GameResult) -> Result<Self, Self::Error> {
+        result
+            .check_finished()
+            .map(|res| Self(res.into()))
+            .ok_or_else(|| anyhow!("An aborted game doesn't have an outcome"))
+    }
+}
+
+/// The inverse of converting a [`GameResult`] into a [`WrScore`]. Fails unless the value is exactly `0`, `0.5` or `1`.
+impl TryFrom<WrScore> for GameResult {
+    type Error = anyhow::Error;
+
+    fn try_from(score: WrScore) -> Result<Self, Self::Error> {
+        if score.0 == 1.0 {
+            Ok(GameResult::P1Win)
+        } else if score.0 == 0.0 {
+            Ok(GameResult::P2Win)
+        } else if score.0 == 0.5 {
+            Ok(GameResult::Draw)
+        } else {
+            Err(anyhow!("The outcome {score} isn't a game result"))
+        }
+    }
+}
+
 impl Display for WrScore {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:.3}", self.0)
@@ -65,9 +99,9 @@ impl Display for WrScore {
 pub type ScalingFactor = Float;
 
 /// [Logistic sigmoid](<https://en.wikipedia.org/wiki/Logistic_function#Mathematical_properties>),
-/// dividing `x` by a [`ScalingFactor`].
+/// dividing `x` by a [`ScalingFactor`]. This is the same mapping that the engines use, see [`cp_to_winrate`].
 pub fn sigmoid(x: Float, scale: ScalingFactor) -> Float {
-    1.0 / (1.0 + (-x / scale).exp())
+    cp_to_winrate(x, scale)
 }
 
 /// Convert an eval score to a win rate prediction by applying a [`sigmoid`].
@@ -207,6 +241,92 @@ impl Weights {
     pub fn num_weights(&self) -> usize {
         self.0.len()
     }
+
+    /// Compare these weights with `other`, e.g. to compare two tuning runs or the weights before and after a change.
+    ///
+    /// The result lists every weight whose value differs, treating `self` as the old and `other` as the new weights.
+    /// To see the changes in the format of an eval, call [`display`] with `other` as weights and `self` as old weights.
+    pub fn diff(&self, other: &Weights) -> WeightsDiff {
+        assert_eq!(self.len(), other.len());
+        WeightsDiff(
+            self.iter()
+                .zip(other.iter())
+                .enumerate()
+                .filter(|(_, (old, new))| old != new)
+                .map(|(idx, (old, new))| WeightChange {
+                    idx,
+                    old: *old,
+                    new: *new,
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A single weight that differs between two [`Weights`], see [`Weights::diff`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WeightChange {
+    /// The index of the weight.
+    pub idx: usize,
+    /// The value in the weights that [`Weights::diff`] has been called on.
+    pub old: Weight,
+    /// The value in the weights that have been passed to [`Weights::diff`].
+    pub new: Weight,
+}
+
+impl WeightChange {
+    /// By how much the weight has changed.
+    pub fn delta(&self) -> Weight {
+        self.new - self.old
+    }
+}
+
+/// The weights that changed between two [`Weights`], as computed by [`Weights::diff`].
+///
+/// When displayed, this prints one line per changed weight, painting weights whose rounded value has changed red.
+#[derive(Debug, Default, Clone)]
+#[must_use]
+pub struct WeightsDiff(pub Vec<WeightChange>);
+
+impl WeightsDiff {
+    /// `true` iff no weight has changed.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The indices of all changed weights, in ascending order.
+    pub fn changed_indices(&self) -> Vec<usize> {
+        self.0.iter().map(|c| c.idx).collect()
+    }
+
+    /// The change with the largest absolute difference, or `None` if no weight has changed.
+    pub fn max_change(&self) -> Option<WeightChange> {
+        self.0
+            .iter()
+            .max_by(|a, b| a.delta().0.abs().total_cmp(&b.delta().0.abs()))
+            .copied()
+    }
+}
+
+impl Display for WeightsDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No weights have changed");
+        }
+        writeln!(f, "{} weights have changed:", self.0.len())?;
+        for change in &self.0 {
+            let special = change.old.rounded() != change.new.rounded();
+            writeln!(
+                f,
+                "{idx:>5}: {old} -> {new} ({delta:+.2})",
+                idx = change.idx,
+                old = change.old.to_string(false, 5),
+                new = change.new.to_string(special, 5),
+                delta = change.delta().0
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl Display for Weights {
@@ -579,7 +699,7 @@ impl<D: Datapoint> Dataset<D> {
     }
 
     /// Converts the entire dataset into a single batch.
-    pub fn as_batch(&self) -> Batch<D> {
+    pub fn as_batch(&self) -> Batch<'_, D> {
         Batch {
             datapoints: &self.data_points,
             num_weights: self.weights_in_pos,
@@ -591,7 +711,7 @@ impl<D: Datapoint> Dataset<D> {
     ///
     /// Note that unless `D::all_sampling_weights_identical()` returns true, this needs to compute the sum of sampling weights,
     /// which makes this an `O(n)` operation, where `n` is the size of the returned batch.
-    pub fn batch(&self, start_idx: usize, end_idx: usize) -> Batch<D> {
+    pub fn batch(&self, start_idx: usize, end_idx: usize) -> Batch<'_, D> {
         let end_idx = end_idx.min(self.data_points.len());
         let datapoints = &self.data_points[start_idx..end_idx];
         let weight_sum = if D::all_sampling_weights_identical() {
@@ -881,6 +1001,34 @@ pub fn print_optimized_weights<D: Datapoint>(
     );
 }
 
+/// A mask of weights that don't get changed by the optimizer, see [`Optimizer::freeze`].
+///
+/// This allows staging a tune, e.g. first tuning only the piece values while keeping the PSQTs fixed, and then the reverse.
+/// Indices beyond the end of the mask are not frozen, so the default mask doesn't freeze any weights.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct FrozenWeights(pub Vec<bool>);
+
+impl FrozenWeights {
+    /// Returns `true` if the weight with index `idx` is frozen.
+    pub fn is_frozen(&self, idx: usize) -> bool {
+        self.0.get(idx).copied().unwrap_or(false)
+    }
+
+    /// Returns `true` if at least one weight is frozen.
+    pub fn any_frozen(&self) -> bool {
+        self.0.iter().any(|frozen| *frozen)
+    }
+
+    /// Sets the gradient of all frozen weights to zero.
+    pub fn zero_frozen(&self, gradient: &mut Gradient) {
+        for (grad, frozen) in gradient.0.iter_mut().zip(self.0.iter()) {
+            if *frozen {
+                *grad = Weight(0.0);
+            }
+        }
+    }
+}
+
 /// The default optimizer. Currently, this is [`Adam`].
 pub type DefaultOptimizer = Adam<QuadraticLoss>;
 
@@ -903,6 +1051,19 @@ pub trait Optimizer<D: Datapoint> {
     /// Can be less than 1 to increase the learning rate.
     fn lr_drop(&mut self, factor: Float);
 
+    /// Don't change the weights that are set in the mask. Their gradient is treated as zero, so they keep their initial value,
+    /// which is zero if the [weights interpretation](WeightsInterpretation) retunes from zero.
+    /// This should be called before starting to optimize, usually before calling [`optimize_dataset`].
+    ///
+    /// The default implementation is meant for optimizers that don't support freezing weights, so it panics if
+    /// any weight is frozen.
+    fn freeze(&mut self, frozen: FrozenWeights) {
+        assert!(
+            !frozen.any_frozen(),
+            "This optimizer doesn't support freezing weights"
+        );
+    }
+
     /// A single iteration of the optimizer.
     fn iteration(
         &mut self,
@@ -934,6 +1095,8 @@ pub trait Optimizer<D: Datapoint> {
 pub struct SimpleGDOptimizer {
     /// The learning rate.
     pub alpha: Float,
+    /// Weights that don't get changed.
+    pub frozen: FrozenWeights,
 }
 
 impl<D: Datapoint> Optimizer<D> for SimpleGDOptimizer {
@@ -945,6 +1108,7 @@ impl<D: Datapoint> Optimizer<D> for SimpleGDOptimizer {
     fn new(_batch: Batch<D>, eval_scale: ScalingFactor) -> Self {
         Self {
             alpha: eval_scale / 4.0,
+            frozen: FrozenWeights::default(),
         }
     }
 
@@ -952,6 +1116,10 @@ impl<D: Datapoint> Optimizer<D> for SimpleGDOptimizer {
         self.alpha /= factor;
     }
 
+    fn freeze(&mut self, frozen: FrozenWeights) {
+        self.frozen = frozen;
+    }
+
     fn iteration(
         &mut self,
         weights: &mut Weights,
@@ -959,8 +1127,9 @@ impl<D: Datapoint> Optimizer<D> for SimpleGDOptimizer {
         eval_scale: ScalingFactor,
         _i: usize,
     ) {
-        let gradient =
+        let mut gradient =
             compute_scaled_gradient_with(weights, batch, eval_scale, QuadraticLoss::default());
+        self.frozen.zero_frozen(&mut gradient);
         for i in 0..weights.len() {
             weights[i].0 -= gradient[i].0 * self.alpha;
         }
@@ -1023,6 +1192,10 @@ impl<D: Datapoint, G: LossGradient> Optimizer<D> for Adam<G> {
         <AdamW<G> as Optimizer<D>>::lr_drop(&mut self.0, factor);
     }
 
+    fn freeze(&mut self, frozen: FrozenWeights) {
+        <AdamW<G> as Optimizer<D>>::freeze(&mut self.0, frozen);
+    }
+
     fn iteration(
         &mut self,
         weights: &mut Weights,
@@ -1045,6 +1218,7 @@ pub struct AdamW<G: LossGradient> {
     m: Weights,
     /// second moment (exponentially moving average)
     v: Weights,
+    frozen: FrozenWeights,
     _phantom: PhantomData<G>,
 }
 
@@ -1070,6 +1244,7 @@ impl<D: Datapoint, G: LossGradient> Optimizer<D> for AdamW<G> {
             hyper_params,
             m: Weights::new(batch.num_weights),
             v: Weights::new(batch.num_weights),
+            frozen: FrozenWeights::default(),
             _phantom: PhantomData,
         }
     }
@@ -1078,6 +1253,10 @@ impl<D: Datapoint, G: LossGradient> Optimizer<D> for AdamW<G> {
         self.hyper_params.alpha /= factor;
     }
 
+    fn freeze(&mut self, frozen: FrozenWeights) {
+        self.frozen = frozen;
+    }
+
     fn iteration(
         &mut self,
         weights: &mut Weights,
@@ -1088,8 +1267,13 @@ impl<D: Datapoint, G: LossGradient> Optimizer<D> for AdamW<G> {
         let iteration = iteration + 1;
         let beta1 = self.hyper_params.beta1;
         let beta2 = self.hyper_params.beta2;
-        let gradient = compute_scaled_gradient::<D, G>(weights, batch, eval_scale);
+        let mut gradient = compute_scaled_gradient::<D, G>(weights, batch, eval_scale);
+        self.frozen.zero_frozen(&mut gradient);
         for i in 0..gradient.len() {
+            // the weight decay would still change frozen weights
+            if self.frozen.is_frozen(i) {
+                continue;
+            }
             // biased since the values are initialized to 0, so the exponential moving average is wrong
             self.m[i] = self.m[i] * beta1 + gradient[i] * (1.0 - beta1);
             self.v[i] = self.v[i] * beta2 + gradient[i] * gradient[i].0 * (1.0 - beta2);
@@ -1111,6 +1295,17 @@ mod tests {
     use std::cmp::Ordering;
     use std::cmp::Ordering::Equal;
 
+    #[test]
+    pub fn game_result_outcome_test() {
+        for result in [GameResult::P1Win, GameResult::P2Win, GameResult::Draw] {
+            let outcome = Outcome::try_from(result).unwrap();
+            assert_eq!(outcome.0, Float::from(result));
+            assert_eq!(GameResult::try_from(outcome).unwrap(), result);
+        }
+        assert!(Outcome::try_from(GameResult::Aborted).is_err());
+        assert!(GameResult::try_from(Outcome::new(0.25)).is_err());
+    }
+
     #[test]
     pub fn simple_loss_test() {
         let weights = Weights(vec![Weight(0.0); 42]);
@@ -1141,6 +1336,26 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn weights_diff_test() {
+        let weights = Weights(vec![Weight(1.0), Weight(-2.5), Weight(3.0), Weight(0.0)]);
+        let diff = weights.diff(&weights.clone());
+        assert!(diff.is_empty());
+        assert!(diff.changed_indices().is_empty());
+        assert!(diff.max_change().is_none());
+        let mut other = weights.clone();
+        other[1] = Weight(-2.0);
+        other[3] = Weight(10.0);
+        let diff = weights.diff(&other);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.changed_indices(), vec![1, 3]);
+        let max_change = diff.max_change().unwrap();
+        assert_eq!(max_change.idx, 3);
+        assert_eq!(max_change.delta(), Weight(10.0));
+        assert_eq!(other.diff(&weights).changed_indices(), vec![1, 3]);
+        assert_eq!(diff.to_string().lines().count(), 3);
+    }
+
     #[test]
     pub fn compute_gradient_test() {
         let weights = Weights(vec![Weight(0.0)]);
@@ -1339,7 +1554,10 @@ mod tests {
             assert!(weights[1].0 <= -100.0);
 
             let optimizers: [AnyOptimizer; 5] = [
-                Box::new(SimpleGDOptimizer { alpha: 1.0 }),
+                Box::new(SimpleGDOptimizer {
+                    alpha: 1.0,
+                    frozen: FrozenWeights::default(),
+                }),
                 Box::new(Adam::<QuadraticLoss>::new(batch, scale)),
                 Box::new(Adam::<CrossEntropyLoss>::new(batch, scale)),
                 Box::new(AdamW::<QuadraticLoss>::new(batch, scale)),
@@ -1403,6 +1621,44 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn frozen_weights_test() {
+        type AnyOptimizer = Box<dyn Optimizer<NonTaperedDatapoint>>;
+        let scale = 1000.0;
+        let win = NonTaperedDatapoint {
+            features: vec![Feature::new(1, 0), Feature::new(-1, 1), Feature::new(1, 2)],
+            outcome: WrScore(1.0),
+        };
+        let lose = NonTaperedDatapoint {
+            features: vec![Feature::new(-1, 0), Feature::new(1, 1), Feature::new(-1, 2)],
+            outcome: WrScore(0.0),
+        };
+        let dataset = vec![win, lose];
+        let batch = Batch {
+            datapoints: dataset.as_slice(),
+            num_weights: 3,
+            weight_sum: 2.0,
+        };
+        let optimizers: [AnyOptimizer; 3] = [
+            Box::new(SimpleGDOptimizer::new(batch, scale)),
+            Box::new(Adam::<QuadraticLoss>::new(batch, scale)),
+            Box::new(AdamW::<CrossEntropyLoss>::new(batch, scale)),
+        ];
+        for mut optimizer in optimizers {
+            let frozen = FrozenWeights(vec![true, false]);
+            assert!(frozen.any_frozen());
+            assert!(!FrozenWeights(vec![false, false]).any_frozen());
+            optimizer.freeze(frozen);
+            let mut weights = Weights(vec![Weight(12.5), Weight(-3.0), Weight(7.0)]);
+            for i in 0..100 {
+                optimizer.iteration(&mut weights, batch, scale, i);
+            }
+            assert_eq!(weights[0].0, 12.5);
+            assert!(weights[1].0 < -3.0, "{weights}");
+            assert!(weights[2].0 > 7.0, "{weights}");
+        }
+    }
+
     #[test]
     pub fn adam_one_weight_test() {
         for outcome in [0.0, 0.5, 1.0] {
@@ -127,6 +127,7 @@ const DEFAULT_NUM_EPOCHS: usize = 4000;
 /// but this is the easiest way to use the tuner. Simply call this function with your eval,
 /// e.g. `run::<Chessboard, MaterialOnlyEval>()`. Make sure to provide a JSON file with a list of datasets.
 /// The filenames in that JSON file should be either absolute or relative to the location of the JSON file.
+/// It's also possible to provide several JSON files, in which case all their datasets are combined.
 pub fn run<B: Board, E: Eval<B>>() {
     if let Err(err) = try_to_run::<B, E>() {
         eprintln!("{err}");
@@ -140,15 +141,36 @@ pub fn try_to_run<B: Board, E: Eval<B>>() -> Res<()> {
     optimize::<B, E>(files.as_ref())
 }
 
-/// Load a list datasets from a JSON file.
+/// Load a list datasets from one or more JSON files.
 ///
-/// The path to this file is extracted from the first command line argument, with a game-specific fallback
+/// The paths to these files are extracted from the command line arguments, with a game-specific fallback
 /// if no command line arguments are used.
 pub fn get_datasets<B: Board>() -> Res<Vec<AnnotatedFenFile>> {
-    let default_path = format!("pliers/datasets/{}/datasets.json", B::game_name());
-    let json_file_path = args().nth(1).unwrap_or(default_path);
-    let json_file_path = Path::new(&json_file_path);
-    load_datasets_from_json(json_file_path)
+    let mut json_file_paths = args().skip(1).collect::<Vec<_>>();
+    if json_file_paths.is_empty() {
+        json_file_paths.push(format!("pliers/datasets/{}/datasets.json", B::game_name()));
+    }
+    load_datasets_from_jsons(&json_file_paths)
+}
+
+/// Load the datasets of several JSON files, see [`load_datasets_from_json`].
+///
+/// This is useful when the data is split into many shards that each have their own JSON file.
+pub fn load_datasets_from_jsons<P: AsRef<Path>>(
+    json_file_paths: &[P],
+) -> Res<Vec<AnnotatedFenFile>> {
+    let mut files = vec![];
+    for path in json_file_paths {
+        files.append(&mut load_datasets_from_json(path.as_ref())?);
+    }
+    if json_file_paths.len() > 1 {
+        println!(
+            "Combined {0} datasets from {1} JSON files",
+            files.len(),
+            json_file_paths.len()
+        );
+    }
+    Ok(files)
 }
 
 /// Load a list of datasets from a JSON file.
@@ -196,10 +218,7 @@ pub fn optimize_for<B: Board, E: Eval<B>, O: Optimizer<E::D>>(
 ) -> Res<()> {
     #[cfg(debug_assertions)]
     println!("Running in debug mode. Run in release mode for increased performance.");
-    let mut dataset = Dataset::new(E::num_weights());
-    for file in file_list {
-        dataset.union(FenReader::<B, E>::load_from_file(file)?);
-    }
+    let mut dataset = load_dataset::<B, E>(file_list)?;
     let e = E::default();
     let batch = dataset.as_batch();
     let scale = e.eval_scale().to_scaling_factor(batch, &e);
@@ -223,6 +242,22 @@ pub fn optimize_for<B: Board, E: Eval<B>, O: Optimizer<E::D>>(
     Ok(())
 }
 
+/// Loads all datasets in the `file_list` and merges them into a single training set.
+pub fn load_dataset<B: Board, E: Eval<B>>(file_list: &[AnnotatedFenFile]) -> Res<Dataset<E::D>> {
+    let mut dataset = Dataset::new(E::num_weights());
+    for file in file_list {
+        dataset.union(FenReader::<B, E>::load_from_file(file)?);
+    }
+    if file_list.len() > 1 {
+        println!(
+            "Loaded {0} positions from {1} datasets in total",
+            dataset.data().len(),
+            file_list.len()
+        );
+    }
+    Ok(dataset)
+}
+
 /// Convenience wrapper for [`optimize`] for chess.
 pub fn optimize_chess_eval<E: Eval<Chessboard>>(file_list: &[AnnotatedFenFile]) -> Res<()> {
     debug_eval_on_lucena::<E>();
@@ -270,11 +305,16 @@ mod tests {
         cp_eval_for_weights, cp_to_wr, loss_for, quadratic_sample_loss, Adam, AdamW, CpScore,
         CrossEntropyLoss, Float, Outcome, QuadraticLoss,
     };
+    use crate::load_data::Perspective;
     use crate::load_data::Perspective::SideToMove;
     use gears::games::chess::pieces::{ChessPieceType, ColoredChessPieceType};
     use gears::games::chess::zobrist::NUM_PIECE_SQUARE_ENTRIES;
     use gears::games::chess::ChessColor::White;
     use gears::games::{AbstractPieceType, ColoredPieceType};
+    use gears::general::board::Strictness::Relaxed;
+    use gears::search::Depth;
+    use motors::datagen::{run_datagen, DatagenOpts};
+    use motors::{create_engine_box_from_str, list_chess_evals, list_chess_searchers};
     use ChessPieceType::*;
 
     #[test]
@@ -319,6 +359,67 @@ mod tests {
         assert!(loss <= 0.01, "{loss}");
     }
 
+    #[test]
+    pub fn multiple_json_files_test() {
+        let dir = std::env::temp_dir().join(format!("pliers_json_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]\n\
+            7k/8/8/8/8/8/8/R6K w - - 0 1 [1-0]\n",
+            "7k/8/8/8/8/8/8/R6K w - - 0 1 [1.0]\n\
+            7k/8/8/8/8/8/8/r6K w - - 0 1 [0.0]\n\
+            7k/8/8/8/8/8/8/1Q5K w - - 0 1 [1-0]\n",
+        ];
+        let mut json_files = vec![];
+        for (i, fens) in fens.iter().enumerate() {
+            std::fs::write(dir.join(format!("fens_{i}.epd")), fens).unwrap();
+            let json_file = dir.join(format!("datasets_{i}.json"));
+            std::fs::write(&json_file, format!("[{{\"path\": \"fens_{i}.epd\"}}]")).unwrap();
+            json_files.push(json_file);
+        }
+        let file_list = load_datasets_from_jsons(&json_files).unwrap();
+        assert_eq!(file_list.len(), 2);
+        let dataset = load_dataset::<Chessboard, PistonEval>(&file_list).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(dataset.data().len(), 5);
+        assert_eq!(dataset.num_weights(), NUM_PIECE_SQUARE_ENTRIES * 2);
+    }
+
+    #[test]
+    pub fn datagen_output_test() {
+        let dir = std::env::temp_dir().join(format!("pliers_datagen_test_{}", std::process::id()));
+        let opts = DatagenOpts {
+            out: dir.clone(),
+            games: 2,
+            depth: Some(Depth::new_unchecked(1)),
+            shard_size: 10,
+            with_score: true,
+            max_plies: 40,
+            ..DatagenOpts::default()
+        };
+        let mut engine =
+            create_engine_box_from_str("caps", &list_chess_searchers(), &list_chess_evals())
+                .unwrap();
+        let num_positions = run_datagen::<Chessboard>(engine.as_mut(), &opts).unwrap();
+        assert!(num_positions > 0);
+        let mut num_loaded = 0;
+        for shard in std::fs::read_dir(&dir).unwrap() {
+            let fens = std::fs::read_to_string(shard.unwrap().path()).unwrap();
+            assert!(fens.lines().count() <= 10);
+            for line in fens.lines() {
+                let (fen, _annotation) = line.split_once(" [").unwrap();
+                let pos = Chessboard::from_fen(fen, Relaxed).unwrap();
+                assert!(!pos.is_in_check());
+            }
+            let dataset =
+                FenReader::<Chessboard, PistonEval>::load_from_str(&fens, Perspective::White)
+                    .unwrap();
+            num_loaded += dataset.data().len();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(num_loaded, num_positions);
+    }
+
     #[test]
     pub fn chess_piece_values_test() {
         let piece_val = |piece| match piece {
@@ -94,11 +94,11 @@ impl<B: Board, E: Eval<B>> FenReader<B, E> {
         // This would be a great time to use the `.remainder()` method, but that isn't stable :/
         let wdl = input.next().ok_or_else(|| anyhow!("Missing wdl"))?;
         let wdl = wdl.trim_matches(IGNORED);
-        if let Some(result) = GameResult::from_str(wdl)
+        if let Some(outcome) = GameResult::from_str(wdl)
             .ok()
-            .and_then(|val| val.check_finished())
+            .and_then(|res| Outcome::try_from(res).ok())
         {
-            return Ok(Outcome::new(result.into()));
+            return Ok(outcome);
         }
         if let Ok(parsed) = parse_fp_from_str(wdl, "wdl") {
             return Ok(Outcome::new(parsed));